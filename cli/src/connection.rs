@@ -0,0 +1,177 @@
+use tokio_cqrs_es2_store::{
+    IAdminStore,
+    IArchiveMarkerStore,
+};
+
+/// Connects to the store of `url` according to its scheme
+pub async fn connect(
+    url: &str
+) -> Result<Box<dyn IAdminStore>, String> {
+    let scheme = url
+        .split(':')
+        .next()
+        .unwrap_or_default();
+
+    match scheme {
+        #[cfg(feature = "with-postgres")]
+        "postgres" | "postgresql" => {
+            use sqlx::postgres::PgPoolOptions;
+            use tokio_cqrs_es2_store::postgres_store::AdminStore;
+
+            let pool = PgPoolOptions::new()
+                .max_connections(1)
+                .connect(url)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(AdminStore::new(pool)))
+        },
+        #[cfg(feature = "with-mysql")]
+        "mysql" | "mariadb" => {
+            use sqlx::mysql::MySqlPoolOptions;
+            use tokio_cqrs_es2_store::mysql_store::AdminStore;
+
+            let url = url.replacen("mariadb:", "mysql:", 1);
+
+            let pool = MySqlPoolOptions::new()
+                .max_connections(1)
+                .connect(url.as_str())
+                .await
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(AdminStore::new(pool)))
+        },
+        #[cfg(feature = "with-sqlite")]
+        "sqlite" => {
+            use sqlx::sqlite::SqlitePoolOptions;
+            use tokio_cqrs_es2_store::sqlite_store::AdminStore;
+
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect(url)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(AdminStore::new(pool)))
+        },
+        #[cfg(feature = "with-mongodb")]
+        "mongodb" | "mongodb+srv" => {
+            use mongodb::Client;
+            use tokio_cqrs_es2_store::mongodb_store::AdminStore;
+
+            let client = Client::with_uri_str(url)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let db = client
+                .default_database()
+                .ok_or("the database name is missing from the URL")?;
+
+            Ok(Box::new(AdminStore::new(db)))
+        },
+        #[cfg(feature = "with-redis")]
+        "redis" | "rediss" => {
+            use redis::Client;
+            use tokio_cqrs_es2_store::redis_store::AdminStore;
+
+            let conn = Client::open(url)
+                .and_then(|x| x.get_connection())
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(AdminStore::new(conn)))
+        },
+        _ => {
+            Err(format!(
+                "unsupported store URL scheme '{}'",
+                scheme
+            ))
+        },
+    }
+}
+
+/// Connects to the archive markers of the store of `url` according
+/// to its scheme
+pub async fn connect_markers(
+    url: &str
+) -> Result<Box<dyn IArchiveMarkerStore>, String> {
+    let scheme = url
+        .split(':')
+        .next()
+        .unwrap_or_default();
+
+    match scheme {
+        #[cfg(feature = "with-postgres")]
+        "postgres" | "postgresql" => {
+            use sqlx::postgres::PgPoolOptions;
+            use tokio_cqrs_es2_store::postgres_store::ArchiveMarkerStore;
+
+            let pool = PgPoolOptions::new()
+                .max_connections(1)
+                .connect(url)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(ArchiveMarkerStore::new(pool)))
+        },
+        #[cfg(feature = "with-mysql")]
+        "mysql" | "mariadb" => {
+            use sqlx::mysql::MySqlPoolOptions;
+            use tokio_cqrs_es2_store::mysql_store::ArchiveMarkerStore;
+
+            let url = url.replacen("mariadb:", "mysql:", 1);
+
+            let pool = MySqlPoolOptions::new()
+                .max_connections(1)
+                .connect(url.as_str())
+                .await
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(ArchiveMarkerStore::new(pool)))
+        },
+        #[cfg(feature = "with-sqlite")]
+        "sqlite" => {
+            use sqlx::sqlite::SqlitePoolOptions;
+            use tokio_cqrs_es2_store::sqlite_store::ArchiveMarkerStore;
+
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect(url)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(ArchiveMarkerStore::new(pool)))
+        },
+        #[cfg(feature = "with-mongodb")]
+        "mongodb" | "mongodb+srv" => {
+            use mongodb::Client;
+            use tokio_cqrs_es2_store::mongodb_store::ArchiveMarkerStore;
+
+            let client = Client::with_uri_str(url)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let db = client
+                .default_database()
+                .ok_or("the database name is missing from the URL")?;
+
+            Ok(Box::new(ArchiveMarkerStore::new(db)))
+        },
+        #[cfg(feature = "with-redis")]
+        "redis" | "rediss" => {
+            use redis::Client;
+            use tokio_cqrs_es2_store::redis_store::ArchiveMarkerStore;
+
+            let conn = Client::open(url)
+                .and_then(|x| x.get_connection())
+                .map_err(|e| e.to_string())?;
+
+            Ok(Box::new(ArchiveMarkerStore::new(conn)))
+        },
+        _ => {
+            Err(format!(
+                "unsupported store URL scheme '{}'",
+                scheme
+            ))
+        },
+    }
+}
@@ -0,0 +1,217 @@
+#![forbid(unsafe_code)]
+use serde_json::Value;
+use structopt::StructOpt;
+
+use tokio_cqrs_es2_store::{
+    admin::check_records,
+    IAdminStore,
+};
+
+// arguments
+use arguments::{
+    Arguments,
+    Command,
+};
+
+use connection::{
+    connect,
+    connect_markers,
+};
+
+mod arguments;
+mod connection;
+
+fn print_record(
+    record: &Value,
+    pretty: bool,
+) {
+    if pretty {
+        println!("{:#}", record);
+    }
+    else {
+        println!("{}", record);
+    }
+}
+
+async fn run(
+    store: &mut dyn IAdminStore,
+    url: &str,
+    command: Command,
+    pretty: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Types => {
+            for x in store.list_aggregate_types().await? {
+                println!("{}", x);
+            }
+        },
+        Command::List {
+            aggregate_type,
+            after,
+            limit,
+        } => {
+            for x in store
+                .list_aggregate_ids(
+                    &aggregate_type,
+                    after.as_deref(),
+                    limit,
+                )
+                .await?
+            {
+                println!("{}", x);
+            }
+        },
+        Command::Dump {
+            aggregate_type,
+            aggregate_id,
+        } => {
+            for x in store
+                .load_events(&aggregate_type, &aggregate_id)
+                .await?
+            {
+                print_record(&x, pretty);
+            }
+
+            if let Some(x) = store
+                .load_snapshot(&aggregate_type, &aggregate_id)
+                .await?
+            {
+                print_record(&x, pretty);
+            }
+        },
+        Command::Queries {
+            aggregate_type,
+            aggregate_id,
+        } => {
+            for x in store
+                .load_queries(&aggregate_type, &aggregate_id)
+                .await?
+            {
+                print_record(&x, pretty);
+            }
+        },
+        Command::DeleteSnapshot {
+            aggregate_type,
+            aggregate_id,
+        } => {
+            match store
+                .delete_snapshot(&aggregate_type, &aggregate_id)
+                .await?
+            {
+                true => println!("snapshot deleted"),
+                false => println!("no snapshot found"),
+            }
+        },
+        Command::Fsck {
+            aggregate_type,
+            archived,
+        } => {
+            let aggregate_types = match aggregate_type {
+                Some(x) => vec![x],
+                None => store.list_aggregate_types().await?,
+            };
+
+            let markers = match archived {
+                true => Some(connect_markers(url).await?),
+                false => None,
+            };
+
+            let mut count = 0;
+
+            for x in aggregate_types {
+                for issue in
+                    check_records(store, markers.as_deref(), &x)
+                        .await?
+                {
+                    println!("{}: {}", x, issue);
+                    count += 1;
+                }
+            }
+
+            if count > 0 {
+                return Err(format!("{} issues found", count).into());
+            }
+
+            println!("no issues found");
+        },
+        Command::PurgeQueries {
+            aggregate_type,
+            query_type,
+            rebuild,
+        } => {
+            // the tool does not know the projections of the
+            // application, dropping the read models without a way to
+            // replay them would lose them
+            let rebuild = match rebuild {
+                Some(x) if x.is_file() => x,
+                Some(x) => {
+                    return Err(format!(
+                        "rebuild program '{}' not found",
+                        x.display()
+                    )
+                    .into());
+                },
+                None => {
+                    return Err(format!(
+                        "refusing to drop the '{}' read models \
+                         without a --rebuild program to replay them",
+                        query_type
+                    )
+                    .into());
+                },
+            };
+
+            let count = store
+                .delete_queries(&aggregate_type, &query_type)
+                .await?;
+
+            println!(
+                "{} '{}' read models dropped, replaying them with \
+                 '{}'",
+                count,
+                query_type,
+                rebuild.display()
+            );
+
+            let mut child = std::process::Command::new(&rebuild);
+            child
+                .arg(&aggregate_type)
+                .arg(&query_type)
+                .env("CQRS_STORE_URL", url);
+
+            let status =
+                tokio::task::spawn_blocking(move || child.status())
+                    .await??;
+
+            if !status.success() {
+                return Err(format!(
+                    "rebuild program '{}' failed with {}, the '{}' \
+                     read models are dropped until it succeeds",
+                    rebuild.display(),
+                    status,
+                    query_type
+                )
+                .into());
+            }
+
+            println!("'{}' read models rebuilt", query_type);
+        },
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Arguments::from_args();
+
+    let mut store = connect(&args.url).await?;
+
+    run(
+        store.as_mut(),
+        &args.url,
+        args.command,
+        args.pretty,
+    )
+    .await
+}
@@ -0,0 +1,18 @@
+-- adds the `event_type` column to an `events` table created before it
+-- was introduced and fills it from the event payloads, works for both
+-- MySQL and MariaDB
+ALTER TABLE events ADD COLUMN event_type VARCHAR(256) NOT NULL DEFAULT '' AFTER sequence;
+
+UPDATE events
+SET event_type = CASE
+    WHEN JSON_TYPE(payload) = 'OBJECT' AND JSON_LENGTH(payload) = 1
+    THEN JSON_UNQUOTE(JSON_EXTRACT(JSON_KEYS(payload), '$[0]'))
+    WHEN JSON_TYPE(payload) = 'STRING'
+    THEN JSON_UNQUOTE(payload)
+    ELSE ''
+END;
+
+ALTER TABLE events ALTER COLUMN event_type DROP DEFAULT;
+
+CREATE INDEX events_type_timestamp ON events (aggregate_type, event_type, timestamp);
+CREATE INDEX events_timestamp ON events (aggregate_type, timestamp);
@@ -0,0 +1,4 @@
+-- turns the `payload` column of a `queries` table created before the
+-- payload filters into a native JSON column, fails on a payload that
+-- is not valid JSON, works for both MySQL and MariaDB
+ALTER TABLE queries MODIFY payload JSON NOT NULL;
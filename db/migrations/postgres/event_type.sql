@@ -0,0 +1,19 @@
+-- adds the `event_type` column to an `events` table created before it
+-- was introduced and fills it from the event payloads
+ALTER TABLE events ADD COLUMN event_type text NOT NULL DEFAULT '';
+
+UPDATE events
+SET event_type = CASE jsonb_typeof(payload)
+    WHEN 'object' THEN COALESCE(
+        (SELECT min(k) FROM jsonb_object_keys(payload) AS k
+         HAVING count(*) = 1),
+        '')
+    WHEN 'string' THEN payload #>> '{}'
+    ELSE ''
+END;
+
+ALTER TABLE events ALTER COLUMN event_type DROP DEFAULT;
+
+CREATE INDEX events_type_timestamp ON events (aggregate_type, event_type, timestamp);
+CREATE INDEX events_timestamp ON events (aggregate_type, timestamp);
+CREATE INDEX events_metadata ON events USING GIN (metadata jsonb_path_ops);
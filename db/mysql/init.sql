@@ -7,12 +7,17 @@ CREATE TABLE events
     aggregate_type VARCHAR(256)                 NOT NULL,
     aggregate_id   VARCHAR(256)                 NOT NULL,
     sequence       bigint CHECK (sequence >= 0) NOT NULL,
+    event_type     VARCHAR(256)                 NOT NULL,
     payload        TEXT                         NOT NULL,
     metadata       TEXT                         NOT NULL,
     timestamp      timestamp DEFAULT (CURRENT_TIMESTAMP),
     PRIMARY KEY (aggregate_type, aggregate_id, sequence)
 );
 
+-- supporting indexes for filtering events by type and time
+CREATE INDEX events_type_timestamp ON events (aggregate_type, event_type, timestamp);
+CREATE INDEX events_timestamp ON events (aggregate_type, timestamp);
+
 -- this table is only needed if snapshotting is employed
 CREATE TABLE snapshots
 (
@@ -31,10 +36,61 @@ CREATE TABLE queries
     aggregate_id   VARCHAR(256)                NOT NULL,
     query_type     VARCHAR(256)                NOT NULL,
     version        bigint CHECK (version >= 0) NOT NULL,
-    payload        TEXT                        NOT NULL,
+    payload        JSON                        NOT NULL,
     PRIMARY KEY (aggregate_type, aggregate_id, query_type)
 );
 
+-- this table is only needed if crypto-shredding is employed
+CREATE TABLE encryption_keys
+(
+    aggregate_type VARCHAR(256) NOT NULL,
+    aggregate_id   VARCHAR(256) NOT NULL,
+    encryption_key VARBINARY(64) NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id)
+);
+
+-- this table is only needed if archival is employed
+CREATE TABLE archived_aggregates
+(
+    aggregate_type VARCHAR(256)                      NOT NULL,
+    aggregate_id   VARCHAR(256)                      NOT NULL,
+    last_sequence  bigint CHECK (last_sequence >= 0) NOT NULL,
+    location       TEXT                              NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id)
+);
+
+-- this table is only needed if dead letters are recorded
+CREATE TABLE dead_letters
+(
+    aggregate_type VARCHAR(256)                 NOT NULL,
+    dispatcher     VARCHAR(256)                 NOT NULL,
+    aggregate_id   VARCHAR(256)                 NOT NULL,
+    sequence       bigint CHECK (sequence >= 0) NOT NULL,
+    events         TEXT                         NOT NULL,
+    error          TEXT                         NOT NULL,
+    attempts       bigint                       NOT NULL,
+    PRIMARY KEY (aggregate_type, dispatcher, aggregate_id, sequence)
+);
+
+-- this table is only needed if projections are employed
+CREATE TABLE projections
+(
+    projection_type VARCHAR(256)                NOT NULL,
+    projection_key  VARCHAR(256)                NOT NULL,
+    version         bigint CHECK (version >= 0) NOT NULL,
+    payload         JSON                        NOT NULL,
+    PRIMARY KEY (projection_type, projection_key)
+);
+
+-- this table is only needed if projections are employed
+CREATE TABLE projection_sequences
+(
+    projection_type VARCHAR(256)                 NOT NULL,
+    aggregate_id    VARCHAR(256)                 NOT NULL,
+    sequence        bigint CHECK (sequence >= 0) NOT NULL,
+    PRIMARY KEY (projection_type, aggregate_id)
+);
+
 CREATE
     USER
     'test_user'@'%'
@@ -7,12 +7,18 @@ CREATE TABLE events
     aggregate_type text                         NOT NULL,
     aggregate_id   text                         NOT NULL,
     sequence       bigint CHECK (sequence >= 0) NOT NULL,
+    event_type     text                         NOT NULL,
     payload        jsonb                        NOT NULL,
     metadata       jsonb                        NOT NULL,
     timestamp      timestamp with time zone DEFAULT (CURRENT_TIMESTAMP),
     PRIMARY KEY (aggregate_type, aggregate_id, sequence)
 );
 
+-- supporting indexes for filtering events by type, time and metadata
+CREATE INDEX events_type_timestamp ON events (aggregate_type, event_type, timestamp);
+CREATE INDEX events_timestamp ON events (aggregate_type, timestamp);
+CREATE INDEX events_metadata ON events USING GIN (metadata jsonb_path_ops);
+
 -- this table is only needed if snapshotting is employed
 CREATE TABLE snapshots
 (
@@ -35,6 +41,57 @@ CREATE TABLE queries
     PRIMARY KEY (aggregate_type, aggregate_id, query_type)
 );
 
+-- this table is only needed if crypto-shredding is employed
+CREATE TABLE encryption_keys
+(
+    aggregate_type text  NOT NULL,
+    aggregate_id   text  NOT NULL,
+    encryption_key bytea NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id)
+);
+
+-- this table is only needed if archival is employed
+CREATE TABLE archived_aggregates
+(
+    aggregate_type text                              NOT NULL,
+    aggregate_id   text                              NOT NULL,
+    last_sequence  bigint CHECK (last_sequence >= 0) NOT NULL,
+    location       text                              NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id)
+);
+
+-- this table is only needed if dead letters are recorded
+CREATE TABLE dead_letters
+(
+    aggregate_type text                         NOT NULL,
+    dispatcher     text                         NOT NULL,
+    aggregate_id   text                         NOT NULL,
+    sequence       bigint CHECK (sequence >= 0) NOT NULL,
+    events         text                         NOT NULL,
+    error          text                         NOT NULL,
+    attempts       bigint                       NOT NULL,
+    PRIMARY KEY (aggregate_type, dispatcher, aggregate_id, sequence)
+);
+
+-- this table is only needed if projections are employed
+CREATE TABLE projections
+(
+    projection_type text                        NOT NULL,
+    projection_key  text                        NOT NULL,
+    version         bigint CHECK (version >= 0) NOT NULL,
+    payload         jsonb                       NOT NULL,
+    PRIMARY KEY (projection_type, projection_key)
+);
+
+-- this table is only needed if projections are employed
+CREATE TABLE projection_sequences
+(
+    projection_type text                         NOT NULL,
+    aggregate_id    text                         NOT NULL,
+    sequence        bigint CHECK (sequence >= 0) NOT NULL,
+    PRIMARY KEY (projection_type, aggregate_id)
+);
+
 CREATE
     USER
     test_user
@@ -48,6 +105,11 @@ GRANT
 ON TABLE
     events,
     snapshots,
-    queries
+    queries,
+    encryption_keys,
+    archived_aggregates,
+    dead_letters,
+    projections,
+    projection_sequences
 TO
     test_user;
@@ -1,4 +1,5 @@
 pub use service::BankAccountService;
+pub use stores::get_health_checks;
 
 mod aggregate;
 mod commands;
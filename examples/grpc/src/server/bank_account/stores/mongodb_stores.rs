@@ -5,6 +5,7 @@ use tokio_cqrs_es2_store::{
         EventStore,
         QueryStore,
     },
+    IHealthCheck,
     Repository,
 };
 
@@ -51,3 +52,12 @@ pub async fn get_query_store() -> Result<ThisQueryStore, Error> {
         db_connection().await.unwrap(),
     ))
 }
+
+pub async fn get_health_checks() -> Vec<Box<dyn IHealthCheck>> {
+    let conn = db_connection().await.unwrap();
+
+    vec![
+        Box::new(ThisEventStore::new(conn.clone())),
+        Box::new(ThisQueryStore::new(conn)),
+    ]
+}
@@ -1,16 +1,27 @@
 #![forbid(unsafe_code)]
-use log::info;
+use log::{
+    info,
+    warn,
+};
+use std::time::Duration;
 
 // arguments
 use arguments::Arguments;
 use structopt::StructOpt;
 
 // service
-use bank_account::BankAccountService;
+use bank_account::{
+    get_health_checks,
+    BankAccountService,
+};
 
 // server
 use bank_account_api::bank_account_server::BankAccountServer;
 use tonic::transport::Server;
+use tonic_health::server::HealthReporter;
+
+// stores
+use tokio_cqrs_es2_store::IHealthCheck;
 
 mod arguments;
 mod bank_account;
@@ -18,6 +29,8 @@ mod bank_account;
 mod bank_account_api;
 mod cqrs;
 
+type AccountServer = BankAccountServer<BankAccountService>;
+
 fn setup_logger() -> Result<(), fern::InitError> {
     fern::Dispatch::new()
         .format(|out, message, record| {
@@ -36,6 +49,40 @@ fn setup_logger() -> Result<(), fern::InitError> {
     Ok(())
 }
 
+/// Reports the service as serving to the gRPC health service while
+/// all its stores are healthy, probing the stores created once at
+/// startup
+async fn report_health(
+    mut reporter: HealthReporter,
+    mut stores: Vec<Box<dyn IHealthCheck>>,
+) {
+    loop {
+        let mut healthy = true;
+
+        for x in stores.iter_mut() {
+            let status = x.health_check().await;
+
+            if !status.is_healthy() {
+                warn!("unhealthy store: {:?}", status);
+                healthy = false;
+            }
+        }
+
+        if healthy {
+            reporter
+                .set_serving::<AccountServer>()
+                .await;
+        }
+        else {
+            reporter
+                .set_not_serving::<AccountServer>()
+                .await;
+        }
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     setup_logger().unwrap();
@@ -49,7 +96,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let account_service = BankAccountService::default();
     info!("Server listening on {}", addr);
 
+    let (health_reporter, health_service) =
+        tonic_health::server::health_reporter();
+
+    tokio::spawn(report_health(
+        health_reporter,
+        get_health_checks().await,
+    ));
+
     Server::builder()
+        .add_service(health_service)
         .add_service(BankAccountServer::new(account_service))
         .serve(addr)
         .await?;
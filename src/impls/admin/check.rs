@@ -0,0 +1,357 @@
+use log::debug;
+use serde_json::Value;
+
+use cqrs_es2::{
+    Error,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+};
+
+use crate::repository::{
+    IAdminStore,
+    IArchiveMarkerStore,
+    IEventStore,
+    IQueryStore,
+};
+
+use super::{
+    issue::Issue,
+    PAGE_SIZE,
+};
+
+/// Checks that `sequences` follow on from `start + 1`, returns the
+/// sequence of the last event, `start` when there is none
+fn check_sequences(
+    aggregate_id: &str,
+    start: i64,
+    sequences: &[i64],
+    issues: &mut Vec<Issue>,
+) -> i64 {
+    let mut last = start;
+
+    for &x in sequences {
+        if x == last && x > 0 {
+            issues.push(Issue::DuplicateSequence {
+                aggregate_id: aggregate_id.to_string(),
+                sequence: x,
+            });
+        }
+        else if x != last + 1 {
+            issues.push(Issue::SequenceGap {
+                aggregate_id: aggregate_id.to_string(),
+                expected: last + 1,
+                found: x,
+            });
+        }
+
+        last = last.max(x);
+    }
+
+    last
+}
+
+fn check_snapshot_version(
+    aggregate_id: &str,
+    version: i64,
+    last_sequence: i64,
+    issues: &mut Vec<Issue>,
+) -> bool {
+    if version > last_sequence {
+        issues.push(Issue::SnapshotAhead {
+            aggregate_id: aggregate_id.to_string(),
+            version,
+            last_sequence,
+        });
+    }
+    else if version < last_sequence {
+        issues.push(Issue::SnapshotBehind {
+            aggregate_id: aggregate_id.to_string(),
+            version,
+            last_sequence,
+        });
+    }
+    else {
+        return true;
+    }
+
+    false
+}
+
+/// Check the event streams and snapshots of all the `A` aggregates of
+/// the `store`: sequences contiguous from 1, payloads deserializing
+/// into `E` and `A`, and snapshots matching the replay of the events
+///
+/// The archived aggregates are to be checked through an
+/// `ArchivedEventStore`.
+pub async fn check_events<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    ES: IEventStore<C, E, A>,
+>(
+    store: &mut ES
+) -> Result<Vec<Issue>, Error> {
+    debug!(
+        "checking '{}' events",
+        A::aggregate_type()
+    );
+
+    let mut issues = Vec::new();
+    let mut after: Option<String> = None;
+
+    loop {
+        let ids = store
+            .list_aggregate_ids(after.as_deref(), PAGE_SIZE)
+            .await?;
+
+        for id in &ids {
+            let contexts = match store.load_events(id).await {
+                Ok(x) => x,
+                Err(e) => {
+                    issues.push(Issue::BadPayload {
+                        aggregate_id: id.clone(),
+                        error: e.to_string(),
+                    });
+                    continue;
+                },
+            };
+
+            let sequences: Vec<i64> = contexts
+                .iter()
+                .map(|x| x.sequence)
+                .collect();
+
+            let last_sequence =
+                check_sequences(id, 0, &sequences, &mut issues);
+
+            let snapshot = match store
+                .load_aggregate_from_snapshot(id)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    issues.push(Issue::BadPayload {
+                        aggregate_id: id.clone(),
+                        error: e.to_string(),
+                    });
+                    continue;
+                },
+            };
+
+            if snapshot.version == 0 ||
+                !check_snapshot_version(
+                    id,
+                    snapshot.version,
+                    last_sequence,
+                    &mut issues,
+                )
+            {
+                continue;
+            }
+
+            let mut aggregate = A::default();
+
+            contexts
+                .iter()
+                .for_each(|x| aggregate.apply(&x.payload));
+
+            if aggregate != snapshot.payload {
+                issues.push(Issue::SnapshotMismatch {
+                    aggregate_id: id.clone(),
+                });
+            }
+        }
+
+        if (ids.len() as i64) < PAGE_SIZE {
+            break;
+        }
+
+        after = ids.last().cloned();
+    }
+
+    Ok(issues)
+}
+
+/// Check the `Q` queries of all the `A` aggregates of the `events`
+/// store against the replay of their events
+pub async fn check_queries<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    ES: IEventStore<C, E, A>,
+    QS: IQueryStore<C, E, A, Q>,
+>(
+    events: &mut ES,
+    queries: &mut QS,
+) -> Result<Vec<Issue>, Error> {
+    let query_type = Q::query_type();
+
+    debug!("checking '{}' queries", query_type);
+
+    let mut issues = Vec::new();
+    let mut after: Option<String> = None;
+
+    loop {
+        let ids = events
+            .list_aggregate_ids(after.as_deref(), PAGE_SIZE)
+            .await?;
+
+        for id in &ids {
+            let contexts = match events.load_events(id).await {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+
+            let query = match queries.load_query(id).await {
+                Ok(x) => x,
+                Err(e) => {
+                    issues.push(Issue::BadPayload {
+                        aggregate_id: id.clone(),
+                        error: e.to_string(),
+                    });
+                    continue;
+                },
+            };
+
+            let last_sequence = contexts
+                .last()
+                .map_or(0, |x| x.sequence);
+
+            if query.version > last_sequence {
+                issues.push(Issue::QueryAhead {
+                    aggregate_id: id.clone(),
+                    query_type: query_type.to_string(),
+                    version: query.version,
+                    last_sequence,
+                });
+                continue;
+            }
+
+            let mut replayed = Q::default();
+
+            contexts
+                .iter()
+                .for_each(|x| replayed.update(x));
+
+            // the version only tells the last event applied, events
+            // routed elsewhere or not changing the query leave it
+            // behind the stream
+            if replayed != query.payload {
+                issues.push(Issue::QueryBehind {
+                    aggregate_id: id.clone(),
+                    query_type: query_type.to_string(),
+                });
+            }
+        }
+
+        if (ids.len() as i64) < PAGE_SIZE {
+            break;
+        }
+
+        after = ids.last().cloned();
+    }
+
+    Ok(issues)
+}
+
+/// Check the stored records of all the aggregates of
+/// `aggregate_type` without deserializing their payloads: sequences
+/// contiguous from 1 and snapshot and query versions not ahead or
+/// behind the events
+///
+/// The stored events of the aggregates archived by the `Archiver`
+/// follow on from the sequence of their marker in `markers`, without
+/// them the truncated event streams are reported as sequence gaps.
+pub async fn check_records<AS: IAdminStore + ?Sized>(
+    store: &mut AS,
+    markers: Option<&dyn IArchiveMarkerStore>,
+    aggregate_type: &str,
+) -> Result<Vec<Issue>, Error> {
+    debug!("checking '{}' records", aggregate_type);
+
+    let mut issues = Vec::new();
+
+    let mut after: Option<String> = None;
+
+    loop {
+        let ids = store
+            .list_aggregate_ids(
+                aggregate_type,
+                after.as_deref(),
+                PAGE_SIZE,
+            )
+            .await?;
+
+        for id in &ids {
+            let sequences: Vec<i64> = store
+                .load_events(aggregate_type, id)
+                .await?
+                .iter()
+                .map(|x| {
+                    x["sequence"]
+                        .as_i64()
+                        .unwrap_or_default()
+                })
+                .collect();
+
+            let start = match markers {
+                Some(markers) => {
+                    markers
+                        .load_marker(aggregate_type, id)
+                        .await?
+                        .map_or(0, |x| x.last_sequence)
+                },
+                None => 0,
+            };
+
+            let last_sequence =
+                check_sequences(id, start, &sequences, &mut issues);
+
+            if let Some(x) = store
+                .load_snapshot(aggregate_type, id)
+                .await?
+            {
+                check_snapshot_version(
+                    id,
+                    version_of(&x),
+                    last_sequence,
+                    &mut issues,
+                );
+            }
+
+            for x in store
+                .load_queries(aggregate_type, id)
+                .await?
+            {
+                if version_of(&x) > last_sequence {
+                    issues.push(Issue::QueryAhead {
+                        aggregate_id: id.clone(),
+                        query_type: x["query_type"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        version: version_of(&x),
+                        last_sequence,
+                    });
+                }
+            }
+        }
+
+        if (ids.len() as i64) < PAGE_SIZE {
+            break;
+        }
+
+        after = ids.last().cloned();
+    }
+
+    Ok(issues)
+}
+
+fn version_of(record: &Value) -> i64 {
+    record["version"]
+        .as_i64()
+        .unwrap_or_default()
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+/// An inconsistency found by the store checks
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// The event sequences do not follow on from 1
+    SequenceGap {
+        /// The aggregate id
+        aggregate_id: String,
+        /// The sequence expected after the previous event
+        expected: i64,
+        /// The sequence found instead
+        found: i64,
+    },
+    /// Several events share the same sequence
+    DuplicateSequence {
+        /// The aggregate id
+        aggregate_id: String,
+        /// The duplicated sequence
+        sequence: i64,
+    },
+    /// A stored record does not deserialize
+    BadPayload {
+        /// The aggregate id
+        aggregate_id: String,
+        /// The deserialization error
+        error: String,
+    },
+    /// The snapshot version is ahead of the last event sequence
+    SnapshotAhead {
+        /// The aggregate id
+        aggregate_id: String,
+        /// The snapshot version
+        version: i64,
+        /// The sequence of the last event
+        last_sequence: i64,
+    },
+    /// The snapshot version is behind the last event sequence
+    SnapshotBehind {
+        /// The aggregate id
+        aggregate_id: String,
+        /// The snapshot version
+        version: i64,
+        /// The sequence of the last event
+        last_sequence: i64,
+    },
+    /// The snapshot differs from the replay of the events
+    SnapshotMismatch {
+        /// The aggregate id
+        aggregate_id: String,
+    },
+    /// The query version is ahead of the last event sequence, each
+    /// dispatch applying at least one event
+    QueryAhead {
+        /// The aggregate id
+        aggregate_id: String,
+        /// The query type
+        query_type: String,
+        /// The query version
+        version: i64,
+        /// The sequence of the last event
+        last_sequence: i64,
+    },
+    /// The query is missing or differs from the replay of the events
+    QueryBehind {
+        /// The aggregate id
+        aggregate_id: String,
+        /// The query type
+        query_type: String,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Issue::SequenceGap {
+                aggregate_id,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "aggregate id '{}': sequence {} expected, {} \
+                     found",
+                    aggregate_id, expected, found
+                )
+            },
+            Issue::DuplicateSequence {
+                aggregate_id,
+                sequence,
+            } => {
+                write!(
+                    f,
+                    "aggregate id '{}': duplicate sequence {}",
+                    aggregate_id, sequence
+                )
+            },
+            Issue::BadPayload {
+                aggregate_id,
+                error,
+            } => {
+                write!(
+                    f,
+                    "aggregate id '{}': bad payload: {}",
+                    aggregate_id, error
+                )
+            },
+            Issue::SnapshotAhead {
+                aggregate_id,
+                version,
+                last_sequence,
+            } => {
+                write!(
+                    f,
+                    "aggregate id '{}': snapshot version {} ahead \
+                     of last sequence {}",
+                    aggregate_id, version, last_sequence
+                )
+            },
+            Issue::SnapshotBehind {
+                aggregate_id,
+                version,
+                last_sequence,
+            } => {
+                write!(
+                    f,
+                    "aggregate id '{}': snapshot version {} behind \
+                     last sequence {}",
+                    aggregate_id, version, last_sequence
+                )
+            },
+            Issue::SnapshotMismatch { aggregate_id } => {
+                write!(
+                    f,
+                    "aggregate id '{}': snapshot differs from the \
+                     events",
+                    aggregate_id
+                )
+            },
+            Issue::QueryAhead {
+                aggregate_id,
+                query_type,
+                version,
+                last_sequence,
+            } => {
+                write!(
+                    f,
+                    "aggregate id '{}': query '{}' version {} ahead \
+                     of last sequence {}",
+                    aggregate_id, query_type, version, last_sequence
+                )
+            },
+            Issue::QueryBehind {
+                aggregate_id,
+                query_type,
+            } => {
+                write!(
+                    f,
+                    "aggregate id '{}': query '{}' behind the events",
+                    aggregate_id, query_type
+                )
+            },
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//!
+//! Administration helpers working on the stores of an application,
+//! complementing the `cqrs-store` command line tool
+//!
+//! The command line tool only sees the stored JSON records, it drops
+//! the read models of a query type and runs a program of the
+//! application replaying them from the events with
+//! `rebuild_queries`. Likewise its
+//! `fsck` checks the records while `check_events` and
+//! `check_queries` also verify that the payloads deserialize and
+//! match the replay of the events.
+
+pub use check::{
+    check_events,
+    check_queries,
+    check_records,
+};
+pub use issue::Issue;
+pub use rebuild::{
+    rebuild_queries,
+    rebuild_query,
+};
+
+mod check;
+mod issue;
+mod rebuild;
+
+mod test;
+
+static PAGE_SIZE: i64 = 1000;
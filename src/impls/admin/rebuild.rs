@@ -0,0 +1,87 @@
+use log::debug;
+
+use cqrs_es2::{
+    Error,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+};
+
+use crate::repository::{
+    IEventStore,
+    IQueryStore,
+};
+
+use super::PAGE_SIZE;
+
+/// Rebuild the `Q` query of `aggregate_id` by replaying all its
+/// events from the `events` store
+pub async fn rebuild_query<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    ES: IEventStore<C, E, A>,
+    QS: IQueryStore<C, E, A, Q>,
+>(
+    events: &mut ES,
+    queries: &mut QS,
+    aggregate_id: &str,
+) -> Result<(), Error> {
+    debug!(
+        "rebuilding query '{}' for aggregate id '{}'",
+        Q::query_type(),
+        aggregate_id
+    );
+
+    let contexts = events.load_events(aggregate_id).await?;
+
+    queries
+        .delete_query(aggregate_id)
+        .await?;
+
+    if contexts.is_empty() {
+        return Ok(());
+    }
+
+    queries
+        .dispatch_events(aggregate_id, &contexts)
+        .await
+}
+
+/// Rebuild the `Q` queries of all the `A` aggregates of the `events`
+/// store, returns the number of rebuilt queries
+pub async fn rebuild_queries<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    ES: IEventStore<C, E, A>,
+    QS: IQueryStore<C, E, A, Q>,
+>(
+    events: &mut ES,
+    queries: &mut QS,
+) -> Result<usize, Error> {
+    let mut count = 0;
+    let mut after: Option<String> = None;
+
+    loop {
+        let ids = events
+            .list_aggregate_ids(after.as_deref(), PAGE_SIZE)
+            .await?;
+
+        for id in &ids {
+            rebuild_query(events, queries, id).await?;
+            count += 1;
+        }
+
+        if (ids.len() as i64) < PAGE_SIZE {
+            break;
+        }
+
+        after = ids.last().cloned();
+    }
+
+    Ok(count)
+}
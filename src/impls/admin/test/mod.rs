@@ -0,0 +1,5 @@
+#[cfg(test)]
+mod test_check;
+
+#[cfg(test)]
+mod test_rebuild;
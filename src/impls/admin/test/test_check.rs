@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+
+use cqrs_es2::{
+    example_impl::*,
+    AggregateContext,
+    Error,
+    EventContext,
+    QueryContext,
+};
+
+use crate::{
+    admin::{
+        check_events,
+        check_queries,
+        Issue,
+    },
+    memory_store::{
+        EventStore,
+        QueryStore,
+    },
+    IEventStore,
+    IQueryStore,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+fn get_event(
+    id: &str,
+    sequence: i64,
+) -> EventContext<CustomerCommand, CustomerEvent> {
+    EventContext::new(
+        id.to_string(),
+        sequence,
+        CustomerEvent::EmailUpdated(EmailUpdated {
+            new_email: format!("test{}@email.com", sequence),
+        }),
+        HashMap::new(),
+    )
+}
+
+fn get_customer(sequence: i64) -> Customer {
+    Customer {
+        customer_id: "".to_string(),
+        name: "".to_string(),
+        email: format!("test{}@email.com", sequence),
+        addresses: Vec::new(),
+    }
+}
+
+fn get_query(
+    id: &str,
+    version: i64,
+    sequence: i64,
+) -> QueryContext<CustomerCommand, CustomerEvent, CustomerContactQuery>
+{
+    QueryContext::new(
+        id.to_string(),
+        version,
+        CustomerContactQuery {
+            name: "".to_string(),
+            email: format!("test{}@email.com", sequence),
+            latest_address: "".to_string(),
+        },
+    )
+}
+
+async fn populate(
+    events: &mut ThisEventStore,
+    queries: &mut ThisQueryStore,
+) -> Result<(), Error> {
+    // consistent
+    events
+        .save_events(&vec![
+            get_event("id_A", 1),
+            get_event("id_A", 2),
+        ])
+        .await?;
+    events
+        .save_aggregate_snapshot(AggregateContext::new(
+            "id_A".to_string(),
+            2,
+            get_customer(2),
+        ))
+        .await?;
+    queries
+        .save_query(get_query("id_A", 1, 2), 0)
+        .await?;
+
+    // sequence gap, snapshot and query ahead
+    events
+        .save_events(&vec![
+            get_event("id_B", 1),
+            get_event("id_B", 3),
+        ])
+        .await?;
+    events
+        .save_aggregate_snapshot(AggregateContext::new(
+            "id_B".to_string(),
+            4,
+            get_customer(3),
+        ))
+        .await?;
+    queries
+        .save_query(get_query("id_B", 4, 3), 0)
+        .await?;
+
+    // duplicate sequence, snapshot behind and query missing
+    events
+        .save_events(&vec![
+            get_event("id_C", 1),
+            get_event("id_C", 1),
+        ])
+        .await?;
+    events
+        .save_aggregate_snapshot(AggregateContext::new(
+            "id_C".to_string(),
+            1,
+            get_customer(1),
+        ))
+        .await?;
+    events
+        .save_events(&vec![get_event("id_C", 2)])
+        .await?;
+
+    // snapshot and query differing from the events
+    events
+        .save_events(&vec![get_event("id_D", 1)])
+        .await?;
+    events
+        .save_aggregate_snapshot(AggregateContext::new(
+            "id_D".to_string(),
+            1,
+            get_customer(5),
+        ))
+        .await?;
+    queries
+        .save_query(get_query("id_D", 1, 5), 0)
+        .await?;
+
+    // query matching the events with its version not recorded
+    events
+        .save_events(&vec![get_event("id_E", 1)])
+        .await?;
+    queries
+        .save_query(get_query("id_E", 0, 1), 0)
+        .await?;
+
+    Ok(())
+}
+
+async fn check_check_events() -> Result<(), Error> {
+    let mut events = ThisEventStore::default();
+    let mut queries = ThisQueryStore::default();
+
+    populate(&mut events, &mut queries).await?;
+
+    assert_eq!(
+        check_events(&mut events).await?,
+        vec![
+            Issue::SequenceGap {
+                aggregate_id: "id_B".to_string(),
+                expected: 2,
+                found: 3,
+            },
+            Issue::SnapshotAhead {
+                aggregate_id: "id_B".to_string(),
+                version: 4,
+                last_sequence: 3,
+            },
+            Issue::DuplicateSequence {
+                aggregate_id: "id_C".to_string(),
+                sequence: 1,
+            },
+            Issue::SnapshotBehind {
+                aggregate_id: "id_C".to_string(),
+                version: 1,
+                last_sequence: 2,
+            },
+            Issue::SnapshotMismatch {
+                aggregate_id: "id_D".to_string(),
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_check_events() {
+    tokio_test::block_on(check_check_events()).unwrap();
+}
+
+async fn check_check_queries() -> Result<(), Error> {
+    let mut events = ThisEventStore::default();
+    let mut queries = ThisQueryStore::default();
+
+    populate(&mut events, &mut queries).await?;
+
+    assert_eq!(
+        check_queries(&mut events, &mut queries).await?,
+        vec![
+            Issue::QueryAhead {
+                aggregate_id: "id_B".to_string(),
+                query_type: "customer_contact_query".to_string(),
+                version: 4,
+                last_sequence: 3,
+            },
+            Issue::QueryBehind {
+                aggregate_id: "id_C".to_string(),
+                query_type: "customer_contact_query".to_string(),
+            },
+            Issue::QueryBehind {
+                aggregate_id: "id_D".to_string(),
+                query_type: "customer_contact_query".to_string(),
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_check_queries() {
+    tokio_test::block_on(check_check_queries()).unwrap();
+}
+
+#[cfg(feature = "with-sqlite")]
+async fn check_check_records() -> Result<(), Error> {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::{
+        admin::check_records,
+        sqlite_store,
+        ArchiveMarker,
+        IArchiveMarkerStore,
+    };
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let mut events = sqlite_store::EventStore::<
+        CustomerCommand,
+        CustomerEvent,
+        Customer,
+    >::new(pool.clone());
+    let mut queries = sqlite_store::QueryStore::<
+        CustomerCommand,
+        CustomerEvent,
+        Customer,
+        CustomerContactQuery,
+    >::new(pool.clone());
+
+    events
+        .save_events(&vec![
+            get_event("id_A", 1),
+            get_event("id_A", 2),
+        ])
+        .await?;
+    events
+        .save_events(&vec![
+            get_event("id_B", 1),
+            get_event("id_B", 3),
+        ])
+        .await?;
+    events
+        .save_aggregate_snapshot(AggregateContext::new(
+            "id_B".to_string(),
+            1,
+            get_customer(1),
+        ))
+        .await?;
+    queries
+        .save_query(get_query("id_B", 1, 1), 0)
+        .await?;
+
+    // archived up to the snapshot, the stored events start after it
+    events
+        .save_events(&vec![
+            get_event("id_C", 1),
+            get_event("id_C", 2),
+            get_event("id_C", 3),
+        ])
+        .await?;
+    for x in &[1, 3] {
+        events
+            .save_aggregate_snapshot(AggregateContext::new(
+                "id_C".to_string(),
+                *x,
+                get_customer(*x),
+            ))
+            .await?;
+    }
+    events
+        .delete_events_until("id_C", 2)
+        .await?;
+
+    let markers = sqlite_store::ArchiveMarkerStore::new(pool.clone());
+    markers
+        .save_marker(
+            "customer",
+            &ArchiveMarker::new(
+                "id_C",
+                2,
+                "customer/id_C.ndjson.zst",
+            ),
+        )
+        .await?;
+
+    let mut admin = sqlite_store::AdminStore::new(pool);
+
+    let issues = vec![
+        Issue::SequenceGap {
+            aggregate_id: "id_B".to_string(),
+            expected: 2,
+            found: 3,
+        },
+        Issue::SnapshotBehind {
+            aggregate_id: "id_B".to_string(),
+            version: 1,
+            last_sequence: 3,
+        },
+    ];
+
+    assert_eq!(
+        check_records(&mut admin, Some(&markers), "customer").await?,
+        issues
+    );
+
+    // without the markers the archived events look missing
+    let mut expected = issues;
+    expected.push(Issue::SequenceGap {
+        aggregate_id: "id_C".to_string(),
+        expected: 1,
+        found: 3,
+    });
+
+    assert_eq!(
+        check_records(&mut admin, None, "customer").await?,
+        expected
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-sqlite")]
+#[test]
+fn test_check_records() {
+    tokio_test::block_on(check_check_records()).unwrap();
+}
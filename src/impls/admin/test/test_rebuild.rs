@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+    EventContext,
+    QueryContext,
+};
+
+use crate::{
+    admin::{
+        rebuild_queries,
+        rebuild_query,
+    },
+    memory_store::{
+        EventStore,
+        QueryStore,
+    },
+    IEventStore,
+    IQueryStore,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+fn get_events(
+    id: &str
+) -> Vec<EventContext<CustomerCommand, CustomerEvent>> {
+    vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: format!("name {}", id),
+            }),
+            HashMap::new(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: format!("{}@email.com", id),
+            }),
+            HashMap::new(),
+        ),
+    ]
+}
+
+fn get_query(
+    id: &str
+) -> QueryContext<CustomerCommand, CustomerEvent, CustomerContactQuery>
+{
+    // the version is the sequence of the last event
+    QueryContext::new(
+        id.to_string(),
+        2,
+        CustomerContactQuery {
+            name: format!("name {}", id),
+            email: format!("{}@email.com", id),
+            latest_address: "".to_string(),
+        },
+    )
+}
+
+async fn check_rebuild_queries() -> Result<(), Error> {
+    let mut events = ThisEventStore::default();
+    let mut queries = ThisQueryStore::default();
+
+    events
+        .save_events(&get_events("id_A"))
+        .await?;
+    events
+        .save_events(&get_events("id_B"))
+        .await?;
+
+    // a stale read model and a missing one
+    queries
+        .save_query(
+            QueryContext::new(
+                "id_A".to_string(),
+                5,
+                Default::default(),
+            ),
+            0,
+        )
+        .await?;
+
+    assert_eq!(
+        rebuild_queries(&mut events, &mut queries).await?,
+        2
+    );
+
+    assert_eq!(
+        queries.load_query("id_A").await?,
+        get_query("id_A")
+    );
+    assert_eq!(
+        queries.load_query("id_B").await?,
+        get_query("id_B")
+    );
+
+    // rebuilding again gives the same read models
+    rebuild_query(&mut events, &mut queries, "id_A").await?;
+    assert_eq!(
+        queries.load_query("id_A").await?,
+        get_query("id_A")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_rebuild_queries() {
+    tokio_test::block_on(check_rebuild_queries()).unwrap();
+}
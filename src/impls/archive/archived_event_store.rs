@@ -0,0 +1,322 @@
+use async_trait::async_trait;
+use log::trace;
+use std::marker::PhantomData;
+
+use cqrs_es2::{
+    AggregateContext,
+    Error,
+    EventContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+};
+
+use crate::repository::{
+    EventFilter,
+    IArchiveMarkerStore,
+    IEventStore,
+    IFilteredEventStore,
+};
+
+use super::file_archive::FileArchive;
+
+/// Number of aggregate ids listed at once by `count_aggregates`
+const COUNT_PAGE_SIZE: i64 = 1000;
+
+/// Async event store reading back the events moved to a
+/// `FileArchive` by the `Archiver` whenever an archived aggregate is
+/// loaded. New events are saved to the wrapped store.
+///
+/// Archived events are not returned by `filter_events`.
+pub struct ArchivedEventStore<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    ES: IEventStore<C, E, A>,
+    MS: IArchiveMarkerStore,
+> {
+    store: ES,
+    markers: MS,
+    archive: FileArchive,
+    _phantom: PhantomData<(C, E, A)>,
+}
+
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IEventStore<C, E, A>,
+        MS: IArchiveMarkerStore,
+    > ArchivedEventStore<C, E, A, ES, MS>
+{
+    /// Constructor
+    pub fn new(
+        store: ES,
+        markers: MS,
+        archive: FileArchive,
+    ) -> Self {
+        let x = Self {
+            store,
+            markers,
+            archive,
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new async archived event store");
+
+        x
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IEventStore<C, E, A>,
+        MS: IArchiveMarkerStore,
+    > IEventStore<C, E, A> for ArchivedEventStore<C, E, A, ES, MS>
+{
+    /// Save new events
+    async fn save_events(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        self.store.save_events(contexts).await
+    }
+
+    /// Save new events together with the aggregate snapshot taken
+    /// after them
+    async fn save_events_and_snapshot(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+        snapshot: Option<&AggregateContext<C, E, A>>,
+    ) -> Result<(), Error> {
+        self.store
+            .save_events_and_snapshot(contexts, snapshot)
+            .await
+    }
+
+    /// Load the archived and the stored events for a particular
+    /// `aggregate_id`
+    async fn load_events(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        let marker = self
+            .markers
+            .load_marker(A::aggregate_type(), aggregate_id)
+            .await?;
+
+        let mut events = self
+            .store
+            .load_events(aggregate_id)
+            .await?;
+
+        let marker = match marker {
+            Some(x) => x,
+            None => {
+                return Ok(events);
+            },
+        };
+
+        trace!(
+            "loading archived events of aggregate id '{}'",
+            aggregate_id
+        );
+
+        let mut result = self
+            .archive
+            .read_events(
+                A::aggregate_type(),
+                aggregate_id,
+                &marker.location,
+            )
+            .await?;
+
+        // events left behind by an interrupted archival are already
+        // archived
+        events.retain(|x| x.sequence > marker.last_sequence);
+
+        result.append(&mut events);
+
+        Ok(result)
+    }
+
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `sequence`, reading the archive only when some of them are
+    /// archived
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        let marker = self
+            .markers
+            .load_marker(A::aggregate_type(), aggregate_id)
+            .await?;
+
+        match marker {
+            Some(x) if x.last_sequence > sequence => {
+                let mut events =
+                    self.load_events(aggregate_id).await?;
+
+                events.retain(|x| x.sequence > sequence);
+
+                Ok(events)
+            },
+            _ => {
+                self.store
+                    .load_events_after(aggregate_id, sequence)
+                    .await
+            },
+        }
+    }
+
+    /// Load the sequence of the last stored or archived event of
+    /// `aggregate_id`, 0 if it has none
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        let sequence = self
+            .store
+            .load_latest_sequence(aggregate_id)
+            .await?;
+
+        let marker = self
+            .markers
+            .load_marker(A::aggregate_type(), aggregate_id)
+            .await?;
+
+        Ok(marker.map_or(sequence, |x| {
+            x.last_sequence.max(sequence)
+        }))
+    }
+
+    /// save a new aggregate snapshot
+    async fn save_aggregate_snapshot(
+        &mut self,
+        context: AggregateContext<C, E, A>,
+    ) -> Result<(), Error> {
+        self.store
+            .save_aggregate_snapshot(context)
+            .await
+    }
+
+    /// Load aggregate at current state from snapshots
+    async fn load_aggregate_from_snapshot(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<AggregateContext<C, E, A>, Error> {
+        self.store
+            .load_aggregate_from_snapshot(aggregate_id)
+            .await
+    }
+
+    /// List up to `limit` ids of the aggregates having stored or
+    /// archived events in ascending order, starting after the id
+    /// `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        let mut result = self
+            .store
+            .list_aggregate_ids(after, limit)
+            .await?;
+
+        let mut archived = self
+            .markers
+            .list_archived_ids(A::aggregate_type(), after, limit)
+            .await?;
+
+        // an aggregate may have both stored and archived events
+        result.append(&mut archived);
+        result.sort();
+        result.dedup();
+        result.truncate(limit.max(0) as usize);
+
+        Ok(result)
+    }
+
+    /// Count the aggregates having stored or archived events, paging
+    /// through their ids as an aggregate may have both
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        let mut count = 0;
+        let mut after: Option<String> = None;
+
+        loop {
+            let ids = self
+                .list_aggregate_ids(after.as_deref(), COUNT_PAGE_SIZE)
+                .await?;
+
+            count += ids.len() as i64;
+
+            if ids.len() < COUNT_PAGE_SIZE as usize {
+                break;
+            }
+
+            after = ids.last().cloned();
+        }
+
+        Ok(count)
+    }
+
+    /// Delete all the stored and archived events and the snapshot of
+    /// `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        let aggregate_type = A::aggregate_type();
+
+        if let Some(x) = self
+            .markers
+            .load_marker(aggregate_type, aggregate_id)
+            .await?
+        {
+            self.archive.delete(&x.location).await?;
+            self.markers
+                .delete_marker(aggregate_type, aggregate_id)
+                .await?;
+        }
+
+        self.store
+            .delete_aggregate(aggregate_id)
+            .await
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence` from the event store, the archived events are kept
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        self.store
+            .delete_events_until(aggregate_id, sequence)
+            .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IFilteredEventStore<C, E, A>,
+        MS: IArchiveMarkerStore,
+    > IFilteredEventStore<C, E, A>
+    for ArchivedEventStore<C, E, A, ES, MS>
+{
+    /// Load the stored events matching the `filter` ordered by commit
+    /// time, aggregate id and sequence
+    async fn filter_events(
+        &mut self,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        self.store.filter_events(filter).await
+    }
+}
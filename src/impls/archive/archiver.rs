@@ -0,0 +1,237 @@
+use chrono::{
+    DateTime,
+    Utc,
+};
+use log::{
+    debug,
+    trace,
+};
+use std::marker::PhantomData;
+
+use cqrs_es2::{
+    Error,
+    IAggregate,
+    ICommand,
+    IEvent,
+};
+
+use crate::repository::{
+    ArchiveMarker,
+    EventFilter,
+    IArchiveMarkerStore,
+    IEventStore,
+    IFilteredEventStore,
+};
+
+use super::file_archive::FileArchive;
+
+static DEFAULT_PAGE_SIZE: i64 = 1000;
+
+/// Moves the events of inactive aggregates from an event store to a
+/// `FileArchive`, leaving an archive marker in their place.
+///
+/// The snapshots are kept in the event store. The archived events
+/// are read back by the `ArchivedEventStore`.
+pub struct Archiver<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    ES: IEventStore<C, E, A>,
+    MS: IArchiveMarkerStore,
+> {
+    store: ES,
+    markers: MS,
+    archive: FileArchive,
+    page_size: i64,
+    _phantom: PhantomData<(C, E, A)>,
+}
+
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IEventStore<C, E, A>,
+        MS: IArchiveMarkerStore,
+    > Archiver<C, E, A, ES, MS>
+{
+    /// Constructor
+    pub fn new(
+        store: ES,
+        markers: MS,
+        archive: FileArchive,
+    ) -> Self {
+        let x = Self {
+            store,
+            markers,
+            archive,
+            page_size: DEFAULT_PAGE_SIZE,
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new archiver");
+
+        x
+    }
+
+    /// Read the events and aggregate ids by pages of `page_size`
+    pub fn with_page_size(
+        mut self,
+        page_size: i64,
+    ) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Archive the events of `aggregate_id`, appending them to its
+    /// previous archive if any, returns `false` if it has no events
+    /// left in the event store
+    pub async fn archive_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<bool, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        let mut events = self
+            .store
+            .load_events(aggregate_id)
+            .await?;
+
+        if events.is_empty() {
+            return Ok(false);
+        }
+
+        debug!(
+            "archiving {} events of aggregate id '{}'",
+            events.len(),
+            aggregate_id
+        );
+
+        let marker = self
+            .markers
+            .load_marker(aggregate_type, aggregate_id)
+            .await?;
+
+        let mut archived = match &marker {
+            Some(x) => {
+                self.archive
+                    .read_events(
+                        aggregate_type,
+                        aggregate_id,
+                        &x.location,
+                    )
+                    .await?
+            },
+            None => Vec::new(),
+        };
+
+        // events left behind by an interrupted archival are already
+        // archived
+        let last_sequence = marker.map_or(0, |x| x.last_sequence);
+        events.retain(|x| x.sequence > last_sequence);
+
+        archived.append(&mut events);
+
+        let last_sequence = archived
+            .last()
+            .map_or(last_sequence, |x| x.sequence);
+
+        let location = self
+            .archive
+            .write_events(aggregate_type, aggregate_id, &archived)
+            .await?;
+
+        self.markers
+            .save_marker(
+                aggregate_type,
+                &ArchiveMarker::new(
+                    aggregate_id,
+                    last_sequence,
+                    &location,
+                ),
+            )
+            .await?;
+
+        // the snapshot and the events committed since they were
+        // loaded are kept
+        self.store
+            .delete_events_until(aggregate_id, last_sequence)
+            .await?;
+
+        Ok(true)
+    }
+}
+
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IFilteredEventStore<C, E, A>,
+        MS: IArchiveMarkerStore,
+    > Archiver<C, E, A, ES, MS>
+{
+    /// Archive the events of all the aggregates having no events
+    /// committed since `cutoff`, returns the ids of the archived
+    /// aggregates
+    pub async fn archive_inactive(
+        &mut self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<Vec<String>, Error> {
+        debug!(
+            "archiving aggregates inactive since {}",
+            cutoff
+        );
+
+        let mut inactive = Vec::new();
+        let mut after: Option<String> = None;
+
+        loop {
+            let ids = self
+                .store
+                .list_aggregate_ids(after.as_deref(), self.page_size)
+                .await?;
+
+            for x in &ids {
+                // aggregates left without events were already
+                // archived and have nothing more to archive
+                if self
+                    .store
+                    .load_latest_sequence(x)
+                    .await? ==
+                    0
+                {
+                    continue;
+                }
+
+                let recent = self
+                    .store
+                    .filter_events(
+                        &EventFilter::new()
+                            .with_aggregate_id(x)
+                            .with_time_range(Some(cutoff), None)
+                            .with_page(0, 1),
+                    )
+                    .await?;
+
+                if recent.is_empty() {
+                    inactive.push(x.clone());
+                }
+            }
+
+            if (ids.len() as i64) < self.page_size {
+                break;
+            }
+
+            after = ids.last().cloned();
+        }
+
+        let mut result = Vec::new();
+
+        for x in inactive {
+            if self.archive_aggregate(&x).await? {
+                result.push(x);
+            }
+        }
+
+        Ok(result)
+    }
+}
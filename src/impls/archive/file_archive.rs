@@ -0,0 +1,315 @@
+use log::{
+    debug,
+    trace,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    fs::File,
+    io,
+    io::{
+        BufRead,
+        BufReader,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Arc,
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use crate::repository::{
+    IPayloadTransformer,
+    PayloadCodec,
+};
+
+/// Directory of zstd compressed NDJSON files holding the archived
+/// events, one file per aggregate and one event per line.
+#[derive(Clone)]
+pub struct FileArchive {
+    dir: PathBuf,
+    codec: PayloadCodec,
+}
+
+impl FileArchive {
+    /// Constructor
+    pub fn new(dir: &Path) -> Self {
+        let x = Self {
+            dir: dir.to_path_buf(),
+            codec: PayloadCodec::default(),
+        };
+
+        trace!("Created new file archive in {:?}", dir);
+
+        x
+    }
+
+    /// Transform the archived payloads with `transformer`, e.g. to
+    /// encrypt them
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
+    /// Write the `events` of `aggregate_id` replacing its previous
+    /// archive, returns the location of the archive
+    pub async fn write_events<C: ICommand, E: IEvent>(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        events: &[EventContext<C, E>],
+    ) -> Result<String, Error> {
+        let location = format!(
+            "{}/{}.ndjson.zst",
+            file_name_of(aggregate_type),
+            file_name_of(aggregate_id)
+        );
+
+        debug!(
+            "archiving {} events of aggregate id '{}' to '{}'",
+            events.len(),
+            aggregate_id,
+            location
+        );
+
+        let mut lines = Vec::new();
+
+        for event in events {
+            let payload = match self
+                .codec
+                .encode_value(
+                    aggregate_type,
+                    aggregate_id,
+                    &event.payload,
+                )
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to archive event of aggregate \
+                             id '{}' with error: {}",
+                            aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            lines.push(json!({
+                "aggregate_id": aggregate_id,
+                "sequence": event.sequence,
+                "payload": payload,
+                "metadata": event.metadata,
+            }));
+        }
+
+        let path = self.dir.join(&location);
+
+        match run_blocking(move || write_file(&path, &lines)).await {
+            Ok(_) => Ok(location),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to write archive '{}' with error: {}",
+                        location, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Read the events of `aggregate_id` archived at `location`
+    pub async fn read_events<C: ICommand, E: IEvent>(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        location: &str,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        trace!(
+            "reading archived events of aggregate id '{}' from '{}'",
+            aggregate_id,
+            location
+        );
+
+        let path = self.dir.join(location);
+
+        let lines = match run_blocking(move || read_file(&path)).await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to read archive '{}' with error: {}",
+                        location, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for line in lines {
+            let sequence = line["sequence"].as_i64().unwrap_or(0);
+
+            let payload = match self
+                .codec
+                .decode_value(
+                    aggregate_type,
+                    aggregate_id,
+                    line["payload"].clone(),
+                )
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad payload found in archive '{}' for \
+                             sequence {} with error: {}",
+                            location, sequence, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let metadata: HashMap<String, String> =
+                match serde_json::from_value(line["metadata"].clone())
+                {
+                    Ok(x) => x,
+                    Err(e) => {
+                        return Err(Error::new(
+                            format!(
+                                "bad metadata found in archive '{}' \
+                                 for sequence {} with error: {}",
+                                location, sequence, e
+                            )
+                            .as_str(),
+                        ));
+                    },
+                };
+
+            result.push(EventContext::new(
+                aggregate_id.to_string(),
+                sequence,
+                payload,
+                metadata,
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Delete the archive at `location`
+    pub async fn delete(
+        &self,
+        location: &str,
+    ) -> Result<(), Error> {
+        debug!("deleting archive '{}'", location);
+
+        let path = self.dir.join(location);
+
+        match run_blocking(move || fs::remove_file(&path)).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to delete archive '{}' with error: \
+                         {}",
+                        location, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+}
+
+/// Escapes all but the ASCII alphanumeric characters, `-` and `_`
+fn file_name_of(id: &str) -> String {
+    let mut result = String::new();
+
+    for x in id.bytes() {
+        if x.is_ascii_alphanumeric() || x == b'-' || x == b'_' {
+            result.push(x as char);
+        }
+        else {
+            result.push_str(&format!("%{:02X}", x));
+        }
+    }
+
+    result
+}
+
+/// Runs the blocking file system and compression work `f` on the
+/// blocking threads rather than on the runtime workers
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> io::Result<T> + Send + 'static
+) -> io::Result<T> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(x) => x,
+        Err(e) => Err(io::Error::new(io::ErrorKind::Other, e)),
+    }
+}
+
+fn write_file(
+    path: &Path,
+    lines: &[Value],
+) -> io::Result<()> {
+    if let Some(x) = path.parent() {
+        fs::create_dir_all(x)?;
+    }
+
+    // write a temporary file first so that a previous archive is
+    // only replaced by a complete one
+    let tmp = path.with_extension("tmp");
+
+    let mut encoder = zstd::Encoder::new(File::create(&tmp)?, 0)?;
+
+    for x in lines {
+        serde_json::to_writer(&mut encoder, x)?;
+        encoder.write_all(b"\n")?;
+    }
+
+    encoder.finish()?.sync_all()?;
+
+    fs::rename(&tmp, path)
+}
+
+fn read_file(path: &Path) -> io::Result<Vec<Value>> {
+    let decoder = zstd::Decoder::new(File::open(path)?)?;
+
+    let mut result = Vec::new();
+
+    for line in BufReader::new(decoder).lines() {
+        let line = line?;
+
+        if line.is_empty() {
+            continue;
+        }
+
+        result.push(serde_json::from_str(&line)?);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,13 @@
+//!
+//! Archival of the events of inactive aggregates to cold storage
+//! files
+
+pub use archived_event_store::ArchivedEventStore;
+pub use archiver::Archiver;
+pub use file_archive::FileArchive;
+
+mod archived_event_store;
+mod archiver;
+mod file_archive;
+
+mod test;
@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    AggregateContext,
+    Error,
+    EventContext,
+    IAggregate,
+};
+
+use crate::{
+    archive::{
+        ArchivedEventStore,
+        Archiver,
+        FileArchive,
+    },
+    memory_store::{
+        ArchiveMarkerStore,
+        EventStore,
+    },
+    IArchiveMarkerStore,
+    IEventStore,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+fn get_metadata() -> HashMap<String, String> {
+    let now = "2021-03-18T12:32:45.930Z".to_string();
+    let mut metadata = HashMap::new();
+    metadata.insert("time".to_string(), now);
+    metadata
+}
+
+fn get_event(
+    id: &str,
+    sequence: i64,
+) -> EventContext<CustomerCommand, CustomerEvent> {
+    EventContext::new(
+        id.to_string(),
+        sequence,
+        CustomerEvent::EmailUpdated(EmailUpdated {
+            new_email: format!("test{}@email.com", sequence),
+        }),
+        get_metadata(),
+    )
+}
+
+async fn check_archive_aggregate() -> Result<(), Error> {
+    let events = Default::default();
+    let snapshots = Default::default();
+    let markers = Default::default();
+
+    let dir =
+        std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    let archive = FileArchive::new(&dir);
+
+    let mut archiver = Archiver::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        ArchiveMarkerStore::new(Arc::clone(&markers)),
+        archive.clone(),
+    );
+
+    let mut store = ArchivedEventStore::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        ArchiveMarkerStore::new(Arc::clone(&markers)),
+        archive,
+    );
+
+    let mut live = ThisEventStore::new(
+        Arc::clone(&events),
+        Arc::clone(&snapshots),
+    );
+
+    let id = "test/id A";
+
+    let contexts = vec![get_event(id, 1), get_event(id, 2)];
+
+    let snapshot = AggregateContext::new(
+        id.to_string(),
+        2,
+        Customer {
+            customer_id: "customer 1".to_string(),
+            name: "test name".to_string(),
+            email: "test2@email.com".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    store
+        .save_events_and_snapshot(&contexts, Some(&snapshot))
+        .await?;
+
+    assert!(archiver.archive_aggregate(id).await?);
+
+    // the events left the event store but not the snapshot
+    assert_eq!(live.load_events(id).await?.len(), 0);
+    assert_eq!(
+        live.load_aggregate_from_snapshot(id)
+            .await?,
+        snapshot
+    );
+
+    let marker = ArchiveMarkerStore::new(Arc::clone(&markers))
+        .load_marker(Customer::aggregate_type(), id)
+        .await?
+        .unwrap();
+    assert_eq!(marker.last_sequence, 2);
+    assert!(dir.join(&marker.location).exists());
+
+    assert_eq!(store.load_events(id).await?, contexts);
+
+    // touching the aggregate again
+    let mut contexts = contexts;
+    contexts.push(get_event(id, 3));
+
+    store
+        .save_events(&vec![get_event(id, 3)])
+        .await?;
+    assert_eq!(store.load_events(id).await?, contexts);
+
+    // archiving again appends to the archive
+    assert!(archiver.archive_aggregate(id).await?);
+    assert!(!archiver.archive_aggregate(id).await?);
+
+    assert_eq!(live.load_events(id).await?.len(), 0);
+    assert_eq!(store.load_events(id).await?, contexts);
+
+    store.delete_aggregate(id).await?;
+
+    assert_eq!(store.load_events(id).await?.len(), 0);
+    assert!(!dir.join(&marker.location).exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn test_archive_aggregate() {
+    tokio_test::block_on(check_archive_aggregate()).unwrap();
+}
+
+async fn check_list_archived_aggregates() -> Result<(), Error> {
+    let events = Default::default();
+    let snapshots = Default::default();
+    let markers = Default::default();
+
+    let dir =
+        std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    let archive = FileArchive::new(&dir);
+
+    let mut archiver = Archiver::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        ArchiveMarkerStore::new(Arc::clone(&markers)),
+        archive.clone(),
+    );
+
+    let mut store = ArchivedEventStore::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        ArchiveMarkerStore::new(Arc::clone(&markers)),
+        archive,
+    );
+
+    let id_a = "test/id A";
+    let id_b = "test/id B";
+    let id_c = "test/id C";
+
+    for id in &[id_a, id_b, id_c] {
+        store
+            .save_events(&vec![
+                get_event(id, 1),
+                get_event(id, 2),
+            ])
+            .await?;
+    }
+
+    assert!(archiver.archive_aggregate(id_a).await?);
+    assert!(archiver.archive_aggregate(id_c).await?);
+
+    // the sql stores have no row left for a fully archived
+    // aggregate
+    events.write().unwrap().remove(id_a);
+
+    assert_eq!(
+        store
+            .list_aggregate_ids(None, 10)
+            .await?,
+        vec![
+            id_a.to_string(),
+            id_b.to_string(),
+            id_c.to_string()
+        ]
+    );
+    assert_eq!(
+        store
+            .list_aggregate_ids(Some(id_a), 1)
+            .await?,
+        vec![id_b.to_string()]
+    );
+    assert_eq!(store.count_aggregates().await?, 3);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn test_list_archived_aggregates() {
+    tokio_test::block_on(check_list_archived_aggregates()).unwrap();
+}
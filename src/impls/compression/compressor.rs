@@ -0,0 +1,250 @@
+use async_trait::async_trait;
+use log::trace;
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::IPayloadTransformer;
+
+/// Default size in bytes under which the payloads are not compressed
+pub const DEFAULT_THRESHOLD: usize = 1024;
+
+// reserved key wrapping the compressed payloads, it can not collide
+// with the variant or field names of serialized Rust types
+static MARKER: &str = "cqrs-es2:compressed";
+
+static VERSION: u64 = 1;
+
+/// Compression algorithms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    /// Zstandard with its compression level, 0 being the default
+    #[cfg(feature = "with-zstd")]
+    Zstd(i32),
+    /// LZ4, faster but compressing less than Zstandard
+    #[cfg(feature = "with-lz4")]
+    Lz4,
+}
+
+impl Compression {
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "with-zstd")]
+            Compression::Zstd(_) => "zstd",
+            #[cfg(feature = "with-lz4")]
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    fn compress(
+        self,
+        data: &[u8],
+    ) -> Result<Vec<u8>, String> {
+        match self {
+            #[cfg(feature = "with-zstd")]
+            Compression::Zstd(level) => {
+                match zstd::encode_all(data, level) {
+                    Ok(x) => Ok(x),
+                    Err(e) => Err(e.to_string()),
+                }
+            },
+            #[cfg(feature = "with-lz4")]
+            Compression::Lz4 => {
+                Ok(lz4_flex::compress_prepend_size(data))
+            },
+        }
+    }
+}
+
+fn decompress(
+    name: &str,
+    data: &[u8],
+) -> Result<Vec<u8>, String> {
+    match name {
+        #[cfg(feature = "with-zstd")]
+        "zstd" => {
+            match zstd::decode_all(data) {
+                Ok(x) => Ok(x),
+                Err(e) => Err(e.to_string()),
+            }
+        },
+        #[cfg(feature = "with-lz4")]
+        "lz4" => {
+            match lz4_flex::decompress_size_prepended(data) {
+                Ok(x) => Ok(x),
+                Err(e) => Err(e.to_string()),
+            }
+        },
+        _ => {
+            Err(format!(
+                "unsupported compression '{}'",
+                name
+            ))
+        },
+    }
+}
+
+fn compressed_data(value: &Value) -> Option<(&str, &str)> {
+    let x = match value {
+        Value::Object(x) if x.len() == 1 => {
+            x.get(MARKER)?.as_object()?
+        },
+        _ => {
+            return None;
+        },
+    };
+
+    if x.get("version") != Some(&json!(VERSION)) {
+        return None;
+    }
+
+    Some((
+        x.get("compression")?.as_str()?,
+        x.get("data")?.as_str()?,
+    ))
+}
+
+/// Payload transformer compressing the payloads larger than a
+/// threshold.
+///
+/// The compressed payloads are stored base64 encoded as
+/// `{"cqrs-es2:compressed": {"version": ..., "compression": ...,
+/// "data": ...}}` in the payload columns. The transformers turn JSON
+/// payloads into JSON payloads so that they fit the existing JSON,
+/// JSONB, TEXT, BSON and Redis columns of every store without a
+/// schema change, at the cost of the base64 encoding, a third of the
+/// compressed size. The payloads up to the threshold, and those whose
+/// envelope would not be smaller than their serialization, are stored
+/// unchanged, so that no payload grows. Both are transparently
+/// restored on loading.
+pub struct Compressor {
+    compression: Compression,
+    threshold: usize,
+}
+
+impl Compressor {
+    /// Constructor compressing the payloads larger than
+    /// `DEFAULT_THRESHOLD` bytes
+    pub fn new(compression: Compression) -> Self {
+        let x = Self {
+            compression,
+            threshold: DEFAULT_THRESHOLD,
+        };
+
+        trace!(
+            "Created new {} compressor",
+            compression.name()
+        );
+
+        x
+    }
+
+    /// Only compress the payloads serialized in more than `threshold`
+    /// bytes
+    pub fn with_threshold(
+        mut self,
+        threshold: usize,
+    ) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+#[async_trait]
+impl IPayloadTransformer for Compressor {
+    /// Compress the `payload` if it is larger than the threshold
+    async fn encode(
+        &self,
+        _aggregate_type: &str,
+        aggregate_id: &str,
+        payload: Value,
+    ) -> Result<Value, Error> {
+        let data = match serde_json::to_vec(&payload) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+        if data.len() <= self.threshold {
+            return Ok(payload);
+        }
+
+        let compressed = match self.compression.compress(&data) {
+            Ok(x) => base64::encode(&x),
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to compress payload of aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut envelope = Map::new();
+        envelope.insert(
+            MARKER.to_string(),
+            json!({
+                "version": VERSION,
+                "compression": self.compression.name(),
+                "data": compressed,
+            }),
+        );
+
+        let envelope = Value::Object(envelope);
+
+        // the base64 encoding and the envelope may outweigh the
+        // compression gains
+        if envelope.to_string().len() >= data.len() {
+            return Ok(payload);
+        }
+
+        Ok(envelope)
+    }
+
+    /// Decompress the `payload` if it was compressed
+    async fn decode(
+        &self,
+        _aggregate_type: &str,
+        aggregate_id: &str,
+        payload: Value,
+    ) -> Result<Value, Error> {
+        let (name, data) = match compressed_data(&payload) {
+            Some(x) => x,
+            None => {
+                return Ok(payload);
+            },
+        };
+
+        let data = match base64::decode(data) {
+            Ok(x) => decompress(name, &x),
+            Err(e) => Err(e.to_string()),
+        };
+
+        let data = match data {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to decompress payload of aggregate \
+                         id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match serde_json::from_slice(&data) {
+            Ok(x) => Ok(x),
+            Err(e) => Err(Error::new(e.to_string().as_str())),
+        }
+    }
+}
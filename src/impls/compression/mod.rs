@@ -0,0 +1,11 @@
+//!
+//! Payload compression
+
+pub use compressor::{
+    Compression,
+    Compressor,
+};
+
+mod compressor;
+
+mod test;
@@ -0,0 +1,238 @@
+use serde_json::{
+    json,
+    Value,
+};
+use std::sync::Arc;
+
+use cqrs_es2::Error;
+
+use crate::{
+    compression::{
+        Compression,
+        Compressor,
+    },
+    IPayloadTransformer,
+    TransformerChain,
+};
+
+fn large_payload() -> Value {
+    json!({
+        "AddressUpdated": {
+            "new_address": "something else happening here ".repeat(100)
+        }
+    })
+}
+
+async fn check_compress(
+    compression: Compression
+) -> Result<(), Error> {
+    let compressor = Compressor::new(compression);
+
+    let payload = large_payload();
+
+    let encoded = compressor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_ne!(encoded, payload);
+    assert!(
+        encoded.to_string().len() < payload.to_string().len() / 4
+    );
+
+    let decoded = compressor
+        .decode("customer", "test_id_A", encoded)
+        .await?;
+    assert_eq!(decoded, payload);
+
+    Ok(())
+}
+
+#[cfg(feature = "with-lz4")]
+async fn check_threshold() -> Result<(), Error> {
+    let compressor = Compressor::new(Compression::Lz4);
+
+    let payload = json!({
+        "NameAdded": {
+            "changed_name": "test_name ".repeat(50)
+        }
+    });
+
+    // small payloads are left unchanged
+    let encoded = compressor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(encoded, payload);
+
+    let decoded = compressor
+        .decode("customer", "test_id_A", encoded)
+        .await?;
+    assert_eq!(decoded, payload);
+
+    let compressor = compressor.with_threshold(0);
+
+    let encoded = compressor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(
+        encoded["cqrs-es2:compressed"]["compression"],
+        json!("lz4")
+    );
+
+    let decoded = compressor
+        .decode("customer", "test_id_A", encoded)
+        .await?;
+    assert_eq!(decoded, payload);
+
+    // payloads which would not shrink are left unchanged
+    let payload = json!({"NameAdded": {"changed_name": "test_name"}});
+
+    let encoded = compressor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(encoded, payload);
+
+    Ok(())
+}
+
+async fn check_never_inflates(
+    compression: Compression
+) -> Result<(), Error> {
+    let compressor = Compressor::new(compression).with_threshold(0);
+
+    // random identifiers barely compress, repeated text does
+    let ids: Vec<String> = (0..64)
+        .map(|_| uuid::Uuid::new_v4().to_string())
+        .collect();
+
+    for count in &[1, 2, 4, 8, 16, 32, 64] {
+        for text in &[
+            ids[..*count].join(" "),
+            "text ".repeat(*count),
+        ] {
+            let payload =
+                json!({ "NameAdded": { "changed_name": text } });
+
+            let encoded = compressor
+                .encode("customer", "test_id_A", payload.clone())
+                .await?;
+            assert!(
+                encoded.to_string().len() <=
+                    payload.to_string().len()
+            );
+
+            let decoded = compressor
+                .decode("customer", "test_id_A", encoded)
+                .await?;
+            assert_eq!(decoded, payload);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "with-lz4")]
+async fn check_untagged_payloads() -> Result<(), Error> {
+    let compressor = Compressor::new(Compression::Lz4);
+
+    // payloads looking like compressed data are not decompressed
+    let payload = json!({
+        "compression": "lz4",
+        "data": "dGVzdF9uYW1l"
+    });
+
+    let decoded = compressor
+        .decode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(decoded, payload);
+
+    Ok(())
+}
+
+#[cfg(all(
+    feature = "with-zstd",
+    feature = "with-encryption"
+))]
+async fn check_chain() -> Result<(), Error> {
+    use crate::encryption::{
+        generate_key,
+        FieldEncryptor,
+        KeyRing,
+    };
+
+    let chain = TransformerChain::new()
+        .with(Arc::new(Compressor::new(
+            Compression::Zstd(0),
+        )))
+        .with(Arc::new(FieldEncryptor::new(
+            KeyRing::new("key_1", &generate_key().unwrap()),
+        )));
+
+    let payload = large_payload();
+
+    let encoded = chain
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(
+        encoded["cqrs-es2:encrypted"]["kid"],
+        json!("key_1")
+    );
+    assert!(
+        encoded.to_string().len() < payload.to_string().len() / 4
+    );
+
+    let decoded = chain
+        .decode("customer", "test_id_A", encoded)
+        .await?;
+    assert_eq!(decoded, payload);
+
+    Ok(())
+}
+
+#[cfg(feature = "with-zstd")]
+#[test]
+fn test_compress_zstd() {
+    tokio_test::block_on(check_compress(Compression::Zstd(0)))
+        .unwrap();
+}
+
+#[cfg(feature = "with-lz4")]
+#[test]
+fn test_compress_lz4() {
+    tokio_test::block_on(check_compress(Compression::Lz4)).unwrap();
+}
+
+#[cfg(feature = "with-zstd")]
+#[test]
+fn test_never_inflates_zstd() {
+    tokio_test::block_on(check_never_inflates(Compression::Zstd(
+        0,
+    )))
+    .unwrap();
+}
+
+#[cfg(feature = "with-lz4")]
+#[test]
+fn test_never_inflates_lz4() {
+    tokio_test::block_on(check_never_inflates(Compression::Lz4))
+        .unwrap();
+}
+
+#[cfg(feature = "with-lz4")]
+#[test]
+fn test_threshold() {
+    tokio_test::block_on(check_threshold()).unwrap();
+}
+
+#[cfg(feature = "with-lz4")]
+#[test]
+fn test_untagged_payloads() {
+    tokio_test::block_on(check_untagged_payloads()).unwrap();
+}
+
+#[cfg(all(
+    feature = "with-zstd",
+    feature = "with-encryption"
+))]
+#[test]
+fn test_chain() {
+    tokio_test::block_on(check_chain()).unwrap();
+}
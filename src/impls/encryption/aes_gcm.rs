@@ -0,0 +1,216 @@
+use ring::{
+    aead::{
+        Aad,
+        LessSafeKey,
+        Nonce,
+        UnboundKey,
+        AES_256_GCM,
+        NONCE_LEN,
+    },
+    rand::{
+        SecureRandom,
+        SystemRandom,
+    },
+};
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+
+static CIPHER: &str = "AES-256-GCM";
+
+// reserved key wrapping the encrypted values, it can not collide with
+// the variant or field names of serialized Rust types
+static MARKER: &str = "cqrs-es2:encrypted";
+
+static VERSION: u64 = 1;
+
+/// Length of the AES-256 keys
+pub const KEY_LEN: usize = 32;
+
+/// Generates a new random key
+pub fn generate_key() -> Result<Vec<u8>, String> {
+    let mut key = vec![0; KEY_LEN];
+
+    match SystemRandom::new().fill(&mut key) {
+        Ok(()) => Ok(key),
+        Err(_) => Err("unable to generate a random key".to_string()),
+    }
+}
+
+fn cipher_key(key: &[u8]) -> Result<LessSafeKey, String> {
+    match UnboundKey::new(&AES_256_GCM, key) {
+        Ok(x) => Ok(LessSafeKey::new(x)),
+        Err(_) => {
+            Err(format!(
+                "invalid key of length {}, expected {}",
+                key.len(),
+                KEY_LEN
+            ))
+        },
+    }
+}
+
+/// Encrypts `plaintext` bound to `aad`, returning the nonce followed
+/// by the ciphertext and its tag
+pub fn seal(
+    key: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, String> {
+    let key = cipher_key(key)?;
+
+    let mut nonce = [0; NONCE_LEN];
+
+    if SystemRandom::new()
+        .fill(&mut nonce)
+        .is_err()
+    {
+        return Err("unable to generate a random nonce".to_string());
+    }
+
+    let mut data = plaintext.to_vec();
+
+    if key
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut data,
+        )
+        .is_err()
+    {
+        return Err("unable to encrypt the payload".to_string());
+    }
+
+    let mut result = nonce.to_vec();
+    result.append(&mut data);
+
+    Ok(result)
+}
+
+/// Decrypts the output of `seal`
+pub fn open(
+    key: &[u8],
+    aad: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, String> {
+    let key = cipher_key(key)?;
+
+    if data.len() < NONCE_LEN {
+        return Err("encrypted payload is too short".to_string());
+    }
+
+    let mut nonce = [0; NONCE_LEN];
+    nonce.copy_from_slice(&data[..NONCE_LEN]);
+
+    let mut data = data[NONCE_LEN..].to_vec();
+
+    match key.open_in_place(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(aad),
+        &mut data,
+    ) {
+        Ok(x) => Ok(x.to_vec()),
+        Err(_) => {
+            Err(
+                "unable to decrypt the payload, wrong key or \
+                 tampered data"
+                    .to_string(),
+            )
+        },
+    }
+}
+
+fn envelope(value: &Value) -> Option<&Map<String, Value>> {
+    match value {
+        Value::Object(x) if x.len() == 1 => {
+            x.get(MARKER).and_then(Value::as_object)
+        },
+        _ => None,
+    }
+}
+
+/// Checks whether `value` was produced by `encrypt_value`
+pub fn is_encrypted(value: &Value) -> bool {
+    match envelope(value) {
+        Some(x) => {
+            x.get("version") == Some(&json!(VERSION)) &&
+                x.get("cipher") == Some(&json!(CIPHER)) &&
+                matches!(x.get("data"), Some(Value::String(_)))
+        },
+        None => false,
+    }
+}
+
+/// Returns the id of the key used by `encrypt_value`, if any
+pub fn key_id(value: &Value) -> Option<&str> {
+    envelope(value)
+        .and_then(|x| x.get("kid"))
+        .and_then(Value::as_str)
+}
+
+/// Encrypts a JSON value into
+/// `{"cqrs-es2:encrypted": {"version": ..., "cipher": ..., "data":
+/// ...}}`, the `kid` is added when the id of the key is given
+pub fn encrypt_value(
+    key: &[u8],
+    kid: Option<&str>,
+    aad: &[u8],
+    value: &Value,
+) -> Result<Value, String> {
+    let plaintext = match serde_json::to_vec(value) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(e.to_string());
+        },
+    };
+
+    let data = seal(key, aad, &plaintext)?;
+
+    let mut result = json!({
+        "version": VERSION,
+        "cipher": CIPHER,
+        "data": base64::encode(&data),
+    });
+
+    if let Some(x) = kid {
+        result["kid"] = json!(x);
+    }
+
+    let mut envelope = Map::new();
+    envelope.insert(MARKER.to_string(), result);
+
+    Ok(Value::Object(envelope))
+}
+
+/// Decrypts the output of `encrypt_value`
+pub fn decrypt_value(
+    key: &[u8],
+    aad: &[u8],
+    value: &Value,
+) -> Result<Value, String> {
+    let data = match envelope(value)
+        .and_then(|x| x.get("data"))
+        .and_then(Value::as_str)
+    {
+        Some(x) => x,
+        None => {
+            return Err("encrypted payload has no data".to_string());
+        },
+    };
+
+    let data = match base64::decode(data) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(e.to_string());
+        },
+    };
+
+    let plaintext = open(key, aad, &data)?;
+
+    match serde_json::from_slice(&plaintext) {
+        Ok(x) => Ok(x),
+        Err(e) => Err(e.to_string()),
+    }
+}
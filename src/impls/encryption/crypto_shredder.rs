@@ -0,0 +1,249 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use serde_json::Value;
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    IKeyStore,
+    IPayloadTransformer,
+};
+
+use super::aes_gcm::{
+    decrypt_value,
+    encrypt_value,
+    generate_key,
+    is_encrypted,
+};
+
+/// Payload transformer encrypting the payloads of every aggregate
+/// with its own AES-256-GCM key, so that destroying the key with
+/// `erase` makes all the stored events, snapshots and queries of the
+/// aggregate unreadable (crypto-shredding).
+///
+/// No new payload can be written for an erased aggregate. Payloads
+/// stored in plain text before the transformer was enabled are still
+/// readable.
+pub struct CryptoShredder<KS: IKeyStore> {
+    key_store: KS,
+}
+
+impl<KS: IKeyStore> CryptoShredder<KS> {
+    /// Constructor
+    pub fn new(key_store: KS) -> Self {
+        let x = Self { key_store };
+
+        trace!("Created new crypto shredder");
+
+        x
+    }
+
+    /// Erase all the payloads of the aggregate by destroying its key
+    pub async fn erase(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "erasing aggregate id '{}'",
+            aggregate_id
+        );
+
+        self.key_store
+            .delete_key(aggregate_type, aggregate_id)
+            .await
+    }
+
+    async fn get_or_create_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<u8>, Error> {
+        if let Some(x) = self
+            .key_store
+            .load_key(aggregate_type, aggregate_id)
+            .await?
+        {
+            return usable_key(aggregate_id, x);
+        }
+
+        let key = match generate_key() {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.as_str()));
+            },
+        };
+
+        self.key_store
+            .save_key(aggregate_type, aggregate_id, &key)
+            .await?;
+
+        // reload in case a concurrent writer saved its key first
+        self.load_key(aggregate_type, aggregate_id)
+            .await
+    }
+
+    async fn load_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<u8>, Error> {
+        match self
+            .key_store
+            .load_key(aggregate_type, aggregate_id)
+            .await?
+        {
+            Some(x) => usable_key(aggregate_id, x),
+            None => Err(erased(aggregate_id)),
+        }
+    }
+}
+
+fn erased(aggregate_id: &str) -> Error {
+    Error::new(
+        format!(
+            "payloads of aggregate id '{}' have been erased",
+            aggregate_id
+        )
+        .as_str(),
+    )
+}
+
+fn usable_key(
+    aggregate_id: &str,
+    key: Vec<u8>,
+) -> Result<Vec<u8>, Error> {
+    // an empty key is the tombstone of an erased aggregate
+    if key.is_empty() {
+        return Err(erased(aggregate_id));
+    }
+
+    Ok(key)
+}
+
+fn associated_data(
+    aggregate_type: &str,
+    aggregate_id: &str,
+) -> String {
+    format!("{}\0{}", aggregate_type, aggregate_id)
+}
+
+fn decrypt(
+    key: &[u8],
+    aggregate_type: &str,
+    aggregate_id: &str,
+    payload: &Value,
+) -> Result<Value, Error> {
+    match decrypt_value(
+        key,
+        associated_data(aggregate_type, aggregate_id).as_bytes(),
+        payload,
+    ) {
+        Ok(x) => Ok(x),
+        Err(e) => {
+            Err(Error::new(
+                format!(
+                    "unable to decrypt payload of aggregate id '{}' \
+                     with error: {}",
+                    aggregate_id, e
+                )
+                .as_str(),
+            ))
+        },
+    }
+}
+
+#[async_trait]
+impl<KS: IKeyStore> IPayloadTransformer for CryptoShredder<KS> {
+    /// Encrypt the `payload` with the key of the aggregate
+    async fn encode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payload: Value,
+    ) -> Result<Value, Error> {
+        let key = self
+            .get_or_create_key(aggregate_type, aggregate_id)
+            .await?;
+
+        match encrypt_value(
+            &key,
+            None,
+            associated_data(aggregate_type, aggregate_id).as_bytes(),
+            &payload,
+        ) {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to encrypt payload of aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Decrypt the `payload` with the key of the aggregate
+    async fn decode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payload: Value,
+    ) -> Result<Value, Error> {
+        if !is_encrypted(&payload) {
+            return Ok(payload);
+        }
+
+        let key = self
+            .load_key(aggregate_type, aggregate_id)
+            .await?;
+
+        decrypt(
+            &key,
+            aggregate_type,
+            aggregate_id,
+            &payload,
+        )
+    }
+
+    /// Decrypt the `payloads` loading the key of the aggregate only
+    /// once
+    async fn decode_all(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payloads: Vec<Value>,
+    ) -> Result<Vec<Value>, Error> {
+        if !payloads.iter().any(is_encrypted) {
+            return Ok(payloads);
+        }
+
+        let key = self
+            .load_key(aggregate_type, aggregate_id)
+            .await?;
+
+        let mut result = Vec::with_capacity(payloads.len());
+
+        for payload in payloads {
+            if is_encrypted(&payload) {
+                result.push(decrypt(
+                    &key,
+                    aggregate_type,
+                    aggregate_id,
+                    &payload,
+                )?);
+            }
+            else {
+                result.push(payload);
+            }
+        }
+
+        Ok(result)
+    }
+}
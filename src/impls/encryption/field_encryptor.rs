@@ -0,0 +1,242 @@
+use async_trait::async_trait;
+use log::trace;
+use serde_json::Value;
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    IKeyProvider,
+    IPayloadTransformer,
+};
+
+use super::aes_gcm::{
+    decrypt_value,
+    encrypt_value,
+    is_encrypted,
+    key_id,
+};
+
+/// Payload transformer encrypting whole payloads, or only the fields
+/// selected by their JSON pointers, with the current key of a key
+/// provider.
+///
+/// The encrypted values are bound to their aggregate and location
+/// in the payload, and remember the id of their key so that they stay
+/// readable after a key rotation. Plain values are left unchanged on
+/// decoding.
+pub struct FieldEncryptor<KP: IKeyProvider> {
+    key_provider: KP,
+    fields: Vec<String>,
+}
+
+impl<KP: IKeyProvider> FieldEncryptor<KP> {
+    /// Constructor encrypting the whole payloads
+    pub fn new(key_provider: KP) -> Self {
+        let x = Self {
+            key_provider,
+            fields: Vec::new(),
+        };
+
+        trace!("Created new field encryptor");
+
+        x
+    }
+
+    /// Encrypt only the field at the JSON `pointer`, e.g.
+    /// `/NameAdded/changed_name`, instead of the whole payload
+    pub fn with_field(
+        mut self,
+        pointer: &str,
+    ) -> Self {
+        self.fields.push(pointer.to_string());
+        self
+    }
+
+    /// The key provider, e.g. to rotate its keys
+    pub fn key_provider(&self) -> &KP {
+        &self.key_provider
+    }
+
+    async fn decrypt(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        pointer: &str,
+        value: &Value,
+    ) -> Result<Value, Error> {
+        let kid = match key_id(value) {
+            Some(x) => x,
+            None => {
+                return Err(Error::new(
+                    format!(
+                        "encrypted field '{}' of aggregate id '{}' \
+                         has no key id",
+                        pointer, aggregate_id
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let key = match self.key_provider.load_key(kid).await? {
+            Some(x) => x,
+            None => {
+                return Err(Error::new(
+                    format!("unknown key id '{}'", kid).as_str(),
+                ));
+            },
+        };
+
+        match decrypt_value(
+            &key,
+            associated_data(aggregate_type, aggregate_id, pointer)
+                .as_bytes(),
+            value,
+        ) {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to decrypt field '{}' of aggregate \
+                         id '{}' with error: {}",
+                        pointer, aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+}
+
+fn associated_data(
+    aggregate_type: &str,
+    aggregate_id: &str,
+    pointer: &str,
+) -> String {
+    format!(
+        "{}\0{}\0{}",
+        aggregate_type, aggregate_id, pointer
+    )
+}
+
+fn find_encrypted(
+    value: &Value,
+    pointer: String,
+    result: &mut Vec<String>,
+) {
+    if is_encrypted(value) {
+        result.push(pointer);
+        return;
+    }
+
+    match value {
+        Value::Object(x) => {
+            for (k, v) in x {
+                let k = k.replace('~', "~0").replace('/', "~1");
+                find_encrypted(
+                    v,
+                    format!("{}/{}", pointer, k),
+                    result,
+                );
+            }
+        },
+        Value::Array(x) => {
+            for (i, v) in x.iter().enumerate() {
+                find_encrypted(
+                    v,
+                    format!("{}/{}", pointer, i),
+                    result,
+                );
+            }
+        },
+        _ => {},
+    }
+}
+
+#[async_trait]
+impl<KP: IKeyProvider> IPayloadTransformer for FieldEncryptor<KP> {
+    /// Encrypt the `payload`, or its selected fields, with the
+    /// current key
+    async fn encode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        mut payload: Value,
+    ) -> Result<Value, Error> {
+        let (kid, key) = self.key_provider.current_key().await?;
+
+        let pointers = if self.fields.is_empty() {
+            vec![String::new()]
+        }
+        else {
+            self.fields.clone()
+        };
+
+        for pointer in pointers {
+            let value = match payload.pointer_mut(&pointer) {
+                Some(x) => x,
+                None => {
+                    continue;
+                },
+            };
+
+            *value = match encrypt_value(
+                &key,
+                Some(&kid),
+                associated_data(
+                    aggregate_type,
+                    aggregate_id,
+                    &pointer,
+                )
+                .as_bytes(),
+                value,
+            ) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to encrypt field '{}' of \
+                             aggregate id '{}' with error: {}",
+                            pointer, aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+        }
+
+        Ok(payload)
+    }
+
+    /// Decrypt every encrypted field of the `payload` with the key
+    /// it was encrypted with
+    async fn decode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        mut payload: Value,
+    ) -> Result<Value, Error> {
+        let mut pointers = Vec::new();
+        find_encrypted(&payload, String::new(), &mut pointers);
+
+        for pointer in pointers {
+            let value = match payload.pointer_mut(&pointer) {
+                Some(x) => x,
+                None => {
+                    continue;
+                },
+            };
+
+            *value = self
+                .decrypt(
+                    aggregate_type,
+                    aggregate_id,
+                    &pointer,
+                    value,
+                )
+                .await?;
+        }
+
+        Ok(payload)
+    }
+}
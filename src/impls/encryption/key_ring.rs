@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::IKeyProvider;
+
+struct Keys {
+    current: String,
+    keys: HashMap<String, Vec<u8>>,
+}
+
+/// In memory key provider holding every key ever used, the keys are
+/// typically loaded from the configuration or a secrets manager at
+/// startup.
+pub struct KeyRing {
+    keys: RwLock<Keys>,
+}
+
+impl KeyRing {
+    /// Constructor with the key `key_id` as current key
+    pub fn new(
+        key_id: &str,
+        key: &[u8],
+    ) -> Self {
+        let mut keys = HashMap::new();
+        keys.insert(key_id.to_string(), key.to_vec());
+
+        let x = Self {
+            keys: RwLock::new(Keys {
+                current: key_id.to_string(),
+                keys,
+            }),
+        };
+
+        trace!("Created new key ring");
+
+        x
+    }
+
+    /// Add a previous key still needed to read old payloads
+    pub fn add_key(
+        &self,
+        key_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        match self.keys.write() {
+            Ok(mut x) => {
+                x.keys
+                    .insert(key_id.to_string(), key.to_vec());
+                Ok(())
+            },
+            Err(e) => Err(Error::new(e.to_string().as_str())),
+        }
+    }
+
+    /// Add the key `key_id` and encrypt the new payloads with it
+    pub fn rotate(
+        &self,
+        key_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        debug!("rotating to key id '{}'", key_id);
+
+        match self.keys.write() {
+            Ok(mut x) => {
+                x.keys
+                    .insert(key_id.to_string(), key.to_vec());
+                x.current = key_id.to_string();
+                Ok(())
+            },
+            Err(e) => Err(Error::new(e.to_string().as_str())),
+        }
+    }
+}
+
+#[async_trait]
+impl IKeyProvider for KeyRing {
+    /// Id and value of the key encrypting new payloads
+    async fn current_key(&self) -> Result<(String, Vec<u8>), Error> {
+        let keys = match self.keys.read() {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+        match keys.keys.get(&keys.current) {
+            Some(x) => Ok((keys.current.clone(), x.clone())),
+            None => {
+                Err(Error::new(
+                    format!(
+                        "current key id '{}' is missing",
+                        keys.current
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Load the key `key_id`, `None` if it is unknown
+    async fn load_key(
+        &self,
+        key_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        match self.keys.read() {
+            Ok(x) => Ok(x.keys.get(key_id).cloned()),
+            Err(e) => Err(Error::new(e.to_string().as_str())),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//!
+//! Payload encryption at rest
+
+pub use aes_gcm::{
+    generate_key,
+    KEY_LEN,
+};
+pub use crypto_shredder::CryptoShredder;
+pub use field_encryptor::FieldEncryptor;
+pub use key_ring::KeyRing;
+
+mod aes_gcm;
+mod crypto_shredder;
+mod field_encryptor;
+mod key_ring;
+
+mod test;
@@ -0,0 +1,5 @@
+#[cfg(test)]
+mod test_crypto_shredder;
+
+#[cfg(test)]
+mod test_field_encryptor;
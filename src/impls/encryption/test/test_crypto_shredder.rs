@@ -0,0 +1,140 @@
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::Error;
+
+use crate::{
+    encryption::CryptoShredder,
+    memory_store::KeyStore,
+    IKeyStore,
+    IPayloadTransformer,
+};
+
+async fn check_encode_decode() -> Result<(), Error> {
+    let shredder = CryptoShredder::new(KeyStore::default());
+
+    let payload = json!({
+        "NameAdded": {
+            "changed_name": "test_name"
+        }
+    });
+
+    let encoded = shredder
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_ne!(encoded, payload);
+    assert!(!encoded
+        .to_string()
+        .contains("test_name"));
+
+    let decoded = shredder
+        .decode("customer", "test_id_A", encoded.clone())
+        .await?;
+    assert_eq!(decoded, payload);
+
+    // the aggregate id is bound to the ciphertext
+    assert!(shredder
+        .decode("customer", "test_id_B", encoded.clone())
+        .await
+        .is_err());
+
+    // so is the aggregate type, which has its own keys
+    assert!(shredder
+        .decode(
+            "other_type",
+            "test_id_A",
+            encoded.clone()
+        )
+        .await
+        .is_err());
+
+    let decoded = shredder
+        .decode_all(
+            "customer",
+            "test_id_A",
+            vec![encoded, payload.clone()],
+        )
+        .await?;
+    assert_eq!(
+        decoded,
+        vec![payload.clone(), payload.clone()]
+    );
+
+    // plain payloads stored before the encryption are readable
+    let decoded = shredder
+        .decode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(decoded, payload);
+
+    Ok(())
+}
+
+async fn check_erase() -> Result<(), Error> {
+    let keys = Arc::new(RwLock::new(HashMap::new()));
+    let key_store = KeyStore::new(keys.clone());
+    let shredder = CryptoShredder::new(KeyStore::new(keys));
+
+    let payload = json!({"name": "test_name"});
+
+    let encoded = shredder
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+
+    assert!(key_store
+        .load_key("customer", "test_id_A")
+        .await?
+        .is_some());
+
+    shredder
+        .erase("customer", "test_id_A")
+        .await?;
+
+    assert_eq!(
+        key_store
+            .load_key("customer", "test_id_A")
+            .await?,
+        Some(Vec::new())
+    );
+
+    let result = shredder
+        .decode("customer", "test_id_A", encoded)
+        .await;
+    assert!(result.is_err());
+
+    // no new key is generated for an erased aggregate
+    let result = shredder
+        .encode("customer", "test_id_A", payload.clone())
+        .await;
+    assert!(result.is_err());
+
+    // other aggregate types keep their own keys
+    let encoded = shredder
+        .encode(
+            "other_type",
+            "test_id_A",
+            payload.clone(),
+        )
+        .await?;
+    let decoded = shredder
+        .decode("other_type", "test_id_A", encoded)
+        .await?;
+    assert_eq!(decoded, payload);
+
+    Ok(())
+}
+
+#[test]
+fn test_encode_decode() {
+    tokio_test::block_on(check_encode_decode()).unwrap();
+}
+
+#[test]
+fn test_erase() {
+    tokio_test::block_on(check_erase()).unwrap();
+}
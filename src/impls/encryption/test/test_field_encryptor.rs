@@ -0,0 +1,172 @@
+use serde_json::json;
+
+use cqrs_es2::Error;
+
+use crate::{
+    encryption::{
+        generate_key,
+        FieldEncryptor,
+        KeyRing,
+    },
+    IPayloadTransformer,
+};
+
+async fn check_encrypt_payload() -> Result<(), Error> {
+    let key = generate_key().unwrap();
+    let encryptor = FieldEncryptor::new(KeyRing::new("key_1", &key));
+
+    let payload = json!({
+        "NameAdded": {
+            "changed_name": "test_name"
+        }
+    });
+
+    let encoded = encryptor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(
+        encoded["cqrs-es2:encrypted"]["kid"],
+        json!("key_1")
+    );
+    assert!(!encoded
+        .to_string()
+        .contains("test_name"));
+
+    let decoded = encryptor
+        .decode("customer", "test_id_A", encoded.clone())
+        .await?;
+    assert_eq!(decoded, payload);
+
+    // the aggregate id is bound to the ciphertext
+    assert!(encryptor
+        .decode("customer", "test_id_B", encoded)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+async fn check_encrypt_fields() -> Result<(), Error> {
+    let key = generate_key().unwrap();
+    let encryptor = FieldEncryptor::new(KeyRing::new("key_1", &key))
+        .with_field("/name")
+        .with_field("/email")
+        .with_field("/missing");
+
+    let payload = json!({
+        "name": "test name",
+        "email": "test@email.com",
+        "latest_address": "one address"
+    });
+
+    let encoded = encryptor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(
+        encoded["latest_address"],
+        json!("one address")
+    );
+    assert_eq!(
+        encoded["name"]["cqrs-es2:encrypted"]["kid"],
+        json!("key_1")
+    );
+    assert!(!encoded
+        .to_string()
+        .contains("test name"));
+    assert!(!encoded
+        .to_string()
+        .contains("test@email.com"));
+    assert!(encoded.get("missing").is_none());
+
+    let decoded = encryptor
+        .decode("customer", "test_id_A", encoded.clone())
+        .await?;
+    assert_eq!(decoded, payload);
+
+    // encrypted values are bound to their field
+    let mut swapped = encoded.clone();
+    swapped["name"] = encoded["email"].clone();
+    assert!(encryptor
+        .decode("customer", "test_id_A", swapped)
+        .await
+        .is_err());
+
+    // plain payloads stored before the encryption are readable
+    let decoded = encryptor
+        .decode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(decoded, payload);
+
+    Ok(())
+}
+
+async fn check_rotate_keys() -> Result<(), Error> {
+    let encryptor = FieldEncryptor::new(KeyRing::new(
+        "key_1",
+        &generate_key().unwrap(),
+    ));
+
+    let payload = json!({"name": "test_name"});
+
+    let encoded_1 = encryptor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+
+    encryptor
+        .key_provider()
+        .rotate("key_2", &generate_key().unwrap())?;
+
+    let encoded_2 = encryptor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+    assert_eq!(
+        encoded_2["cqrs-es2:encrypted"]["kid"],
+        json!("key_2")
+    );
+
+    // payloads encrypted with the previous key are still readable
+    let decoded = encryptor
+        .decode("customer", "test_id_A", encoded_1)
+        .await?;
+    assert_eq!(decoded, payload);
+
+    let decoded = encryptor
+        .decode("customer", "test_id_A", encoded_2)
+        .await?;
+    assert_eq!(decoded, payload);
+
+    // unknown keys
+    let encryptor = FieldEncryptor::new(KeyRing::new(
+        "key_3",
+        &generate_key().unwrap(),
+    ));
+    let encoded_3 = encryptor
+        .encode("customer", "test_id_A", payload.clone())
+        .await?;
+
+    let encryptor = FieldEncryptor::new(KeyRing::new(
+        "key_4",
+        &generate_key().unwrap(),
+    ));
+    assert!(encryptor
+        .decode("customer", "test_id_A", encoded_3)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_encrypt_payload() {
+    tokio_test::block_on(check_encrypt_payload()).unwrap();
+}
+
+#[test]
+fn test_encrypt_fields() {
+    tokio_test::block_on(check_encrypt_fields()).unwrap();
+}
+
+#[test]
+fn test_rotate_keys() {
+    tokio_test::block_on(check_rotate_keys()).unwrap();
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    ArchiveMarker,
+    IArchiveMarkerStore,
+};
+
+type LockedMarkerMap = RwLock<HashMap<String, ArchiveMarker>>;
+
+/// Async memory archive marker store useful for testing purposes
+/// only
+#[derive(Default)]
+pub struct ArchiveMarkerStore {
+    markers: Arc<LockedMarkerMap>,
+}
+
+impl ArchiveMarkerStore {
+    /// Constructor
+    pub fn new(markers: Arc<LockedMarkerMap>) -> Self {
+        let x = Self { markers };
+
+        trace!(
+            "Created new async memory archive marker store from \
+             passed Arcs"
+        );
+
+        x
+    }
+
+    fn key_of(
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> String {
+        format!("{};{}", aggregate_type, aggregate_id)
+    }
+}
+
+#[async_trait]
+impl IArchiveMarkerStore for ArchiveMarkerStore {
+    /// Save the `marker` of an aggregate of `aggregate_type`,
+    /// replacing its previous marker
+    async fn save_marker(
+        &self,
+        aggregate_type: &str,
+        marker: &ArchiveMarker,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing archive marker for aggregate id '{}'",
+            marker.aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.markers.write().unwrap().insert(
+            Self::key_of(aggregate_type, &marker.aggregate_id),
+            marker.clone(),
+        );
+
+        Ok(())
+    }
+
+    /// Load the marker of `aggregate_id`, `None` if it was never
+    /// archived
+    async fn load_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<ArchiveMarker>, Error> {
+        trace!(
+            "loading archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        Ok(self
+            .markers
+            .read()
+            .unwrap()
+            .get(&Self::key_of(
+                aggregate_type,
+                aggregate_id,
+            ))
+            .cloned())
+    }
+
+    /// List up to `limit` ids of the archived aggregates of
+    /// `aggregate_type` in ascending order, starting after the id
+    /// `after` when given
+    async fn list_archived_ids(
+        &self,
+        aggregate_type: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' archived aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        let prefix = Self::key_of(aggregate_type, "");
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        let mut result: Vec<String> = self
+            .markers
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(k, _)| k.starts_with(&prefix))
+            .map(|(_, x)| x.aggregate_id.clone())
+            .filter(|x| {
+                match after {
+                    Some(after) => x.as_str() > after,
+                    None => true,
+                }
+            })
+            .collect();
+
+        result.sort();
+        result.truncate(limit.max(0) as usize);
+
+        Ok(result)
+    }
+
+    /// Delete the marker of `aggregate_id`
+    async fn delete_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.markers
+            .write()
+            .unwrap()
+            .remove(&Self::key_of(
+                aggregate_type,
+                aggregate_id,
+            ));
+
+        Ok(())
+    }
+}
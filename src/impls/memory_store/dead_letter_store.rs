@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    DeadLetter,
+    IDeadLetterStore,
+};
+
+type LockedDeadLetterMap =
+    RwLock<BTreeMap<(String, String, String, i64), DeadLetter>>;
+
+/// Async memory dead letter store useful for testing purposes only
+#[derive(Default)]
+pub struct DeadLetterStore {
+    letters: Arc<LockedDeadLetterMap>,
+}
+
+impl DeadLetterStore {
+    /// Constructor
+    pub fn new(letters: Arc<LockedDeadLetterMap>) -> Self {
+        let x = Self { letters };
+
+        trace!(
+            "Created new async memory dead letter store from passed \
+             Arcs"
+        );
+
+        x
+    }
+
+    fn key_of(
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> (String, String, String, i64) {
+        (
+            aggregate_type.to_string(),
+            letter.dispatcher.clone(),
+            letter.aggregate_id.clone(),
+            letter.sequence,
+        )
+    }
+}
+
+#[async_trait]
+impl IDeadLetterStore for DeadLetterStore {
+    /// Save the `letter` of an aggregate of `aggregate_type`,
+    /// replacing the previous one of the same dispatcher, aggregate
+    /// id and sequence
+    async fn save_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.letters.write().unwrap().insert(
+            Self::key_of(aggregate_type, letter),
+            letter.clone(),
+        );
+
+        Ok(())
+    }
+
+    /// Load up to `limit` dead letters of `dispatcher` ordered by
+    /// aggregate id and sequence
+    async fn load_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}'",
+            dispatcher
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        Ok(self
+            .letters
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(k, _)| {
+                k.0 == aggregate_type && k.1 == dispatcher
+            })
+            .take(limit.max(0) as usize)
+            .map(|(_, v)| v.clone())
+            .collect())
+    }
+
+    /// Load the dead letters of `dispatcher` for `aggregate_id`
+    /// ordered by sequence
+    async fn load_aggregate_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}' for aggregate \
+             id '{}'",
+            dispatcher,
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        Ok(self
+            .letters
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(k, _)| {
+                k.0 == aggregate_type &&
+                    k.1 == dispatcher &&
+                    k.2 == aggregate_id
+            })
+            .map(|(_, v)| v.clone())
+            .collect())
+    }
+
+    /// Delete the `letter`
+    async fn delete_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.letters
+            .write()
+            .unwrap()
+            .remove(&Self::key_of(aggregate_type, letter));
+
+        Ok(())
+    }
+}
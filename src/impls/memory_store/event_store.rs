@@ -181,4 +181,101 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             Some(x) => Ok(x.clone()),
         }
     }
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+
+        let mut result: Vec<String> = self
+            .events
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|x| {
+                match after {
+                    None => true,
+                    Some(after) => x.as_str() > after,
+                }
+            })
+            .cloned()
+            .collect();
+
+        result.sort();
+        result.truncate(limit as usize);
+
+        Ok(result)
+    }
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        trace!("counting aggregates");
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+
+        Ok(self.events.read().unwrap().len() as i64)
+    }
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting aggregate id '{}'",
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.events
+            .write()
+            .unwrap()
+            .remove(aggregate_id);
+        self.snapshots
+            .write()
+            .unwrap()
+            .remove(aggregate_id);
+
+        Ok(())
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting events up to sequence '{}' for aggregate id \
+             '{}'",
+            sequence, aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        if let Some(x) = self
+            .events
+            .write()
+            .unwrap()
+            .get_mut(aggregate_id)
+        {
+            x.retain(|x| x.sequence > sequence);
+        }
+
+        Ok(())
+    }
 }
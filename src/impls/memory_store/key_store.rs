@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::IKeyStore;
+
+type LockedKeyMap = RwLock<HashMap<(String, String), Vec<u8>>>;
+
+/// Async memory key store useful for testing purposes only
+#[derive(Default)]
+pub struct KeyStore {
+    keys: Arc<LockedKeyMap>,
+}
+
+impl KeyStore {
+    /// Constructor
+    pub fn new(keys: Arc<LockedKeyMap>) -> Self {
+        let x = Self { keys };
+
+        trace!("Created new async memory key store from passed Arcs");
+
+        x
+    }
+}
+
+#[async_trait]
+impl IKeyStore for KeyStore {
+    /// Save the key of the aggregate unless it already has one
+    async fn save_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        debug!(
+            "storing a new key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.keys
+            .write()
+            .unwrap()
+            .entry((
+                aggregate_type.to_string(),
+                aggregate_id.to_string(),
+            ))
+            .or_insert_with(|| key.to_vec());
+
+        Ok(())
+    }
+
+    /// Load the key of the aggregate, `None` if it has none and an
+    /// empty key if it was erased
+    async fn load_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        trace!(
+            "loading key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        Ok(self
+            .keys
+            .read()
+            .unwrap()
+            .get(&(
+                aggregate_type.to_string(),
+                aggregate_id.to_string(),
+            ))
+            .cloned())
+    }
+
+    /// Destroy the key of the aggregate, leaving an empty key as its
+    /// tombstone
+    async fn delete_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "erasing key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.keys.write().unwrap().insert(
+            (
+                aggregate_type.to_string(),
+                aggregate_id.to_string(),
+            ),
+            Vec::new(),
+        );
+
+        Ok(())
+    }
+}
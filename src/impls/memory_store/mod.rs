@@ -1,9 +1,17 @@
 //!
 //! A simple memory store for testing purposes only
 
+pub use archive_marker_store::ArchiveMarkerStore;
+pub use dead_letter_store::DeadLetterStore;
 pub use event_store::EventStore;
+pub use key_store::KeyStore;
+pub use projection_store::ProjectionStore;
 pub use query_store::QueryStore;
 
+mod archive_marker_store;
+mod dead_letter_store;
 mod event_store;
+mod key_store;
+mod projection_store;
 mod query_store;
 mod test;
@@ -0,0 +1,307 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    marker::PhantomData,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use crate::repository::{
+    projection_conflict,
+    sequence_conflict,
+    AppliedSequence,
+    IEventDispatcher,
+    IProjection,
+    IProjectionStore,
+    ProjectionContext,
+};
+
+type LockedProjectionContextMap<P> =
+    RwLock<BTreeMap<String, ProjectionContext<P>>>;
+
+type LockedAppliedSequenceMap = RwLock<HashMap<String, i64>>;
+
+/// Async memory projection store useful for testing purposes only
+pub struct ProjectionStore<
+    C: ICommand,
+    E: IEvent,
+    P: IProjection<C, E>,
+> {
+    projections: Arc<LockedProjectionContextMap<P>>,
+    applied: Arc<LockedAppliedSequenceMap>,
+    _phantom: PhantomData<(C, E)>,
+}
+
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    ProjectionStore<C, E, P>
+{
+    /// Constructor
+    pub fn new(
+        projections: Arc<LockedProjectionContextMap<P>>,
+        applied: Arc<LockedAppliedSequenceMap>,
+    ) -> Self {
+        let x = Self {
+            projections,
+            applied,
+            _phantom: PhantomData,
+        };
+
+        trace!(
+            "Created new async memory projection store from passed \
+             Arcs"
+        );
+
+        x
+    }
+}
+
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>> Default
+    for ProjectionStore<C, E, P>
+{
+    fn default() -> Self {
+        let x = Self {
+            projections: Default::default(),
+            applied: Default::default(),
+            _phantom: PhantomData,
+        };
+
+        trace!("Created default async memory projection store");
+
+        x
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IProjectionStore<C, E, P> for ProjectionStore<C, E, P>
+{
+    /// saves the updated row
+    async fn save_projection(
+        &mut self,
+        context: ProjectionContext<P>,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+        let key = context.key.clone();
+
+        debug!(
+            "storing projection '{}' with key '{}'",
+            projection_type, &key
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        let mut map = self.projections.write().unwrap();
+
+        let version = map.get(&key).map_or(0, |x| x.version);
+
+        if version != expected_version {
+            return Err(projection_conflict(
+                projection_type,
+                &key,
+                expected_version,
+            ));
+        }
+
+        map.insert(key, context);
+
+        Ok(())
+    }
+
+    /// saves the updated rows and the applied sequences, all of them
+    /// or none
+    async fn save_projections(
+        &mut self,
+        contexts: Vec<(ProjectionContext<P>, i64)>,
+        applied: Vec<AppliedSequence>,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        debug!(
+            "storing '{}' rows of projection '{}'",
+            contexts.len(),
+            projection_type
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        let mut map = self.projections.write().unwrap();
+        let mut sequences = self.applied.write().unwrap();
+
+        for x in &applied {
+            let sequence = sequences
+                .get(&x.aggregate_id)
+                .copied()
+                .unwrap_or_default();
+
+            if sequence != x.expected_sequence {
+                return Err(sequence_conflict(
+                    projection_type,
+                    &x.aggregate_id,
+                    x.expected_sequence,
+                ));
+            }
+        }
+
+        for (context, expected_version) in &contexts {
+            let version = map
+                .get(&context.key)
+                .map_or(0, |x| x.version);
+
+            if version != *expected_version {
+                return Err(projection_conflict(
+                    projection_type,
+                    &context.key,
+                    *expected_version,
+                ));
+            }
+        }
+
+        for (context, _) in contexts {
+            map.insert(context.key.clone(), context);
+        }
+
+        for x in applied {
+            sequences.insert(x.aggregate_id, x.sequence);
+        }
+
+        Ok(())
+    }
+
+    /// loads the sequence of the last event of `aggregate_id` applied
+    /// to the rows
+    async fn load_applied_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        trace!(
+            "loading applied sequence of projection '{}' with \
+             aggregate id '{}'",
+            P::projection_type(),
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        Ok(self
+            .applied
+            .read()
+            .unwrap()
+            .get(aggregate_id)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    /// loads the row of `key`
+    async fn load_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<ProjectionContext<P>, Error> {
+        trace!(
+            "loading projection '{}' with key '{}'",
+            P::projection_type(),
+            key
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        match self
+            .projections
+            .read()
+            .unwrap()
+            .get(key)
+        {
+            None => {
+                Ok(ProjectionContext::new(
+                    key.to_string(),
+                    0,
+                    Default::default(),
+                ))
+            },
+            Some(x) => Ok(x.clone()),
+        }
+    }
+
+    /// loads up to `limit` rows in ascending order of key, starting
+    /// after the key `after` when given
+    async fn load_all_projections(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ProjectionContext<P>>, Error> {
+        trace!(
+            "loading '{}' projections '{}' after '{:?}'",
+            limit,
+            P::projection_type(),
+            after
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        Ok(self
+            .projections
+            .read()
+            .unwrap()
+            .values()
+            .filter(|x| {
+                match after {
+                    Some(after) => x.key.as_str() > after,
+                    None => true,
+                }
+            })
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    /// deletes the row of `key`
+    async fn delete_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting projection '{}' with key '{}'",
+            P::projection_type(),
+            key
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.projections
+            .write()
+            .unwrap()
+            .remove(key);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IEventDispatcher<C, E> for ProjectionStore<C, E, P>
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        events: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        self.dispatch_projection_events(events)
+            .await
+    }
+}
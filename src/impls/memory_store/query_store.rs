@@ -23,8 +23,11 @@ use cqrs_es2::{
 };
 
 use crate::repository::{
+    query_conflict,
     IEventDispatcher,
+    IFilteredQueryStore,
     IQueryStore,
+    QueryFilter,
 };
 
 type LockedQueryContextMap<C, E, Q> =
@@ -94,6 +97,7 @@ impl<
     async fn save_query(
         &mut self,
         context: QueryContext<C, E, Q>,
+        expected_version: i64,
     ) -> Result<(), Error> {
         let query_type = Q::query_type();
         let aggregate_id = context.aggregate_id.clone();
@@ -106,6 +110,19 @@ impl<
         // uninteresting unwrap: this is not a struct for production
         // use
         let mut map = self.queries.write().unwrap();
+
+        let version = map
+            .get(&aggregate_id)
+            .map_or(0, |x| x.version);
+
+        if version != expected_version {
+            return Err(query_conflict(
+                query_type,
+                &aggregate_id,
+                expected_version,
+            ));
+        }
+
         map.insert(aggregate_id, context);
 
         Ok(())
@@ -143,6 +160,98 @@ impl<
             Some(x) => Ok(x.clone()),
         }
     }
+
+    /// loads the most recent queries of several aggregates
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading query '{}' for '{}' aggregate ids",
+            query_type,
+            aggregate_ids.len()
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        let map = self.queries.read().unwrap();
+
+        Ok(aggregate_ids
+            .iter()
+            .map(|aggregate_id| {
+                match map.get(aggregate_id) {
+                    None => {
+                        QueryContext::new(
+                            aggregate_id.to_string(),
+                            0,
+                            Default::default(),
+                        )
+                    },
+                    Some(x) => x.clone(),
+                }
+            })
+            .collect())
+    }
+
+    /// loads up to `limit` queries in ascending order of aggregate
+    /// id, starting after the id `after` when given
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading '{}' queries '{}' after '{:?}'",
+            limit,
+            query_type,
+            after
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        let map = self.queries.read().unwrap();
+
+        let mut result: Vec<QueryContext<C, E, Q>> = map
+            .values()
+            .filter(|x| {
+                match after {
+                    Some(after) => x.aggregate_id.as_str() > after,
+                    None => true,
+                }
+            })
+            .cloned()
+            .collect();
+
+        result.sort_by(|a, b| a.aggregate_id.cmp(&b.aggregate_id));
+        result.truncate(limit.max(0) as usize);
+
+        Ok(result)
+    }
+
+    /// deletes the query of `aggregate_id`
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting query '{}' for aggregate id '{}'",
+            Q::query_type(),
+            aggregate_id
+        );
+
+        // uninteresting unwrap: this is not a struct for production
+        // use
+        self.queries
+            .write()
+            .unwrap()
+            .remove(aggregate_id);
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -162,3 +271,39 @@ impl<
             .await
     }
 }
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+    > IFilteredQueryStore<C, E, A, Q> for QueryStore<C, E, A, Q>
+{
+    /// Load the queries matching the `filter` ordered by the sort
+    /// fields then by aggregate id
+    async fn filter_queries(
+        &mut self,
+        filter: &QueryFilter,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let query_type = Q::query_type();
+
+        trace!(
+            "filtering queries '{}' with '{:?}'",
+            query_type,
+            filter
+        );
+
+        // uninteresting unwrap: this will not be used in production,
+        // for tests only
+        let contexts = self
+            .queries
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+
+        filter.apply(contexts)
+    }
+}
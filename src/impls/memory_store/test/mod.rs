@@ -1,5 +1,8 @@
 #[cfg(test)]
 mod test_event_store;
 
+#[cfg(test)]
+mod test_projection_store;
+
 #[cfg(test)]
 mod test_query_store;
@@ -158,6 +158,165 @@ async fn check_save_load_snapshots() -> Result<(), Error> {
     Ok(())
 }
 
+async fn check_list_aggregate_ids() -> Result<(), Error> {
+    let mut store = ThisEventStore::default();
+
+    assert_eq!(
+        0,
+        store.count_aggregates().await.unwrap()
+    );
+
+    for id in &["test_id_C", "test_id_A", "test_id_B"] {
+        store
+            .save_events(&vec![EventContext::new(
+                id.to_string(),
+                1,
+                CustomerEvent::NameAdded(NameAdded {
+                    changed_name: "test_event_A".to_string(),
+                }),
+                get_metadata(),
+            )])
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        3,
+        store.count_aggregates().await.unwrap()
+    );
+
+    let ids = store
+        .list_aggregate_ids(None, 2)
+        .await
+        .unwrap();
+    assert_eq!(ids, vec!["test_id_A", "test_id_B"]);
+
+    let ids = store
+        .list_aggregate_ids(Some("test_id_B"), 2)
+        .await
+        .unwrap();
+    assert_eq!(ids, vec!["test_id_C"]);
+
+    Ok(())
+}
+
+async fn check_delete_aggregate() -> Result<(), Error> {
+    let mut store = ThisEventStore::default();
+
+    let id = "test_id_A";
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test_event_A".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    let context = AggregateContext::new(
+        id.to_string(),
+        1,
+        Customer {
+            customer_id: "customer 1".to_string(),
+            name: "test_event_A".to_string(),
+            email: "".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+    store
+        .save_aggregate_snapshot(context)
+        .await
+        .unwrap();
+
+    store
+        .delete_aggregate(id)
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(id).await.unwrap();
+    assert_eq!(0, stored_events.len());
+
+    let stored_context = store
+        .load_aggregate_from_snapshot(id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_context,
+        AggregateContext::new(id.to_string(), 0, Default::default())
+    );
+
+    Ok(())
+}
+
+async fn check_delete_events_until() -> Result<(), Error> {
+    let mut store = ThisEventStore::default();
+
+    let id = "test_id_A";
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test_event_A".to_string(),
+            }),
+            get_metadata(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test A".to_string(),
+            }),
+            get_metadata(),
+        ),
+    ];
+
+    let context = AggregateContext::new(
+        id.to_string(),
+        1,
+        Customer {
+            customer_id: "customer 1".to_string(),
+            name: "test_event_A".to_string(),
+            email: "".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+    store
+        .save_aggregate_snapshot(context.clone())
+        .await
+        .unwrap();
+
+    store
+        .delete_events_until(id, 1)
+        .await
+        .unwrap();
+
+    // the later events and the snapshot are kept
+    let stored_events = store.load_events(id).await.unwrap();
+    assert_eq!(stored_events, contexts[1..].to_vec());
+
+    let stored_context = store
+        .load_aggregate_from_snapshot(id)
+        .await
+        .unwrap();
+    assert_eq!(stored_context, context);
+
+    Ok(())
+}
+
 #[test]
 fn test_save_load_events() {
     tokio_test::block_on(check_save_load_events()).unwrap();
@@ -167,3 +326,18 @@ fn test_save_load_events() {
 fn test_save_load_snapshots() {
     tokio_test::block_on(check_save_load_snapshots()).unwrap();
 }
+
+#[test]
+fn test_list_aggregate_ids() {
+    tokio_test::block_on(check_list_aggregate_ids()).unwrap();
+}
+
+#[test]
+fn test_delete_aggregate() {
+    tokio_test::block_on(check_delete_aggregate()).unwrap();
+}
+
+#[test]
+fn test_delete_events_until() {
+    tokio_test::block_on(check_delete_events_until()).unwrap();
+}
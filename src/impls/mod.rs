@@ -6,6 +6,20 @@
 ))]
 pub use sql::*;
 
+pub mod admin;
+
+#[cfg(feature = "with-archive")]
+pub mod archive;
+
+#[cfg(any(
+    feature = "with-zstd",
+    feature = "with-lz4",
+))]
+pub mod compression;
+
+#[cfg(feature = "with-encryption")]
+pub mod encryption;
+
 pub mod memory_store;
 
 #[cfg(any(
@@ -21,3 +35,5 @@ pub mod mongodb_store;
 
 #[cfg(feature = "with-redis")]
 pub mod redis_store;
+
+pub mod transfer;
@@ -0,0 +1,445 @@
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use log::{
+    debug,
+    trace,
+};
+use serde_json::Value;
+
+use mongodb::{
+    bson::{
+        doc,
+        Bson,
+        Document,
+    },
+    options::FindOptions,
+    Collection,
+    Cursor,
+    Database,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    event_record,
+    query_record,
+    snapshot_record,
+    IAdminStore,
+};
+
+use super::{
+    event_document::EventDocument,
+    payload::from_bson_payload,
+    query_document::QueryDocument,
+    snapshot_document::SnapshotDocument,
+};
+
+/// Async MongoDB admin store
+pub struct AdminStore {
+    db: Database,
+}
+
+impl AdminStore {
+    /// Constructor
+    pub fn new(db: Database) -> Self {
+        let x = Self { db };
+
+        trace!("Created new async MongoDB admin store");
+
+        x
+    }
+
+    fn get_events_collection(&self) -> Collection<EventDocument> {
+        self.db
+            .collection::<EventDocument>("events")
+    }
+
+    fn get_snapshots_collection(
+        &self
+    ) -> Collection<SnapshotDocument> {
+        self.db
+            .collection::<SnapshotDocument>("snapshots")
+    }
+
+    fn get_queries_collection(&self) -> Collection<QueryDocument> {
+        self.db
+            .collection::<QueryDocument>("queries")
+    }
+
+    async fn distinct(
+        &self,
+        field: &str,
+        filter: Document,
+    ) -> Result<Vec<String>, Error> {
+        let values = match self
+            .get_events_collection()
+            .distinct(field, filter, None)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to list {} from events table with \
+                         error: {}",
+                        field, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result: Vec<String> = values
+            .into_iter()
+            .filter_map(|x| {
+                match x {
+                    Bson::String(x) => Some(x),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        result.sort();
+
+        Ok(result)
+    }
+}
+
+fn json_payload(
+    aggregate_id: &str,
+    payload: Bson,
+) -> Result<Value, Error> {
+    match from_bson_payload(payload) {
+        Ok(x) => Ok(x),
+        Err(e) => {
+            Err(Error::new(
+                format!(
+                    "bad payload found for aggregate id '{}' with \
+                     error: {}",
+                    aggregate_id, e
+                )
+                .as_str(),
+            ))
+        },
+    }
+}
+
+async fn collect<
+    T: serde::de::DeserializeOwned + Unpin + Send + Sync,
+>(
+    mut cursor: Cursor<T>,
+    aggregate_id: &str,
+) -> Result<Vec<T>, Error> {
+    let mut result = Vec::new();
+
+    loop {
+        match cursor.try_next().await {
+            Ok(Some(x)) => result.push(x),
+            Ok(None) => break,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load next entry for aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        }
+    }
+
+    Ok(result)
+}
+
+#[async_trait]
+impl IAdminStore for AdminStore {
+    /// List the aggregate types having events
+    async fn list_aggregate_types(
+        &mut self
+    ) -> Result<Vec<String>, Error> {
+        trace!("listing aggregate types");
+
+        self.distinct("aggregate_type", doc! {})
+            .await
+    }
+
+    /// List up to `limit` ids of the aggregates of `aggregate_type`
+    /// having events in ascending order, starting after the id
+    /// `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        aggregate_type: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' '{}' aggregate ids after '{:?}'",
+            limit,
+            aggregate_type,
+            after
+        );
+
+        let mut filter = doc! {
+            "aggregate_type": aggregate_type,
+        };
+
+        if let Some(x) = after {
+            filter.insert("aggregate_id", doc! { "$gt": x });
+        }
+
+        let mut result = self
+            .distinct("aggregate_id", filter)
+            .await?;
+
+        result.truncate(limit.max(0) as usize);
+
+        Ok(result)
+    }
+
+    /// Load the event records of `aggregate_id`
+    async fn load_events(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<Value>, Error> {
+        trace!(
+            "loading events for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "sequence": 1 })
+            .build();
+
+        let cursor = match self
+            .get_events_collection()
+            .find(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "aggregate_id": aggregate_id,
+                },
+                find_options,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load events table for aggregate \
+                         id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for d in collect(cursor, aggregate_id).await? {
+            let metadata = match serde_json::to_value(&d.metadata) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad metadata found in events table for \
+                             aggregate id '{}' with error: {}",
+                            aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            result.push(event_record(
+                aggregate_type,
+                aggregate_id,
+                d.sequence,
+                json_payload(aggregate_id, d.payload)?,
+                metadata,
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Load the snapshot record of `aggregate_id`, `None` if it has
+    /// no snapshot
+    async fn load_snapshot(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Value>, Error> {
+        trace!(
+            "loading snapshot for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let d = match self
+            .get_snapshots_collection()
+            .find_one(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "aggregate_id": aggregate_id,
+                },
+                None,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load snapshots table for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match d {
+            None => Ok(None),
+            Some(d) => {
+                Ok(Some(snapshot_record(
+                    aggregate_type,
+                    aggregate_id,
+                    d.version,
+                    json_payload(aggregate_id, d.payload)?,
+                )))
+            },
+        }
+    }
+
+    /// Load the query records of all the query types of
+    /// `aggregate_id`
+    async fn load_queries(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<Value>, Error> {
+        trace!(
+            "loading queries for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "query_type": 1 })
+            .build();
+
+        let cursor = match self
+            .get_queries_collection()
+            .find(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "aggregate_id": aggregate_id,
+                },
+                find_options,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load queries table for aggregate \
+                         id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for d in collect(cursor, aggregate_id).await? {
+            result.push(query_record(
+                aggregate_type,
+                aggregate_id,
+                &d.query_type,
+                d.version,
+                json_payload(aggregate_id, d.payload)?,
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Delete the snapshot of `aggregate_id`, returns whether it had
+    /// one
+    async fn delete_snapshot(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<bool, Error> {
+        debug!(
+            "deleting snapshot of aggregate id '{}'",
+            aggregate_id
+        );
+
+        match self
+            .get_snapshots_collection()
+            .delete_one(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "aggregate_id": aggregate_id,
+                },
+                None,
+            )
+            .await
+        {
+            Ok(x) => Ok(x.deleted_count > 0),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to delete snapshot for aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Delete the queries of `query_type` of all the aggregates of
+    /// `aggregate_type`, returns the number of deleted queries
+    async fn delete_queries(
+        &mut self,
+        aggregate_type: &str,
+        query_type: &str,
+    ) -> Result<u64, Error> {
+        debug!(
+            "deleting '{}' queries of '{}' aggregates",
+            query_type, aggregate_type
+        );
+
+        match self
+            .get_queries_collection()
+            .delete_many(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "query_type": query_type,
+                },
+                None,
+            )
+            .await
+        {
+            Ok(x) => Ok(x.deleted_count),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to delete '{}' queries with error: \
+                         {}",
+                        query_type, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+}
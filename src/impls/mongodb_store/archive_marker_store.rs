@@ -0,0 +1,292 @@
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use log::{
+    debug,
+    trace,
+};
+
+use mongodb::{
+    bson::{
+        doc,
+        Document,
+    },
+    options::{
+        FindOptions,
+        ReplaceOptions,
+    },
+    Collection,
+    Database,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    ArchiveMarker,
+    IArchiveMarkerStore,
+};
+
+/// Async MongoDB archive marker store
+pub struct ArchiveMarkerStore {
+    db: Database,
+}
+
+impl ArchiveMarkerStore {
+    /// Constructor
+    pub fn new(db: Database) -> Self {
+        let x = Self { db };
+
+        trace!("Created new async MongoDB archive marker store");
+
+        x
+    }
+
+    fn get_markers_collection(&self) -> Collection<Document> {
+        self.db
+            .collection::<Document>("archived_aggregates")
+    }
+}
+
+fn id_of(
+    aggregate_type: &str,
+    aggregate_id: &str,
+) -> Document {
+    doc! {
+        "aggregate_type": aggregate_type,
+        "aggregate_id": aggregate_id,
+    }
+}
+
+#[async_trait]
+impl IArchiveMarkerStore for ArchiveMarkerStore {
+    /// Save the `marker` of an aggregate of `aggregate_type`,
+    /// replacing its previous marker
+    async fn save_marker(
+        &self,
+        aggregate_type: &str,
+        marker: &ArchiveMarker,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing archive marker for aggregate id '{}'",
+            marker.aggregate_id
+        );
+
+        let id = id_of(aggregate_type, &marker.aggregate_id);
+
+        let options = ReplaceOptions::builder()
+            .upsert(true)
+            .build();
+
+        match self
+            .get_markers_collection()
+            .replace_one(
+                doc! { "_id": id.clone() },
+                doc! {
+                    "_id": id,
+                    "last_sequence": marker.last_sequence,
+                    "location": &marker.location,
+                },
+                options,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert archive marker for \
+                         aggregate id '{}' with error: {}",
+                        marker.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load the marker of `aggregate_id`, `None` if it was never
+    /// archived
+    async fn load_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<ArchiveMarker>, Error> {
+        trace!(
+            "loading archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let entry = match self
+            .get_markers_collection()
+            .find_one(
+                doc! { "_id": id_of(aggregate_type, aggregate_id) },
+                None,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load archived_aggregates table \
+                         for aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let d = match entry {
+            Some(x) => x,
+            None => {
+                return Ok(None);
+            },
+        };
+
+        match (
+            d.get_i64("last_sequence"),
+            d.get_str("location"),
+        ) {
+            (Ok(last_sequence), Ok(location)) => {
+                Ok(Some(ArchiveMarker::new(
+                    aggregate_id,
+                    last_sequence,
+                    location,
+                )))
+            },
+            _ => {
+                Err(Error::new(
+                    format!(
+                        "bad marker found in archived_aggregates \
+                         table for aggregate id '{}'",
+                        aggregate_id
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// List up to `limit` ids of the archived aggregates of
+    /// `aggregate_type` in ascending order, starting after the id
+    /// `after` when given
+    async fn list_archived_ids(
+        &self,
+        aggregate_type: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' archived aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "_id.aggregate_id": 1 })
+            .limit(limit)
+            .build();
+
+        let mut cursor = match self
+            .get_markers_collection()
+            .find(
+                doc! {
+                    "_id.aggregate_type": aggregate_type,
+                    "_id.aggregate_id": { "$gt": after.unwrap_or("") },
+                },
+                find_options,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to list aggregate ids from \
+                         archived_aggregates table with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        loop {
+            let d = match cursor.try_next().await {
+                Ok(Some(x)) => x,
+                Ok(None) => {
+                    break;
+                },
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to list aggregate ids from \
+                             archived_aggregates table with error: \
+                             {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            match d
+                .get_document("_id")
+                .and_then(|x| x.get_str("aggregate_id"))
+            {
+                Ok(x) => result.push(x.to_string()),
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad id found in archived_aggregates \
+                             table with error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+        }
+
+        Ok(result)
+    }
+
+    /// Delete the marker of `aggregate_id`
+    async fn delete_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        match self
+            .get_markers_collection()
+            .delete_one(
+                doc! { "_id": id_of(aggregate_type, aggregate_id) },
+                None,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete archive marker for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use log::{
+    debug,
+    trace,
+};
+
+use mongodb::{
+    bson::{
+        doc,
+        Document,
+    },
+    options::{
+        FindOptions,
+        ReplaceOptions,
+    },
+    Collection,
+    Database,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    DeadLetter,
+    IDeadLetterStore,
+};
+
+use super::payload::{
+    from_bson_payload,
+    to_bson_payload,
+};
+
+/// Async MongoDB dead letter store
+pub struct DeadLetterStore {
+    db: Database,
+}
+
+impl DeadLetterStore {
+    /// Constructor
+    pub fn new(db: Database) -> Self {
+        let x = Self { db };
+
+        trace!("Created new async MongoDB dead letter store");
+
+        x
+    }
+
+    fn get_letters_collection(&self) -> Collection<Document> {
+        self.db
+            .collection::<Document>("dead_letters")
+    }
+
+    /// Load the dead letters of `dispatcher` matching `filter`
+    async fn find_letters(
+        &self,
+        dispatcher: &str,
+        filter: Document,
+        find_options: FindOptions,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        let mut cursor = match self
+            .get_letters_collection()
+            .find(filter, find_options)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load dead_letters table for \
+                         dispatcher '{}' with error: {}",
+                        dispatcher, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        loop {
+            let d = match cursor.try_next().await {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load next entry from \
+                             dead_letters table for dispatcher '{}' \
+                             with error: {}",
+                            dispatcher, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let d = match d {
+                None => {
+                    break;
+                },
+                Some(x) => x,
+            };
+
+            match letter_of(dispatcher, d) {
+                Some(x) => result.push(x),
+                None => {
+                    return Err(Error::new(
+                        format!(
+                            "bad dead letter found in dead_letters \
+                             table for dispatcher '{}'",
+                            dispatcher
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+        }
+
+        Ok(result)
+    }
+}
+
+fn id_of(
+    aggregate_type: &str,
+    letter: &DeadLetter,
+) -> Document {
+    doc! {
+        "aggregate_type": aggregate_type,
+        "dispatcher": &letter.dispatcher,
+        "aggregate_id": &letter.aggregate_id,
+        "sequence": letter.sequence,
+    }
+}
+
+fn letter_of(
+    dispatcher: &str,
+    d: Document,
+) -> Option<DeadLetter> {
+    let id = d.get_document("_id").ok()?;
+
+    Some(DeadLetter {
+        dispatcher: dispatcher.to_string(),
+        aggregate_id: id
+            .get_str("aggregate_id")
+            .ok()?
+            .to_string(),
+        sequence: id.get_i64("sequence").ok()?,
+        events: from_bson_payload(d.get("events")?.clone()).ok()?,
+        error: d.get_str("error").ok()?.to_string(),
+        attempts: d.get_i64("attempts").ok()?,
+    })
+}
+
+#[async_trait]
+impl IDeadLetterStore for DeadLetterStore {
+    /// Save the `letter` of an aggregate of `aggregate_type`,
+    /// replacing the previous one of the same dispatcher, aggregate
+    /// id and sequence
+    async fn save_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        let events = match to_bson_payload(&letter.events) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to convert events of dead letter \
+                         for aggregate id '{}' with error: {}",
+                        letter.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let id = id_of(aggregate_type, letter);
+
+        let options = ReplaceOptions::builder()
+            .upsert(true)
+            .build();
+
+        match self
+            .get_letters_collection()
+            .replace_one(
+                doc! { "_id": id.clone() },
+                doc! {
+                    "_id": id,
+                    "events": events,
+                    "error": &letter.error,
+                    "attempts": letter.attempts,
+                },
+                options,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert dead letter for aggregate \
+                         id '{}' with error: {}",
+                        letter.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load up to `limit` dead letters of `dispatcher` ordered by
+    /// aggregate id and sequence
+    async fn load_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}'",
+            dispatcher
+        );
+
+        let find_options = FindOptions::builder()
+            .sort(doc! {
+                "_id.aggregate_id": 1,
+                "_id.sequence": 1,
+            })
+            .limit(limit)
+            .build();
+
+        self.find_letters(
+            dispatcher,
+            doc! {
+                "_id.aggregate_type": aggregate_type,
+                "_id.dispatcher": dispatcher,
+            },
+            find_options,
+        )
+        .await
+    }
+
+    /// Load the dead letters of `dispatcher` for `aggregate_id`
+    /// ordered by sequence
+    async fn load_aggregate_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}' for aggregate \
+             id '{}'",
+            dispatcher,
+            aggregate_id
+        );
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "_id.sequence": 1 })
+            .build();
+
+        self.find_letters(
+            dispatcher,
+            doc! {
+                "_id.aggregate_type": aggregate_type,
+                "_id.dispatcher": dispatcher,
+                "_id.aggregate_id": aggregate_id,
+            },
+            find_options,
+        )
+        .await
+    }
+
+    /// Delete the `letter`
+    async fn delete_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        match self
+            .get_letters_collection()
+            .delete_one(
+                doc! { "_id": id_of(aggregate_type, letter) },
+                None,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete dead letter for aggregate \
+                         id '{}' with error: {}",
+                        letter.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+use mongodb::error::{
+    ErrorKind,
+    WriteFailure,
+};
+
+/// MongoDB error code of a duplicate key for a unique index
+static DUPLICATE_KEY: i32 = 11000;
+
+/// Whether `error` is a duplicate key for a unique index, e.g. a
+/// document upserted concurrently
+pub(super) fn is_duplicate_key(
+    error: &mongodb::error::Error
+) -> bool {
+    matches!(
+        *error.kind,
+        ErrorKind::Write(WriteFailure::WriteError(ref x))
+            if x.code == DUPLICATE_KEY
+    )
+}
@@ -1,3 +1,7 @@
+use mongodb::bson::{
+    Bson,
+    DateTime,
+};
 use serde::{
     Deserialize,
     Serialize,
@@ -12,6 +16,10 @@ pub struct EventDocument {
     pub aggregate_type: String,
     pub aggregate_id: String,
     pub sequence: i64,
-    pub payload: String,
+    #[serde(default)]
+    pub event_type: String,
+    pub payload: Bson,
     pub metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub timestamp: Option<DateTime>,
 }
@@ -2,16 +2,27 @@ use async_trait::async_trait;
 use futures::stream::TryStreamExt;
 use log::{
     debug,
+    error,
     trace,
 };
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::Arc,
+};
 
 use mongodb::{
     bson::{
         doc,
         Bson,
+        DateTime,
+        Document,
+    },
+    options::{
+        FindOneOptions,
+        FindOptions,
     },
-    options::FindOptions,
+    Client,
+    ClientSession,
     Collection,
     Database,
 };
@@ -25,16 +36,39 @@ use cqrs_es2::{
     IEvent,
 };
 
-use crate::repository::IEventStore;
+use crate::repository::{
+    event_type_of,
+    EventFilter,
+    HealthStatus,
+    IEventStore,
+    IFilteredEventStore,
+    IHealthCheck,
+    IPayloadTransformer,
+    PayloadCodec,
+};
 
 use super::{
     event_document::EventDocument,
+    health::check_health,
+    payload::{
+        decode_bson_payload,
+        decode_bson_payloads,
+        encode_bson_payload,
+    },
     snapshot_document::SnapshotDocument,
+    transaction::{
+        is_replica_set,
+        start_transaction,
+    },
 };
 
 /// Async MongoDB event store
 pub struct EventStore<C: ICommand, E: IEvent, A: IAggregate<C, E>> {
     db: Database,
+    client: Option<Client>,
+    codec: PayloadCodec,
+    indexes_created: bool,
+    replica_set: Option<bool>,
     _phantom: PhantomData<(C, E, A)>,
 }
 
@@ -45,6 +79,10 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
     pub fn new(db: Database) -> Self {
         let x = Self {
             db,
+            client: None,
+            codec: PayloadCodec::default(),
+            indexes_created: false,
+            replica_set: None,
             _phantom: PhantomData,
         };
 
@@ -53,6 +91,27 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
         x
     }
 
+    /// Enables multi-document transactions when connected to a
+    /// replica set, `client` must be the client `db` was obtained
+    /// from
+    pub fn with_client(
+        mut self,
+        client: Client,
+    ) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Transform the payloads with `transformer` on their way to and
+    /// from the database, e.g. to encrypt them
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
     fn get_events_collection(&self) -> Collection<EventDocument> {
         self.db
             .collection::<EventDocument>("events")
@@ -64,16 +123,108 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
         self.db
             .collection::<SnapshotDocument>("snapshots")
     }
-}
 
-#[async_trait]
-impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
-    for EventStore<C, E, A>
-{
-    /// Save new events
-    async fn save_events(
-        &mut self,
+    /// Creates the indexes needed by the store if missing, notably
+    /// the unique index on the events sequences. It is called on
+    /// first use but can be called at startup instead.
+    pub async fn ensure_indexes(&mut self) -> Result<(), Error> {
+        if self.indexes_created {
+            return Ok(());
+        }
+
+        self.create_indexes(doc! {
+            "createIndexes": "events",
+            "indexes": [
+                {
+                    "key": {
+                        "aggregate_type": 1,
+                        "aggregate_id": 1,
+                        "sequence": 1,
+                    },
+                    "name": "events_aggregate_sequence",
+                    "unique": true,
+                },
+                {
+                    "key": {
+                        "aggregate_type": 1,
+                        "event_type": 1,
+                        "timestamp": 1,
+                    },
+                    "name": "events_type_timestamp",
+                },
+                {
+                    "key": {
+                        "aggregate_type": 1,
+                        "timestamp": 1,
+                    },
+                    "name": "events_timestamp",
+                },
+            ],
+        })
+        .await?;
+
+        self.create_indexes(doc! {
+            "createIndexes": "snapshots",
+            "indexes": [
+                {
+                    "key": {
+                        "aggregate_type": 1,
+                        "aggregate_id": 1,
+                    },
+                    "name": "snapshots_aggregate",
+                    "unique": true,
+                },
+            ],
+        })
+        .await?;
+
+        debug!("Created events and snapshots indexes");
+
+        self.indexes_created = true;
+
+        Ok(())
+    }
+
+    async fn create_indexes(
+        &self,
+        command: Document,
+    ) -> Result<(), Error> {
+        match self.db.run_command(command, None).await {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to create indexes with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    async fn supports_transactions(&mut self) -> Result<bool, Error> {
+        if self.client.is_none() {
+            return Ok(false);
+        }
+
+        if let Some(x) = self.replica_set {
+            return Ok(x);
+        }
+
+        let replica_set = is_replica_set(&self.db).await?;
+
+        self.replica_set = Some(replica_set);
+
+        Ok(replica_set)
+    }
+
+    async fn insert_events(
+        &self,
         contexts: &Vec<EventContext<C, E>>,
+        session: Option<&mut ClientSession>,
     ) -> Result<(), Error> {
         if contexts.len() == 0 {
             trace!("Skip saving zero contexts");
@@ -94,38 +245,70 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             &aggregate_id
         );
 
+        let timestamp = DateTime::now();
+
         let mut all_docs = Vec::new();
         for context in contexts {
-            let payload =
-                match serde_json::to_string(&context.payload) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        return Err(Error::new(
-                            format!(
-                                "unable to serialize the event \
-                                 payload for aggregate id '{}' with \
-                                 error: {}",
-                                &aggregate_id, e
-                            )
-                            .as_str(),
-                        ));
-                    },
-                };
+            let payload = match serde_json::to_value(&context.payload)
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to serialize the event payload \
+                             for aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let event_type = event_type_of(&payload);
+
+            let bson_payload = match encode_bson_payload(
+                &self.codec,
+                aggregate_type,
+                &aggregate_id,
+                &payload,
+            )
+            .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to serialize the event payload \
+                             for aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
 
             all_docs.push(EventDocument {
                 aggregate_type: aggregate_type.to_string(),
                 aggregate_id: aggregate_id.to_string(),
                 sequence: context.sequence,
-                payload,
+                event_type,
+                payload: bson_payload,
                 metadata: context.metadata.clone(),
+                timestamp: Some(timestamp),
             });
         }
 
-        match self
-            .get_events_collection()
-            .insert_many(all_docs, None)
-            .await
-        {
+        let col = self.get_events_collection();
+
+        let res = match session {
+            Some(x) => {
+                col.insert_many_with_session(all_docs, None, x)
+                    .await
+            },
+            None => col.insert_many(all_docs, None).await,
+        };
+
+        match res {
             Ok(x) => {
                 if x.inserted_ids.len() != contexts.len() {
                     return Err(Error::new(
@@ -154,102 +337,10 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
         Ok(())
     }
 
-    /// Load all events for a particular `aggregate_id`
-    async fn load_events(
-        &mut self,
-        aggregate_id: &str,
-    ) -> Result<Vec<EventContext<C, E>>, Error> {
-        let aggregate_type = A::aggregate_type();
-
-        trace!(
-            "loading events for aggregate id '{}'",
-            aggregate_id
-        );
-
-        let find_options = FindOptions::builder()
-            .sort(doc! { "sequence": 1 })
-            .build();
-
-        let mut cursor = match self
-            .get_events_collection()
-            .find(
-                doc! {
-                    "aggregate_type": aggregate_type,
-                    "aggregate_id": aggregate_id,
-                },
-                find_options,
-            )
-            .await
-        {
-            Ok(x) => x,
-            Err(e) => {
-                return Err(Error::new(
-                    format!(
-                        "unable to load events table for aggregate \
-                         id '{}' with error: {}",
-                        aggregate_id, e
-                    )
-                    .as_str(),
-                ));
-            },
-        };
-
-        let mut result = Vec::new();
-
-        loop {
-            let d = match cursor.try_next().await {
-                Ok(x) => x,
-                Err(e) => {
-                    return Err(Error::new(
-                        format!(
-                            "unable to load next entry from events \
-                             table for aggregate id '{}' with \
-                             error: {}",
-                            aggregate_id, e
-                        )
-                        .as_str(),
-                    ));
-                },
-            };
-
-            let d: EventDocument = match d {
-                None => {
-                    break;
-                },
-                Some(x) => x,
-            };
-
-            let payload =
-                match serde_json::from_str(d.payload.as_str()) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        return Err(Error::new(
-                            format!(
-                                "bad payload found in events table \
-                                 for aggregate id '{}' with error: \
-                                 {}",
-                                aggregate_id, e
-                            )
-                            .as_str(),
-                        ));
-                    },
-                };
-
-            result.push(EventContext::new(
-                aggregate_id.to_string(),
-                d.sequence,
-                payload,
-                d.metadata,
-            ));
-        }
-
-        Ok(result)
-    }
-
-    /// save a new aggregate snapshot
-    async fn save_aggregate_snapshot(
-        &mut self,
+    async fn write_snapshot(
+        &self,
         context: AggregateContext<C, E, A>,
+        session: Option<&mut ClientSession>,
     ) -> Result<(), Error> {
         let aggregate_type = A::aggregate_type();
 
@@ -260,7 +351,14 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             &aggregate_id
         );
 
-        let payload = match serde_json::to_string(&context.payload) {
+        let payload = match encode_bson_payload(
+            &self.codec,
+            aggregate_type,
+            &aggregate_id,
+            &context.payload,
+        )
+        .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -278,29 +376,29 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
 
         match context.version {
             1 => {
-                match col
-                    .insert_one(
-                        SnapshotDocument {
-                            aggregate_type: aggregate_type
-                                .to_string(),
-                            aggregate_id: aggregate_id.to_string(),
-                            version: 1,
-                            payload,
-                        },
-                        None,
-                    )
-                    .await
-                {
+                let d = SnapshotDocument {
+                    aggregate_type: aggregate_type.to_string(),
+                    aggregate_id: aggregate_id.to_string(),
+                    version: 1,
+                    payload,
+                };
+
+                let res = match session {
+                    Some(x) => {
+                        col.insert_one_with_session(d, None, x)
+                            .await
+                    },
+                    None => col.insert_one(d, None).await,
+                };
+
+                match res {
                     Ok(x) => {
                         match x.inserted_id {
                             Bson::ObjectId(id) => {
                                 if id.to_string().is_empty() {
                                     return Err(Error::new(
-                                        format!(
-                                            "insert snapshot got \
-                                             empty document id",
-                                        )
-                                        .as_str(),
+                                        "insert snapshot got empty \
+                                         document id",
                                     ));
                                 }
                             },
@@ -330,22 +428,32 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
                 };
             },
             _ => {
-                match col
-                    .update_one(
-                        doc! {
-                            "aggregate_type": aggregate_type.to_string(),
-                            "aggregate_id": aggregate_id.to_string(),
-                        },
-                        doc! {
-                            "$set": {
-                                "version": context.version,
-                                "payload": payload,
-                            }
-                        },
-                        None,
-                    )
-                    .await
-                {
+                let query = doc! {
+                    "aggregate_type": aggregate_type.to_string(),
+                    "aggregate_id": aggregate_id.to_string(),
+                };
+
+                let update = doc! {
+                    "$set": {
+                        "version": context.version,
+                        "payload": payload,
+                    }
+                };
+
+                let res = match session {
+                    Some(x) => {
+                        col.update_one_with_session(
+                            query, update, None, x,
+                        )
+                        .await
+                    },
+                    None => {
+                        col.update_one(query, update, None)
+                            .await
+                    },
+                };
+
+                match res {
                     Ok(_) => {},
                     Err(e) => {
                         return Err(Error::new(
@@ -364,60 +472,355 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
         Ok(())
     }
 
-    /// Load aggregate at current state from snapshots
-    async fn load_aggregate_from_snapshot(
-        &mut self,
-        aggregate_id: &str,
-    ) -> Result<AggregateContext<C, E, A>, Error> {
-        let aggregate_type = A::aggregate_type();
-
-        trace!(
-            "loading snapshot for aggregate id '{}'",
-            aggregate_id
-        );
-
-        let entry = match self
-            .get_snapshots_collection()
-            .find_one(
-                doc! {
-                    "aggregate_type": aggregate_type.to_string(),
-                    "aggregate_id": aggregate_id.to_string(),
-                },
-                None,
-            )
+    async fn distinct_aggregate_ids(
+        &self,
+        filter: Document,
+    ) -> Result<Vec<String>, Error> {
+        let values = match self
+            .get_events_collection()
+            .distinct("aggregate_id", filter, None)
             .await
         {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
                     format!(
-                        "unable to check snapshots table for \
-                         aggregate id '{}' with error: {}",
-                        &aggregate_id, e
+                        "unable to list aggregate ids from events \
+                         table with error: {}",
+                        e
                     )
                     .as_str(),
                 ));
             },
         };
 
-        let d = match entry {
-            Some(x) => x,
-            None => {
-                trace!(
-                    "returning default aggregate for aggregate id \
-                     '{}'",
-                    aggregate_id
-                );
+        Ok(values
+            .into_iter()
+            .filter_map(|x| {
+                match x {
+                    Bson::String(id) => Some(id),
+                    _ => None,
+                }
+            })
+            .collect())
+    }
+}
 
-                return Ok(AggregateContext::new(
-                    aggregate_id.to_string(),
-                    0,
-                    A::default(),
-                ));
-            },
-        };
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
+    for EventStore<C, E, A>
+{
+    /// Save new events
+    async fn save_events(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        self.ensure_indexes().await?;
+        self.insert_events(contexts, None).await
+    }
+
+    /// Save new events together with the aggregate snapshot taken
+    /// after them, in a single transaction when connected to a
+    /// replica set
+    async fn save_events_and_snapshot(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+        snapshot: Option<&AggregateContext<C, E, A>>,
+    ) -> Result<(), Error> {
+        self.ensure_indexes().await?;
+
+        if !self.supports_transactions().await? {
+            self.insert_events(contexts, None)
+                .await?;
+
+            if let Some(x) = snapshot {
+                self.write_snapshot(x.clone(), None)
+                    .await?;
+            }
+
+            return Ok(());
+        }
+
+        let mut session =
+            start_transaction(self.client.as_ref().unwrap()).await?;
+
+        let mut res = self
+            .insert_events(contexts, Some(&mut session))
+            .await;
+
+        if res.is_ok() {
+            if let Some(x) = snapshot {
+                res = self
+                    .write_snapshot(x.clone(), Some(&mut session))
+                    .await;
+            }
+        }
+
+        match res {
+            Ok(_) => {
+                match session.commit_transaction().await {
+                    Ok(_) => {},
+                    Err(e) => {
+                        return Err(Error::new(
+                            format!(
+                                "unable to commit events \
+                                 transaction with error: {}",
+                                e
+                            )
+                            .as_str(),
+                        ));
+                    },
+                };
+
+                Ok(())
+            },
+            Err(e) => {
+                if let Err(x) = session.abort_transaction().await {
+                    error!(
+                        "unable to abort events transaction with \
+                         error: {}",
+                        x
+                    );
+                }
+
+                Err(e)
+            },
+        }
+    }
+
+    /// Load all events for a particular `aggregate_id`
+    async fn load_events(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        self.load_events_after(aggregate_id, 0)
+            .await
+    }
+
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `sequence`
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        let aggregate_type = A::aggregate_type();
 
-        let payload = match serde_json::from_str(d.payload.as_str()) {
+        trace!(
+            "loading events after sequence '{}' for aggregate id \
+             '{}'",
+            sequence,
+            aggregate_id
+        );
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "sequence": 1 })
+            .build();
+
+        let mut cursor = match self
+            .get_events_collection()
+            .find(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "aggregate_id": aggregate_id,
+                    "sequence": { "$gt": sequence },
+                },
+                find_options,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load events table for aggregate \
+                         id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut documents = Vec::new();
+        let mut payloads = Vec::new();
+
+        loop {
+            let d = match cursor.try_next().await {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load next entry from events \
+                             table for aggregate id '{}' with \
+                             error: {}",
+                            aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let d: EventDocument = match d {
+                None => {
+                    break;
+                },
+                Some(x) => x,
+            };
+
+            payloads.push(d.payload);
+            documents.push((d.sequence, d.metadata));
+        }
+
+        let payloads: Vec<E> = match decode_bson_payloads(
+            &self.codec,
+            aggregate_type,
+            aggregate_id,
+            payloads,
+        )
+        .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in events table for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let result = documents
+            .into_iter()
+            .zip(payloads)
+            .map(|((sequence, metadata), payload)| {
+                EventContext::new(
+                    aggregate_id.to_string(),
+                    sequence,
+                    payload,
+                    metadata,
+                )
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Load the sequence of the last event of `aggregate_id`, 0 if it
+    /// has none
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "loading latest sequence for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let find_options = FindOneOptions::builder()
+            .sort(doc! { "sequence": -1 })
+            .build();
+
+        match self
+            .get_events_collection()
+            .find_one(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "aggregate_id": aggregate_id,
+                },
+                find_options,
+            )
+            .await
+        {
+            Ok(x) => Ok(x.map_or(0, |x| x.sequence)),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to load latest sequence from events \
+                         table for aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// save a new aggregate snapshot
+    async fn save_aggregate_snapshot(
+        &mut self,
+        context: AggregateContext<C, E, A>,
+    ) -> Result<(), Error> {
+        self.ensure_indexes().await?;
+        self.write_snapshot(context, None).await
+    }
+
+    /// Load aggregate at current state from snapshots
+    async fn load_aggregate_from_snapshot(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<AggregateContext<C, E, A>, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "loading snapshot for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let entry = match self
+            .get_snapshots_collection()
+            .find_one(
+                doc! {
+                    "aggregate_type": aggregate_type.to_string(),
+                    "aggregate_id": aggregate_id.to_string(),
+                },
+                None,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to check snapshots table for \
+                         aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let d = match entry {
+            Some(x) => x,
+            None => {
+                trace!(
+                    "returning default aggregate for aggregate id \
+                     '{}'",
+                    aggregate_id
+                );
+
+                return Ok(AggregateContext::new(
+                    aggregate_id.to_string(),
+                    0,
+                    A::default(),
+                ));
+            },
+        };
+
+        let payload = match decode_bson_payload(
+            &self.codec,
+            aggregate_type,
+            aggregate_id,
+            d.payload,
+        )
+        .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -437,4 +840,320 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             payload,
         ))
     }
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "listing '{}' aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        let mut filter = doc! {
+            "aggregate_type": aggregate_type,
+        };
+
+        if let Some(x) = after {
+            filter.insert("aggregate_id", doc! { "$gt": x });
+        }
+
+        let mut result = self
+            .distinct_aggregate_ids(filter)
+            .await?;
+
+        result.sort();
+        result.truncate(limit.max(0) as usize);
+
+        Ok(result)
+    }
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!("counting aggregates");
+
+        let result = self
+            .distinct_aggregate_ids(doc! {
+                "aggregate_type": aggregate_type,
+            })
+            .await?;
+
+        Ok(result.len() as i64)
+    }
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        let aggregate_type = A::aggregate_type();
+
+        debug!(
+            "deleting aggregate id '{}'",
+            aggregate_id
+        );
+
+        let filter = doc! {
+            "aggregate_type": aggregate_type,
+            "aggregate_id": aggregate_id,
+        };
+
+        match self
+            .get_events_collection()
+            .delete_many(filter.clone(), None)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete events for aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match self
+            .get_snapshots_collection()
+            .delete_many(filter, None)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete snapshot for aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        let aggregate_type = A::aggregate_type();
+
+        debug!(
+            "deleting events up to sequence '{}' for aggregate id \
+             '{}'",
+            sequence, aggregate_id
+        );
+
+        match self
+            .get_events_collection()
+            .delete_many(
+                doc! {
+                    "aggregate_type": aggregate_type,
+                    "aggregate_id": aggregate_id,
+                    "sequence": { "$lte": sequence },
+                },
+                None,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete events for aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
+    IFilteredEventStore<C, E, A> for EventStore<C, E, A>
+{
+    /// Load the events matching the `filter` ordered by commit
+    /// time, aggregate id and sequence
+    async fn filter_events(
+        &mut self,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!("filtering events with '{:?}'", filter);
+
+        self.ensure_indexes().await?;
+
+        let mut query = doc! {
+            "aggregate_type": aggregate_type,
+        };
+
+        if let Some(aggregate_id) = &filter.aggregate_id {
+            query.insert("aggregate_id", aggregate_id.clone());
+        }
+
+        if !filter.event_types.is_empty() {
+            query.insert(
+                "event_type",
+                doc! { "$in": filter.event_types.clone() },
+            );
+        }
+
+        let mut time_range = Document::new();
+
+        if let Some(from) = filter.from {
+            time_range.insert(
+                "$gte",
+                DateTime::from_millis(from.timestamp_millis()),
+            );
+        }
+
+        if let Some(to) = filter.to {
+            time_range.insert(
+                "$lt",
+                DateTime::from_millis(to.timestamp_millis()),
+            );
+        }
+
+        if !time_range.is_empty() {
+            query.insert("timestamp", time_range);
+        }
+
+        for (key, value) in &filter.metadata {
+            // a dot or a leading dollar would change the path or the
+            // operator of the query
+            if key.is_empty() ||
+                key.contains('.') ||
+                key.starts_with('$')
+            {
+                return Err(Error::new(
+                    format!(
+                        "unable to filter events on metadata key \
+                         '{}'",
+                        key
+                    )
+                    .as_str(),
+                ));
+            }
+
+            query.insert(
+                format!("metadata.{}", key),
+                value.clone(),
+            );
+        }
+
+        let find_options = FindOptions::builder()
+            .sort(doc! {
+                "timestamp": 1,
+                "aggregate_id": 1,
+                "sequence": 1,
+            })
+            .skip(filter.offset as u64)
+            .limit(filter.limit)
+            .build();
+
+        let mut cursor = match self
+            .get_events_collection()
+            .find(query, find_options)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to filter events table with error: \
+                         {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        loop {
+            let d = match cursor.try_next().await {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load next entry from events \
+                             table with error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let d: EventDocument = match d {
+                None => {
+                    break;
+                },
+                Some(x) => x,
+            };
+
+            let payload = match decode_bson_payload(
+                &self.codec,
+                aggregate_type,
+                &d.aggregate_id,
+                d.payload,
+            )
+            .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad payload found in events table for \
+                             aggregate id '{}' with error: {}",
+                            &d.aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            result.push(EventContext::new(
+                d.aggregate_id,
+                d.sequence,
+                payload,
+                d.metadata,
+            ));
+        }
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IHealthCheck
+    for EventStore<C, E, A>
+{
+    /// Ping the database
+    async fn health_check(&mut self) -> HealthStatus {
+        check_health(&self.db).await
+    }
 }
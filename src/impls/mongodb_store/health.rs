@@ -0,0 +1,34 @@
+use log::debug;
+use std::time::Instant;
+
+use mongodb::{
+    bson::doc,
+    Database,
+};
+
+use crate::repository::HealthStatus;
+
+/// Probe the database with the `ping` command, the collections are
+/// created by the first writes so they are not looked up
+pub(super) async fn check_health(db: &Database) -> HealthStatus {
+    let start = Instant::now();
+
+    match db
+        .run_command(doc! { "ping": 1 }, None)
+        .await
+    {
+        Ok(_) => HealthStatus::reachable("mongodb", start.elapsed()),
+        Err(e) => {
+            debug!(
+                "MongoDB health check failed with error: {}",
+                e
+            );
+
+            HealthStatus::unreachable(
+                "mongodb",
+                start.elapsed(),
+                e.to_string(),
+            )
+        },
+    }
+}
@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+
+use mongodb::{
+    bson::{
+        doc,
+        spec::BinarySubtype,
+        Binary,
+        Document,
+    },
+    options::UpdateOptions,
+    Collection,
+    Database,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::IKeyStore;
+
+/// Async MongoDB key store
+pub struct KeyStore {
+    db: Database,
+}
+
+impl KeyStore {
+    /// Constructor
+    pub fn new(db: Database) -> Self {
+        let x = Self { db };
+
+        trace!("Created new async MongoDB key store");
+
+        x
+    }
+
+    fn get_keys_collection(&self) -> Collection<Document> {
+        self.db
+            .collection::<Document>("encryption_keys")
+    }
+}
+
+#[async_trait]
+impl IKeyStore for KeyStore {
+    /// Save the key of the aggregate unless it already has one
+    async fn save_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        debug!(
+            "storing a new key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let key = Binary {
+            subtype: BinarySubtype::Generic,
+            bytes: key.to_vec(),
+        };
+
+        let options = UpdateOptions::builder()
+            .upsert(true)
+            .build();
+
+        match self
+            .get_keys_collection()
+            .update_one(
+                doc! {
+                    "_id": {
+                        "aggregate_type": aggregate_type,
+                        "aggregate_id": aggregate_id,
+                    }
+                },
+                doc! {
+                    "$setOnInsert": {
+                        "encryption_key": key,
+                    }
+                },
+                options,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert key for aggregate id '{}' \
+                         with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load the key of the aggregate, `None` if it has none and an
+    /// empty key if it was erased
+    async fn load_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        trace!(
+            "loading key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let entry = match self
+            .get_keys_collection()
+            .find_one(
+                doc! {
+                    "_id": {
+                        "aggregate_type": aggregate_type,
+                        "aggregate_id": aggregate_id,
+                    }
+                },
+                None,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load encryption_keys table for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let d = match entry {
+            Some(x) => x,
+            None => {
+                return Ok(None);
+            },
+        };
+
+        match d.get_binary_generic("encryption_key") {
+            Ok(x) => Ok(Some(x.clone())),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "bad key found in encryption_keys table for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Destroy the key of the aggregate, leaving an empty key as its
+    /// tombstone
+    async fn delete_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "erasing key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let key = Binary {
+            subtype: BinarySubtype::Generic,
+            bytes: Vec::new(),
+        };
+
+        let options = UpdateOptions::builder()
+            .upsert(true)
+            .build();
+
+        match self
+            .get_keys_collection()
+            .update_one(
+                doc! {
+                    "_id": {
+                        "aggregate_type": aggregate_type,
+                        "aggregate_id": aggregate_id,
+                    }
+                },
+                doc! {
+                    "$set": {
+                        "encryption_key": key,
+                    }
+                },
+                options,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to erase key for aggregate id '{}' \
+                         with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+
+use crate::repository::PayloadCodec;
+
+use mongodb::bson::{
+    from_bson,
+    to_bson,
+    to_document,
+    Bson,
+};
+
+/// Converts a payload to an embedded BSON document, payloads which
+/// are not maps (e.g. unit enum variants) are stored as plain values
+pub fn to_bson_payload<T: Serialize>(
+    payload: &T
+) -> Result<Bson, String> {
+    match to_document(payload) {
+        Ok(x) => Ok(Bson::Document(x)),
+        Err(_) => {
+            match to_bson(payload) {
+                Ok(x) => Ok(x),
+                Err(e) => Err(e.to_string()),
+            }
+        },
+    }
+}
+
+/// Converts a stored payload back, accepting the legacy payloads
+/// stored as JSON strings
+pub fn from_bson_payload<T: DeserializeOwned>(
+    payload: Bson
+) -> Result<T, String> {
+    if let Bson::String(x) = &payload {
+        if let Ok(x) = serde_json::from_str(x.as_str()) {
+            return Ok(x);
+        }
+    }
+
+    match from_bson(payload) {
+        Ok(x) => Ok(x),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Serializes and transforms a payload to an embedded BSON document
+pub async fn encode_bson_payload<T: Serialize + Sync>(
+    codec: &PayloadCodec,
+    aggregate_type: &str,
+    aggregate_id: &str,
+    payload: &T,
+) -> Result<Bson, String> {
+    let payload = codec
+        .encode_value(aggregate_type, aggregate_id, payload)
+        .await?;
+
+    to_bson_payload(&payload)
+}
+
+/// Restores and deserializes a stored payload
+pub async fn decode_bson_payload<T: DeserializeOwned>(
+    codec: &PayloadCodec,
+    aggregate_type: &str,
+    aggregate_id: &str,
+    payload: Bson,
+) -> Result<T, String> {
+    let payload: serde_json::Value = from_bson_payload(payload)?;
+
+    codec
+        .decode_value(aggregate_type, aggregate_id, payload)
+        .await
+}
+
+/// Restores and deserializes the stored payloads of an aggregate
+/// loaded together
+pub async fn decode_bson_payloads<T: DeserializeOwned>(
+    codec: &PayloadCodec,
+    aggregate_type: &str,
+    aggregate_id: &str,
+    payloads: Vec<Bson>,
+) -> Result<Vec<T>, String> {
+    let mut values = Vec::with_capacity(payloads.len());
+
+    for payload in payloads {
+        let payload: serde_json::Value = from_bson_payload(payload)?;
+        values.push(payload);
+    }
+
+    codec
+        .decode_values(aggregate_type, aggregate_id, values)
+        .await
+}
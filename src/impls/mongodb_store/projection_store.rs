@@ -0,0 +1,728 @@
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use log::{
+    debug,
+    error,
+    trace,
+};
+use std::marker::PhantomData;
+
+use mongodb::{
+    bson::{
+        doc,
+        Document,
+    },
+    options::{
+        FindOptions,
+        UpdateOptions,
+    },
+    Client,
+    ClientSession,
+    Collection,
+    Database,
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use crate::repository::{
+    projection_conflict,
+    sequence_conflict,
+    AppliedSequence,
+    IEventDispatcher,
+    IProjection,
+    IProjectionStore,
+    ProjectionContext,
+};
+
+use super::{
+    errors::is_duplicate_key,
+    payload::{
+        from_bson_payload,
+        to_bson_payload,
+    },
+    transaction::{
+        is_replica_set,
+        start_transaction,
+    },
+};
+
+/// Async MongoDB projection store
+pub struct ProjectionStore<
+    C: ICommand,
+    E: IEvent,
+    P: IProjection<C, E>,
+> {
+    db: Database,
+    client: Option<Client>,
+    replica_set: Option<bool>,
+    _phantom: PhantomData<(C, E, P)>,
+}
+
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    ProjectionStore<C, E, P>
+{
+    /// Constructor
+    pub fn new(db: Database) -> Self {
+        let x = Self {
+            db,
+            client: None,
+            replica_set: None,
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new async MongoDB projection store");
+
+        x
+    }
+
+    /// Enables multi-document transactions when connected to a
+    /// replica set, `client` must be the client `db` was obtained
+    /// from
+    pub fn with_client(
+        mut self,
+        client: Client,
+    ) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    fn get_projections_collection(&self) -> Collection<Document> {
+        self.db
+            .collection::<Document>("projections")
+    }
+
+    fn get_sequences_collection(&self) -> Collection<Document> {
+        self.db
+            .collection::<Document>("projection_sequences")
+    }
+
+    async fn supports_transactions(&mut self) -> Result<bool, Error> {
+        if self.client.is_none() {
+            return Ok(false);
+        }
+
+        if let Some(x) = self.replica_set {
+            return Ok(x);
+        }
+
+        let replica_set = is_replica_set(&self.db).await?;
+
+        self.replica_set = Some(replica_set);
+
+        Ok(replica_set)
+    }
+
+    async fn write_projection(
+        &self,
+        context: ProjectionContext<P>,
+        expected_version: i64,
+        session: Option<&mut ClientSession>,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        let key = context.key;
+
+        debug!(
+            "storing projection '{}' with key '{}'",
+            projection_type, &key
+        );
+
+        let payload = match to_bson_payload(&context.payload) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to serialize the payload of \
+                         projection '{}' with key '{}', error: {}",
+                        &projection_type, &key, e,
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut filter = doc! { "_id": id_of(projection_type, &key) };
+
+        let fields = doc! {
+            "version": context.version,
+            "payload": payload,
+        };
+
+        // a new row is only inserted when no row matches, an
+        // existing one only updated when still at the expected
+        // version
+        let (update, options) = match expected_version {
+            0 => {
+                (
+                    doc! { "$setOnInsert": fields },
+                    UpdateOptions::builder()
+                        .upsert(true)
+                        .build(),
+                )
+            },
+            _ => {
+                filter.insert("version", expected_version);
+
+                (
+                    doc! { "$set": fields },
+                    UpdateOptions::builder().build(),
+                )
+            },
+        };
+
+        let col = self.get_projections_collection();
+
+        let res = match session {
+            Some(x) => {
+                col.update_one_with_session(
+                    filter, update, options, x,
+                )
+                .await
+            },
+            None => {
+                col.update_one(filter, update, options)
+                    .await
+            },
+        };
+
+        let saved = match res {
+            Ok(x) => {
+                match expected_version {
+                    0 => x.upserted_id.is_some(),
+                    _ => x.matched_count == 1,
+                }
+            },
+            // a row upserted concurrently hits the unique id
+            Err(e) if is_duplicate_key(&e) => false,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert/update projection '{}' \
+                         with key '{}', error: {}",
+                        &projection_type, &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        if !saved {
+            return Err(projection_conflict(
+                projection_type,
+                &key,
+                expected_version,
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn write_applied_sequence(
+        &self,
+        applied: AppliedSequence,
+        session: Option<&mut ClientSession>,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        debug!(
+            "storing applied sequence '{}' of projection '{}' with \
+             aggregate id '{}'",
+            applied.sequence, projection_type, &applied.aggregate_id
+        );
+
+        let mut filter = doc! {
+            "_id": sequence_id_of(projection_type, &applied.aggregate_id),
+        };
+
+        let fields = doc! { "sequence": applied.sequence };
+
+        // as for the rows, a sequence is only inserted when none
+        // matches, an existing one only updated when still the
+        // expected one
+        let (update, options) = match applied.expected_sequence {
+            0 => {
+                (
+                    doc! { "$setOnInsert": fields },
+                    UpdateOptions::builder()
+                        .upsert(true)
+                        .build(),
+                )
+            },
+            _ => {
+                filter.insert("sequence", applied.expected_sequence);
+
+                (
+                    doc! { "$set": fields },
+                    UpdateOptions::builder().build(),
+                )
+            },
+        };
+
+        let col = self.get_sequences_collection();
+
+        let res = match session {
+            Some(x) => {
+                col.update_one_with_session(
+                    filter, update, options, x,
+                )
+                .await
+            },
+            None => {
+                col.update_one(filter, update, options)
+                    .await
+            },
+        };
+
+        let saved = match res {
+            Ok(x) => {
+                match applied.expected_sequence {
+                    0 => x.upserted_id.is_some(),
+                    _ => x.matched_count == 1,
+                }
+            },
+            Err(e) if is_duplicate_key(&e) => false,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert/update applied sequence \
+                         of projection '{}' with aggregate id '{}', \
+                         error: {}",
+                        &projection_type, &applied.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        if !saved {
+            return Err(sequence_conflict(
+                projection_type,
+                &applied.aggregate_id,
+                applied.expected_sequence,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn projection_from_document(
+        d: Document
+    ) -> Result<ProjectionContext<P>, Error> {
+        let projection_type = P::projection_type();
+
+        let key = match d
+            .get_document("_id")
+            .and_then(|x| x.get_str("projection_key"))
+        {
+            Ok(x) => x.to_string(),
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad key found in projections table for \
+                         projection '{}' with error: {}",
+                        projection_type, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let version = match d.get_i64("version") {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad version found in projections table for \
+                         projection '{}' with key '{}', error: {}",
+                        projection_type, &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let payload = match d
+            .get("payload")
+            .cloned()
+            .ok_or_else(|| "missing payload".to_string())
+            .and_then(from_bson_payload)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in projections table for \
+                         projection '{}' with key '{}', error: {}",
+                        projection_type, &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(ProjectionContext::new(
+            key, version, payload,
+        ))
+    }
+}
+
+fn id_of(
+    projection_type: &str,
+    key: &str,
+) -> Document {
+    doc! {
+        "projection_type": projection_type,
+        "projection_key": key,
+    }
+}
+
+fn sequence_id_of(
+    projection_type: &str,
+    aggregate_id: &str,
+) -> Document {
+    doc! {
+        "projection_type": projection_type,
+        "aggregate_id": aggregate_id,
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IProjectionStore<C, E, P> for ProjectionStore<C, E, P>
+{
+    /// saves the updated row
+    async fn save_projection(
+        &mut self,
+        context: ProjectionContext<P>,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        self.write_projection(context, expected_version, None)
+            .await
+    }
+
+    /// saves the updated rows and the applied sequences, in a single
+    /// transaction when connected to a replica set
+    async fn save_projections(
+        &mut self,
+        contexts: Vec<(ProjectionContext<P>, i64)>,
+        applied: Vec<AppliedSequence>,
+    ) -> Result<(), Error> {
+        if !self.supports_transactions().await? {
+            for (context, expected_version) in contexts {
+                self.write_projection(
+                    context,
+                    expected_version,
+                    None,
+                )
+                .await?;
+            }
+
+            for x in applied {
+                self.write_applied_sequence(x, None)
+                    .await?;
+            }
+
+            return Ok(());
+        }
+
+        let mut session =
+            start_transaction(self.client.as_ref().unwrap()).await?;
+
+        let mut res = Ok(());
+
+        for (context, expected_version) in contexts {
+            res = self
+                .write_projection(
+                    context,
+                    expected_version,
+                    Some(&mut session),
+                )
+                .await;
+
+            if res.is_err() {
+                break;
+            }
+        }
+
+        if res.is_ok() {
+            for x in applied {
+                res = self
+                    .write_applied_sequence(x, Some(&mut session))
+                    .await;
+
+                if res.is_err() {
+                    break;
+                }
+            }
+        }
+
+        match res {
+            Ok(_) => {
+                match session.commit_transaction().await {
+                    Ok(_) => {},
+                    Err(e) => {
+                        return Err(Error::new(
+                            format!(
+                                "unable to commit projections \
+                                 transaction with error: {}",
+                                e
+                            )
+                            .as_str(),
+                        ));
+                    },
+                };
+
+                Ok(())
+            },
+            Err(e) => {
+                if let Err(x) = session.abort_transaction().await {
+                    error!(
+                        "unable to abort projections transaction \
+                         with error: {}",
+                        x
+                    );
+                }
+
+                Err(e)
+            },
+        }
+    }
+
+    /// loads the sequence of the last event of `aggregate_id` applied
+    /// to the rows
+    async fn load_applied_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading applied sequence of projection '{}' with \
+             aggregate id '{}'",
+            projection_type,
+            aggregate_id
+        );
+
+        let entry = match self
+            .get_sequences_collection()
+            .find_one(
+                doc! {
+                    "_id": sequence_id_of(projection_type, aggregate_id),
+                },
+                None,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load projection_sequences table \
+                         for projection '{}' with aggregate id '{}', \
+                         error: {}",
+                        projection_type, aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match entry {
+            None => Ok(0),
+            Some(x) => {
+                match x.get_i64("sequence") {
+                    Ok(x) => Ok(x),
+                    Err(e) => {
+                        Err(Error::new(
+                            format!(
+                                "bad sequence found in \
+                                 projection_sequences table for \
+                                 projection '{}' with aggregate id \
+                                 '{}', error: {}",
+                                projection_type, aggregate_id, e
+                            )
+                            .as_str(),
+                        ))
+                    },
+                }
+            },
+        }
+    }
+
+    /// loads the row of `key`
+    async fn load_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<ProjectionContext<P>, Error> {
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading projection '{}' with key '{}'",
+            projection_type,
+            key
+        );
+
+        let entry = match self
+            .get_projections_collection()
+            .find_one(
+                doc! { "_id": id_of(projection_type, key) },
+                None,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load projections table for \
+                         projection '{}' with key '{}', error: {}",
+                        projection_type, key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match entry {
+            None => {
+                Ok(ProjectionContext::new(
+                    key.to_string(),
+                    0,
+                    Default::default(),
+                ))
+            },
+            Some(x) => Self::projection_from_document(x),
+        }
+    }
+
+    /// loads up to `limit` rows in ascending order of key, starting
+    /// after the key `after` when given
+    async fn load_all_projections(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ProjectionContext<P>>, Error> {
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading '{}' projections '{}' after '{:?}'",
+            limit,
+            projection_type,
+            after
+        );
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "_id.projection_key": 1 })
+            .limit(limit)
+            .build();
+
+        let mut cursor = match self
+            .get_projections_collection()
+            .find(
+                doc! {
+                    "_id.projection_type": projection_type,
+                    "_id.projection_key": { "$gt": after.unwrap_or("") },
+                },
+                find_options,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load projections table for \
+                         projection '{}' with error: {}",
+                        projection_type, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        loop {
+            let d = match cursor.try_next().await {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load next entry from \
+                             projections table for projection '{}' \
+                             with error: {}",
+                            projection_type, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            match d {
+                None => {
+                    break;
+                },
+                Some(x) => {
+                    result.push(Self::projection_from_document(x)?);
+                },
+            };
+        }
+
+        Ok(result)
+    }
+
+    /// deletes the row of `key`
+    async fn delete_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        debug!(
+            "deleting projection '{}' with key '{}'",
+            projection_type, key
+        );
+
+        match self
+            .get_projections_collection()
+            .delete_one(
+                doc! { "_id": id_of(projection_type, key) },
+                None,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete projection '{}' with key \
+                         '{}', error: {}",
+                        &projection_type, &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IEventDispatcher<C, E> for ProjectionStore<C, E, P>
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        events: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        self.dispatch_projection_events(events)
+            .await
+    }
+}
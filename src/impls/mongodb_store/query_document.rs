@@ -1,3 +1,4 @@
+use mongodb::bson::Bson;
 use serde::{
     Deserialize,
     Serialize,
@@ -10,5 +11,5 @@ pub struct QueryDocument {
     pub aggregate_id: String,
     pub query_type: String,
     pub version: i64,
-    pub payload: String,
+    pub payload: Bson,
 }
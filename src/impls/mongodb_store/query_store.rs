@@ -1,14 +1,24 @@
 use async_trait::async_trait;
+use futures::stream::TryStreamExt;
 use log::{
     debug,
     trace,
 };
-use std::marker::PhantomData;
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::Arc,
+};
 
 use mongodb::{
     bson::{
         doc,
-        Bson,
+        to_bson,
+        Document,
+    },
+    options::{
+        FindOptions,
+        UpdateOptions,
     },
     Collection,
     Database,
@@ -25,11 +35,28 @@ use cqrs_es2::{
 };
 
 use crate::repository::{
+    query_conflict,
+    Comparison,
+    HealthStatus,
     IEventDispatcher,
+    IFilteredQueryStore,
+    IHealthCheck,
+    IPayloadTransformer,
     IQueryStore,
+    PayloadCodec,
+    QueryFilter,
+    SortOrder,
 };
 
-use super::query_document::QueryDocument;
+use super::{
+    errors::is_duplicate_key,
+    health::check_health,
+    payload::{
+        decode_bson_payload,
+        encode_bson_payload,
+    },
+    query_document::QueryDocument,
+};
 
 /// Async MongoDB query store
 pub struct QueryStore<
@@ -39,6 +66,8 @@ pub struct QueryStore<
     Q: IQuery<C, E>,
 > {
     db: Database,
+    codec: PayloadCodec,
+    indexes_created: bool,
     _phantom: PhantomData<(C, E, A, Q)>,
 }
 
@@ -53,6 +82,8 @@ impl<
     pub fn new(db: Database) -> Self {
         let x = Self {
             db,
+            codec: PayloadCodec::default(),
+            indexes_created: false,
             _phantom: PhantomData,
         };
 
@@ -61,10 +92,152 @@ impl<
         x
     }
 
+    /// Transform the payloads with `transformer` on their way to and
+    /// from the database, e.g. to encrypt them. The stored payloads
+    /// can then no longer be searched, `filter_queries` fails.
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
+    /// Creates the unique index on the queries if missing, so that a
+    /// query is never inserted twice. It is called on first save but
+    /// can be called at startup instead.
+    pub async fn ensure_indexes(&mut self) -> Result<(), Error> {
+        if self.indexes_created {
+            return Ok(());
+        }
+
+        let command = doc! {
+            "createIndexes": "queries",
+            "indexes": [
+                {
+                    "key": {
+                        "aggregate_type": 1,
+                        "aggregate_id": 1,
+                        "query_type": 1,
+                    },
+                    "name": "queries_aggregate_query",
+                    "unique": true,
+                },
+            ],
+        };
+
+        match self.db.run_command(command, None).await {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to create indexes with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        debug!("Created queries indexes");
+
+        self.indexes_created = true;
+
+        Ok(())
+    }
+
     fn get_queries_collection(&self) -> Collection<QueryDocument> {
         self.db
             .collection::<QueryDocument>("queries")
     }
+
+    async fn query_from_document(
+        &self,
+        d: QueryDocument,
+    ) -> Result<QueryContext<C, E, Q>, Error> {
+        let payload = match decode_bson_payload(
+            &self.codec,
+            A::aggregate_type(),
+            &d.aggregate_id,
+            d.payload,
+        )
+        .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in queries table for \
+                         query '{}' for aggregate id '{}' with \
+                         error: {}",
+                        d.query_type, d.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(QueryContext::new(
+            d.aggregate_id,
+            d.version,
+            payload,
+        ))
+    }
+
+    async fn find_queries(
+        &self,
+        filter: Document,
+        find_options: Option<FindOptions>,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let query_type = Q::query_type();
+
+        let mut cursor = match self
+            .get_queries_collection()
+            .find(filter, find_options)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load queries table for query \
+                         '{}' with error: {}",
+                        query_type, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        loop {
+            let d = match cursor.try_next().await {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load next entry from queries \
+                             table for query '{}' with error: {}",
+                            query_type, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            match d {
+                None => {
+                    break;
+                },
+                Some(x) => {
+                    result.push(self.query_from_document(x).await?);
+                },
+            };
+        }
+
+        Ok(result)
+    }
 }
 
 #[async_trait]
@@ -79,7 +252,10 @@ impl<
     async fn save_query(
         &mut self,
         context: QueryContext<C, E, Q>,
+        expected_version: i64,
     ) -> Result<(), Error> {
+        self.ensure_indexes().await?;
+
         let aggregate_type = A::aggregate_type();
         let query_type = Q::query_type();
 
@@ -90,7 +266,14 @@ impl<
             query_type, &aggregate_id
         );
 
-        let payload = match serde_json::to_string(&context.payload) {
+        let payload = match encode_bson_payload(
+            &self.codec,
+            aggregate_type,
+            &aggregate_id,
+            &context.payload,
+        )
+        .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -106,92 +289,71 @@ impl<
 
         let col = self.get_queries_collection();
 
-        match context.version {
-            1 => {
-                match col
-                    .insert_one(
-                        QueryDocument {
-                            aggregate_type: aggregate_type
-                                .to_string(),
-                            aggregate_id: aggregate_id.clone(),
-                            query_type: query_type.to_string(),
-                            version: context.version,
-                            payload,
-                        },
-                        None,
-                    )
-                    .await
-                {
-                    Ok(x) => {
-                        match x.inserted_id {
-                            Bson::ObjectId(id) => {
-                                if id.to_string().is_empty() {
-                                    return Err(Error::new(
-                                        format!(
-                                            "insert query got empty \
-                                             document id ",
-                                        )
-                                        .as_str(),
-                                    ));
-                                }
-                            },
-                            _ => {
-                                return Err(Error::new(
-                                    format!(
-                                        "unexpected return value \
-                                         from insert query {:?}",
-                                        x.inserted_id
-                                    )
-                                    .as_str(),
-                                ));
-                            },
-                        };
-                    },
-                    Err(e) => {
-                        return Err(Error::new(
-                            format!(
-                                "unable to insert new query for \
-                                 aggregate id '{}' with error: {}",
-                                &aggregate_id, e
-                            )
-                            .as_str(),
-                        ));
-                    },
-                };
+        let mut filter = doc! {
+            "aggregate_type": aggregate_type.to_string(),
+            "aggregate_id": aggregate_id.clone(),
+            "query_type": query_type.to_string(),
+        };
+
+        let fields = doc! {
+            "version": context.version,
+            "payload": payload,
+        };
+
+        // a new query is only inserted when no query matches, an
+        // existing one only updated when still at the expected
+        // version
+        let (update, options) = match expected_version {
+            0 => {
+                (
+                    doc! { "$setOnInsert": fields },
+                    UpdateOptions::builder()
+                        .upsert(true)
+                        .build(),
+                )
             },
             _ => {
-                match col
-                    .update_one(
-                        doc! {
-                            "aggregate_type": aggregate_type.to_string(),
-                            "aggregate_id": aggregate_id.clone(),
-                            "query_type": query_type.to_string(),
-                        },
-                        doc! {
-                            "$set": {
-                                "version": context.version,
-                                "payload": payload,
-                            }
-                        },
-                        None,
+                filter.insert("version", expected_version);
+
+                (
+                    doc! { "$set": fields },
+                    UpdateOptions::builder().build(),
+                )
+            },
+        };
+
+        let saved = match col
+            .update_one(filter, update, options)
+            .await
+        {
+            Ok(x) => {
+                match expected_version {
+                    0 => x.upserted_id.is_some(),
+                    _ => x.matched_count == 1,
+                }
+            },
+            // a query upserted concurrently hits the unique index
+            Err(e) if is_duplicate_key(&e) => false,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert/update query {} for \
+                         aggregate id '{}' with error: {}",
+                        &query_type, &aggregate_id, e
                     )
-                    .await
-                {
-                    Ok(_) => {},
-                    Err(e) => {
-                        return Err(Error::new(
-                            format!(
-                                "unable to update query {} for \
-                                 aggregate id '{}' with error: {}",
-                                &query_type, &aggregate_id, e
-                            )
-                            .as_str(),
-                        ));
-                    },
-                };
+                    .as_str(),
+                ));
             },
         };
 
+        if !saved {
+            return Err(query_conflict(
+                query_type,
+                &aggregate_id,
+                expected_version,
+            ));
+        }
+
         Ok(())
     }
 
@@ -252,7 +414,14 @@ impl<
             },
         };
 
-        let payload = match serde_json::from_str(d.payload.as_str()) {
+        let payload = match decode_bson_payload(
+            &self.codec,
+            aggregate_type,
+            aggregate_id,
+            d.payload,
+        )
+        .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -273,6 +442,132 @@ impl<
             payload,
         ))
     }
+
+    /// loads the most recent queries of several aggregates
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        if aggregate_ids.is_empty() {
+            trace!("Skip loading zero queries");
+            return Ok(Vec::new());
+        }
+
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading query '{}' for '{}' aggregate ids",
+            query_type,
+            aggregate_ids.len()
+        );
+
+        let found: HashMap<String, QueryContext<C, E, Q>> = self
+            .find_queries(
+                doc! {
+                    "aggregate_type": aggregate_type.to_string(),
+                    "aggregate_id": { "$in": aggregate_ids },
+                    "query_type": query_type.to_string(),
+                },
+                None,
+            )
+            .await?
+            .into_iter()
+            .map(|x| (x.aggregate_id.clone(), x))
+            .collect();
+
+        Ok(aggregate_ids
+            .iter()
+            .map(|aggregate_id| {
+                match found.get(aggregate_id) {
+                    Some(x) => x.clone(),
+                    None => {
+                        QueryContext::new(
+                            aggregate_id.to_string(),
+                            0,
+                            Default::default(),
+                        )
+                    },
+                }
+            })
+            .collect())
+    }
+
+    /// loads up to `limit` queries in ascending order of aggregate
+    /// id, starting after the id `after` when given
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading '{}' queries '{}' after '{:?}'",
+            limit,
+            query_type,
+            after
+        );
+
+        let mut filter = doc! {
+            "aggregate_type": aggregate_type.to_string(),
+            "query_type": query_type.to_string(),
+        };
+
+        if let Some(x) = after {
+            filter.insert("aggregate_id", doc! { "$gt": x });
+        }
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "aggregate_id": 1 })
+            .limit(limit)
+            .build();
+
+        self.find_queries(filter, Some(find_options))
+            .await
+    }
+
+    /// deletes the query of `aggregate_id`
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        debug!(
+            "deleting query '{}' for aggregate id '{}'",
+            query_type, aggregate_id
+        );
+
+        match self
+            .get_queries_collection()
+            .delete_one(
+                doc! {
+                    "aggregate_type": aggregate_type.to_string(),
+                    "aggregate_id": aggregate_id.to_string(),
+                    "query_type": query_type.to_string(),
+                },
+                None,
+            )
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete query '{}' for aggregate \
+                         id '{}' with error: {}",
+                        &query_type, &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -292,3 +587,123 @@ impl<
             .await
     }
 }
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+    > IFilteredQueryStore<C, E, A, Q> for QueryStore<C, E, A, Q>
+{
+    /// Load the queries matching the `filter` ordered by the sort
+    /// fields then by aggregate id
+    async fn filter_queries(
+        &mut self,
+        filter: &QueryFilter,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        trace!(
+            "filtering queries '{}' with '{:?}'",
+            query_type,
+            filter
+        );
+
+        // the filters run on the stored payloads, which are encrypted
+        // or compressed by a transformer
+        if !self.codec.is_identity() {
+            return Err(Error::new(
+                format!(
+                    "unable to filter queries '{}' with transformed \
+                     payloads",
+                    query_type
+                )
+                .as_str(),
+            ));
+        }
+
+        let mut conditions = Vec::new();
+
+        for x in &filter.conditions {
+            let value = match to_bson(&x.value) {
+                Ok(v) => v,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to convert the value of field \
+                             '{}' with error: {}",
+                            &x.field, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let op = match x.comparison {
+                Comparison::Eq => "$eq",
+                Comparison::Gt => "$gt",
+                Comparison::Gte => "$gte",
+                Comparison::Lt => "$lt",
+                Comparison::Lte => "$lte",
+            };
+
+            let mut comparison = Document::new();
+            comparison.insert(op, value);
+
+            let mut condition = Document::new();
+            condition.insert(
+                format!("payload.{}", &x.field),
+                comparison,
+            );
+
+            conditions.push(condition);
+        }
+
+        let mut query = doc! {
+            "aggregate_type": aggregate_type.to_string(),
+            "query_type": query_type.to_string(),
+        };
+
+        if !conditions.is_empty() {
+            query.insert("$and", conditions);
+        }
+
+        let mut sort = Document::new();
+
+        for (field, order) in &filter.sort {
+            let direction = match order {
+                SortOrder::Ascending => 1,
+                SortOrder::Descending => -1,
+            };
+
+            sort.insert(format!("payload.{}", field), direction);
+        }
+
+        sort.insert("aggregate_id", 1);
+
+        let find_options = FindOptions::builder()
+            .sort(sort)
+            .skip(filter.offset.max(0) as u64)
+            .limit(filter.limit)
+            .build();
+
+        self.find_queries(query, Some(find_options))
+            .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+    > IHealthCheck for QueryStore<C, E, A, Q>
+{
+    /// Ping the database
+    async fn health_check(&mut self) -> HealthStatus {
+        check_health(&self.db).await
+    }
+}
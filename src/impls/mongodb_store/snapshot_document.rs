@@ -1,3 +1,4 @@
+use mongodb::bson::Bson;
 use serde::{
     Deserialize,
     Serialize,
@@ -9,5 +10,5 @@ pub struct SnapshotDocument {
     pub aggregate_type: String,
     pub aggregate_id: String,
     pub version: i64,
-    pub payload: String,
+    pub payload: Bson,
 }
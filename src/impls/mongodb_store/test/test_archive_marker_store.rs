@@ -0,0 +1,151 @@
+use mongodb::{
+    options::ClientOptions,
+    Client,
+};
+
+use cqrs_es2::Error;
+
+use crate::{
+    mongodb_store::ArchiveMarkerStore,
+    ArchiveMarker,
+    IArchiveMarkerStore,
+};
+
+use super::common::*;
+
+async fn check_save_load_markers() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let store = ArchiveMarkerStore::new(db);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    let marker =
+        ArchiveMarker::new(&id, 2, "Customer/test_A.ndjson.zst");
+
+    store
+        .save_marker("Customer", &marker)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        Some(marker)
+    );
+
+    // markers are replaced by later archivals
+    let marker =
+        ArchiveMarker::new(&id, 5, "Customer/test_B.ndjson.zst");
+
+    store
+        .save_marker("Customer", &marker)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        Some(marker)
+    );
+
+    // markers are per aggregate type
+    assert_eq!(
+        store
+            .load_marker("Other", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    // markers are listed per aggregate type in id order
+    let listed = format!("Listed {}", id);
+
+    for x in &["id B", "id A"] {
+        store
+            .save_marker(
+                &listed,
+                &ArchiveMarker::new(x, 1, "Listed/test.ndjson.zst"),
+            )
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        store
+            .list_archived_ids(&listed, None, 10)
+            .await
+            .unwrap(),
+        vec!["id A".to_string(), "id B".to_string()]
+    );
+    assert_eq!(
+        store
+            .list_archived_ids(&listed, Some("id A"), 10)
+            .await
+            .unwrap(),
+        vec!["id B".to_string()]
+    );
+    assert_eq!(
+        store
+            .list_archived_ids(&listed, None, 1)
+            .await
+            .unwrap(),
+        vec!["id A".to_string()]
+    );
+
+    for x in &["id A", "id B"] {
+        store
+            .delete_marker(&listed, x)
+            .await
+            .unwrap();
+    }
+
+    store
+        .delete_marker("Customer", &id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_save_load_markers() {
+    tokio_test::block_on(check_save_load_markers()).unwrap();
+}
@@ -0,0 +1,154 @@
+use mongodb::{
+    options::ClientOptions,
+    Client,
+};
+
+use cqrs_es2::Error;
+
+use crate::{
+    mongodb_store::DeadLetterStore,
+    DeadLetter,
+    IDeadLetterStore,
+};
+
+use super::common::*;
+
+async fn check_save_load_dead_letters() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let store = DeadLetterStore::new(db);
+
+    let dispatcher = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        Vec::new()
+    );
+
+    let events = serde_json::json!([{ "sequence": 2 }]);
+
+    let first = DeadLetter::new(
+        &dispatcher,
+        "a",
+        2,
+        events.clone(),
+        "failed",
+    );
+    let second = DeadLetter::new(
+        &dispatcher,
+        "a",
+        1,
+        events.clone(),
+        "failed",
+    );
+    let third =
+        DeadLetter::new(&dispatcher, "b", 1, events, "failed");
+
+    for x in [&first, &third, &second] {
+        store
+            .save_dead_letter("Customer", x)
+            .await
+            .unwrap();
+    }
+
+    // letters are ordered by aggregate id and sequence
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        vec![
+            second.clone(),
+            first.clone(),
+            third.clone()
+        ]
+    );
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 2)
+            .await
+            .unwrap(),
+        vec![second.clone(), first.clone()]
+    );
+
+    // the letters of an aggregate are ordered by sequence
+    assert_eq!(
+        store
+            .load_aggregate_dead_letters("Customer", &dispatcher, "a")
+            .await
+            .unwrap(),
+        vec![second.clone(), first.clone()]
+    );
+
+    // letters are replaced by later failures
+    let mut first = first;
+    first.error = "failed again".to_string();
+    first.attempts = 2;
+
+    store
+        .save_dead_letter("Customer", &first)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        vec![
+            second.clone(),
+            first.clone(),
+            third.clone()
+        ]
+    );
+
+    // letters are per aggregate type
+    assert_eq!(
+        store
+            .load_dead_letters("Other", &dispatcher, 10)
+            .await
+            .unwrap(),
+        Vec::new()
+    );
+
+    store
+        .delete_dead_letter("Customer", &second)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        vec![first, third]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_save_load_dead_letters() {
+    tokio_test::block_on(check_save_load_dead_letters()).unwrap();
+}
@@ -1,3 +1,7 @@
+use chrono::{
+    Duration,
+    Utc,
+};
 use std::collections::HashMap;
 
 use mongodb::{
@@ -14,7 +18,10 @@ use cqrs_es2::{
 
 use crate::{
     mongodb_store::EventStore,
+    EventFilter,
     IEventStore,
+    IFilteredEventStore,
+    IHealthCheck,
 };
 
 use super::common::*;
@@ -203,6 +210,618 @@ async fn check_save_load_snapshots() -> Result<(), Error> {
     Ok(())
 }
 
+async fn check_filter_events() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisEventStore::new(db);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let mut metadata = get_metadata();
+    metadata.insert("test_id".to_string(), id.clone());
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test_event_A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            3,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test B".to_string(),
+            }),
+            metadata.clone(),
+        ),
+    ];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    let filter = EventFilter::new()
+        .with_event_types(vec!["EmailUpdated".to_string()])
+        .with_metadata("test_id", &id);
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events, contexts[1..].to_vec());
+
+    let filter = filter.with_page(1, 1);
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events, contexts[2..].to_vec());
+
+    // keys changing the path or the operator of the query are refused
+    for key in &["a.b", "$where", ""] {
+        let filter = EventFilter::new().with_metadata(key, &id);
+
+        assert!(store
+            .filter_events(&filter)
+            .await
+            .is_err());
+    }
+
+    Ok(())
+}
+
+async fn check_filter_events_by_time() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisEventStore::new(db);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let mut metadata = get_metadata();
+    metadata.insert("test_id".to_string(), id.clone());
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test_event_A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            3,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test B".to_string(),
+            }),
+            metadata.clone(),
+        ),
+    ];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    let now = Utc::now();
+    let hour = Duration::hours(1);
+
+    // the events were committed within the hour
+    let filter = EventFilter::new()
+        .with_metadata("test_id", &id)
+        .with_time_range(Some(now - hour), Some(now + hour));
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events, contexts);
+
+    // the lower bound is inclusive, the upper one exclusive
+    let filter = filter.with_time_range(Some(now + hour), None);
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events.len(), 0);
+
+    let filter = filter.with_time_range(None, Some(now - hour));
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events.len(), 0);
+
+    Ok(())
+}
+
+async fn check_list_aggregate_ids() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisEventStore::new(db);
+
+    let mut ids = vec![
+        uuid::Uuid::new_v4().to_string(),
+        uuid::Uuid::new_v4().to_string(),
+    ];
+    ids.sort();
+
+    for id in &ids {
+        store
+            .save_events(&vec![EventContext::new(
+                id.to_string(),
+                1,
+                CustomerEvent::NameAdded(NameAdded {
+                    changed_name: "test_event_A".to_string(),
+                }),
+                get_metadata(),
+            )])
+            .await
+            .unwrap();
+    }
+
+    assert!(store.count_aggregates().await.unwrap() >= 2);
+
+    let stored_ids = store
+        .list_aggregate_ids(None, i64::MAX)
+        .await
+        .unwrap();
+    assert!(stored_ids.contains(&ids[0]));
+    assert!(stored_ids.contains(&ids[1]));
+
+    let stored_ids = store
+        .list_aggregate_ids(Some(&ids[0]), i64::MAX)
+        .await
+        .unwrap();
+    assert!(!stored_ids.contains(&ids[0]));
+    assert!(stored_ids.contains(&ids[1]));
+
+    let stored_ids = store
+        .list_aggregate_ids(None, 1)
+        .await
+        .unwrap();
+    assert_eq!(1, stored_ids.len());
+
+    Ok(())
+}
+
+async fn check_save_events_and_snapshot() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisEventStore::new(db).with_client(client);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test name".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    let context = AggregateContext::new(
+        id.to_string(),
+        1,
+        Customer {
+            customer_id: "".to_string(),
+            name: "test name".to_string(),
+            email: "".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    store
+        .save_events_and_snapshot(&contexts, Some(&context))
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(stored_events, contexts);
+
+    let stored_context = store
+        .load_aggregate_from_snapshot(&id)
+        .await
+        .unwrap();
+    assert_eq!(stored_context, context);
+
+    // the unique index rejects an already stored sequence
+    assert!(store
+        .save_events_and_snapshot(&contexts, Some(&context))
+        .await
+        .is_err());
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(stored_events, contexts);
+
+    Ok(())
+}
+
+async fn check_delete_aggregate() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisEventStore::new(db);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test_event_A".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    let context = AggregateContext::new(
+        id.to_string(),
+        1,
+        Customer {
+            customer_id: "customer 1".to_string(),
+            name: "test_event_A".to_string(),
+            email: "".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+    store
+        .save_aggregate_snapshot(context)
+        .await
+        .unwrap();
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(0, stored_events.len());
+
+    let stored_context = store
+        .load_aggregate_from_snapshot(&id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_context,
+        AggregateContext::new(id.to_string(), 0, Default::default())
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-encryption")]
+async fn check_crypto_shredding() -> Result<(), Error> {
+    use std::sync::Arc;
+
+    use cqrs_es2::IAggregate;
+
+    use crate::{
+        encryption::CryptoShredder,
+        mongodb_store::KeyStore,
+    };
+
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let shredder = Arc::new(CryptoShredder::new(KeyStore::new(
+        db.clone(),
+    )));
+
+    let mut store =
+        ThisEventStore::new(db).with_transformer(shredder.clone());
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test_event_A".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(stored_events, contexts);
+
+    shredder
+        .erase(Customer::aggregate_type(), &id)
+        .await
+        .unwrap();
+
+    assert!(store.load_events(&id).await.is_err());
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(feature = "with-archive")]
+async fn check_archive_inactive() -> Result<(), Error> {
+    use chrono::{
+        Duration,
+        Utc,
+    };
+
+    use crate::{
+        archive::{
+            ArchivedEventStore,
+            Archiver,
+            FileArchive,
+        },
+        mongodb_store::ArchiveMarkerStore,
+    };
+
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisEventStore::new(db.clone());
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test_event_A".to_string(),
+            }),
+            get_metadata(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test A".to_string(),
+            }),
+            get_metadata(),
+        ),
+    ];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    for version in 1..3 {
+        store
+            .save_aggregate_snapshot(AggregateContext::new(
+                id.to_string(),
+                version,
+                Customer::default(),
+            ))
+            .await
+            .unwrap();
+    }
+
+    let dir =
+        std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+
+    let mut archiver = Archiver::new(
+        store,
+        ArchiveMarkerStore::new(db.clone()),
+        FileArchive::new(&dir),
+    );
+
+    // aggregates active since the cutoff are kept
+    let archived = archiver
+        .archive_inactive(Utc::now() - Duration::days(365))
+        .await
+        .unwrap();
+    assert!(!archived.contains(&id));
+
+    assert!(archiver
+        .archive_aggregate(&id)
+        .await
+        .unwrap());
+
+    // the snapshot is kept in the event store
+    let snapshot = ThisEventStore::new(db.clone())
+        .load_aggregate_from_snapshot(&id)
+        .await
+        .unwrap();
+    assert_eq!(snapshot.version, 2);
+
+    let mut store = ArchivedEventStore::new(
+        ThisEventStore::new(db.clone()),
+        ArchiveMarkerStore::new(db),
+        FileArchive::new(&dir),
+    );
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(stored_events, contexts);
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    Ok(())
+}
+
+async fn check_health_check() -> Result<(), Error> {
+    let client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let mut store = ThisEventStore::new(client.database("test"));
+
+    let status = store.health_check().await;
+
+    assert!(status.is_healthy());
+    assert_eq!("mongodb", status.backend);
+
+    Ok(())
+}
+
 #[test]
 fn test_save_load_events() {
     tokio_test::block_on(check_save_load_events()).unwrap();
@@ -212,3 +831,45 @@ fn test_save_load_events() {
 fn test_save_load_snapshots() {
     tokio_test::block_on(check_save_load_snapshots()).unwrap();
 }
+
+#[test]
+fn test_filter_events() {
+    tokio_test::block_on(check_filter_events()).unwrap();
+}
+
+#[test]
+fn test_filter_events_by_time() {
+    tokio_test::block_on(check_filter_events_by_time()).unwrap();
+}
+
+#[test]
+fn test_list_aggregate_ids() {
+    tokio_test::block_on(check_list_aggregate_ids()).unwrap();
+}
+
+#[test]
+fn test_save_events_and_snapshot() {
+    tokio_test::block_on(check_save_events_and_snapshot()).unwrap();
+}
+
+#[test]
+fn test_delete_aggregate() {
+    tokio_test::block_on(check_delete_aggregate()).unwrap();
+}
+
+#[cfg(feature = "with-encryption")]
+#[test]
+fn test_crypto_shredding() {
+    tokio_test::block_on(check_crypto_shredding()).unwrap();
+}
+
+#[cfg(feature = "with-archive")]
+#[test]
+fn test_archive_inactive() {
+    tokio_test::block_on(check_archive_inactive()).unwrap();
+}
+
+#[test]
+fn test_health_check() {
+    tokio_test::block_on(check_health_check()).unwrap();
+}
@@ -0,0 +1,44 @@
+use cqrs_es2::example_impl::*;
+
+use mongodb::bson::{
+    doc,
+    Bson,
+};
+
+use super::super::payload::*;
+
+#[test]
+fn test_payload_stored_as_document() {
+    let event = CustomerEvent::NameAdded(NameAdded {
+        changed_name: "test name".to_string(),
+    });
+
+    let payload = to_bson_payload(&event).unwrap();
+
+    assert_eq!(
+        payload,
+        Bson::Document(doc! {
+            "NameAdded": {
+                "changed_name": "test name",
+            },
+        })
+    );
+
+    let stored: CustomerEvent = from_bson_payload(payload).unwrap();
+
+    assert_eq!(stored, event);
+}
+
+#[test]
+fn test_legacy_string_payload() {
+    let event = CustomerEvent::NameAdded(NameAdded {
+        changed_name: "test name".to_string(),
+    });
+
+    let payload =
+        Bson::String(serde_json::to_string(&event).unwrap());
+
+    let stored: CustomerEvent = from_bson_payload(payload).unwrap();
+
+    assert_eq!(stored, event);
+}
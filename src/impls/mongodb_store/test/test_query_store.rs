@@ -1,3 +1,8 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
+
 use mongodb::{
     options::ClientOptions,
     Client,
@@ -6,12 +11,20 @@ use mongodb::{
 use cqrs_es2::{
     example_impl::*,
     Error,
+    EventContext,
     QueryContext,
 };
 
 use crate::{
+    is_query_conflict,
     mongodb_store::QueryStore,
-    repository::IQueryStore,
+    repository::{
+        IFilteredQueryStore,
+        IQueryStore,
+        QueryFilter,
+        SortOrder,
+        TransformerChain,
+    },
 };
 
 use super::common::*;
@@ -65,7 +78,7 @@ async fn check_save_load_queries() -> Result<(), Error> {
     );
 
     store
-        .save_query(context.clone())
+        .save_query(context.clone(), 0)
         .await
         .unwrap();
 
@@ -84,7 +97,7 @@ async fn check_save_load_queries() -> Result<(), Error> {
     );
 
     store
-        .save_query(context.clone())
+        .save_query(context.clone(), 1)
         .await
         .unwrap();
 
@@ -92,6 +105,351 @@ async fn check_save_load_queries() -> Result<(), Error> {
 
     assert_eq!(stored_context, context);
 
+    // the query was saved meanwhile
+    for expected_version in &[0, 1] {
+        let e = store
+            .save_query(context.clone(), *expected_version)
+            .await
+            .unwrap_err();
+
+        assert!(is_query_conflict(&e));
+    }
+
+    Ok(())
+}
+
+async fn check_load_many_queries() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisQueryStore::new(db);
+
+    let mut ids = vec![
+        uuid::Uuid::new_v4().to_string(),
+        uuid::Uuid::new_v4().to_string(),
+    ];
+    ids.sort();
+
+    let mut contexts = Vec::new();
+
+    for id in &ids {
+        let context = QueryContext::new(
+            id.to_string(),
+            1,
+            CustomerContactQuery {
+                name: format!("name {}", id),
+                email: "test@email.com".to_string(),
+                latest_address: "one address".to_string(),
+            },
+        );
+
+        store
+            .save_query(context.clone(), 0)
+            .await
+            .unwrap();
+
+        contexts.push(context);
+    }
+
+    let missing_id = uuid::Uuid::new_v4().to_string();
+
+    let stored_contexts = store
+        .load_queries(&[
+            ids[1].clone(),
+            missing_id.clone(),
+            ids[0].clone(),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_contexts,
+        vec![
+            contexts[1].clone(),
+            QueryContext::new(missing_id, 0, Default::default()),
+            contexts[0].clone(),
+        ]
+    );
+
+    let stored_contexts = store
+        .load_all_queries(Some(&ids[0]), i64::MAX)
+        .await
+        .unwrap();
+    assert!(!stored_contexts.contains(&contexts[0]));
+    assert!(stored_contexts.contains(&contexts[1]));
+
+    let stored_contexts = store
+        .load_all_queries(None, 1)
+        .await
+        .unwrap();
+    assert_eq!(1, stored_contexts.len());
+
+    Ok(())
+}
+
+async fn check_filter_queries() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisQueryStore::new(db);
+
+    let tag = uuid::Uuid::new_v4().to_string();
+
+    let mut contexts = Vec::new();
+
+    for i in 1..4 {
+        let context = QueryContext::new(
+            uuid::Uuid::new_v4().to_string(),
+            1,
+            CustomerContactQuery {
+                name: format!("name {}", i),
+                email: tag.clone(),
+                latest_address: "one address".to_string(),
+            },
+        );
+
+        store
+            .save_query(context.clone(), 0)
+            .await
+            .unwrap();
+
+        contexts.push(context);
+    }
+
+    let filter = QueryFilter::new()
+        .with_eq("email", tag.as_str())
+        .with_gte("name", "name 2")
+        .with_sort("name", SortOrder::Descending);
+
+    let stored_contexts = store
+        .filter_queries(&filter)
+        .await
+        .unwrap();
+    assert_eq!(
+        stored_contexts,
+        vec![contexts[2].clone(), contexts[1].clone()]
+    );
+
+    let filter = filter.with_page(1, 1);
+
+    let stored_contexts = store
+        .filter_queries(&filter)
+        .await
+        .unwrap();
+    assert_eq!(
+        stored_contexts,
+        vec![contexts[1].clone()]
+    );
+
+    Ok(())
+}
+
+async fn check_filter_transformed_queries() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    // the transformed payloads can not be searched
+    let mut store = ThisQueryStore::new(db)
+        .with_transformer(Arc::new(TransformerChain::new()));
+
+    let filter = QueryFilter::new().with_eq("email", "an email");
+
+    assert!(store
+        .filter_queries(&filter)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+async fn check_delete_query() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisQueryStore::new(db);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let context = QueryContext::new(
+        id.to_string(),
+        1,
+        CustomerContactQuery {
+            name: "test name".to_string(),
+            email: "test@email.com".to_string(),
+            latest_address: "one address".to_string(),
+        },
+    );
+
+    store
+        .save_query(context.clone(), 0)
+        .await
+        .unwrap();
+
+    store.delete_query(&id).await.unwrap();
+
+    let stored_context = store.load_query(&id).await.unwrap();
+
+    assert_eq!(
+        stored_context,
+        QueryContext::new(id.to_string(), 0, Default::default())
+    );
+
+    Ok(())
+}
+
+async fn check_dispatch_events() -> Result<(), Error> {
+    let mut client_options =
+        match ClientOptions::parse(CONNECTION_STRING).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+    client_options.app_name = Some("UnitTesting".to_string());
+
+    let client = match Client::with_options(client_options) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let db = client.database("test");
+
+    let mut store = ThisQueryStore::new(db);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let address = |sequence: i64, address: &str| {
+        EventContext::new(
+            id.to_string(),
+            sequence,
+            CustomerEvent::AddressUpdated(AddressUpdated {
+                new_address: address.to_string(),
+            }),
+            HashMap::new(),
+        )
+    };
+
+    // the first batch is stored with the last sequence as version
+    store
+        .dispatch_events(
+            &id,
+            &[
+                address(1, "one address"),
+                address(2, "two address"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let stored_context = store.load_query(&id).await.unwrap();
+
+    assert_eq!(stored_context.version, 2);
+    assert_eq!(
+        stored_context.payload.latest_address,
+        "two address"
+    );
+
+    // redelivered events are skipped
+    store
+        .dispatch_events(&id, &[address(1, "one address")])
+        .await
+        .unwrap();
+
+    let stored_context = store.load_query(&id).await.unwrap();
+
+    assert_eq!(stored_context.version, 2);
+    assert_eq!(
+        stored_context.payload.latest_address,
+        "two address"
+    );
+
+    // only the new events of a partly applied batch are applied
+    store
+        .dispatch_events(
+            &id,
+            &[
+                address(2, "two address"),
+                address(3, "three address"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let stored_context = store.load_query(&id).await.unwrap();
+
+    assert_eq!(stored_context.version, 3);
+    assert_eq!(
+        stored_context.payload.latest_address,
+        "three address"
+    );
+
     Ok(())
 }
 
@@ -99,3 +457,28 @@ async fn check_save_load_queries() -> Result<(), Error> {
 fn test_save_load_queries() {
     tokio_test::block_on(check_save_load_queries()).unwrap();
 }
+
+#[test]
+fn test_load_many_queries() {
+    tokio_test::block_on(check_load_many_queries()).unwrap();
+}
+
+#[test]
+fn test_filter_queries() {
+    tokio_test::block_on(check_filter_queries()).unwrap();
+}
+
+#[test]
+fn test_filter_transformed_queries() {
+    tokio_test::block_on(check_filter_transformed_queries()).unwrap();
+}
+
+#[test]
+fn test_delete_query() {
+    tokio_test::block_on(check_delete_query()).unwrap();
+}
+
+#[test]
+fn test_dispatch_events() {
+    tokio_test::block_on(check_dispatch_events()).unwrap();
+}
@@ -0,0 +1,75 @@
+use log::debug;
+
+use mongodb::{
+    bson::doc,
+    Client,
+    ClientSession,
+    Database,
+};
+
+use cqrs_es2::Error;
+
+/// Whether the server of `db` is a replica set, the multi-document
+/// transactions are not supported otherwise
+pub(super) async fn is_replica_set(
+    db: &Database
+) -> Result<bool, Error> {
+    let reply = match db
+        .run_command(doc! { "isMaster": 1 }, None)
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(
+                format!(
+                    "unable to check the server topology with \
+                     error: {}",
+                    e
+                )
+                .as_str(),
+            ));
+        },
+    };
+
+    let replica_set = reply.contains_key("setName");
+
+    debug!(
+        "Connected to a replica set: {}",
+        replica_set
+    );
+
+    Ok(replica_set)
+}
+
+/// Starts a session of `client` with a transaction started
+pub(super) async fn start_transaction(
+    client: &Client
+) -> Result<ClientSession, Error> {
+    let mut session = match client.start_session(None).await {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(
+                format!(
+                    "unable to start session with error: {}",
+                    e
+                )
+                .as_str(),
+            ));
+        },
+    };
+
+    match session.start_transaction(None).await {
+        Ok(_) => {},
+        Err(e) => {
+            return Err(Error::new(
+                format!(
+                    "unable to start transaction with error: {}",
+                    e
+                )
+                .as_str(),
+            ));
+        },
+    };
+
+    Ok(session)
+}
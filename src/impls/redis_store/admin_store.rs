@@ -0,0 +1,365 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+use redis::{
+    Commands,
+    Connection,
+    Iter,
+    RedisResult,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    event_record,
+    query_record,
+    snapshot_record,
+    IAdminStore,
+};
+
+/// Async Redis admin store
+pub struct AdminStore {
+    conn: Connection,
+}
+
+impl AdminStore {
+    /// Constructor
+    pub fn new(conn: Connection) -> Self {
+        let x = Self { conn };
+
+        trace!("Created new async Redis admin store");
+
+        x
+    }
+
+    /// The keys matching `pattern` in ascending order, once each as
+    /// SCAN can return a key several times
+    fn scan_keys(
+        &mut self,
+        pattern: &str,
+    ) -> Result<Vec<String>, Error> {
+        let res: RedisResult<Iter<'_, String>> =
+            self.conn.scan_match(pattern);
+
+        match res {
+            Ok(x) => {
+                Ok(x.collect::<BTreeSet<String>>()
+                    .into_iter()
+                    .collect())
+            },
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to scan keys matching {} with \
+                         error: {}",
+                        pattern, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    fn get_entry(
+        &mut self,
+        key: &str,
+    ) -> Result<Option<Value>, Error> {
+        let res: RedisResult<Option<String>> = self.conn.get(key);
+
+        let entry = match res {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load key {} with error: {}",
+                        key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match entry {
+            None => Ok(None),
+            Some(x) => parse_entry(key, &x).map(Some),
+        }
+    }
+}
+
+fn parse_entry(
+    key: &str,
+    entry: &str,
+) -> Result<Value, Error> {
+    match serde_json::from_str(entry) {
+        Ok(x) => Ok(x),
+        Err(e) => {
+            Err(Error::new(
+                format!(
+                    "unable to serialize entry for key {} with \
+                     error: {}",
+                    key, e
+                )
+                .as_str(),
+            ))
+        },
+    }
+}
+
+fn version_of(v: &Value) -> i64 {
+    v["version"]
+        .as_i64()
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl IAdminStore for AdminStore {
+    /// List the aggregate types having events
+    async fn list_aggregate_types(
+        &mut self
+    ) -> Result<Vec<String>, Error> {
+        trace!("listing aggregate types");
+
+        let mut result: Vec<String> = self
+            .scan_keys("events;*")?
+            .into_iter()
+            .filter_map(|x| {
+                x.split(';')
+                    .nth(1)
+                    .map(|x| x.to_string())
+            })
+            .collect();
+
+        result.sort();
+        result.dedup();
+
+        Ok(result)
+    }
+
+    /// List up to `limit` ids of the aggregates of `aggregate_type`
+    /// having events in ascending order, starting after the id
+    /// `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        aggregate_type: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' '{}' aggregate ids after '{:?}'",
+            limit,
+            aggregate_type,
+            after
+        );
+
+        let prefix = format!("events;{};", aggregate_type);
+
+        let result: Vec<String> = self
+            .scan_keys(format!("{}*", &prefix).as_str())?
+            .into_iter()
+            .map(|x| x[prefix.len()..].to_string())
+            .filter(|x| {
+                match after {
+                    Some(after) => x.as_str() > after,
+                    None => true,
+                }
+            })
+            .take(limit.max(0) as usize)
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Load the event records of `aggregate_id`
+    async fn load_events(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<Value>, Error> {
+        trace!(
+            "loading events for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let key = format!(
+            "events;{};{}",
+            aggregate_type, aggregate_id
+        );
+
+        let res: RedisResult<Vec<String>> =
+            self.conn.lrange(&key, 0, -1);
+
+        let rows = match res {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load events table for aggregate \
+                         id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for row in rows {
+            let v = parse_entry(&key, &row)?;
+
+            result.push(event_record(
+                aggregate_type,
+                aggregate_id,
+                v["sequence"]
+                    .as_i64()
+                    .unwrap_or_default(),
+                v["payload"].clone(),
+                v["metadata"].clone(),
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Load the snapshot record of `aggregate_id`, `None` if it has
+    /// no snapshot
+    async fn load_snapshot(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Value>, Error> {
+        trace!(
+            "loading snapshot for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let key = format!(
+            "snapshots;{};{}",
+            aggregate_type, aggregate_id
+        );
+
+        Ok(self.get_entry(&key)?.map(|v| {
+            snapshot_record(
+                aggregate_type,
+                aggregate_id,
+                version_of(&v),
+                v["payload"].clone(),
+            )
+        }))
+    }
+
+    /// Load the query records of all the query types of
+    /// `aggregate_id`
+    async fn load_queries(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<Value>, Error> {
+        trace!(
+            "loading queries for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let prefix = format!(
+            "queries;{};{};",
+            aggregate_type, aggregate_id
+        );
+
+        let keys =
+            self.scan_keys(format!("{}*", &prefix).as_str())?;
+
+        let mut result = Vec::new();
+
+        for key in keys {
+            if let Some(v) = self.get_entry(&key)? {
+                result.push(query_record(
+                    aggregate_type,
+                    aggregate_id,
+                    &key[prefix.len()..],
+                    version_of(&v),
+                    v["payload"].clone(),
+                ));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Delete the snapshot of `aggregate_id`, returns whether it had
+    /// one
+    async fn delete_snapshot(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<bool, Error> {
+        debug!(
+            "deleting snapshot of aggregate id '{}'",
+            aggregate_id
+        );
+
+        let res: RedisResult<i64> = self.conn.del(format!(
+            "snapshots;{};{}",
+            aggregate_type, aggregate_id
+        ));
+
+        match res {
+            Ok(x) => Ok(x > 0),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to delete snapshot for aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Delete the queries of `query_type` of all the aggregates of
+    /// `aggregate_type`, returns the number of deleted queries
+    async fn delete_queries(
+        &mut self,
+        aggregate_type: &str,
+        query_type: &str,
+    ) -> Result<u64, Error> {
+        debug!(
+            "deleting '{}' queries of '{}' aggregates",
+            query_type, aggregate_type
+        );
+
+        let keys = self.scan_keys(
+            format!(
+                "queries;{};*;{}",
+                aggregate_type, query_type
+            )
+            .as_str(),
+        )?;
+
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        let res: RedisResult<u64> = self.conn.del(&keys);
+
+        match res {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to delete '{}' queries with error: \
+                         {}",
+                        query_type, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+}
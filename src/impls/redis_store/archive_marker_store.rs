@@ -0,0 +1,256 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use serde_json::json;
+use std::{
+    collections::BTreeSet,
+    sync::{
+        Mutex,
+        MutexGuard,
+    },
+};
+
+use redis::{
+    Commands,
+    Connection,
+    Iter,
+    RedisResult,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    ArchiveMarker,
+    IArchiveMarkerStore,
+};
+
+/// Async Redis archive marker store
+pub struct ArchiveMarkerStore {
+    conn: Mutex<Connection>,
+}
+
+impl ArchiveMarkerStore {
+    /// Constructor
+    pub fn new(conn: Connection) -> Self {
+        let x = Self {
+            conn: Mutex::new(conn),
+        };
+
+        trace!("Created new async Redis archive marker store");
+
+        x
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>, Error> {
+        match self.conn.lock() {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to lock the Redis connection with \
+                         error: {}",
+                        e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    fn key_of(
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> String {
+        format!(
+            "archived_aggregates;{};{}",
+            aggregate_type, aggregate_id
+        )
+    }
+}
+
+#[async_trait]
+impl IArchiveMarkerStore for ArchiveMarkerStore {
+    /// Save the `marker` of an aggregate of `aggregate_type`,
+    /// replacing its previous marker
+    async fn save_marker(
+        &self,
+        aggregate_type: &str,
+        marker: &ArchiveMarker,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing archive marker for aggregate id '{}'",
+            marker.aggregate_id
+        );
+
+        let entry = json!({
+            "last_sequence": marker.last_sequence,
+            "location": marker.location,
+        });
+
+        let res: RedisResult<()> = self.lock()?.set(
+            Self::key_of(aggregate_type, &marker.aggregate_id),
+            entry.to_string(),
+        );
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert archive marker for \
+                         aggregate id '{}' with error: {}",
+                        marker.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load the marker of `aggregate_id`, `None` if it was never
+    /// archived
+    async fn load_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<ArchiveMarker>, Error> {
+        trace!(
+            "loading archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let res: RedisResult<Option<String>> = self.lock()?.get(
+            Self::key_of(aggregate_type, aggregate_id),
+        );
+
+        let entry = match res {
+            Ok(Some(x)) => x,
+            Ok(None) => {
+                return Ok(None);
+            },
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load archived_aggregates table \
+                         for aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let entry: serde_json::Value =
+            serde_json::from_str(&entry).unwrap_or_default();
+
+        match (
+            entry["last_sequence"].as_i64(),
+            entry["location"].as_str(),
+        ) {
+            (Some(last_sequence), Some(location)) => {
+                Ok(Some(ArchiveMarker::new(
+                    aggregate_id,
+                    last_sequence,
+                    location,
+                )))
+            },
+            _ => {
+                Err(Error::new(
+                    format!(
+                        "bad marker found in archived_aggregates \
+                         table for aggregate id '{}'",
+                        aggregate_id
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// List up to `limit` ids of the archived aggregates of
+    /// `aggregate_type` in ascending order, starting after the id
+    /// `after` when given
+    async fn list_archived_ids(
+        &self,
+        aggregate_type: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' archived aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        let prefix = Self::key_of(aggregate_type, "");
+
+        let mut conn = self.lock()?;
+
+        let res: RedisResult<Iter<'_, String>> =
+            conn.scan_match(format!("{}*", &prefix));
+
+        // SCAN can return a key several times
+        let keys: BTreeSet<String> = match res {
+            Ok(x) => x.collect(),
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to scan archived_aggregates table \
+                         keys with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(keys
+            .into_iter()
+            .map(|x| x[prefix.len()..].to_string())
+            .filter(|x| {
+                match after {
+                    Some(after) => x.as_str() > after,
+                    None => true,
+                }
+            })
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    /// Delete the marker of `aggregate_id`
+    async fn delete_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let res: RedisResult<()> = self.lock()?.del(Self::key_of(
+            aggregate_type,
+            aggregate_id,
+        ));
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete archive marker for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
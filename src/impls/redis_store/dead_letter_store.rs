@@ -0,0 +1,284 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{
+        Mutex,
+        MutexGuard,
+    },
+};
+
+use redis::{
+    Commands,
+    Connection,
+    RedisResult,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    DeadLetter,
+    IDeadLetterStore,
+};
+
+/// Async Redis dead letter store
+pub struct DeadLetterStore {
+    conn: Mutex<Connection>,
+}
+
+impl DeadLetterStore {
+    /// Constructor
+    pub fn new(conn: Connection) -> Self {
+        let x = Self {
+            conn: Mutex::new(conn),
+        };
+
+        trace!("Created new async Redis dead letter store");
+
+        x
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>, Error> {
+        match self.conn.lock() {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to lock the Redis connection with \
+                         error: {}",
+                        e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    fn key_of(
+        aggregate_type: &str,
+        dispatcher: &str,
+    ) -> String {
+        format!(
+            "dead_letters;{};{}",
+            aggregate_type, dispatcher
+        )
+    }
+
+    fn field_of(letter: &DeadLetter) -> String {
+        format!(
+            "{};{:020}",
+            letter.aggregate_id, letter.sequence
+        )
+    }
+
+    /// Load up to `limit` dead letters of `dispatcher` ordered by
+    /// aggregate id and sequence, only the ones of `aggregate_id`
+    /// when given
+    fn load_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        aggregate_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        let res: RedisResult<HashMap<String, String>> = self
+            .lock()?
+            .hgetall(Self::key_of(aggregate_type, dispatcher));
+
+        let entries = match res {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load dead_letters table for \
+                         dispatcher '{}' with error: {}",
+                        dispatcher, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut entries: Vec<(String, String)> =
+            entries.into_iter().collect();
+
+        entries.sort();
+
+        let mut result = Vec::new();
+
+        for (_, x) in entries {
+            if result.len() >= limit {
+                break;
+            }
+
+            let entry: serde_json::Value =
+                serde_json::from_str(&x).unwrap_or_default();
+
+            match (
+                entry["aggregate_id"].as_str(),
+                entry["sequence"].as_i64(),
+                entry["error"].as_str(),
+                entry["attempts"].as_i64(),
+            ) {
+                (Some(x), _, _, _)
+                    if aggregate_id.map_or(false, |id| id != x) => {},
+                (
+                    Some(aggregate_id),
+                    Some(sequence),
+                    Some(error),
+                    Some(attempts),
+                ) => {
+                    result.push(DeadLetter {
+                        dispatcher: dispatcher.to_string(),
+                        aggregate_id: aggregate_id.to_string(),
+                        sequence,
+                        events: entry["events"].clone(),
+                        error: error.to_string(),
+                        attempts,
+                    });
+                },
+                _ => {
+                    return Err(Error::new(
+                        format!(
+                            "bad dead letter found in dead_letters \
+                             table for dispatcher '{}'",
+                            dispatcher
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+        }
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl IDeadLetterStore for DeadLetterStore {
+    /// Save the `letter` of an aggregate of `aggregate_type`,
+    /// replacing the previous one of the same dispatcher, aggregate
+    /// id and sequence
+    async fn save_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        let entry = json!({
+            "aggregate_id": letter.aggregate_id,
+            "sequence": letter.sequence,
+            "events": letter.events,
+            "error": letter.error,
+            "attempts": letter.attempts,
+        });
+
+        let res: RedisResult<()> = self.lock()?.hset(
+            Self::key_of(aggregate_type, &letter.dispatcher),
+            Self::field_of(letter),
+            entry.to_string(),
+        );
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert dead letter for aggregate \
+                         id '{}' with error: {}",
+                        letter.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load up to `limit` dead letters of `dispatcher` ordered by
+    /// aggregate id and sequence
+    async fn load_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}'",
+            dispatcher
+        );
+
+        self.load_letters(
+            aggregate_type,
+            dispatcher,
+            None,
+            usize::try_from(limit).unwrap_or_default(),
+        )
+    }
+
+    /// Load the dead letters of `dispatcher` for `aggregate_id`
+    /// ordered by sequence
+    async fn load_aggregate_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}' for aggregate \
+             id '{}'",
+            dispatcher,
+            aggregate_id
+        );
+
+        self.load_letters(
+            aggregate_type,
+            dispatcher,
+            Some(aggregate_id),
+            usize::MAX,
+        )
+    }
+
+    /// Delete the `letter`
+    async fn delete_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        let res: RedisResult<()> = self.lock()?.hdel(
+            Self::key_of(aggregate_type, &letter.dispatcher),
+            Self::field_of(letter),
+        );
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete dead letter for aggregate \
+                         id '{}' with error: {}",
+                        letter.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
@@ -4,11 +4,16 @@ use log::{
     trace,
 };
 use serde_json::json;
-use std::marker::PhantomData;
+use std::{
+    collections::BTreeSet,
+    marker::PhantomData,
+    sync::Arc,
+};
 
 use redis::{
     Commands,
     Connection,
+    Iter,
     RedisResult,
 };
 
@@ -21,11 +26,20 @@ use cqrs_es2::{
     IEvent,
 };
 
-use crate::repository::IEventStore;
+use crate::repository::{
+    HealthStatus,
+    IEventStore,
+    IHealthCheck,
+    IPayloadTransformer,
+    PayloadCodec,
+};
+
+use super::health::check_health;
 
 /// Async Redis event store
 pub struct EventStore<C: ICommand, E: IEvent, A: IAggregate<C, E>> {
     conn: Connection,
+    codec: PayloadCodec,
     _phantom: PhantomData<(C, E, A)>,
 }
 
@@ -36,6 +50,7 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
     pub fn new(conn: Connection) -> Self {
         let x = Self {
             conn,
+            codec: PayloadCodec::default(),
             _phantom: PhantomData,
         };
 
@@ -43,6 +58,47 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
 
         x
     }
+
+    /// Transform the payloads with `transformer` on their way to and
+    /// from the database, e.g. to encrypt them
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
+    /// The ids of the aggregates having events in ascending order,
+    /// once each as SCAN can return a key several times
+    fn scan_aggregate_ids(
+        &mut self
+    ) -> Result<BTreeSet<String>, Error> {
+        let prefix = format!("events;{};", A::aggregate_type());
+
+        let res: RedisResult<Iter<'_, String>> = self
+            .conn
+            .scan_match(format!("{}*", &prefix));
+
+        let keys: Vec<String> = match res {
+            Ok(x) => x.collect(),
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to scan events table keys with \
+                         error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(keys
+            .into_iter()
+            .map(|x| x[prefix.len()..].to_string())
+            .collect())
+    }
 }
 
 #[async_trait]
@@ -79,9 +135,31 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
         );
 
         for context in contexts {
+            let payload = match self
+                .codec
+                .encode_value(
+                    aggregate_type,
+                    &aggregate_id,
+                    &context.payload,
+                )
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to serialize the event payload \
+                             for aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
             let r = json!({
                 "sequence": context.sequence,
-                "payload": context.payload,
+                "payload": payload,
                 "metadata": context.metadata
             });
 
@@ -123,11 +201,24 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
     async fn load_events(
         &mut self,
         aggregate_id: &str,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        self.load_events_after(aggregate_id, 0)
+            .await
+    }
+
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `after`, the older ones are skipped before being decoded
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        after: i64,
     ) -> Result<Vec<EventContext<C, E>>, Error> {
         let aggregate_type = A::aggregate_type();
 
         trace!(
-            "loading events for aggregate id '{}'",
+            "loading events after sequence '{}' for aggregate id \
+             '{}'",
+            after,
             aggregate_id
         );
 
@@ -176,7 +267,8 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             },
         };
 
-        let mut result = Vec::new();
+        let mut events = Vec::new();
+        let mut payloads = Vec::new();
 
         for row in rows {
             let v: serde_json::Value =
@@ -195,21 +287,14 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
                     },
                 };
 
-            let payload = match serde_json::from_value(
-                v.get("payload").unwrap().clone(),
-            ) {
-                Ok(x) => x,
-                Err(e) => {
-                    return Err(Error::new(
-                        format!(
-                            "bad payload found in events table for \
-                             aggregate id '{}' with error: {}",
-                            aggregate_id, e
-                        )
-                        .as_str(),
-                    ));
-                },
-            };
+            let skipped = v
+                .get("sequence")
+                .and_then(|x| x.as_i64())
+                .is_some_and(|x| x <= after);
+
+            if skipped {
+                continue;
+            }
 
             let metadata = match serde_json::from_value(
                 v.get("metadata").unwrap().clone(),
@@ -243,17 +328,114 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
                 },
             };
 
-            result.push(EventContext::new(
-                aggregate_id.to_string(),
-                sequence,
-                payload,
-                metadata,
-            ));
+            events.push((sequence, metadata));
+            payloads.push(v.get("payload").unwrap().clone());
         }
 
+        let payloads: Vec<E> = match self
+            .codec
+            .decode_values(aggregate_type, aggregate_id, payloads)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in events table for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let result = events
+            .into_iter()
+            .zip(payloads)
+            .map(|((sequence, metadata), payload)| {
+                EventContext::new(
+                    aggregate_id.to_string(),
+                    sequence,
+                    payload,
+                    metadata,
+                )
+            })
+            .collect();
+
         Ok(result)
     }
 
+    /// Load the sequence of the last event of `aggregate_id`, 0 if it
+    /// has none
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "loading latest sequence for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let key = format!(
+            "events;{};{}",
+            aggregate_type, aggregate_id
+        );
+
+        let res: RedisResult<Option<String>> =
+            self.conn.lindex(&key, -1);
+
+        let row = match res {
+            Ok(Some(x)) => x,
+            Ok(None) => {
+                return Ok(0);
+            },
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load latest sequence from events \
+                         table for aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let v: serde_json::Value = match serde_json::from_str(&row) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to serialize entry from events \
+                         table for aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match v
+            .get("sequence")
+            .and_then(|x| x.as_i64())
+        {
+            Some(x) => Ok(x),
+            None => {
+                Err(Error::new(
+                    format!(
+                        "bad sequence found in events table for \
+                         aggregate id '{}'",
+                        aggregate_id
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
     /// save a new aggregate snapshot
     async fn save_aggregate_snapshot(
         &mut self,
@@ -268,9 +450,31 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             &aggregate_id
         );
 
+        let payload = match self
+            .codec
+            .encode_value(
+                aggregate_type,
+                &aggregate_id,
+                &context.payload,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to serialize aggregate snapshot for \
+                         aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
         let r = json!({
             "version": context.version,
-            "payload": context.payload,
+            "payload": payload,
         });
 
         let r = match serde_json::to_string(&r) {
@@ -394,9 +598,15 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
                 },
             };
 
-        let payload = match serde_json::from_value(
-            v.get("payload").unwrap().clone(),
-        ) {
+        let payload = match self
+            .codec
+            .decode_value(
+                aggregate_type,
+                aggregate_id,
+                v.get("payload").unwrap().clone(),
+            )
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -432,4 +642,163 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             payload,
         ))
     }
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        Ok(self
+            .scan_aggregate_ids()?
+            .into_iter()
+            .filter(|x| {
+                match after {
+                    Some(after) => x.as_str() > after,
+                    None => true,
+                }
+            })
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        trace!("counting aggregates");
+
+        Ok(self.scan_aggregate_ids()?.len() as i64)
+    }
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        let aggregate_type = A::aggregate_type();
+
+        debug!(
+            "deleting aggregate id '{}'",
+            aggregate_id
+        );
+
+        let keys = vec![
+            format!(
+                "events;{};{}",
+                aggregate_type, aggregate_id
+            ),
+            format!(
+                "snapshots;{};{}",
+                aggregate_type, aggregate_id
+            ),
+        ];
+
+        let res: RedisResult<()> = self.conn.del(keys);
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete aggregate id '{}' with \
+                         error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        let aggregate_type = A::aggregate_type();
+
+        debug!(
+            "deleting events up to sequence '{}' for aggregate id \
+             '{}'",
+            sequence, aggregate_id
+        );
+
+        let key = format!(
+            "events;{};{}",
+            aggregate_type, aggregate_id
+        );
+
+        let res: RedisResult<Vec<String>> =
+            self.conn.lrange(&key, 0, -1);
+
+        let rows = match res {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load events table for aggregate \
+                         id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        // the events are appended in sequence order, later events
+        // pushed concurrently stay at the tail of the list
+        let count = rows
+            .iter()
+            .take_while(|x| {
+                serde_json::from_str::<serde_json::Value>(x)
+                    .ok()
+                    .and_then(|x| x.get("sequence")?.as_i64())
+                    .is_some_and(|x| x <= sequence)
+            })
+            .count();
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        let res: RedisResult<()> =
+            self.conn
+                .ltrim(&key, count as isize, -1);
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete events for aggregate id \
+                         '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IHealthCheck
+    for EventStore<C, E, A>
+{
+    /// Ping the server
+    async fn health_check(&mut self) -> HealthStatus {
+        check_health(&mut self.conn)
+    }
 }
@@ -0,0 +1,28 @@
+use log::debug;
+use std::time::Instant;
+
+use redis::Connection;
+
+use crate::repository::HealthStatus;
+
+/// Probe the server with the `PING` command, Redis has no tables to
+/// look up
+pub(super) fn check_health(conn: &mut Connection) -> HealthStatus {
+    let start = Instant::now();
+
+    match redis::cmd("PING").query::<String>(conn) {
+        Ok(_) => HealthStatus::reachable("redis", start.elapsed()),
+        Err(e) => {
+            debug!(
+                "Redis health check failed with error: {}",
+                e
+            );
+
+            HealthStatus::unreachable(
+                "redis",
+                start.elapsed(),
+                e.to_string(),
+            )
+        },
+    }
+}
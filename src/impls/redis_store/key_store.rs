@@ -0,0 +1,165 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use std::sync::{
+    Mutex,
+    MutexGuard,
+};
+
+use redis::{
+    Commands,
+    Connection,
+    RedisResult,
+};
+
+use cqrs_es2::Error;
+
+use crate::repository::IKeyStore;
+
+/// Async Redis key store
+pub struct KeyStore {
+    conn: Mutex<Connection>,
+}
+
+impl KeyStore {
+    /// Constructor
+    pub fn new(conn: Connection) -> Self {
+        let x = Self {
+            conn: Mutex::new(conn),
+        };
+
+        trace!("Created new async Redis key store");
+
+        x
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>, Error> {
+        match self.conn.lock() {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to lock the Redis connection with \
+                         error: {}",
+                        e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    fn key_of(
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> String {
+        format!(
+            "encryption_keys;{};{}",
+            aggregate_type, aggregate_id
+        )
+    }
+}
+
+#[async_trait]
+impl IKeyStore for KeyStore {
+    /// Save the key of the aggregate unless it already has one
+    async fn save_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        debug!(
+            "storing a new key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let res: RedisResult<bool> = self.lock()?.set_nx(
+            Self::key_of(aggregate_type, aggregate_id),
+            key,
+        );
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert key for aggregate id '{}' \
+                         with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load the key of the aggregate, `None` if it has none and an
+    /// empty key if it was erased
+    async fn load_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        trace!(
+            "loading key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let res: RedisResult<Option<Vec<u8>>> = self.lock()?.get(
+            Self::key_of(aggregate_type, aggregate_id),
+        );
+
+        match res {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to load encryption_keys table for \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Destroy the key of the aggregate, leaving an empty key as its
+    /// tombstone
+    async fn delete_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "erasing key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let res: RedisResult<()> = self.lock()?.set(
+            Self::key_of(aggregate_type, aggregate_id),
+            Vec::<u8>::new(),
+        );
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to erase key for aggregate id '{}' \
+                         with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
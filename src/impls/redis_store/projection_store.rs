@@ -0,0 +1,447 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    marker::PhantomData,
+};
+
+use redis::{
+    Commands,
+    Connection,
+    RedisResult,
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use crate::repository::{
+    projection_conflict,
+    sequence_conflict,
+    AppliedSequence,
+    IEventDispatcher,
+    IProjection,
+    IProjectionStore,
+    ProjectionContext,
+};
+
+/// Takes the number `n` of applied sequences followed by the
+/// arguments by triples of field, expected version and entry, the `n`
+/// first ones for the hash `KEYS[2]` of the applied sequences and the
+/// others for the hash `KEYS[1]` of the rows, sets every field to its
+/// entry when all of their stored versions, 0 when missing, are the
+/// expected ones, returns 0 when none was set or the position of the
+/// first triple whose version differs
+static SAVE_PROJECTIONS_SCRIPT: &str = "
+local function key_of(i)
+    if (i - 2) / 3 < tonumber(ARGV[1]) then
+        return KEYS[2]
+    end
+    return KEYS[1]
+end
+for i = 2, #ARGV, 3 do
+    local entry = redis.call('HGET', key_of(i), ARGV[i])
+    local version = 0
+    if entry then
+        version = cjson.decode(entry)['version']
+    end
+    if version ~= tonumber(ARGV[i + 1]) then
+        return (i + 1) / 3
+    end
+end
+for i = 2, #ARGV, 3 do
+    redis.call('HSET', key_of(i), ARGV[i], ARGV[i + 2])
+end
+return 0
+";
+
+/// Async Redis projection store
+pub struct ProjectionStore<
+    C: ICommand,
+    E: IEvent,
+    P: IProjection<C, E>,
+> {
+    conn: Connection,
+    _phantom: PhantomData<(C, E, P)>,
+}
+
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    ProjectionStore<C, E, P>
+{
+    /// Constructor
+    pub fn new(conn: Connection) -> Self {
+        let x = Self {
+            conn,
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new async Redis projection store");
+
+        x
+    }
+
+    fn projections_key() -> String {
+        format!("projections;{}", P::projection_type())
+    }
+
+    fn sequences_key() -> String {
+        format!(
+            "projection_sequences;{}",
+            P::projection_type()
+        )
+    }
+
+    fn projection_from_entry(
+        key: &str,
+        entry: &str,
+    ) -> Result<ProjectionContext<P>, Error> {
+        let projection_type = P::projection_type();
+
+        let v: serde_json::Value = match serde_json::from_str(entry) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to deserialize entry from \
+                         projections table for projection '{}' with \
+                         key '{}', error: {}",
+                        projection_type, key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let version = match v["version"].as_i64() {
+            Some(x) => x,
+            None => {
+                return Err(Error::new(
+                    format!(
+                        "bad version found in projections table for \
+                         projection '{}' with key '{}'",
+                        projection_type, key
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let payload =
+            match serde_json::from_value(v["payload"].clone()) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad payload found in projections table \
+                             for projection '{}' with key '{}', \
+                             error: {}",
+                            projection_type, key, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(ProjectionContext::new(
+            key.to_string(),
+            version,
+            payload,
+        ))
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IProjectionStore<C, E, P> for ProjectionStore<C, E, P>
+{
+    /// saves the updated row
+    async fn save_projection(
+        &mut self,
+        context: ProjectionContext<P>,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        self.save_projections(
+            vec![(context, expected_version)],
+            Vec::new(),
+        )
+        .await
+    }
+
+    /// saves the updated rows and the applied sequences in a single
+    /// script, all of them or none
+    async fn save_projections(
+        &mut self,
+        contexts: Vec<(ProjectionContext<P>, i64)>,
+        applied: Vec<AppliedSequence>,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        debug!(
+            "storing '{}' rows of projection '{}'",
+            contexts.len(),
+            projection_type
+        );
+
+        let mut cmd = redis::cmd("EVAL");
+
+        cmd.arg(SAVE_PROJECTIONS_SCRIPT)
+            .arg(2)
+            .arg(Self::projections_key())
+            .arg(Self::sequences_key())
+            .arg(applied.len());
+
+        for x in &applied {
+            let entry = json!({ "version": x.sequence });
+
+            cmd.arg(&x.aggregate_id)
+                .arg(x.expected_sequence)
+                .arg(entry.to_string());
+        }
+
+        for (context, expected_version) in &contexts {
+            let entry = json!({
+                "version": context.version,
+                "payload": context.payload,
+            });
+
+            cmd.arg(&context.key)
+                .arg(*expected_version)
+                .arg(entry.to_string());
+        }
+
+        let res: RedisResult<usize> = cmd.query(&mut self.conn);
+
+        match res {
+            Ok(0) => {},
+            Ok(x) if x <= applied.len() => {
+                let x = &applied[x - 1];
+
+                return Err(sequence_conflict(
+                    projection_type,
+                    &x.aggregate_id,
+                    x.expected_sequence,
+                ));
+            },
+            Ok(x) => {
+                let (context, expected_version) =
+                    &contexts[x - 1 - applied.len()];
+
+                return Err(projection_conflict(
+                    projection_type,
+                    &context.key,
+                    *expected_version,
+                ));
+            },
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert/update projection '{}' \
+                         with error: {}",
+                        projection_type, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// loads the sequence of the last event of `aggregate_id` applied
+    /// to the rows
+    async fn load_applied_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading applied sequence of projection '{}' with \
+             aggregate id '{}'",
+            projection_type,
+            aggregate_id
+        );
+
+        let res: RedisResult<Option<String>> = self
+            .conn
+            .hget(Self::sequences_key(), aggregate_id);
+
+        let entry = match res {
+            Ok(None) => {
+                return Ok(0);
+            },
+            Ok(Some(x)) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load projection_sequences table \
+                         for projection '{}' with aggregate id \
+                         '{}', error: {}",
+                        projection_type, aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        match serde_json::from_str::<serde_json::Value>(&entry)
+            .ok()
+            .and_then(|x| x["version"].as_i64())
+        {
+            Some(x) => Ok(x),
+            None => {
+                Err(Error::new(
+                    format!(
+                        "bad sequence found in projection_sequences \
+                         table for projection '{}' with aggregate \
+                         id '{}'",
+                        projection_type, aggregate_id
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// loads the row of `key`
+    async fn load_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<ProjectionContext<P>, Error> {
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading projection '{}' with key '{}'",
+            projection_type,
+            key
+        );
+
+        let res: RedisResult<Option<String>> = self
+            .conn
+            .hget(Self::projections_key(), key);
+
+        match res {
+            Ok(None) => {
+                Ok(ProjectionContext::new(
+                    key.to_string(),
+                    0,
+                    Default::default(),
+                ))
+            },
+            Ok(Some(x)) => Self::projection_from_entry(key, &x),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to load projections table for \
+                         projection '{}' with key '{}', error: {}",
+                        projection_type, key, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// loads up to `limit` rows in ascending order of key, starting
+    /// after the key `after` when given
+    async fn load_all_projections(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ProjectionContext<P>>, Error> {
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading '{}' projections '{}' after '{:?}'",
+            limit,
+            projection_type,
+            after
+        );
+
+        let res: RedisResult<HashMap<String, String>> = self
+            .conn
+            .hgetall(Self::projections_key());
+
+        let entries = match res {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load projections table for \
+                         projection '{}' with error: {}",
+                        projection_type, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut entries: Vec<(String, String)> = entries
+            .into_iter()
+            .filter(|x| x.0.as_str() > after.unwrap_or(""))
+            .collect();
+
+        entries.sort();
+
+        entries
+            .into_iter()
+            .take(usize::try_from(limit).unwrap_or_default())
+            .map(|(key, x)| Self::projection_from_entry(&key, &x))
+            .collect()
+    }
+
+    /// deletes the row of `key`
+    async fn delete_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        debug!(
+            "deleting projection '{}' with key '{}'",
+            projection_type, key
+        );
+
+        let res: RedisResult<()> = self
+            .conn
+            .hdel(Self::projections_key(), key);
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete projection '{}' with key \
+                         '{}', error: {}",
+                        projection_type, key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IEventDispatcher<C, E> for ProjectionStore<C, E, P>
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        events: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        self.dispatch_projection_events(events)
+            .await
+    }
+}
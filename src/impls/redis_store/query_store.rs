@@ -4,11 +4,16 @@ use log::{
     trace,
 };
 use serde_json::json;
-use std::marker::PhantomData;
+use std::{
+    collections::BTreeSet,
+    marker::PhantomData,
+    sync::Arc,
+};
 
 use redis::{
     Commands,
     Connection,
+    Iter,
     RedisResult,
 };
 
@@ -23,10 +28,34 @@ use cqrs_es2::{
 };
 
 use crate::repository::{
+    query_conflict,
+    HealthStatus,
     IEventDispatcher,
+    IFilteredQueryStore,
+    IHealthCheck,
+    IPayloadTransformer,
     IQueryStore,
+    PayloadCodec,
+    QueryFilter,
 };
 
+use super::health::check_health;
+
+/// Sets the query `KEYS[1]` to `ARGV[2]` when its stored version is
+/// `ARGV[1]`, 0 when missing, returns 1 when it was set
+static SAVE_QUERY_SCRIPT: &str = "
+local entry = redis.call('GET', KEYS[1])
+local version = 0
+if entry then
+    version = cjson.decode(entry)['version']
+end
+if version ~= tonumber(ARGV[1]) then
+    return 0
+end
+redis.call('SET', KEYS[1], ARGV[2])
+return 1
+";
+
 /// Async Redis query store
 pub struct QueryStore<
     C: ICommand,
@@ -35,6 +64,7 @@ pub struct QueryStore<
     Q: IQuery<C, E>,
 > {
     conn: Connection,
+    codec: PayloadCodec,
     _phantom: PhantomData<(C, E, A, Q)>,
 }
 
@@ -49,6 +79,7 @@ impl<
     pub fn new(conn: Connection) -> Self {
         let x = Self {
             conn,
+            codec: PayloadCodec::default(),
             _phantom: PhantomData,
         };
 
@@ -56,6 +87,180 @@ impl<
 
         x
     }
+
+    /// Transform the payloads with `transformer` on their way to and
+    /// from the database, e.g. to encrypt them
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
+    fn query_key(aggregate_id: &str) -> String {
+        format!(
+            "queries;{};{};{}",
+            A::aggregate_type(),
+            aggregate_id,
+            Q::query_type()
+        )
+    }
+
+    async fn query_from_entry(
+        &self,
+        aggregate_id: &str,
+        entry: &str,
+    ) -> Result<QueryContext<C, E, Q>, Error> {
+        let key = Self::query_key(aggregate_id);
+
+        let v: serde_json::Value = match serde_json::from_str(entry) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to serialize entry from queries \
+                         table for key {} with error: {}",
+                        &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let payload = match self
+            .codec
+            .decode_value(
+                A::aggregate_type(),
+                aggregate_id,
+                v.get("payload").unwrap().clone(),
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in queries table for key \
+                         {} with error: {}",
+                        &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let version = match serde_json::from_value(
+            v.get("version").unwrap().clone(),
+        ) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad version found in queries table for key \
+                         {} with error: {}",
+                        &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(QueryContext::new(
+            aggregate_id.to_string(),
+            version,
+            payload,
+        ))
+    }
+
+    /// The ids of the aggregates having a query in ascending order,
+    /// once each as SCAN can return a key several times
+    fn scan_aggregate_ids(
+        &mut self
+    ) -> Result<BTreeSet<String>, Error> {
+        let prefix = format!("queries;{};", A::aggregate_type());
+        let suffix = format!(";{}", Q::query_type());
+
+        let res: RedisResult<Iter<'_, String>> = self
+            .conn
+            .scan_match(format!("{}*{}", &prefix, &suffix));
+
+        let keys: Vec<String> = match res {
+            Ok(x) => x.collect(),
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to scan queries table keys with \
+                         error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(keys
+            .into_iter()
+            .map(|x| {
+                x[prefix.len()..x.len() - suffix.len()].to_string()
+            })
+            .collect())
+    }
+
+    async fn get_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        if aggregate_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let keys: Vec<String> = aggregate_ids
+            .iter()
+            .map(|x| Self::query_key(x))
+            .collect();
+
+        let res: RedisResult<Vec<Option<String>>> =
+            redis::cmd("MGET")
+                .arg(&keys)
+                .query(&mut self.conn);
+
+        let entries = match res {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load queries table for '{}' keys \
+                         with error: {}",
+                        keys.len(),
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for (aggregate_id, entry) in aggregate_ids.iter().zip(entries)
+        {
+            result.push(match entry {
+                Some(x) => {
+                    self.query_from_entry(aggregate_id, x.as_str())
+                        .await?
+                },
+                None => {
+                    QueryContext::new(
+                        aggregate_id.to_string(),
+                        0,
+                        Default::default(),
+                    )
+                },
+            });
+        }
+
+        Ok(result)
+    }
 }
 
 #[async_trait]
@@ -70,6 +275,7 @@ impl<
     async fn save_query(
         &mut self,
         context: QueryContext<C, E, Q>,
+        expected_version: i64,
     ) -> Result<(), Error> {
         let aggregate_type = A::aggregate_type();
         let query_type = Q::query_type();
@@ -81,9 +287,31 @@ impl<
             query_type, &aggregate_id
         );
 
+        let payload = match self
+            .codec
+            .encode_value(
+                aggregate_type,
+                &aggregate_id,
+                &context.payload,
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to serialize the payload of query \
+                         '{}' with aggregate id '{}', error: {}",
+                        &query_type, &aggregate_id, e,
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
         let r = json!({
             "version": context.version,
-            "payload": context.payload,
+            "payload": payload,
         });
 
         let r = match serde_json::to_string(&r) {
@@ -100,16 +328,26 @@ impl<
             },
         };
 
-        let res: RedisResult<()> = self.conn.set(
-            format!(
+        let res: RedisResult<i64> = redis::cmd("EVAL")
+            .arg(SAVE_QUERY_SCRIPT)
+            .arg(1)
+            .arg(format!(
                 "queries;{};{};{}",
                 aggregate_type, aggregate_id, query_type
-            ),
-            r,
-        );
+            ))
+            .arg(expected_version)
+            .arg(r)
+            .query(&mut self.conn);
 
         match res {
-            Ok(_) => {},
+            Ok(1) => {},
+            Ok(_) => {
+                return Err(query_conflict(
+                    query_type,
+                    &aggregate_id,
+                    expected_version,
+                ));
+            },
             Err(e) => {
                 return Err(Error::new(
                     format!(
@@ -209,9 +447,15 @@ impl<
                 },
             };
 
-        let payload = match serde_json::from_value(
-            v.get("payload").unwrap().clone(),
-        ) {
+        let payload = match self
+            .codec
+            .decode_value(
+                aggregate_type,
+                aggregate_id,
+                v.get("payload").unwrap().clone(),
+            )
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -247,6 +491,81 @@ impl<
             payload,
         ))
     }
+
+    /// loads the most recent queries of several aggregates
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading query '{}' for '{}' aggregate ids",
+            query_type,
+            aggregate_ids.len()
+        );
+
+        self.get_queries(aggregate_ids).await
+    }
+
+    /// loads up to `limit` queries in ascending order of aggregate
+    /// id, starting after the id `after` when given
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading '{}' queries '{}' after '{:?}'",
+            limit,
+            query_type,
+            after
+        );
+
+        let aggregate_ids: Vec<String> = self
+            .scan_aggregate_ids()?
+            .into_iter()
+            .filter(|x| {
+                match after {
+                    Some(after) => x.as_str() > after,
+                    None => true,
+                }
+            })
+            .take(limit.max(0) as usize)
+            .collect();
+
+        self.get_queries(&aggregate_ids).await
+    }
+
+    /// deletes the query of `aggregate_id`
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        let key = Self::query_key(aggregate_id);
+
+        debug!("deleting query for key {}", &key);
+
+        let res: RedisResult<()> = self.conn.del(&key);
+
+        match res {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete query for key {} with \
+                         error: {}",
+                        &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -266,3 +585,48 @@ impl<
             .await
     }
 }
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+    > IFilteredQueryStore<C, E, A, Q> for QueryStore<C, E, A, Q>
+{
+    /// Load the queries matching the `filter` ordered by the sort
+    /// fields then by aggregate id, filtering in process since Redis
+    /// has no secondary indexes
+    async fn filter_queries(
+        &mut self,
+        filter: &QueryFilter,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let query_type = Q::query_type();
+
+        trace!(
+            "filtering queries '{}' with '{:?}'",
+            query_type,
+            filter
+        );
+
+        let contexts = self
+            .load_all_queries(None, i64::MAX)
+            .await?;
+
+        filter.apply(contexts)
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+    > IHealthCheck for QueryStore<C, E, A, Q>
+{
+    /// Ping the server
+    async fn health_check(&mut self) -> HealthStatus {
+        check_health(&mut self.conn)
+    }
+}
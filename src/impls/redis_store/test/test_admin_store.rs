@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use redis::{
+    Client,
+    Connection,
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    AggregateContext,
+    Error,
+    EventContext,
+    IAggregate,
+    IQuery,
+    QueryContext,
+};
+
+use crate::{
+    redis_store::{
+        AdminStore,
+        EventStore,
+        QueryStore,
+    },
+    IAdminStore,
+    IEventStore,
+    IQueryStore,
+};
+
+use super::common::*;
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+fn get_metadata() -> HashMap<String, String> {
+    let now = "2021-03-18T12:32:45.930Z".to_string();
+    let mut metadata = HashMap::new();
+    metadata.insert("time".to_string(), now);
+    metadata
+}
+
+async fn check_admin_store() -> Result<(), Error> {
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let connect = || -> Result<Connection, Error> {
+        match client.get_connection() {
+            Ok(x) => Ok(x),
+            Err(e) => Err(Error::new(e.to_string().as_str())),
+        }
+    };
+
+    let mut events = ThisEventStore::new(connect()?);
+    let mut queries = ThisQueryStore::new(connect()?);
+    let mut admin = AdminStore::new(connect()?);
+
+    let aggregate_type = Customer::aggregate_type();
+    let query_type = CustomerContactQuery::query_type();
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(
+        admin
+            .load_events(aggregate_type, &id)
+            .await?
+            .len(),
+        0
+    );
+    assert_eq!(
+        admin
+            .load_snapshot(aggregate_type, &id)
+            .await?,
+        None
+    );
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test name".to_string(),
+            }),
+            get_metadata(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test@email.com".to_string(),
+            }),
+            get_metadata(),
+        ),
+    ];
+
+    let snapshot = AggregateContext::new(
+        id.to_string(),
+        1,
+        Customer {
+            customer_id: id.to_string(),
+            name: "test name".to_string(),
+            email: "test@email.com".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    events
+        .save_events_and_snapshot(&contexts, Some(&snapshot))
+        .await?;
+
+    queries
+        .save_query(
+            QueryContext::new(
+                id.to_string(),
+                1,
+                CustomerContactQuery {
+                    name: "test name".to_string(),
+                    email: "test@email.com".to_string(),
+                    latest_address: "one address".to_string(),
+                },
+            ),
+            0,
+        )
+        .await?;
+
+    assert!(admin
+        .list_aggregate_types()
+        .await?
+        .contains(&aggregate_type.to_string()));
+
+    assert_eq!(
+        admin
+            .list_aggregate_ids(aggregate_type, Some(&id[..35]), 1)
+            .await?,
+        vec![id.to_string()]
+    );
+
+    let records = admin
+        .load_events(aggregate_type, &id)
+        .await?;
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1]["kind"], "event");
+    assert_eq!(
+        records[1]["aggregate_type"],
+        aggregate_type
+    );
+    assert_eq!(records[1]["sequence"], 2);
+    assert_eq!(
+        records[0]["metadata"]["time"],
+        "2021-03-18T12:32:45.930Z"
+    );
+
+    let record = admin
+        .load_snapshot(aggregate_type, &id)
+        .await?
+        .unwrap();
+
+    assert_eq!(record["kind"], "snapshot");
+    assert_eq!(record["version"], 1);
+
+    let records = admin
+        .load_queries(aggregate_type, &id)
+        .await?;
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["query_type"], query_type);
+    assert_eq!(
+        records[0]["payload"]["name"],
+        "test name"
+    );
+
+    // deleting the snapshot leaves the events untouched
+    assert!(
+        admin
+            .delete_snapshot(aggregate_type, &id)
+            .await?
+    );
+    assert!(
+        !admin
+            .delete_snapshot(aggregate_type, &id)
+            .await?
+    );
+    assert_eq!(
+        admin
+            .load_snapshot(aggregate_type, &id)
+            .await?,
+        None
+    );
+    assert_eq!(events.load_events(&id).await?, contexts);
+
+    assert_eq!(
+        admin
+            .delete_queries(
+                aggregate_type,
+                uuid::Uuid::new_v4()
+                    .to_string()
+                    .as_str()
+            )
+            .await?,
+        0
+    );
+
+    events.delete_aggregate(&id).await?;
+    queries.delete_query(&id).await?;
+
+    Ok(())
+}
+
+#[test]
+fn test_admin_store() {
+    tokio_test::block_on(check_admin_store()).unwrap();
+}
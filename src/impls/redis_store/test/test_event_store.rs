@@ -12,6 +12,7 @@ use cqrs_es2::{
 use crate::{
     redis_store::EventStore,
     IEventStore,
+    IHealthCheck,
 };
 
 use super::common::*;
@@ -190,6 +191,233 @@ async fn check_save_load_snapshots() -> Result<(), Error> {
     Ok(())
 }
 
+async fn check_list_aggregate_ids() -> Result<(), Error> {
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let conn = match client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let mut store = ThisEventStore::new(conn);
+
+    let mut ids = vec![
+        uuid::Uuid::new_v4().to_string(),
+        uuid::Uuid::new_v4().to_string(),
+    ];
+    ids.sort();
+
+    for id in &ids {
+        store
+            .save_events(&vec![EventContext::new(
+                id.to_string(),
+                1,
+                CustomerEvent::NameAdded(NameAdded {
+                    changed_name: "test_event_A".to_string(),
+                }),
+                get_metadata(),
+            )])
+            .await
+            .unwrap();
+    }
+
+    assert!(store.count_aggregates().await.unwrap() >= 2);
+
+    let stored_ids = store
+        .list_aggregate_ids(None, i64::MAX)
+        .await
+        .unwrap();
+    assert!(stored_ids.contains(&ids[0]));
+    assert!(stored_ids.contains(&ids[1]));
+
+    let stored_ids = store
+        .list_aggregate_ids(Some(&ids[0]), i64::MAX)
+        .await
+        .unwrap();
+    assert!(!stored_ids.contains(&ids[0]));
+    assert!(stored_ids.contains(&ids[1]));
+
+    let stored_ids = store
+        .list_aggregate_ids(None, 1)
+        .await
+        .unwrap();
+    assert_eq!(1, stored_ids.len());
+
+    Ok(())
+}
+
+async fn check_delete_aggregate() -> Result<(), Error> {
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let conn = match client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let mut store = ThisEventStore::new(conn);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test_event_A".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    let context = AggregateContext::new(
+        id.to_string(),
+        1,
+        Customer {
+            customer_id: "customer 1".to_string(),
+            name: "test_event_A".to_string(),
+            email: "".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+    store
+        .save_aggregate_snapshot(context)
+        .await
+        .unwrap();
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(0, stored_events.len());
+
+    let stored_context = store
+        .load_aggregate_from_snapshot(&id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_context,
+        AggregateContext::new(id.to_string(), 0, Default::default())
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-encryption")]
+async fn check_crypto_shredding() -> Result<(), Error> {
+    use std::sync::Arc;
+
+    use cqrs_es2::IAggregate;
+
+    use crate::{
+        encryption::CryptoShredder,
+        redis_store::KeyStore,
+    };
+
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let conn = match client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let shredder = Arc::new(CryptoShredder::new(KeyStore::new(
+        match client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        },
+    )));
+
+    let mut store =
+        ThisEventStore::new(conn).with_transformer(shredder.clone());
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test_event_A".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(stored_events, contexts);
+
+    shredder
+        .erase(Customer::aggregate_type(), &id)
+        .await
+        .unwrap();
+
+    assert!(store.load_events(&id).await.is_err());
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+async fn check_health_check() -> Result<(), Error> {
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let conn = match client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let mut store = ThisEventStore::new(conn);
+
+    let status = store.health_check().await;
+
+    assert!(status.is_healthy());
+    assert_eq!("redis", status.backend);
+
+    Ok(())
+}
+
 #[test]
 fn test_save_load_events() {
     tokio_test::block_on(check_save_load_events()).unwrap();
@@ -199,3 +427,24 @@ fn test_save_load_events() {
 fn test_save_load_snapshots() {
     tokio_test::block_on(check_save_load_snapshots()).unwrap();
 }
+
+#[test]
+fn test_list_aggregate_ids() {
+    tokio_test::block_on(check_list_aggregate_ids()).unwrap();
+}
+
+#[test]
+fn test_delete_aggregate() {
+    tokio_test::block_on(check_delete_aggregate()).unwrap();
+}
+
+#[cfg(feature = "with-encryption")]
+#[test]
+fn test_crypto_shredding() {
+    tokio_test::block_on(check_crypto_shredding()).unwrap();
+}
+
+#[test]
+fn test_health_check() {
+    tokio_test::block_on(check_health_check()).unwrap();
+}
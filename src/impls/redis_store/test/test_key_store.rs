@@ -0,0 +1,100 @@
+use redis::Client;
+
+use cqrs_es2::Error;
+
+use crate::{
+    redis_store::KeyStore,
+    IKeyStore,
+};
+
+use super::common::*;
+
+async fn check_save_load_keys() -> Result<(), Error> {
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let conn = match client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let store = KeyStore::new(conn);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    store
+        .save_key("customer", &id, &[1, 2, 3])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        Some(vec![1, 2, 3])
+    );
+
+    // existing keys are never overwritten
+    store
+        .save_key("customer", &id, &[4, 5, 6])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        Some(vec![1, 2, 3])
+    );
+
+    // keys are not shared between aggregate types
+    assert_eq!(
+        store
+            .load_key("other_type", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    store
+        .delete_key("customer", &id)
+        .await
+        .unwrap();
+
+    // erased keys leave an empty key as tombstone
+    store
+        .save_key("customer", &id, &[4, 5, 6])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        Some(Vec::new())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_save_load_keys() {
+    tokio_test::block_on(check_save_load_keys()).unwrap();
+}
@@ -0,0 +1,342 @@
+use redis::Client;
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+};
+
+use crate::{
+    is_query_conflict,
+    redis_store::ProjectionStore,
+    repository::test::projections::{
+        address_updated,
+        CustomerLocations,
+    },
+    AppliedSequence,
+    IProjectionStore,
+    ProjectionContext,
+};
+
+use super::common::*;
+
+type ThisProjectionStore = ProjectionStore<
+    CustomerCommand,
+    CustomerEvent,
+    CustomerLocations,
+>;
+
+fn locations(aggregate_ids: &[&str]) -> CustomerLocations {
+    CustomerLocations {
+        aggregate_ids: aggregate_ids
+            .iter()
+            .map(|x| x.to_string())
+            .collect(),
+    }
+}
+
+async fn check_save_load_projections() -> Result<(), Error> {
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let conn = match client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let mut store = ThisProjectionStore::new(conn);
+
+    let prefix = uuid::Uuid::new_v4().to_string();
+
+    let key_a = format!("{}a", &prefix);
+    let key_b = format!("{}b", &prefix);
+
+    let stored_context = store
+        .load_projection(&key_a)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_context,
+        ProjectionContext::new(key_a.clone(), 0, Default::default())
+    );
+
+    let context =
+        ProjectionContext::new(key_a.clone(), 1, locations(&["x"]));
+
+    store
+        .save_projection(context.clone(), 0)
+        .await
+        .unwrap();
+
+    let stored_context = store
+        .load_projection(&key_a)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context, context);
+
+    let context = ProjectionContext::new(
+        key_a.clone(),
+        2,
+        locations(&["x", "y"]),
+    );
+
+    store
+        .save_projection(context.clone(), 1)
+        .await
+        .unwrap();
+
+    let stored_context = store
+        .load_projection(&key_a)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context, context);
+
+    // the row was saved meanwhile
+    for expected_version in &[0, 1] {
+        let e = store
+            .save_projection(context.clone(), *expected_version)
+            .await
+            .unwrap_err();
+
+        assert!(is_query_conflict(&e));
+    }
+
+    let other =
+        ProjectionContext::new(key_b.clone(), 1, locations(&["z"]));
+
+    store
+        .save_projection(other.clone(), 0)
+        .await
+        .unwrap();
+
+    // no row is saved when one of them was saved meanwhile
+    let updated =
+        ProjectionContext::new(key_b.clone(), 2, locations(&["w"]));
+
+    let e = store
+        .save_projections(
+            vec![(updated, 1), (context.clone(), 1)],
+            Vec::new(),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(is_query_conflict(&e));
+
+    let stored_context = store
+        .load_projection(&key_b)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context, other);
+
+    // rows are paged in ascending order of key
+    assert_eq!(
+        store
+            .load_all_projections(Some(&prefix), 2)
+            .await
+            .unwrap(),
+        vec![context.clone(), other.clone()]
+    );
+    assert_eq!(
+        store
+            .load_all_projections(Some(&prefix), 1)
+            .await
+            .unwrap(),
+        vec![context]
+    );
+    assert_eq!(
+        store
+            .load_all_projections(Some(&key_a), 1)
+            .await
+            .unwrap(),
+        vec![other]
+    );
+
+    store
+        .delete_projection(&key_a)
+        .await
+        .unwrap();
+
+    let stored_context = store
+        .load_projection(&key_a)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context.version, 0);
+
+    Ok(())
+}
+
+async fn check_dispatch_projection_events() -> Result<(), Error> {
+    let client = match Client::open(CONNECTION_STRING) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let conn = match client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(e.to_string().as_str()));
+        },
+    };
+
+    let mut store = ThisProjectionStore::new(conn);
+
+    let city = uuid::Uuid::new_v4().to_string();
+
+    let id_a = format!("{}A", &city);
+    let id_b = format!("{}B", &city);
+    let id_c = format!("{}C", &city);
+
+    let main_street = format!("1 main street, {}", &city);
+    let side_street = format!("2 side street, {}", &city);
+
+    // the events of several aggregates update the rows of their
+    // address and city, each row is saved once
+    store
+        .dispatch_projection_events(&[
+            address_updated(&id_a, 1, &main_street),
+            address_updated(&id_b, 1, &main_street),
+            address_updated(&id_a, 2, &side_street),
+        ])
+        .await
+        .unwrap();
+
+    let stored_context = store
+        .load_projection(&city)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context.version, 1);
+    assert_eq!(
+        stored_context.payload,
+        locations(&[id_a.as_str(), id_b.as_str()])
+    );
+
+    let stored_context = store
+        .load_projection(&main_street)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_context.payload,
+        locations(&[id_a.as_str(), id_b.as_str()])
+    );
+
+    let stored_context = store
+        .load_projection(&side_street)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_context.payload,
+        locations(&[id_a.as_str()])
+    );
+
+    // a later batch updates the rows saved by the previous one
+    store
+        .dispatch_projection_events(&[address_updated(
+            &id_c,
+            1,
+            &side_street,
+        )])
+        .await
+        .unwrap();
+
+    let stored_context = store
+        .load_projection(&city)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context.version, 2);
+    assert_eq!(
+        stored_context.payload,
+        locations(&[
+            id_a.as_str(),
+            id_b.as_str(),
+            id_c.as_str()
+        ])
+    );
+
+    // redelivered events are skipped, the rows are not saved again
+    store
+        .dispatch_projection_events(&[
+            address_updated(&id_a, 2, &side_street),
+            address_updated(&id_c, 1, &side_street),
+        ])
+        .await
+        .unwrap();
+
+    let stored_context = store
+        .load_projection(&city)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context.version, 2);
+
+    assert_eq!(
+        store
+            .load_applied_sequence(&id_a)
+            .await
+            .unwrap(),
+        2
+    );
+
+    // the sequences are only saved when still the expected ones
+    let e = store
+        .save_projections(
+            Vec::new(),
+            vec![AppliedSequence::new(&id_a, 3, 1)],
+        )
+        .await
+        .unwrap_err();
+
+    assert!(is_query_conflict(&e));
+
+    // only the new events of a batch are applied
+    store
+        .dispatch_projection_events(&[
+            address_updated(&id_a, 2, &main_street),
+            address_updated(&id_a, 3, &main_street),
+        ])
+        .await
+        .unwrap();
+
+    let stored_context = store
+        .load_projection(&city)
+        .await
+        .unwrap();
+
+    assert_eq!(stored_context.version, 3);
+
+    assert_eq!(
+        store
+            .load_applied_sequence(&id_a)
+            .await
+            .unwrap(),
+        3
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_save_load_projections() {
+    tokio_test::block_on(check_save_load_projections()).unwrap();
+}
+
+#[test]
+fn test_dispatch_projection_events() {
+    tokio_test::block_on(check_dispatch_projection_events()).unwrap();
+}
@@ -0,0 +1,211 @@
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use crate::repository::EventFilter;
+
+/// The SQL flavors supported by the filter query builder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dialect {
+    #[cfg(feature = "with-postgres")]
+    Postgres,
+    #[cfg(feature = "with-mysql")]
+    MySql,
+    #[cfg(feature = "with-sqlite")]
+    Sqlite,
+}
+
+/// A value to be bound to a filter query placeholder
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindValue {
+    Text(String),
+    Json(serde_json::Value),
+    Timestamp(DateTime<Utc>),
+    Int(i64),
+    #[cfg(any(
+        feature = "with-mysql",
+        feature = "with-sqlite"
+    ))]
+    Float(f64),
+}
+
+/// A filter query along with its ordered bind values
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterQuery {
+    pub sql: String,
+    pub binds: Vec<BindValue>,
+}
+
+impl FilterQuery {
+    pub fn new() -> Self {
+        Self {
+            sql: String::new(),
+            binds: Vec::new(),
+        }
+    }
+
+    pub fn placeholder(
+        &mut self,
+        dialect: Dialect,
+        value: BindValue,
+    ) -> String {
+        self.binds.push(value);
+
+        match dialect {
+            #[cfg(feature = "with-postgres")]
+            Dialect::Postgres => format!("${}", self.binds.len()),
+            #[cfg(feature = "with-mysql")]
+            Dialect::MySql => "?".to_string(),
+            #[cfg(feature = "with-sqlite")]
+            Dialect::Sqlite => "?".to_string(),
+        }
+    }
+}
+
+/// Builds the JSON path of a top level key for MySQL and SQLite
+#[cfg(any(
+    feature = "with-mysql",
+    feature = "with-sqlite"
+))]
+fn json_path(key: &str) -> String {
+    format!("$.\"{}\"", key.replace('"', "\\\""))
+}
+
+/// Builds the query selecting `aggregate_id, sequence, payload,
+/// metadata` of the events matching `filter`
+pub fn build_events_filter_query(
+    dialect: Dialect,
+    aggregate_type: &str,
+    filter: &EventFilter,
+) -> FilterQuery {
+    let mut query = FilterQuery::new();
+
+    let mut conditions = Vec::new();
+
+    let p = query.placeholder(
+        dialect,
+        BindValue::Text(aggregate_type.to_string()),
+    );
+    conditions.push(format!("aggregate_type = {}", p));
+
+    if let Some(aggregate_id) = &filter.aggregate_id {
+        let p = query.placeholder(
+            dialect,
+            BindValue::Text(aggregate_id.clone()),
+        );
+        conditions.push(format!("aggregate_id = {}", p));
+    }
+
+    if !filter.event_types.is_empty() {
+        let placeholders: Vec<String> = filter
+            .event_types
+            .iter()
+            .map(|x| {
+                query.placeholder(dialect, BindValue::Text(x.clone()))
+            })
+            .collect();
+
+        conditions.push(format!(
+            "event_type IN ({})",
+            placeholders.join(", ")
+        ));
+    }
+
+    if let Some(from) = filter.from {
+        let p =
+            query.placeholder(dialect, BindValue::Timestamp(from));
+        conditions.push(format!("timestamp >= {}", p));
+    }
+
+    if let Some(to) = filter.to {
+        let p = query.placeholder(dialect, BindValue::Timestamp(to));
+        conditions.push(format!("timestamp < {}", p));
+    }
+
+    for (key, value) in &filter.metadata {
+        let condition = match dialect {
+            #[cfg(feature = "with-postgres")]
+            Dialect::Postgres => {
+                // containment is served by the GIN index on metadata
+                let mut entry = serde_json::Map::new();
+                entry.insert(
+                    key.clone(),
+                    serde_json::Value::String(value.clone()),
+                );
+
+                let x = query.placeholder(
+                    dialect,
+                    BindValue::Json(serde_json::Value::Object(entry)),
+                );
+                format!("metadata @> {}", x)
+            },
+            #[cfg(feature = "with-mysql")]
+            Dialect::MySql => {
+                let k = query.placeholder(
+                    dialect,
+                    BindValue::Text(json_path(key)),
+                );
+                let v = query.placeholder(
+                    dialect,
+                    BindValue::Text(value.clone()),
+                );
+                format!(
+                    "JSON_UNQUOTE(JSON_EXTRACT(metadata, {})) = {}",
+                    k, v
+                )
+            },
+            #[cfg(feature = "with-sqlite")]
+            Dialect::Sqlite => {
+                let k = query.placeholder(
+                    dialect,
+                    BindValue::Text(json_path(key)),
+                );
+                let v = query.placeholder(
+                    dialect,
+                    BindValue::Text(value.clone()),
+                );
+                format!("json_extract(metadata, {}) = {}", k, v)
+            },
+        };
+
+        conditions.push(condition);
+    }
+
+    let mut sql = format!(
+        "
+SELECT
+    aggregate_id,
+    sequence,
+    payload,
+    metadata
+FROM
+    events
+WHERE
+    {}
+ORDER BY
+    timestamp,
+    aggregate_id,
+    sequence",
+        conditions.join("\n    AND\n    ")
+    );
+
+    if filter.limit.is_some() || filter.offset > 0 {
+        let limit = query.placeholder(
+            dialect,
+            BindValue::Int(filter.limit.unwrap_or(i64::MAX)),
+        );
+        let offset =
+            query.placeholder(dialect, BindValue::Int(filter.offset));
+
+        sql.push_str(
+            format!("\nLIMIT {}\nOFFSET {}", limit, offset).as_str(),
+        );
+    }
+
+    sql.push_str(";\n");
+
+    query.sql = sql;
+
+    query
+}
@@ -7,6 +7,9 @@ mod mysql_constants;
 #[cfg(feature = "with-postgres")]
 mod postgres_constants;
 
+mod event_filter_query;
+mod query_filter_query;
+
 //#[cfg(feature = "with-mssql")]
 //mod ms_sql_store;
 
@@ -18,3 +21,5 @@ pub mod postgres_store;
 
 #[cfg(feature = "with-sqlite")]
 pub mod sqlite_store;
+
+mod test;
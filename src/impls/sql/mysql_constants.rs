@@ -5,6 +5,7 @@ INSERT INTO
         aggregate_type, 
         aggregate_id,
         sequence,
+        event_type,
         payload, 
         metadata
     )
@@ -14,6 +15,7 @@ VALUES
         ?,
         ?,
         ?,
+        ?,
         ?
     );
 ";
@@ -33,6 +35,78 @@ ORDER BY
     sequence;
 ";
 
+pub static SELECT_EVENTS_AFTER: &str = "
+SELECT
+    sequence,
+    payload,
+    metadata
+FROM
+    events
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?
+    AND
+    sequence > ?
+ORDER BY
+    sequence;
+";
+
+pub static SELECT_LATEST_SEQUENCE: &str = "
+SELECT
+    COALESCE(MAX(sequence), 0)
+FROM
+    events
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?;
+";
+
+pub static SELECT_AGGREGATE_IDS: &str = "
+SELECT DISTINCT
+    aggregate_id
+FROM
+    events
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id > ?
+ORDER BY
+    aggregate_id
+LIMIT
+    ?;
+";
+
+pub static COUNT_AGGREGATES: &str = "
+SELECT
+    COUNT(DISTINCT aggregate_id)
+FROM
+    events
+WHERE
+    aggregate_type = ?;
+";
+
+pub static DELETE_EVENTS: &str = "
+DELETE FROM
+    events
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?;
+";
+
+pub static DELETE_EVENTS_UNTIL: &str = "
+DELETE FROM
+    events
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?
+    AND
+    sequence <= ?;
+";
+
 pub static INSERT_SNAPSHOT: &str = "
 INSERT INTO
     snapshots 
@@ -75,9 +149,18 @@ WHERE
     aggregate_id = ?;
 ";
 
+pub static DELETE_SNAPSHOT: &str = "
+DELETE FROM
+    snapshots
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?;
+";
+
 pub static INSERT_QUERY: &str = "
 INSERT INTO
-    queries 
+    queries
     (
         version,
         payload,
@@ -106,7 +189,9 @@ WHERE
     AND
     aggregate_id = ?
     AND
-    query_type = ?;
+    query_type = ?
+    AND
+    version = ?;
 ";
 
 pub static SELECT_QUERY: &str = "
@@ -122,3 +207,385 @@ WHERE
     AND
     query_type = ?;
 ";
+
+pub static DELETE_QUERY: &str = "
+DELETE FROM
+    queries
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?
+    AND
+    query_type = ?;
+";
+
+pub static SELECT_ALL_QUERIES: &str = "
+SELECT
+    aggregate_id,
+    version,
+    payload
+FROM
+    queries
+WHERE
+    aggregate_type = ?
+    AND
+    query_type = ?
+    AND
+    aggregate_id > ?
+ORDER BY
+    aggregate_id
+LIMIT
+    ?;
+";
+
+pub static SELECT_AGGREGATE_TYPES: &str = "
+SELECT DISTINCT
+    aggregate_type
+FROM
+    events
+ORDER BY
+    aggregate_type;
+";
+
+pub static SELECT_AGGREGATE_QUERIES: &str = "
+SELECT
+    query_type,
+    version,
+    payload
+FROM
+    queries
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?
+ORDER BY
+    query_type;
+";
+
+pub static DELETE_QUERIES: &str = "
+DELETE FROM
+    queries
+WHERE
+    aggregate_type = ?
+    AND
+    query_type = ?;
+";
+
+pub static INSERT_KEY: &str = "
+INSERT INTO
+    encryption_keys
+    (
+        aggregate_type,
+        aggregate_id,
+        encryption_key
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?
+    )
+ON DUPLICATE KEY UPDATE
+    aggregate_id = aggregate_id;
+";
+
+pub static SELECT_KEY: &str = "
+SELECT
+    encryption_key
+FROM
+    encryption_keys
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?;
+";
+
+pub static ERASE_KEY: &str = "
+INSERT INTO
+    encryption_keys
+    (
+        aggregate_type,
+        aggregate_id,
+        encryption_key
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ''
+    )
+ON DUPLICATE KEY UPDATE
+    encryption_key = VALUES(encryption_key);
+";
+
+pub static UPSERT_MARKER: &str = "
+INSERT INTO
+    archived_aggregates
+    (
+        aggregate_type,
+        aggregate_id,
+        last_sequence,
+        location
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?,
+        ?
+    )
+ON DUPLICATE KEY UPDATE
+    last_sequence = VALUES(last_sequence),
+    location = VALUES(location);
+";
+
+pub static SELECT_MARKER: &str = "
+SELECT
+    last_sequence,
+    location
+FROM
+    archived_aggregates
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?;
+";
+
+pub static SELECT_MARKER_IDS: &str = "
+SELECT
+    aggregate_id
+FROM
+    archived_aggregates
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id > ?
+ORDER BY
+    aggregate_id
+LIMIT
+    ?;
+";
+
+pub static DELETE_MARKER: &str = "
+DELETE FROM
+    archived_aggregates
+WHERE
+    aggregate_type = ?
+    AND
+    aggregate_id = ?;
+";
+
+pub static UPSERT_DEAD_LETTER: &str = "
+INSERT INTO
+    dead_letters
+    (
+        aggregate_type,
+        dispatcher,
+        aggregate_id,
+        sequence,
+        events,
+        error,
+        attempts
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?,
+        ?,
+        ?,
+        ?,
+        ?
+    )
+ON DUPLICATE KEY UPDATE
+    events = VALUES(events),
+    error = VALUES(error),
+    attempts = VALUES(attempts);
+";
+
+pub static SELECT_DEAD_LETTERS: &str = "
+SELECT
+    aggregate_id,
+    sequence,
+    events,
+    error,
+    attempts
+FROM
+    dead_letters
+WHERE
+    aggregate_type = ?
+    AND
+    dispatcher = ?
+ORDER BY
+    aggregate_id,
+    sequence
+LIMIT
+    ?;
+";
+
+pub static SELECT_AGGREGATE_DEAD_LETTERS: &str = "
+SELECT
+    aggregate_id,
+    sequence,
+    events,
+    error,
+    attempts
+FROM
+    dead_letters
+WHERE
+    aggregate_type = ?
+    AND
+    dispatcher = ?
+    AND
+    aggregate_id = ?
+ORDER BY
+    sequence;
+";
+
+pub static DELETE_DEAD_LETTER: &str = "
+DELETE FROM
+    dead_letters
+WHERE
+    aggregate_type = ?
+    AND
+    dispatcher = ?
+    AND
+    aggregate_id = ?
+    AND
+    sequence = ?;
+";
+
+pub static INSERT_PROJECTION: &str = "
+INSERT INTO
+    projections
+    (
+        version,
+        payload,
+        projection_type,
+        projection_key
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?,
+        ?
+    );
+";
+
+pub static UPDATE_PROJECTION: &str = "
+UPDATE
+    projections
+SET
+    version = ?,
+    payload = ?
+WHERE
+    projection_type = ?
+    AND
+    projection_key = ?
+    AND
+    version = ?;
+";
+
+pub static SELECT_PROJECTION: &str = "
+SELECT
+    version,
+    payload
+FROM
+    projections
+WHERE
+    projection_type = ?
+    AND
+    projection_key = ?;
+";
+
+pub static SELECT_ALL_PROJECTIONS: &str = "
+SELECT
+    projection_key,
+    version,
+    payload
+FROM
+    projections
+WHERE
+    projection_type = ?
+    AND
+    projection_key > ?
+ORDER BY
+    projection_key
+LIMIT
+    ?;
+";
+
+pub static DELETE_PROJECTION: &str = "
+DELETE FROM
+    projections
+WHERE
+    projection_type = ?
+    AND
+    projection_key = ?;
+";
+
+pub static INSERT_APPLIED_SEQUENCE: &str = "
+INSERT INTO
+    projection_sequences
+    (
+        sequence,
+        projection_type,
+        aggregate_id
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?
+    );
+";
+
+pub static UPDATE_APPLIED_SEQUENCE: &str = "
+UPDATE
+    projection_sequences
+SET
+    sequence = ?
+WHERE
+    projection_type = ?
+    AND
+    aggregate_id = ?
+    AND
+    sequence = ?;
+";
+
+pub static SELECT_APPLIED_SEQUENCE: &str = "
+SELECT
+    sequence
+FROM
+    projection_sequences
+WHERE
+    projection_type = ?
+    AND
+    aggregate_id = ?;
+";
+
+/// Builds the query selecting the queries of `count` aggregate ids
+pub fn select_queries(count: usize) -> String {
+    format!(
+        "
+SELECT
+    aggregate_id,
+    version,
+    payload
+FROM
+    queries
+WHERE
+    aggregate_type = ?
+    AND
+    query_type = ?
+    AND
+    aggregate_id IN ({});
+",
+        vec!["?"; count].join(", ")
+    )
+}
+
+pub static SELECT_ONE: &str = "
+SELECT 1;
+";
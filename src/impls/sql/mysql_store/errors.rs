@@ -0,0 +1,13 @@
+use sqlx::mysql::MySqlDatabaseError;
+
+/// MySQL error number of a duplicate entry for a unique key
+static DUPLICATE_KEY: u16 = 1062;
+
+/// Whether `error` is a duplicate entry for a unique key, e.g. a row
+/// inserted concurrently
+pub(super) fn is_duplicate_key(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .and_then(|x| x.try_downcast_ref::<MySqlDatabaseError>())
+        .is_some_and(|x| x.number() == DUPLICATE_KEY)
+}
@@ -3,9 +3,15 @@ use log::{
     debug,
     trace,
 };
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::Arc,
+};
 
-use sqlx::mysql::MySqlPool;
+use sqlx::{
+    mysql::MySqlPool,
+    MySql,
+};
 
 use cqrs_es2::{
     AggregateContext,
@@ -16,13 +22,32 @@ use cqrs_es2::{
     IEvent,
 };
 
-use crate::repository::IEventStore;
+use crate::repository::{
+    event_type_of,
+    EventFilter,
+    HealthStatus,
+    IEventStore,
+    IFilteredEventStore,
+    IHealthCheck,
+    IPayloadTransformer,
+    PayloadCodec,
+};
+
+use super::health::check_health;
 
-use super::super::mysql_constants::*;
+use super::super::{
+    event_filter_query::{
+        build_events_filter_query,
+        BindValue,
+        Dialect,
+    },
+    mysql_constants::*,
+};
 
 /// Async MySql/MariaDB event store
 pub struct EventStore<C: ICommand, E: IEvent, A: IAggregate<C, E>> {
     pool: MySqlPool,
+    codec: PayloadCodec,
     _phantom: PhantomData<(C, E, A)>,
 }
 
@@ -33,6 +58,7 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
     pub fn new(pool: MySqlPool) -> Self {
         let x = Self {
             pool,
+            codec: PayloadCodec::default(),
             _phantom: PhantomData,
         };
 
@@ -40,6 +66,16 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
 
         x
     }
+
+    /// Transform the payloads with `transformer` on their way to and
+    /// from the database, e.g. to encrypt them
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
 }
 
 #[async_trait]
@@ -102,10 +138,31 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
                     },
                 };
 
+            let event_type = event_type_of(&payload);
+
+            let payload = match self
+                .codec
+                .encode(aggregate_type, &aggregate_id, payload)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to encode the event payload for \
+                             aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
             match sqlx::query(INSERT_EVENT)
                 .bind(&aggregate_type)
                 .bind(&aggregate_id)
                 .bind(context.sequence)
+                .bind(&event_type)
                 .bind(&payload)
                 .bind(&metadata)
                 .execute(&self.pool)
@@ -143,11 +200,24 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
     async fn load_events(
         &mut self,
         aggregate_id: &str,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        self.load_events_after(aggregate_id, 0)
+            .await
+    }
+
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `sequence`
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
     ) -> Result<Vec<EventContext<C, E>>, Error> {
         let aggregate_type = A::aggregate_type();
 
         trace!(
-            "loading events for aggregate id '{}'",
+            "loading events after sequence '{}' for aggregate id \
+             '{}'",
+            sequence,
             aggregate_id
         );
 
@@ -155,9 +225,10 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             i64,
             serde_json::Value,
             serde_json::Value,
-        )> = match sqlx::query_as(SELECT_EVENTS)
+        )> = match sqlx::query_as(SELECT_EVENTS_AFTER)
             .bind(&aggregate_type)
             .bind(&aggregate_id)
+            .bind(sequence)
             .fetch_all(&self.pool)
             .await
         {
@@ -174,24 +245,35 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             },
         };
 
-        let mut result = Vec::new();
+        let (rows, payloads): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .map(|x| ((x.0, x.2), x.1))
+            .unzip();
 
-        for row in rows {
-            let payload = match serde_json::from_value(row.1) {
-                Ok(x) => x,
-                Err(e) => {
-                    return Err(Error::new(
-                        format!(
-                            "bad payload found in events table for \
-                             aggregate id '{}' with error: {}",
-                            &aggregate_id, e
-                        )
-                        .as_str(),
-                    ));
-                },
-            };
+        let payloads: Vec<E> = match self
+            .codec
+            .decode_values(aggregate_type, aggregate_id, payloads)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in events table for \
+                         aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
 
-            let metadata = match serde_json::from_value(row.2) {
+        for ((sequence, metadata), payload) in
+            rows.into_iter().zip(payloads)
+        {
+            let metadata = match serde_json::from_value(metadata) {
                 Ok(x) => x,
                 Err(e) => {
                     return Err(Error::new(
@@ -207,7 +289,7 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
 
             result.push(EventContext::new(
                 aggregate_id.to_string(),
-                row.0,
+                sequence,
                 payload,
                 metadata,
             ));
@@ -216,6 +298,41 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
         Ok(result)
     }
 
+    /// Load the sequence of the last event of `aggregate_id`, 0 if it
+    /// has none
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "loading latest sequence for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let row: (i64,) = match sqlx::query_as(SELECT_LATEST_SEQUENCE)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load latest sequence from events \
+                         table for aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(row.0)
+    }
+
     /// save a new aggregate snapshot
     async fn save_aggregate_snapshot(
         &mut self,
@@ -235,7 +352,15 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             _ => UPDATE_SNAPSHOT,
         };
 
-        let payload = match serde_json::to_value(context.payload) {
+        let payload = match self
+            .codec
+            .encode_value(
+                aggregate_type,
+                &aggregate_id,
+                &context.payload,
+            )
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -331,7 +456,11 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
 
         let row = rows[0].clone();
 
-        let payload = match serde_json::from_value(row.1) {
+        let payload = match self
+            .codec
+            .decode_value(aggregate_type, aggregate_id, row.1)
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -351,4 +480,251 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             payload,
         ))
     }
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "listing '{}' aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        let rows: Vec<(String,)> =
+            match sqlx::query_as(SELECT_AGGREGATE_IDS)
+                .bind(aggregate_type)
+                .bind(after.unwrap_or(""))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to list aggregate ids from \
+                             events table with error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows.into_iter().map(|x| x.0).collect())
+    }
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!("counting aggregates");
+
+        let row: (i64,) = match sqlx::query_as(COUNT_AGGREGATES)
+            .bind(aggregate_type)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to count aggregates in events table \
+                         with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(row.0)
+    }
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        let aggregate_type = A::aggregate_type();
+
+        debug!(
+            "deleting aggregate id '{}'",
+            aggregate_id
+        );
+
+        for sql in &[DELETE_EVENTS, DELETE_SNAPSHOT] {
+            match sqlx::query(sql)
+                .bind(aggregate_type)
+                .bind(aggregate_id)
+                .execute(&self.pool)
+                .await
+            {
+                Ok(_) => {},
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to delete aggregate id '{}' \
+                             with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting events up to sequence '{}' for aggregate id \
+             '{}'",
+            sequence, aggregate_id
+        );
+
+        match sqlx::query(DELETE_EVENTS_UNTIL)
+            .bind(A::aggregate_type())
+            .bind(aggregate_id)
+            .bind(sequence)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete events for aggregate id \
+                         '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
+    IFilteredEventStore<C, E, A> for EventStore<C, E, A>
+{
+    /// Load the events matching the `filter` ordered by commit
+    /// time, aggregate id and sequence
+    async fn filter_events(
+        &mut self,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!("filtering events with '{:?}'", filter);
+
+        let query = build_events_filter_query(
+            Dialect::MySql,
+            aggregate_type,
+            filter,
+        );
+
+        let mut sql_query = sqlx::query_as::<
+            MySql,
+            (
+                String,
+                i64,
+                serde_json::Value,
+                serde_json::Value,
+            ),
+        >(query.sql.as_str());
+
+        for value in query.binds {
+            sql_query = match value {
+                BindValue::Text(x) => sql_query.bind(x),
+                BindValue::Json(x) => sql_query.bind(x),
+                BindValue::Timestamp(x) => sql_query.bind(x),
+                BindValue::Int(x) => sql_query.bind(x),
+                BindValue::Float(x) => sql_query.bind(x),
+            };
+        }
+
+        let rows = match sql_query.fetch_all(&self.pool).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to filter events table with error: \
+                         {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for row in rows {
+            let payload = match self
+                .codec
+                .decode_value(aggregate_type, &row.0, row.2)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad payload found in events table for \
+                             aggregate id '{}' with error: {}",
+                            &row.0, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let metadata = match serde_json::from_value(row.3) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad metadata found in events table for \
+                             aggregate id '{}' with error: {}",
+                            &row.0, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            result.push(EventContext::new(
+                row.0, row.1, payload, metadata,
+            ));
+        }
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IHealthCheck
+    for EventStore<C, E, A>
+{
+    /// Probe the database and look up the `events` and `snapshots`
+    /// tables
+    async fn health_check(&mut self) -> HealthStatus {
+        check_health(&self.pool, &["events", "snapshots"]).await
+    }
 }
@@ -0,0 +1,72 @@
+use log::debug;
+use std::time::Instant;
+
+use sqlx::mysql::MySqlPool;
+
+use crate::repository::HealthStatus;
+
+use super::super::mysql_constants::SELECT_ONE;
+
+static COUNT_TABLES: &str = "
+SELECT
+    COUNT(*)
+FROM
+    information_schema.tables
+WHERE
+    table_schema = DATABASE()
+    AND
+    table_name = ?;
+";
+
+/// Probe the database with `SELECT 1` and look the `tables` up in
+/// the current database
+pub(super) async fn check_health(
+    pool: &MySqlPool,
+    tables: &[&str],
+) -> HealthStatus {
+    let start = Instant::now();
+
+    if let Err(e) = sqlx::query(SELECT_ONE)
+        .execute(pool)
+        .await
+    {
+        debug!(
+            "MySQL health check failed with error: {}",
+            e
+        );
+
+        return HealthStatus::unreachable(
+            "mysql",
+            start.elapsed(),
+            e.to_string(),
+        );
+    }
+
+    let mut status =
+        HealthStatus::reachable("mysql", start.elapsed());
+
+    for table in tables {
+        let row: (i64,) = match sqlx::query_as(COUNT_TABLES)
+            .bind(table)
+            .fetch_one(pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                status.error = Some(format!(
+                    "unable to look up table '{}' with error: {}",
+                    table, e
+                ));
+                return status;
+            },
+        };
+
+        if row.0 == 0 {
+            status
+                .missing_tables
+                .push(table.to_string());
+        }
+    }
+
+    status
+}
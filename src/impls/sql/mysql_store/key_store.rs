@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+
+use sqlx::mysql::MySqlPool;
+
+use cqrs_es2::Error;
+
+use crate::repository::IKeyStore;
+
+use super::super::mysql_constants::*;
+
+/// Async MySql/MariaDB key store
+pub struct KeyStore {
+    pool: MySqlPool,
+}
+
+impl KeyStore {
+    /// Constructor
+    pub fn new(pool: MySqlPool) -> Self {
+        let x = Self { pool };
+
+        trace!("Created new async MySQL key store");
+
+        x
+    }
+}
+
+#[async_trait]
+impl IKeyStore for KeyStore {
+    /// Save the key of the aggregate unless it already has one
+    async fn save_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        debug!(
+            "storing a new key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        match sqlx::query(INSERT_KEY)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert key for aggregate id '{}' \
+                         with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load the key of the aggregate, `None` if it has none and an
+    /// empty key if it was erased
+    async fn load_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        trace!(
+            "loading key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let rows: Vec<(Vec<u8>,)> = match sqlx::query_as(SELECT_KEY)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load encryption_keys table for \
+                         aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(rows.into_iter().next().map(|x| x.0))
+    }
+
+    /// Destroy the key of the aggregate, leaving an empty key as its
+    /// tombstone
+    async fn delete_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "erasing key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        match sqlx::query(ERASE_KEY)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to erase key for aggregate id '{}' \
+                         with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
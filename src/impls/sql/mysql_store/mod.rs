@@ -1,10 +1,22 @@
 //!
 //! MySql/MariaDB store
 
+pub use admin_store::AdminStore;
+pub use archive_marker_store::ArchiveMarkerStore;
+pub use dead_letter_store::DeadLetterStore;
 pub use event_store::EventStore;
+pub use key_store::KeyStore;
+pub use projection_store::ProjectionStore;
 pub use query_store::QueryStore;
 
+mod admin_store;
+mod archive_marker_store;
+mod dead_letter_store;
+mod errors;
 mod event_store;
+mod health;
+mod key_store;
+mod projection_store;
 mod query_store;
 
 mod test;
@@ -1,8 +1,23 @@
 #[cfg(test)]
 mod common;
 
+#[cfg(test)]
+mod test_admin_store;
+
+#[cfg(test)]
+mod test_archive_marker_store;
+
+#[cfg(test)]
+mod test_dead_letter_store;
+
 #[cfg(test)]
 mod test_event_store;
 
+#[cfg(test)]
+mod test_key_store;
+
+#[cfg(test)]
+mod test_projection_store;
+
 #[cfg(test)]
 mod test_query_store;
@@ -0,0 +1,151 @@
+use sqlx::mysql::MySqlPoolOptions;
+
+use cqrs_es2::Error;
+
+use crate::{
+    mysql_store::DeadLetterStore,
+    DeadLetter,
+    IDeadLetterStore,
+};
+
+use super::common::*;
+
+async fn check_save_load_dead_letters(
+    uri: &str
+) -> Result<(), Error> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let store = DeadLetterStore::new(pool);
+
+    let dispatcher = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        Vec::new()
+    );
+
+    let events = serde_json::json!([{ "sequence": 2 }]);
+
+    let first = DeadLetter::new(
+        &dispatcher,
+        "a",
+        2,
+        events.clone(),
+        "failed",
+    );
+    let second = DeadLetter::new(
+        &dispatcher,
+        "a",
+        1,
+        events.clone(),
+        "failed",
+    );
+    let third =
+        DeadLetter::new(&dispatcher, "b", 1, events, "failed");
+
+    for x in [&first, &third, &second] {
+        store
+            .save_dead_letter("Customer", x)
+            .await
+            .unwrap();
+    }
+
+    // letters are ordered by aggregate id and sequence
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        vec![
+            second.clone(),
+            first.clone(),
+            third.clone()
+        ]
+    );
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 2)
+            .await
+            .unwrap(),
+        vec![second.clone(), first.clone()]
+    );
+
+    // the letters of an aggregate are ordered by sequence
+    assert_eq!(
+        store
+            .load_aggregate_dead_letters("Customer", &dispatcher, "a")
+            .await
+            .unwrap(),
+        vec![second.clone(), first.clone()]
+    );
+
+    // letters are replaced by later failures
+    let mut first = first;
+    first.error = "failed again".to_string();
+    first.attempts = 2;
+
+    store
+        .save_dead_letter("Customer", &first)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        vec![
+            second.clone(),
+            first.clone(),
+            third.clone()
+        ]
+    );
+
+    // letters are per aggregate type
+    assert_eq!(
+        store
+            .load_dead_letters("Other", &dispatcher, 10)
+            .await
+            .unwrap(),
+        Vec::new()
+    );
+
+    store
+        .delete_dead_letter("Customer", &second)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_dead_letters("Customer", &dispatcher, 10)
+            .await
+            .unwrap(),
+        vec![first, third]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_mariadb_save_load_dead_letters() {
+    tokio_test::block_on(check_save_load_dead_letters(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mysql_save_load_dead_letters() {
+    tokio_test::block_on(check_save_load_dead_letters(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
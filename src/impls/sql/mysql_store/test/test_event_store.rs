@@ -1,3 +1,7 @@
+use chrono::{
+    Duration,
+    Utc,
+};
 use std::collections::HashMap;
 
 use sqlx::mysql::MySqlPoolOptions;
@@ -11,7 +15,10 @@ use cqrs_es2::{
 
 use crate::{
     mysql_store::EventStore,
+    EventFilter,
     IEventStore,
+    IFilteredEventStore,
+    IHealthCheck,
 };
 
 use super::common::*;
@@ -174,6 +181,451 @@ async fn check_save_load_snapshots(uri: &str) -> Result<(), Error> {
     Ok(())
 }
 
+async fn check_filter_events(uri: &str) -> Result<(), Error> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let mut store = ThisEventStore::new(pool);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let mut metadata = get_metadata();
+    metadata.insert("test_id".to_string(), id.clone());
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test_event_A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            3,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test B".to_string(),
+            }),
+            metadata.clone(),
+        ),
+    ];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    let filter = EventFilter::new()
+        .with_event_types(vec!["EmailUpdated".to_string()])
+        .with_metadata("test_id", &id);
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events, contexts[1..].to_vec());
+
+    let filter = filter.with_page(1, 1);
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events, contexts[2..].to_vec());
+
+    Ok(())
+}
+
+async fn check_filter_events_by_time(uri: &str) -> Result<(), Error> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let mut store = ThisEventStore::new(pool);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let mut metadata = get_metadata();
+    metadata.insert("test_id".to_string(), id.clone());
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test_event_A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test A".to_string(),
+            }),
+            metadata.clone(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            3,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test B".to_string(),
+            }),
+            metadata.clone(),
+        ),
+    ];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    let now = Utc::now();
+    let hour = Duration::hours(1);
+
+    // the events were committed within the hour
+    let filter = EventFilter::new()
+        .with_metadata("test_id", &id)
+        .with_time_range(Some(now - hour), Some(now + hour));
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events, contexts);
+
+    // the lower bound is inclusive, the upper one exclusive
+    let filter = filter.with_time_range(Some(now + hour), None);
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events.len(), 0);
+
+    let filter = filter.with_time_range(None, Some(now - hour));
+
+    let stored_events = store
+        .filter_events(&filter)
+        .await
+        .unwrap();
+    assert_eq!(stored_events.len(), 0);
+
+    Ok(())
+}
+
+async fn check_list_aggregate_ids(uri: &str) -> Result<(), Error> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let mut store = ThisEventStore::new(pool);
+
+    let mut ids = vec![
+        uuid::Uuid::new_v4().to_string(),
+        uuid::Uuid::new_v4().to_string(),
+    ];
+    ids.sort();
+
+    for id in &ids {
+        store
+            .save_events(&vec![EventContext::new(
+                id.to_string(),
+                1,
+                CustomerEvent::NameAdded(NameAdded {
+                    changed_name: "test_event_A".to_string(),
+                }),
+                get_metadata(),
+            )])
+            .await
+            .unwrap();
+    }
+
+    assert!(store.count_aggregates().await.unwrap() >= 2);
+
+    let stored_ids = store
+        .list_aggregate_ids(None, i64::MAX)
+        .await
+        .unwrap();
+    assert!(stored_ids.contains(&ids[0]));
+    assert!(stored_ids.contains(&ids[1]));
+
+    let stored_ids = store
+        .list_aggregate_ids(Some(&ids[0]), i64::MAX)
+        .await
+        .unwrap();
+    assert!(!stored_ids.contains(&ids[0]));
+    assert!(stored_ids.contains(&ids[1]));
+
+    let stored_ids = store
+        .list_aggregate_ids(None, 1)
+        .await
+        .unwrap();
+    assert_eq!(1, stored_ids.len());
+
+    Ok(())
+}
+
+async fn check_delete_aggregate(uri: &str) -> Result<(), Error> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let mut store = ThisEventStore::new(pool);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test_event_A".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    let context = AggregateContext::new(
+        id.to_string(),
+        1,
+        Customer {
+            customer_id: "customer 1".to_string(),
+            name: "test_event_A".to_string(),
+            email: "".to_string(),
+            addresses: Vec::new(),
+        },
+    );
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+    store
+        .save_aggregate_snapshot(context)
+        .await
+        .unwrap();
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(0, stored_events.len());
+
+    let stored_context = store
+        .load_aggregate_from_snapshot(&id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stored_context,
+        AggregateContext::new(id.to_string(), 0, Default::default())
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-encryption")]
+async fn check_crypto_shredding(uri: &str) -> Result<(), Error> {
+    use std::sync::Arc;
+
+    use cqrs_es2::IAggregate;
+
+    use crate::{
+        encryption::CryptoShredder,
+        mysql_store::KeyStore,
+    };
+
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let shredder = Arc::new(CryptoShredder::new(KeyStore::new(
+        pool.clone(),
+    )));
+
+    let mut store =
+        ThisEventStore::new(pool).with_transformer(shredder.clone());
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![EventContext::new(
+        id.to_string(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "test_event_A".to_string(),
+        }),
+        get_metadata(),
+    )];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(stored_events, contexts);
+
+    shredder
+        .erase(Customer::aggregate_type(), &id)
+        .await
+        .unwrap();
+
+    assert!(store.load_events(&id).await.is_err());
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[cfg(feature = "with-archive")]
+async fn check_archive_inactive(uri: &str) -> Result<(), Error> {
+    use chrono::{
+        Duration,
+        Utc,
+    };
+
+    use crate::{
+        archive::{
+            ArchivedEventStore,
+            Archiver,
+            FileArchive,
+        },
+        mysql_store::ArchiveMarkerStore,
+    };
+
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let mut store = ThisEventStore::new(pool.clone());
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let contexts = vec![
+        EventContext::new(
+            id.to_string(),
+            1,
+            CustomerEvent::NameAdded(NameAdded {
+                changed_name: "test_event_A".to_string(),
+            }),
+            get_metadata(),
+        ),
+        EventContext::new(
+            id.to_string(),
+            2,
+            CustomerEvent::EmailUpdated(EmailUpdated {
+                new_email: "test A".to_string(),
+            }),
+            get_metadata(),
+        ),
+    ];
+
+    store
+        .save_events(&contexts)
+        .await
+        .unwrap();
+
+    for version in 1..3 {
+        store
+            .save_aggregate_snapshot(AggregateContext::new(
+                id.to_string(),
+                version,
+                Customer::default(),
+            ))
+            .await
+            .unwrap();
+    }
+
+    let dir =
+        std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+
+    let mut archiver = Archiver::new(
+        store,
+        ArchiveMarkerStore::new(pool.clone()),
+        FileArchive::new(&dir),
+    );
+
+    // aggregates active since the cutoff are kept
+    let archived = archiver
+        .archive_inactive(Utc::now() - Duration::days(365))
+        .await
+        .unwrap();
+    assert!(!archived.contains(&id));
+
+    assert!(archiver
+        .archive_aggregate(&id)
+        .await
+        .unwrap());
+
+    // the snapshot is kept in the event store
+    let snapshot = ThisEventStore::new(pool.clone())
+        .load_aggregate_from_snapshot(&id)
+        .await
+        .unwrap();
+    assert_eq!(snapshot.version, 2);
+
+    let mut store = ArchivedEventStore::new(
+        ThisEventStore::new(pool.clone()),
+        ArchiveMarkerStore::new(pool),
+        FileArchive::new(&dir),
+    );
+
+    let stored_events = store.load_events(&id).await.unwrap();
+    assert_eq!(stored_events, contexts);
+
+    store
+        .delete_aggregate(&id)
+        .await
+        .unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    Ok(())
+}
+
+async fn check_health_check(uri: &str) -> Result<(), Error> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(uri)
+        .await
+        .unwrap();
+
+    let mut store = ThisEventStore::new(pool);
+
+    let status = store.health_check().await;
+
+    assert!(status.is_healthy());
+    assert_eq!("mysql", status.backend);
+
+    Ok(())
+}
+
 #[test]
 fn test_mariadb_save_load_events() {
     tokio_test::block_on(check_save_load_events(
@@ -205,3 +657,119 @@ fn test_mysql_save_load_snapshots() {
     ))
     .unwrap();
 }
+
+#[test]
+fn test_mariadb_filter_events() {
+    tokio_test::block_on(check_filter_events(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mariadb_filter_events_by_time() {
+    tokio_test::block_on(check_filter_events_by_time(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mysql_filter_events() {
+    tokio_test::block_on(check_filter_events(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mysql_filter_events_by_time() {
+    tokio_test::block_on(check_filter_events_by_time(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mariadb_list_aggregate_ids() {
+    tokio_test::block_on(check_list_aggregate_ids(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mysql_list_aggregate_ids() {
+    tokio_test::block_on(check_list_aggregate_ids(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mariadb_delete_aggregate() {
+    tokio_test::block_on(check_delete_aggregate(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mysql_delete_aggregate() {
+    tokio_test::block_on(check_delete_aggregate(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
+
+#[cfg(feature = "with-encryption")]
+#[test]
+fn test_mariadb_crypto_shredding() {
+    tokio_test::block_on(check_crypto_shredding(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[cfg(feature = "with-encryption")]
+#[test]
+fn test_mysql_crypto_shredding() {
+    tokio_test::block_on(check_crypto_shredding(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
+
+#[cfg(feature = "with-archive")]
+#[test]
+fn test_mariadb_archive_inactive() {
+    tokio_test::block_on(check_archive_inactive(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[cfg(feature = "with-archive")]
+#[test]
+fn test_mysql_archive_inactive() {
+    tokio_test::block_on(check_archive_inactive(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mariadb_health_check() {
+    tokio_test::block_on(check_health_check(
+        CONNECTION_STRING_MARIADB,
+    ))
+    .unwrap();
+}
+
+#[test]
+fn test_mysql_health_check() {
+    tokio_test::block_on(check_health_check(
+        CONNECTION_STRING_MYSQL,
+    ))
+    .unwrap();
+}
@@ -5,6 +5,7 @@ INSERT INTO
         aggregate_type, 
         aggregate_id,
         sequence,
+        event_type,
         payload, 
         metadata
     )
@@ -14,7 +15,8 @@ VALUES
         $2,
         $3,
         $4,
-        $5
+        $5,
+        $6
     );
 ";
 
@@ -33,6 +35,78 @@ ORDER BY
     sequence;
 ";
 
+pub static SELECT_EVENTS_AFTER: &str = "
+SELECT
+    sequence,
+    payload,
+    metadata
+FROM
+    events
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2
+    AND
+    sequence > $3
+ORDER BY
+    sequence;
+";
+
+pub static SELECT_LATEST_SEQUENCE: &str = "
+SELECT
+    COALESCE(MAX(sequence), 0)
+FROM
+    events
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2;
+";
+
+pub static SELECT_AGGREGATE_IDS: &str = "
+SELECT DISTINCT
+    aggregate_id
+FROM
+    events
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id > $2
+ORDER BY
+    aggregate_id
+LIMIT
+    $3;
+";
+
+pub static COUNT_AGGREGATES: &str = "
+SELECT
+    COUNT(DISTINCT aggregate_id)
+FROM
+    events
+WHERE
+    aggregate_type = $1;
+";
+
+pub static DELETE_EVENTS: &str = "
+DELETE FROM
+    events
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2;
+";
+
+pub static DELETE_EVENTS_UNTIL: &str = "
+DELETE FROM
+    events
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2
+    AND
+    sequence <= $3;
+";
+
 pub static INSERT_SNAPSHOT: &str = "
 INSERT INTO
     snapshots 
@@ -75,9 +149,18 @@ WHERE
     aggregate_id = $2;
 ";
 
+pub static DELETE_SNAPSHOT: &str = "
+DELETE FROM
+    snapshots
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2;
+";
+
 pub static INSERT_QUERY: &str = "
 INSERT INTO
-    queries 
+    queries
     (
         version,
         payload,
@@ -92,7 +175,8 @@ VALUES
         $3,
         $4,
         $5
-    );
+    )
+ON CONFLICT DO NOTHING;
 ";
 
 pub static UPDATE_QUERY: &str = "
@@ -106,7 +190,9 @@ WHERE
     AND
     aggregate_id = $4
     AND
-    query_type = $5;
+    query_type = $5
+    AND
+    version = $6;
 ";
 
 pub static SELECT_QUERY: &str = "
@@ -122,3 +208,404 @@ WHERE
     AND
     query_type = $3;
 ";
+
+pub static DELETE_QUERY: &str = "
+DELETE FROM
+    queries
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2
+    AND
+    query_type = $3;
+";
+
+pub static SELECT_QUERIES: &str = "
+SELECT
+    aggregate_id,
+    version,
+    payload
+FROM
+    queries
+WHERE
+    aggregate_type = $1
+    AND
+    query_type = $2
+    AND
+    aggregate_id = ANY($3);
+";
+
+pub static SELECT_ALL_QUERIES: &str = "
+SELECT
+    aggregate_id,
+    version,
+    payload
+FROM
+    queries
+WHERE
+    aggregate_type = $1
+    AND
+    query_type = $2
+    AND
+    aggregate_id > $3
+ORDER BY
+    aggregate_id
+LIMIT
+    $4;
+";
+
+pub static SELECT_AGGREGATE_TYPES: &str = "
+SELECT DISTINCT
+    aggregate_type
+FROM
+    events
+ORDER BY
+    aggregate_type;
+";
+
+pub static SELECT_AGGREGATE_QUERIES: &str = "
+SELECT
+    query_type,
+    version,
+    payload
+FROM
+    queries
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2
+ORDER BY
+    query_type;
+";
+
+pub static DELETE_QUERIES: &str = "
+DELETE FROM
+    queries
+WHERE
+    aggregate_type = $1
+    AND
+    query_type = $2;
+";
+
+pub static INSERT_KEY: &str = "
+INSERT INTO
+    encryption_keys
+    (
+        aggregate_type,
+        aggregate_id,
+        encryption_key
+    )
+VALUES
+    (
+        $1,
+        $2,
+        $3
+    )
+ON CONFLICT DO NOTHING;
+";
+
+pub static SELECT_KEY: &str = "
+SELECT
+    encryption_key
+FROM
+    encryption_keys
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2;
+";
+
+pub static ERASE_KEY: &str = "
+INSERT INTO
+    encryption_keys
+    (
+        aggregate_type,
+        aggregate_id,
+        encryption_key
+    )
+VALUES
+    (
+        $1,
+        $2,
+        ''
+    )
+ON CONFLICT (aggregate_type, aggregate_id)
+DO UPDATE SET
+    encryption_key = EXCLUDED.encryption_key;
+";
+
+pub static UPSERT_MARKER: &str = "
+INSERT INTO
+    archived_aggregates
+    (
+        aggregate_type,
+        aggregate_id,
+        last_sequence,
+        location
+    )
+VALUES
+    (
+        $1,
+        $2,
+        $3,
+        $4
+    )
+ON CONFLICT
+    (
+        aggregate_type,
+        aggregate_id
+    )
+DO UPDATE SET
+    last_sequence = EXCLUDED.last_sequence,
+    location = EXCLUDED.location;
+";
+
+pub static SELECT_MARKER: &str = "
+SELECT
+    last_sequence,
+    location
+FROM
+    archived_aggregates
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2;
+";
+
+pub static SELECT_MARKER_IDS: &str = "
+SELECT
+    aggregate_id
+FROM
+    archived_aggregates
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id > $2
+ORDER BY
+    aggregate_id
+LIMIT
+    $3;
+";
+
+pub static DELETE_MARKER: &str = "
+DELETE FROM
+    archived_aggregates
+WHERE
+    aggregate_type = $1
+    AND
+    aggregate_id = $2;
+";
+
+pub static UPSERT_DEAD_LETTER: &str = "
+INSERT INTO
+    dead_letters
+    (
+        aggregate_type,
+        dispatcher,
+        aggregate_id,
+        sequence,
+        events,
+        error,
+        attempts
+    )
+VALUES
+    (
+        $1,
+        $2,
+        $3,
+        $4,
+        $5,
+        $6,
+        $7
+    )
+ON CONFLICT
+    (
+        aggregate_type,
+        dispatcher,
+        aggregate_id,
+        sequence
+    )
+DO UPDATE SET
+    events = EXCLUDED.events,
+    error = EXCLUDED.error,
+    attempts = EXCLUDED.attempts;
+";
+
+pub static SELECT_DEAD_LETTERS: &str = "
+SELECT
+    aggregate_id,
+    sequence,
+    events,
+    error,
+    attempts
+FROM
+    dead_letters
+WHERE
+    aggregate_type = $1
+    AND
+    dispatcher = $2
+ORDER BY
+    aggregate_id,
+    sequence
+LIMIT
+    $3;
+";
+
+pub static SELECT_AGGREGATE_DEAD_LETTERS: &str = "
+SELECT
+    aggregate_id,
+    sequence,
+    events,
+    error,
+    attempts
+FROM
+    dead_letters
+WHERE
+    aggregate_type = $1
+    AND
+    dispatcher = $2
+    AND
+    aggregate_id = $3
+ORDER BY
+    sequence;
+";
+
+pub static DELETE_DEAD_LETTER: &str = "
+DELETE FROM
+    dead_letters
+WHERE
+    aggregate_type = $1
+    AND
+    dispatcher = $2
+    AND
+    aggregate_id = $3
+    AND
+    sequence = $4;
+";
+
+pub static INSERT_PROJECTION: &str = "
+INSERT INTO
+    projections
+    (
+        version,
+        payload,
+        projection_type,
+        projection_key
+    )
+VALUES
+    (
+        $1,
+        $2,
+        $3,
+        $4
+    )
+ON CONFLICT DO NOTHING;
+";
+
+pub static UPDATE_PROJECTION: &str = "
+UPDATE
+    projections
+SET
+    version = $1,
+    payload = $2
+WHERE
+    projection_type = $3
+    AND
+    projection_key = $4
+    AND
+    version = $5;
+";
+
+pub static SELECT_PROJECTION: &str = "
+SELECT
+    version,
+    payload
+FROM
+    projections
+WHERE
+    projection_type = $1
+    AND
+    projection_key = $2;
+";
+
+pub static SELECT_ALL_PROJECTIONS: &str = "
+SELECT
+    projection_key,
+    version,
+    payload
+FROM
+    projections
+WHERE
+    projection_type = $1
+    AND
+    projection_key > $2
+ORDER BY
+    projection_key
+LIMIT
+    $3;
+";
+
+pub static DELETE_PROJECTION: &str = "
+DELETE FROM
+    projections
+WHERE
+    projection_type = $1
+    AND
+    projection_key = $2;
+";
+
+pub static INSERT_APPLIED_SEQUENCE: &str = "
+INSERT INTO
+    projection_sequences
+    (
+        sequence,
+        projection_type,
+        aggregate_id
+    )
+VALUES
+    (
+        $1,
+        $2,
+        $3
+    )
+ON CONFLICT DO NOTHING;
+";
+
+pub static UPDATE_APPLIED_SEQUENCE: &str = "
+UPDATE
+    projection_sequences
+SET
+    sequence = $1
+WHERE
+    projection_type = $2
+    AND
+    aggregate_id = $3
+    AND
+    sequence = $4;
+";
+
+pub static SELECT_APPLIED_SEQUENCE: &str = "
+SELECT
+    sequence
+FROM
+    projection_sequences
+WHERE
+    projection_type = $1
+    AND
+    aggregate_id = $2;
+";
+
+pub static SELECT_ONE: &str = "
+SELECT 1;
+";
+
+pub static COUNT_TABLES: &str = "
+SELECT
+    COUNT(*)
+FROM
+    information_schema.tables
+WHERE
+    table_schema = current_schema()
+    AND
+    table_name = $1;
+";
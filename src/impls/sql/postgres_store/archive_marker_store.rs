@@ -0,0 +1,183 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+
+use sqlx::postgres::PgPool;
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    ArchiveMarker,
+    IArchiveMarkerStore,
+};
+
+use super::super::postgres_constants::*;
+
+/// Async Postgres archive marker store
+pub struct ArchiveMarkerStore {
+    pool: PgPool,
+}
+
+impl ArchiveMarkerStore {
+    /// Constructor
+    pub fn new(pool: PgPool) -> Self {
+        let x = Self { pool };
+
+        trace!("Created new async Postgres archive marker store");
+
+        x
+    }
+}
+
+#[async_trait]
+impl IArchiveMarkerStore for ArchiveMarkerStore {
+    /// Save the `marker` of an aggregate of `aggregate_type`,
+    /// replacing its previous marker
+    async fn save_marker(
+        &self,
+        aggregate_type: &str,
+        marker: &ArchiveMarker,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing archive marker for aggregate id '{}'",
+            marker.aggregate_id
+        );
+
+        match sqlx::query(UPSERT_MARKER)
+            .bind(aggregate_type)
+            .bind(&marker.aggregate_id)
+            .bind(marker.last_sequence)
+            .bind(&marker.location)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert archive marker for \
+                         aggregate id '{}' with error: {}",
+                        &marker.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load the marker of `aggregate_id`, `None` if it was never
+    /// archived
+    async fn load_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<ArchiveMarker>, Error> {
+        trace!(
+            "loading archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let rows: Vec<(i64, String)> =
+            match sqlx::query_as(SELECT_MARKER)
+                .bind(aggregate_type)
+                .bind(aggregate_id)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load archived_aggregates \
+                             table for aggregate id '{}' with \
+                             error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows
+            .into_iter()
+            .next()
+            .map(|x| ArchiveMarker::new(aggregate_id, x.0, &x.1)))
+    }
+
+    /// List up to `limit` ids of the archived aggregates of
+    /// `aggregate_type` in ascending order, starting after the id
+    /// `after` when given
+    async fn list_archived_ids(
+        &self,
+        aggregate_type: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' archived aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        let rows: Vec<(String,)> =
+            match sqlx::query_as(SELECT_MARKER_IDS)
+                .bind(aggregate_type)
+                .bind(after.unwrap_or(""))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to list aggregate ids from \
+                             archived_aggregates table with error: \
+                             {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows.into_iter().map(|x| x.0).collect())
+    }
+
+    /// Delete the marker of `aggregate_id`
+    async fn delete_marker(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting archive marker for aggregate id '{}'",
+            aggregate_id
+        );
+
+        match sqlx::query(DELETE_MARKER)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete archive marker for \
+                         aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
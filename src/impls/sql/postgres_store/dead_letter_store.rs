@@ -0,0 +1,221 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+
+use sqlx::postgres::PgPool;
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    DeadLetter,
+    IDeadLetterStore,
+};
+
+use super::super::postgres_constants::*;
+
+/// Async Postgres dead letter store
+pub struct DeadLetterStore {
+    pool: PgPool,
+}
+
+impl DeadLetterStore {
+    /// Constructor
+    pub fn new(pool: PgPool) -> Self {
+        let x = Self { pool };
+
+        trace!("Created new async Postgres dead letter store");
+
+        x
+    }
+}
+
+#[async_trait]
+impl IDeadLetterStore for DeadLetterStore {
+    /// Save the `letter` of an aggregate of `aggregate_type`,
+    /// replacing the previous one of the same dispatcher, aggregate
+    /// id and sequence
+    async fn save_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "storing dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        match sqlx::query(UPSERT_DEAD_LETTER)
+            .bind(aggregate_type)
+            .bind(&letter.dispatcher)
+            .bind(&letter.aggregate_id)
+            .bind(letter.sequence)
+            .bind(letter.events.to_string())
+            .bind(&letter.error)
+            .bind(letter.attempts)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert dead letter for aggregate \
+                         id '{}' with error: {}",
+                        &letter.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load up to `limit` dead letters of `dispatcher` ordered by
+    /// aggregate id and sequence
+    async fn load_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}'",
+            dispatcher
+        );
+
+        let rows: Vec<(String, i64, String, String, i64)> =
+            match sqlx::query_as(SELECT_DEAD_LETTERS)
+                .bind(aggregate_type)
+                .bind(dispatcher)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load dead_letters table for \
+                             dispatcher '{}' with error: {}",
+                            dispatcher, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        letters_of(dispatcher, rows)
+    }
+
+    /// Load the dead letters of `dispatcher` for `aggregate_id`
+    /// ordered by sequence
+    async fn load_aggregate_dead_letters(
+        &self,
+        aggregate_type: &str,
+        dispatcher: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        trace!(
+            "loading dead letters of dispatcher '{}' for aggregate \
+             id '{}'",
+            dispatcher,
+            aggregate_id
+        );
+
+        let rows: Vec<(String, i64, String, String, i64)> =
+            match sqlx::query_as(SELECT_AGGREGATE_DEAD_LETTERS)
+                .bind(aggregate_type)
+                .bind(dispatcher)
+                .bind(aggregate_id)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load dead_letters table for \
+                             dispatcher '{}' with error: {}",
+                            dispatcher, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        letters_of(dispatcher, rows)
+    }
+
+    /// Delete the `letter`
+    async fn delete_dead_letter(
+        &self,
+        aggregate_type: &str,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "deleting dead letter for aggregate id '{}'",
+            letter.aggregate_id
+        );
+
+        match sqlx::query(DELETE_DEAD_LETTER)
+            .bind(aggregate_type)
+            .bind(&letter.dispatcher)
+            .bind(&letter.aggregate_id)
+            .bind(letter.sequence)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete dead letter for aggregate \
+                         id '{}' with error: {}",
+                        &letter.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+/// The dead letters of `dispatcher` in the selected `rows`
+fn letters_of(
+    dispatcher: &str,
+    rows: Vec<(String, i64, String, String, i64)>,
+) -> Result<Vec<DeadLetter>, Error> {
+    let mut result = Vec::new();
+
+    for x in rows {
+        let events = match serde_json::from_str(&x.2) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad events found in dead letter of \
+                         aggregate id '{}' with error: {}",
+                        &x.0, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        result.push(DeadLetter {
+            dispatcher: dispatcher.to_string(),
+            aggregate_id: x.0,
+            sequence: x.1,
+            events,
+            error: x.3,
+            attempts: x.4,
+        });
+    }
+
+    Ok(result)
+}
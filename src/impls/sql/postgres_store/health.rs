@@ -0,0 +1,64 @@
+use log::debug;
+use std::time::Instant;
+
+use sqlx::postgres::PgPool;
+
+use crate::repository::HealthStatus;
+
+use super::super::postgres_constants::{
+    COUNT_TABLES,
+    SELECT_ONE,
+};
+
+/// Probe the database with `SELECT 1` and look the `tables` up in
+/// the current schema
+pub(super) async fn check_health(
+    pool: &PgPool,
+    tables: &[&str],
+) -> HealthStatus {
+    let start = Instant::now();
+
+    if let Err(e) = sqlx::query(SELECT_ONE)
+        .execute(pool)
+        .await
+    {
+        debug!(
+            "Postgres health check failed with error: {}",
+            e
+        );
+
+        return HealthStatus::unreachable(
+            "postgres",
+            start.elapsed(),
+            e.to_string(),
+        );
+    }
+
+    let mut status =
+        HealthStatus::reachable("postgres", start.elapsed());
+
+    for table in tables {
+        let row: (i64,) = match sqlx::query_as(COUNT_TABLES)
+            .bind(table)
+            .fetch_one(pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                status.error = Some(format!(
+                    "unable to look up table '{}' with error: {}",
+                    table, e
+                ));
+                return status;
+            },
+        };
+
+        if row.0 == 0 {
+            status
+                .missing_tables
+                .push(table.to_string());
+        }
+    }
+
+    status
+}
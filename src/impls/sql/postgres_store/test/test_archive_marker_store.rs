@@ -0,0 +1,135 @@
+use sqlx::postgres::PgPoolOptions;
+
+use cqrs_es2::Error;
+
+use crate::{
+    postgres_store::ArchiveMarkerStore,
+    ArchiveMarker,
+    IArchiveMarkerStore,
+};
+
+use super::common::*;
+
+async fn check_save_load_markers() -> Result<(), Error> {
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(CONNECTION_STRING)
+        .await
+        .unwrap();
+
+    let store = ArchiveMarkerStore::new(pool);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    let marker =
+        ArchiveMarker::new(&id, 2, "Customer/test_A.ndjson.zst");
+
+    store
+        .save_marker("Customer", &marker)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        Some(marker)
+    );
+
+    // markers are replaced by later archivals
+    let marker =
+        ArchiveMarker::new(&id, 5, "Customer/test_B.ndjson.zst");
+
+    store
+        .save_marker("Customer", &marker)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        Some(marker)
+    );
+
+    // markers are per aggregate type
+    assert_eq!(
+        store
+            .load_marker("Other", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    // markers are listed per aggregate type in id order
+    let listed = format!("Listed {}", id);
+
+    for x in &["id B", "id A"] {
+        store
+            .save_marker(
+                &listed,
+                &ArchiveMarker::new(x, 1, "Listed/test.ndjson.zst"),
+            )
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        store
+            .list_archived_ids(&listed, None, 10)
+            .await
+            .unwrap(),
+        vec!["id A".to_string(), "id B".to_string()]
+    );
+    assert_eq!(
+        store
+            .list_archived_ids(&listed, Some("id A"), 10)
+            .await
+            .unwrap(),
+        vec!["id B".to_string()]
+    );
+    assert_eq!(
+        store
+            .list_archived_ids(&listed, None, 1)
+            .await
+            .unwrap(),
+        vec!["id A".to_string()]
+    );
+
+    for x in &["id A", "id B"] {
+        store
+            .delete_marker(&listed, x)
+            .await
+            .unwrap();
+    }
+
+    store
+        .delete_marker("Customer", &id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_marker("Customer", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_save_load_markers() {
+    tokio_test::block_on(check_save_load_markers()).unwrap();
+}
@@ -0,0 +1,310 @@
+#[cfg(any(
+    feature = "with-mysql",
+    feature = "with-sqlite"
+))]
+use serde_json::Value;
+
+use crate::repository::{
+    Comparison,
+    QueryFilter,
+    SortOrder,
+};
+
+use super::event_filter_query::{
+    BindValue,
+    Dialect,
+    FilterQuery,
+};
+
+/// Builds the JSON path of a dot separated field for MySQL and
+/// SQLite
+#[cfg(any(
+    feature = "with-mysql",
+    feature = "with-sqlite"
+))]
+fn json_field_path(field: &str) -> String {
+    let keys: Vec<String> = field
+        .split('.')
+        .map(|x| format!(".\"{}\"", x.replace('"', "\\\"")))
+        .collect();
+
+    format!("${}", keys.join(""))
+}
+
+/// Builds the Postgres expression extracting a dot separated field
+/// of the payload as `jsonb`
+#[cfg(feature = "with-postgres")]
+fn postgres_field(
+    query: &mut FilterQuery,
+    field: &str,
+) -> String {
+    let keys: Vec<String> = field
+        .split('.')
+        .map(|x| {
+            query.placeholder(
+                Dialect::Postgres,
+                BindValue::Text(x.to_string()),
+            )
+        })
+        .collect();
+
+    format!(
+        "jsonb_extract_path(payload, {})",
+        keys.join(", ")
+    )
+}
+
+fn operator(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::Eq => "=",
+        Comparison::Gt => ">",
+        Comparison::Gte => ">=",
+        Comparison::Lt => "<",
+        Comparison::Lte => "<=",
+    }
+}
+
+fn direction(order: SortOrder) -> &'static str {
+    match order {
+        SortOrder::Ascending => "ASC",
+        SortOrder::Descending => "DESC",
+    }
+}
+
+/// Converts a JSON number to the matching bind value
+#[cfg(any(
+    feature = "with-mysql",
+    feature = "with-sqlite"
+))]
+fn number_bind(value: &Value) -> BindValue {
+    match value.as_i64() {
+        Some(x) => BindValue::Int(x),
+        None => BindValue::Float(value.as_f64().unwrap_or_default()),
+    }
+}
+
+/// Builds the query selecting `aggregate_id, version, payload` of
+/// the queries matching `filter`
+pub fn build_queries_filter_query(
+    dialect: Dialect,
+    aggregate_type: &str,
+    query_type: &str,
+    filter: &QueryFilter,
+) -> FilterQuery {
+    let mut query = FilterQuery::new();
+
+    let mut conditions = Vec::new();
+
+    let p = query.placeholder(
+        dialect,
+        BindValue::Text(aggregate_type.to_string()),
+    );
+    conditions.push(format!("aggregate_type = {}", p));
+
+    let p = query.placeholder(
+        dialect,
+        BindValue::Text(query_type.to_string()),
+    );
+    conditions.push(format!("query_type = {}", p));
+
+    for x in &filter.conditions {
+        let op = operator(x.comparison);
+
+        let condition = match dialect {
+            #[cfg(feature = "with-postgres")]
+            Dialect::Postgres => {
+                // jsonb compares numbers numerically and strings
+                // lexicographically
+                let f = postgres_field(&mut query, &x.field);
+                let v = query.placeholder(
+                    dialect,
+                    BindValue::Json(x.value.clone()),
+                );
+                format!("{} {} {}", f, op, v)
+            },
+            #[cfg(feature = "with-mysql")]
+            Dialect::MySql => {
+                // MariaDB has no JSON type, compare SQL scalars
+                let f = query.placeholder(
+                    dialect,
+                    BindValue::Text(json_field_path(&x.field)),
+                );
+
+                match &x.value {
+                    Value::Number(_) => {
+                        let v = query.placeholder(
+                            dialect,
+                            number_bind(&x.value),
+                        );
+                        format!(
+                            "CAST(JSON_EXTRACT(payload, {}) AS \
+                             DECIMAL(65, 10)) {} {}",
+                            f, op, v
+                        )
+                    },
+                    Value::String(s) => {
+                        let v = query.placeholder(
+                            dialect,
+                            BindValue::Text(s.clone()),
+                        );
+                        format!(
+                            "JSON_UNQUOTE(JSON_EXTRACT(payload, \
+                             {})) {} {}",
+                            f, op, v
+                        )
+                    },
+                    other => {
+                        let v = query.placeholder(
+                            dialect,
+                            BindValue::Text(other.to_string()),
+                        );
+                        format!(
+                            "JSON_UNQUOTE(JSON_EXTRACT(payload, \
+                             {})) {} {}",
+                            f, op, v
+                        )
+                    },
+                }
+            },
+            #[cfg(feature = "with-sqlite")]
+            Dialect::Sqlite => {
+                // json_extract returns SQL scalars, booleans as 0/1
+                let f = query.placeholder(
+                    dialect,
+                    BindValue::Text(json_field_path(&x.field)),
+                );
+
+                match &x.value {
+                    Value::Null => {
+                        format!("json_type(payload, {}) = 'null'", f)
+                    },
+                    Value::Bool(b) => {
+                        let v = query.placeholder(
+                            dialect,
+                            BindValue::Int(i64::from(*b)),
+                        );
+                        format!(
+                            "json_extract(payload, {}) {} {}",
+                            f, op, v
+                        )
+                    },
+                    Value::Number(_) => {
+                        let v = query.placeholder(
+                            dialect,
+                            number_bind(&x.value),
+                        );
+                        format!(
+                            "json_extract(payload, {}) {} {}",
+                            f, op, v
+                        )
+                    },
+                    Value::String(s) => {
+                        let v = query.placeholder(
+                            dialect,
+                            BindValue::Text(s.clone()),
+                        );
+                        format!(
+                            "json_extract(payload, {}) {} {}",
+                            f, op, v
+                        )
+                    },
+                    other => {
+                        let v = query.placeholder(
+                            dialect,
+                            BindValue::Text(other.to_string()),
+                        );
+                        format!(
+                            "json_extract(payload, {}) {} {}",
+                            f, op, v
+                        )
+                    },
+                }
+            },
+        };
+
+        conditions.push(condition);
+    }
+
+    let mut order_by = Vec::new();
+
+    for (field, order) in &filter.sort {
+        let d = direction(*order);
+
+        match dialect {
+            #[cfg(feature = "with-postgres")]
+            Dialect::Postgres => {
+                let f = postgres_field(&mut query, field);
+                order_by.push(format!("{} {}", f, d));
+            },
+            #[cfg(feature = "with-mysql")]
+            Dialect::MySql => {
+                // numbers first by value, then strings by text
+                let n = query.placeholder(
+                    dialect,
+                    BindValue::Text(json_field_path(field)),
+                );
+                let s = query.placeholder(
+                    dialect,
+                    BindValue::Text(json_field_path(field)),
+                );
+                order_by.push(format!(
+                    "CAST(JSON_EXTRACT(payload, {}) AS DECIMAL(65, \
+                     10)) {}",
+                    n, d
+                ));
+                order_by.push(format!(
+                    "JSON_UNQUOTE(JSON_EXTRACT(payload, {})) {}",
+                    s, d
+                ));
+            },
+            #[cfg(feature = "with-sqlite")]
+            Dialect::Sqlite => {
+                let f = query.placeholder(
+                    dialect,
+                    BindValue::Text(json_field_path(field)),
+                );
+                order_by.push(format!(
+                    "json_extract(payload, {}) {}",
+                    f, d
+                ));
+            },
+        }
+    }
+
+    order_by.push("aggregate_id".to_string());
+
+    let mut sql = format!(
+        "
+SELECT
+    aggregate_id,
+    version,
+    payload
+FROM
+    queries
+WHERE
+    {}
+ORDER BY
+    {}",
+        conditions.join("\n    AND\n    "),
+        order_by.join(",\n    ")
+    );
+
+    if filter.limit.is_some() || filter.offset > 0 {
+        let limit = query.placeholder(
+            dialect,
+            BindValue::Int(filter.limit.unwrap_or(i64::MAX)),
+        );
+        let offset =
+            query.placeholder(dialect, BindValue::Int(filter.offset));
+
+        sql.push_str(
+            format!("\nLIMIT {}\nOFFSET {}", limit, offset).as_str(),
+        );
+    }
+
+    sql.push_str(";\n");
+
+    query.sql = sql;
+
+    query
+}
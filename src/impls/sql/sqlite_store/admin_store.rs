@@ -0,0 +1,297 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use serde_json::Value;
+
+use sqlx::sqlite::SqlitePool;
+
+use cqrs_es2::Error;
+
+use crate::repository::{
+    event_record,
+    query_record,
+    snapshot_record,
+    IAdminStore,
+};
+
+use super::super::mysql_constants::*;
+
+/// Async SQLite admin store
+pub struct AdminStore {
+    pool: SqlitePool,
+}
+
+impl AdminStore {
+    /// Constructor
+    pub fn new(pool: SqlitePool) -> Self {
+        let x = Self { pool };
+
+        trace!("Created new async SQLite admin store");
+
+        x
+    }
+}
+
+#[async_trait]
+impl IAdminStore for AdminStore {
+    /// List the aggregate types having events
+    async fn list_aggregate_types(
+        &mut self
+    ) -> Result<Vec<String>, Error> {
+        trace!("listing aggregate types");
+
+        let rows: Vec<(String,)> =
+            match sqlx::query_as(SELECT_AGGREGATE_TYPES)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to list aggregate types from \
+                             events table with error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows.into_iter().map(|x| x.0).collect())
+    }
+
+    /// List up to `limit` ids of the aggregates of `aggregate_type`
+    /// having events in ascending order, starting after the id
+    /// `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        aggregate_type: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        trace!(
+            "listing '{}' '{}' aggregate ids after '{:?}'",
+            limit,
+            aggregate_type,
+            after
+        );
+
+        let rows: Vec<(String,)> =
+            match sqlx::query_as(SELECT_AGGREGATE_IDS)
+                .bind(aggregate_type)
+                .bind(after.unwrap_or(""))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to list aggregate ids from \
+                             events table with error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows.into_iter().map(|x| x.0).collect())
+    }
+
+    /// Load the event records of `aggregate_id`
+    async fn load_events(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<Value>, Error> {
+        trace!(
+            "loading events for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let rows: Vec<(i64, Value, Value)> =
+            match sqlx::query_as(SELECT_EVENTS)
+                .bind(aggregate_type)
+                .bind(aggregate_id)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load events table for \
+                             aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows
+            .into_iter()
+            .map(|x| {
+                event_record(
+                    aggregate_type,
+                    aggregate_id,
+                    x.0,
+                    x.1,
+                    x.2,
+                )
+            })
+            .collect())
+    }
+
+    /// Load the snapshot record of `aggregate_id`, `None` if it has
+    /// no snapshot
+    async fn load_snapshot(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Value>, Error> {
+        trace!(
+            "loading snapshot for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let rows: Vec<(i64, Value)> =
+            match sqlx::query_as(SELECT_SNAPSHOT)
+                .bind(aggregate_type)
+                .bind(aggregate_id)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load snapshots table for \
+                             aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows.into_iter().next().map(|x| {
+            snapshot_record(aggregate_type, aggregate_id, x.0, x.1)
+        }))
+    }
+
+    /// Load the query records of all the query types of
+    /// `aggregate_id`
+    async fn load_queries(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Vec<Value>, Error> {
+        trace!(
+            "loading queries for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let rows: Vec<(String, i64, Value)> =
+            match sqlx::query_as(SELECT_AGGREGATE_QUERIES)
+                .bind(aggregate_type)
+                .bind(aggregate_id)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load queries table for \
+                             aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows
+            .into_iter()
+            .map(|x| {
+                query_record(
+                    aggregate_type,
+                    aggregate_id,
+                    &x.0,
+                    x.1,
+                    x.2,
+                )
+            })
+            .collect())
+    }
+
+    /// Delete the snapshot of `aggregate_id`, returns whether it had
+    /// one
+    async fn delete_snapshot(
+        &mut self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<bool, Error> {
+        debug!(
+            "deleting snapshot of aggregate id '{}'",
+            aggregate_id
+        );
+
+        match sqlx::query(DELETE_SNAPSHOT)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(x) => Ok(x.rows_affected() > 0),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to delete snapshot for aggregate id \
+                         '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+
+    /// Delete the queries of `query_type` of all the aggregates of
+    /// `aggregate_type`, returns the number of deleted queries
+    async fn delete_queries(
+        &mut self,
+        aggregate_type: &str,
+        query_type: &str,
+    ) -> Result<u64, Error> {
+        debug!(
+            "deleting '{}' queries of '{}' aggregates",
+            query_type, aggregate_type
+        );
+
+        match sqlx::query(DELETE_QUERIES)
+            .bind(aggregate_type)
+            .bind(query_type)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(x) => Ok(x.rows_affected()),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "unable to delete '{}' queries with error: \
+                         {}",
+                        query_type, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+}
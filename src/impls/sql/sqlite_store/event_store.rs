@@ -3,9 +3,15 @@ use log::{
     debug,
     trace,
 };
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::Arc,
+};
 
-use sqlx::sqlite::SqlitePool;
+use sqlx::{
+    sqlite::SqlitePool,
+    Sqlite,
+};
 
 use cqrs_es2::{
     AggregateContext,
@@ -16,9 +22,27 @@ use cqrs_es2::{
     IEvent,
 };
 
-use crate::repository::IEventStore;
+use crate::repository::{
+    event_type_of,
+    EventFilter,
+    HealthStatus,
+    IEventStore,
+    IFilteredEventStore,
+    IHealthCheck,
+    IPayloadTransformer,
+    PayloadCodec,
+};
 
-use super::super::mysql_constants::*;
+use super::health::check_health;
+
+use super::super::{
+    event_filter_query::{
+        build_events_filter_query,
+        BindValue,
+        Dialect,
+    },
+    mysql_constants::*,
+};
 
 static CREATE_EVENTS_TABLE: &str = "
 CREATE TABLE IF NOT EXISTS
@@ -27,6 +51,7 @@ CREATE TABLE IF NOT EXISTS
         aggregate_type TEXT                         NOT NULL,
         aggregate_id   TEXT                         NOT NULL,
         sequence       bigint CHECK (sequence >= 0) NOT NULL,
+        event_type     TEXT                         NOT NULL,
         payload        TEXT                         NOT NULL,
         metadata       TEXT                         NOT NULL,
         timestamp      timestamp DEFAULT (CURRENT_TIMESTAMP),
@@ -34,6 +59,48 @@ CREATE TABLE IF NOT EXISTS
     );
 ";
 
+static COUNT_EVENT_TYPE_COLUMN: &str = "
+SELECT
+    COUNT(*)
+FROM
+    pragma_table_info('events')
+WHERE
+    name = 'event_type'
+";
+
+// tables created before the `event_type` column was introduced get
+// the column added and backfilled from the payloads
+static ADD_EVENT_TYPE_COLUMN: &str = "
+ALTER TABLE
+    events
+ADD COLUMN
+    event_type TEXT NOT NULL DEFAULT '';
+
+UPDATE
+    events
+SET
+    event_type = CASE
+        WHEN json_type(payload) = 'object'
+            AND (SELECT COUNT(*) FROM json_each(events.payload)) = 1
+        THEN (SELECT key FROM json_each(events.payload))
+        WHEN json_type(payload) = 'text'
+        THEN json_extract(payload, '$')
+        ELSE ''
+    END;
+";
+
+static CREATE_EVENTS_INDEXES: &str = "
+CREATE INDEX IF NOT EXISTS
+    events_type_timestamp
+ON
+    events (aggregate_type, event_type, timestamp);
+
+CREATE INDEX IF NOT EXISTS
+    events_timestamp
+ON
+    events (aggregate_type, timestamp);
+";
+
 static CREATE_SNAPSHOT_TABLE: &str = "
 CREATE TABLE IF NOT EXISTS
     snapshots
@@ -50,6 +117,8 @@ CREATE TABLE IF NOT EXISTS
 /// Async SQLite event store
 pub struct EventStore<C: ICommand, E: IEvent, A: IAggregate<C, E>> {
     pool: SqlitePool,
+    codec: PayloadCodec,
+    migrated: bool,
     _phantom: PhantomData<(C, E, A)>,
 }
 
@@ -60,6 +129,8 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
     pub fn new(pool: SqlitePool) -> Self {
         let x = Self {
             pool,
+            codec: PayloadCodec::default(),
+            migrated: false,
             _phantom: PhantomData,
         };
 
@@ -68,6 +139,16 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
         x
     }
 
+    /// Transform the payloads with `transformer` on their way to and
+    /// from the database, e.g. to encrypt them
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
     async fn create_events_table(&mut self) -> Result<(), Error> {
         let res = match sqlx::query(CREATE_EVENTS_TABLE)
             .execute(&self.pool)
@@ -91,6 +172,69 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
             res.rows_affected()
         );
 
+        // the legacy tables are migrated once per store
+        if self.migrated {
+            return Ok(());
+        }
+
+        let columns: i64 =
+            match sqlx::query_as(COUNT_EVENT_TYPE_COLUMN)
+                .fetch_one(&self.pool)
+                .await
+            {
+                Ok((x,)) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to inspect events table with \
+                             error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        if columns == 0 {
+            match sqlx::query(ADD_EVENT_TYPE_COLUMN)
+                .execute(&self.pool)
+                .await
+            {
+                Ok(_) => {},
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to add event type column with \
+                             error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            debug!("Added event type column to events table");
+        }
+
+        match sqlx::query(CREATE_EVENTS_INDEXES)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to create events indexes with \
+                         error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        self.migrated = true;
+
         Ok(())
     }
 
@@ -183,10 +327,31 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
                     },
                 };
 
+            let event_type = event_type_of(&payload);
+
+            let payload = match self
+                .codec
+                .encode(aggregate_type, &aggregate_id, payload)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to encode the event payload for \
+                             aggregate id '{}' with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
             match sqlx::query(INSERT_EVENT)
                 .bind(&aggregate_type)
                 .bind(&aggregate_id)
                 .bind(context.sequence)
+                .bind(&event_type)
                 .bind(&payload)
                 .bind(&metadata)
                 .execute(&self.pool)
@@ -224,13 +389,26 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
     async fn load_events(
         &mut self,
         aggregate_id: &str,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        self.load_events_after(aggregate_id, 0)
+            .await
+    }
+
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `sequence`
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
     ) -> Result<Vec<EventContext<C, E>>, Error> {
         self.create_events_table().await?;
 
         let aggregate_type = A::aggregate_type();
 
         trace!(
-            "loading events for aggregate id '{}'",
+            "loading events after sequence '{}' for aggregate id \
+             '{}'",
+            sequence,
             aggregate_id
         );
 
@@ -238,9 +416,10 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             i64,
             serde_json::Value,
             serde_json::Value,
-        )> = match sqlx::query_as(SELECT_EVENTS)
+        )> = match sqlx::query_as(SELECT_EVENTS_AFTER)
             .bind(&aggregate_type)
             .bind(&aggregate_id)
+            .bind(sequence)
             .fetch_all(&self.pool)
             .await
         {
@@ -257,24 +436,35 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             },
         };
 
-        let mut result = Vec::new();
+        let (rows, payloads): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .map(|x| ((x.0, x.2), x.1))
+            .unzip();
 
-        for row in rows {
-            let payload = match serde_json::from_value(row.1) {
-                Ok(x) => x,
-                Err(e) => {
-                    return Err(Error::new(
-                        format!(
-                            "bad payload found in events table for \
-                             aggregate id '{}' with error: {}",
-                            &aggregate_id, e
-                        )
-                        .as_str(),
-                    ));
-                },
-            };
+        let payloads: Vec<E> = match self
+            .codec
+            .decode_values(aggregate_type, aggregate_id, payloads)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in events table for \
+                         aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
 
-            let metadata = match serde_json::from_value(row.2) {
+        let mut result = Vec::new();
+
+        for ((sequence, metadata), payload) in
+            rows.into_iter().zip(payloads)
+        {
+            let metadata = match serde_json::from_value(metadata) {
                 Ok(x) => x,
                 Err(e) => {
                     return Err(Error::new(
@@ -290,7 +480,7 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
 
             result.push(EventContext::new(
                 aggregate_id.to_string(),
-                row.0,
+                sequence,
                 payload,
                 metadata,
             ));
@@ -299,6 +489,43 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
         Ok(result)
     }
 
+    /// Load the sequence of the last event of `aggregate_id`, 0 if it
+    /// has none
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        self.create_events_table().await?;
+
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "loading latest sequence for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let row: (i64,) = match sqlx::query_as(SELECT_LATEST_SEQUENCE)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load latest sequence from events \
+                         table for aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(row.0)
+    }
+
     /// save a new aggregate snapshot
     async fn save_aggregate_snapshot(
         &mut self,
@@ -320,7 +547,15 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             _ => UPDATE_SNAPSHOT,
         };
 
-        let payload = match serde_json::to_value(context.payload) {
+        let payload = match self
+            .codec
+            .encode_value(
+                aggregate_type,
+                &aggregate_id,
+                &context.payload,
+            )
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -418,7 +653,11 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
 
         let row = rows[0].clone();
 
-        let payload = match serde_json::from_value(row.1) {
+        let payload = match self
+            .codec
+            .decode_value(aggregate_type, aggregate_id, row.1)
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -438,4 +677,262 @@ impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IEventStore<C, E, A>
             payload,
         ))
     }
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!(
+            "listing '{}' aggregate ids after '{:?}'",
+            limit,
+            after
+        );
+
+        self.create_events_table().await?;
+
+        let rows: Vec<(String,)> =
+            match sqlx::query_as(SELECT_AGGREGATE_IDS)
+                .bind(aggregate_type)
+                .bind(after.unwrap_or(""))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to list aggregate ids from \
+                             events table with error: {}",
+                            e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows.into_iter().map(|x| x.0).collect())
+    }
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!("counting aggregates");
+
+        self.create_events_table().await?;
+
+        let row: (i64,) = match sqlx::query_as(COUNT_AGGREGATES)
+            .bind(aggregate_type)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to count aggregates in events table \
+                         with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(row.0)
+    }
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.create_events_table().await?;
+        self.create_snapshot_table().await?;
+
+        let aggregate_type = A::aggregate_type();
+
+        debug!(
+            "deleting aggregate id '{}'",
+            aggregate_id
+        );
+
+        for sql in &[DELETE_EVENTS, DELETE_SNAPSHOT] {
+            match sqlx::query(sql)
+                .bind(aggregate_type)
+                .bind(aggregate_id)
+                .execute(&self.pool)
+                .await
+            {
+                Ok(_) => {},
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to delete aggregate id '{}' \
+                             with error: {}",
+                            &aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        self.create_events_table().await?;
+
+        debug!(
+            "deleting events up to sequence '{}' for aggregate id \
+             '{}'",
+            sequence, aggregate_id
+        );
+
+        match sqlx::query(DELETE_EVENTS_UNTIL)
+            .bind(A::aggregate_type())
+            .bind(aggregate_id)
+            .bind(sequence)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete events for aggregate id \
+                         '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
+    IFilteredEventStore<C, E, A> for EventStore<C, E, A>
+{
+    /// Load the events matching the `filter` ordered by commit
+    /// time, aggregate id and sequence
+    async fn filter_events(
+        &mut self,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        let aggregate_type = A::aggregate_type();
+
+        trace!("filtering events with '{:?}'", filter);
+
+        self.create_events_table().await?;
+
+        let query = build_events_filter_query(
+            Dialect::Sqlite,
+            aggregate_type,
+            filter,
+        );
+
+        let mut sql_query = sqlx::query_as::<
+            Sqlite,
+            (
+                String,
+                i64,
+                serde_json::Value,
+                serde_json::Value,
+            ),
+        >(query.sql.as_str());
+
+        for value in query.binds {
+            sql_query = match value {
+                BindValue::Text(x) => sql_query.bind(x),
+                BindValue::Json(x) => sql_query.bind(x),
+                BindValue::Timestamp(x) => sql_query.bind(x),
+                BindValue::Int(x) => sql_query.bind(x),
+                BindValue::Float(x) => sql_query.bind(x),
+            };
+        }
+
+        let rows = match sql_query.fetch_all(&self.pool).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to filter events table with error: \
+                         {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for row in rows {
+            let payload = match self
+                .codec
+                .decode_value(aggregate_type, &row.0, row.2)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad payload found in events table for \
+                             aggregate id '{}' with error: {}",
+                            &row.0, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            let metadata = match serde_json::from_value(row.3) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad metadata found in events table for \
+                             aggregate id '{}' with error: {}",
+                            &row.0, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            result.push(EventContext::new(
+                row.0, row.1, payload, metadata,
+            ));
+        }
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> IHealthCheck
+    for EventStore<C, E, A>
+{
+    /// Probe the database, the `events` and `snapshots` tables are
+    /// created on first use and not looked up
+    async fn health_check(&mut self) -> HealthStatus {
+        check_health(&self.pool).await
+    }
 }
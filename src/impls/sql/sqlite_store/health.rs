@@ -0,0 +1,32 @@
+use log::debug;
+use std::time::Instant;
+
+use sqlx::sqlite::SqlitePool;
+
+use crate::repository::HealthStatus;
+
+use super::super::mysql_constants::SELECT_ONE;
+
+/// Probe the database with `SELECT 1`, the tables are not looked up
+/// since the stores only create them on their first use
+pub(super) async fn check_health(pool: &SqlitePool) -> HealthStatus {
+    let start = Instant::now();
+
+    if let Err(e) = sqlx::query(SELECT_ONE)
+        .execute(pool)
+        .await
+    {
+        debug!(
+            "SQLite health check failed with error: {}",
+            e
+        );
+
+        return HealthStatus::unreachable(
+            "sqlite",
+            start.elapsed(),
+            e.to_string(),
+        );
+    }
+
+    HealthStatus::reachable("sqlite", start.elapsed())
+}
@@ -0,0 +1,210 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+
+use sqlx::sqlite::SqlitePool;
+
+use cqrs_es2::Error;
+
+use crate::repository::IKeyStore;
+
+use super::super::mysql_constants::SELECT_KEY;
+
+static CREATE_KEY_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS
+encryption_keys
+(
+    aggregate_type TEXT NOT NULL,
+    aggregate_id   TEXT NOT NULL,
+    encryption_key BLOB NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id)
+);
+";
+
+static INSERT_KEY: &str = "
+INSERT OR IGNORE INTO
+    encryption_keys
+    (
+        aggregate_type,
+        aggregate_id,
+        encryption_key
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?
+    );
+";
+
+static ERASE_KEY: &str = "
+INSERT OR REPLACE INTO
+    encryption_keys
+    (
+        aggregate_type,
+        aggregate_id,
+        encryption_key
+    )
+VALUES
+    (
+        ?,
+        ?,
+        X''
+    );
+";
+
+/// Async SQLite key store
+pub struct KeyStore {
+    pool: SqlitePool,
+}
+
+impl KeyStore {
+    /// Constructor
+    pub fn new(pool: SqlitePool) -> Self {
+        let x = Self { pool };
+
+        trace!("Created new async SQLite key store");
+
+        x
+    }
+
+    async fn create_key_table(&self) -> Result<(), Error> {
+        let res = match sqlx::query(CREATE_KEY_TABLE)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to create encryption_keys table \
+                         with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        debug!(
+            "Created encryption_keys table with '{}' affected rows",
+            res.rows_affected()
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IKeyStore for KeyStore {
+    /// Save the key of the aggregate unless it already has one
+    async fn save_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        self.create_key_table().await?;
+
+        debug!(
+            "storing a new key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        match sqlx::query(INSERT_KEY)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert key for aggregate id '{}' \
+                         with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Load the key of the aggregate, `None` if it has none and an
+    /// empty key if it was erased
+    async fn load_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.create_key_table().await?;
+
+        trace!(
+            "loading key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        let rows: Vec<(Vec<u8>,)> = match sqlx::query_as(SELECT_KEY)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load encryption_keys table for \
+                         aggregate id '{}' with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(rows.into_iter().next().map(|x| x.0))
+    }
+
+    /// Destroy the key of the aggregate, leaving an empty key as its
+    /// tombstone
+    async fn delete_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.create_key_table().await?;
+
+        debug!(
+            "erasing key for aggregate id '{}'",
+            aggregate_id
+        );
+
+        match sqlx::query(ERASE_KEY)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to erase key for aggregate id '{}' \
+                         with error: {}",
+                        &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
@@ -0,0 +1,575 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+use std::marker::PhantomData;
+
+use sqlx::{
+    sqlite::SqlitePool,
+    Sqlite,
+    Transaction,
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use crate::repository::{
+    projection_conflict,
+    sequence_conflict,
+    AppliedSequence,
+    IEventDispatcher,
+    IProjection,
+    IProjectionStore,
+    ProjectionContext,
+};
+
+use super::super::mysql_constants::{
+    DELETE_PROJECTION,
+    SELECT_ALL_PROJECTIONS,
+    SELECT_APPLIED_SEQUENCE,
+    SELECT_PROJECTION,
+    UPDATE_APPLIED_SEQUENCE,
+    UPDATE_PROJECTION,
+};
+
+static CREATE_PROJECTION_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS
+projections
+(
+    projection_type TEXT                        NOT NULL,
+    projection_key  TEXT                        NOT NULL,
+    version         bigint CHECK (version >= 0) NOT NULL,
+    payload         TEXT CHECK (json_valid(payload)) NOT NULL,
+    PRIMARY KEY (projection_type, projection_key)
+);
+";
+
+static INSERT_PROJECTION: &str = "
+INSERT INTO
+    projections
+    (
+        version,
+        payload,
+        projection_type,
+        projection_key
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?,
+        ?
+    )
+ON CONFLICT DO NOTHING;
+";
+
+static CREATE_APPLIED_SEQUENCE_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS
+projection_sequences
+(
+    projection_type TEXT                         NOT NULL,
+    aggregate_id    TEXT                         NOT NULL,
+    sequence        bigint CHECK (sequence >= 0) NOT NULL,
+    PRIMARY KEY (projection_type, aggregate_id)
+);
+";
+
+static INSERT_APPLIED_SEQUENCE: &str = "
+INSERT INTO
+    projection_sequences
+    (
+        sequence,
+        projection_type,
+        aggregate_id
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?
+    )
+ON CONFLICT DO NOTHING;
+";
+
+/// Async SQLite projection store
+pub struct ProjectionStore<
+    C: ICommand,
+    E: IEvent,
+    P: IProjection<C, E>,
+> {
+    pool: SqlitePool,
+    _phantom: PhantomData<(C, E, P)>,
+}
+
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    ProjectionStore<C, E, P>
+{
+    /// Constructor
+    pub fn new(pool: SqlitePool) -> Self {
+        let x = Self {
+            pool,
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new async SQLite projection store");
+
+        x
+    }
+
+    async fn create_projection_table(&mut self) -> Result<(), Error> {
+        let res = match sqlx::query(CREATE_PROJECTION_TABLE)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to create projections table with \
+                         error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        debug!(
+            "Created projections table with '{}' affected rows",
+            res.rows_affected()
+        );
+
+        let res = match sqlx::query(CREATE_APPLIED_SEQUENCE_TABLE)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to create projection_sequences \
+                         table with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        debug!(
+            "Created projection_sequences table with '{}' affected \
+             rows",
+            res.rows_affected()
+        );
+
+        Ok(())
+    }
+
+    async fn write_projection(
+        tx: &mut Transaction<'_, Sqlite>,
+        context: ProjectionContext<P>,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        let key = context.key;
+
+        debug!(
+            "storing projection '{}' with key '{}'",
+            projection_type, &key
+        );
+
+        let payload = match serde_json::to_value(&context.payload) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to serialize the payload of \
+                         projection '{}' with key '{}', error: {}",
+                        &projection_type, &key, e,
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let sql = match expected_version {
+            0 => INSERT_PROJECTION,
+            _ => UPDATE_PROJECTION,
+        };
+
+        let mut query = sqlx::query(sql)
+            .bind(context.version)
+            .bind(&payload)
+            .bind(projection_type)
+            .bind(&key);
+
+        if expected_version != 0 {
+            query = query.bind(expected_version);
+        }
+
+        match query.execute(&mut *tx).await {
+            Ok(x) => {
+                if x.rows_affected() == 0 {
+                    return Err(projection_conflict(
+                        projection_type,
+                        &key,
+                        expected_version,
+                    ));
+                }
+            },
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert/update projection '{}' \
+                         with key '{}', error: {}",
+                        &projection_type, &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    async fn write_applied_sequence(
+        tx: &mut Transaction<'_, Sqlite>,
+        applied: AppliedSequence,
+    ) -> Result<(), Error> {
+        let projection_type = P::projection_type();
+
+        debug!(
+            "storing applied sequence '{}' of projection '{}' with \
+             aggregate id '{}'",
+            applied.sequence, projection_type, &applied.aggregate_id
+        );
+
+        let sql = match applied.expected_sequence {
+            0 => INSERT_APPLIED_SEQUENCE,
+            _ => UPDATE_APPLIED_SEQUENCE,
+        };
+
+        let mut query = sqlx::query(sql)
+            .bind(applied.sequence)
+            .bind(projection_type)
+            .bind(&applied.aggregate_id);
+
+        if applied.expected_sequence != 0 {
+            query = query.bind(applied.expected_sequence);
+        }
+
+        match query.execute(&mut *tx).await {
+            Ok(x) => {
+                if x.rows_affected() == 0 {
+                    return Err(sequence_conflict(
+                        projection_type,
+                        &applied.aggregate_id,
+                        applied.expected_sequence,
+                    ));
+                }
+            },
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to insert/update applied sequence \
+                         of projection '{}' with aggregate id '{}', \
+                         error: {}",
+                        &projection_type, &applied.aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    fn projection_from_row(
+        row: (String, i64, serde_json::Value)
+    ) -> Result<ProjectionContext<P>, Error> {
+        let payload = match serde_json::from_value(row.2) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in projections table for \
+                         projection '{}' with key '{}', error: {}",
+                        P::projection_type(),
+                        &row.0,
+                        e,
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(ProjectionContext::new(
+            row.0, row.1, payload,
+        ))
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IProjectionStore<C, E, P> for ProjectionStore<C, E, P>
+{
+    /// saves the updated row
+    async fn save_projection(
+        &mut self,
+        context: ProjectionContext<P>,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        self.save_projections(
+            vec![(context, expected_version)],
+            Vec::new(),
+        )
+        .await
+    }
+
+    /// saves the updated rows and the applied sequences in a
+    /// transaction
+    async fn save_projections(
+        &mut self,
+        contexts: Vec<(ProjectionContext<P>, i64)>,
+        applied: Vec<AppliedSequence>,
+    ) -> Result<(), Error> {
+        self.create_projection_table().await?;
+
+        let mut tx = match self.pool.begin().await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to start projections transaction \
+                         with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        for (context, expected_version) in contexts {
+            Self::write_projection(
+                &mut tx,
+                context,
+                expected_version,
+            )
+            .await?;
+        }
+
+        for x in applied {
+            Self::write_applied_sequence(&mut tx, x).await?;
+        }
+
+        match tx.commit().await {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to commit projections transaction \
+                         with error: {}",
+                        e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// loads the sequence of the last event of `aggregate_id` applied
+    /// to the rows
+    async fn load_applied_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        self.create_projection_table().await?;
+
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading applied sequence of projection '{}' with \
+             aggregate id '{}'",
+            projection_type,
+            aggregate_id
+        );
+
+        let rows: Vec<(i64,)> =
+            match sqlx::query_as(SELECT_APPLIED_SEQUENCE)
+                .bind(projection_type)
+                .bind(aggregate_id)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load projection_sequences \
+                             table for projection '{}' with \
+                             aggregate id '{}', error: {}",
+                            &projection_type, &aggregate_id, e,
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        Ok(rows
+            .into_iter()
+            .next()
+            .map_or(0, |x| x.0))
+    }
+
+    /// loads the row of `key`
+    async fn load_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<ProjectionContext<P>, Error> {
+        self.create_projection_table().await?;
+
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading projection '{}' with key '{}'",
+            projection_type,
+            key
+        );
+
+        let rows: Vec<(i64, serde_json::Value)> =
+            match sqlx::query_as(SELECT_PROJECTION)
+                .bind(projection_type)
+                .bind(key)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load projections table for \
+                             projection '{}' with key '{}', error: \
+                             {}",
+                            &projection_type, &key, e,
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        match rows.into_iter().next() {
+            None => {
+                Ok(ProjectionContext::new(
+                    key.to_string(),
+                    0,
+                    Default::default(),
+                ))
+            },
+            Some(x) => {
+                Self::projection_from_row((key.to_string(), x.0, x.1))
+            },
+        }
+    }
+
+    /// loads up to `limit` rows in ascending order of key, starting
+    /// after the key `after` when given
+    async fn load_all_projections(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ProjectionContext<P>>, Error> {
+        self.create_projection_table().await?;
+
+        let projection_type = P::projection_type();
+
+        trace!(
+            "loading '{}' projections '{}' after '{:?}'",
+            limit,
+            projection_type,
+            after
+        );
+
+        let rows: Vec<(String, i64, serde_json::Value)> =
+            match sqlx::query_as(SELECT_ALL_PROJECTIONS)
+                .bind(projection_type)
+                .bind(after.unwrap_or(""))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load projections table for \
+                             projection '{}' with error: {}",
+                            &projection_type, e,
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        rows.into_iter()
+            .map(Self::projection_from_row)
+            .collect()
+    }
+
+    /// deletes the row of `key`
+    async fn delete_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<(), Error> {
+        self.create_projection_table().await?;
+
+        let projection_type = P::projection_type();
+
+        debug!(
+            "deleting projection '{}' with key '{}'",
+            projection_type, key
+        );
+
+        match sqlx::query(DELETE_PROJECTION)
+            .bind(projection_type)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete projection '{}' with key \
+                         '{}', error: {}",
+                        &projection_type, &key, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: ICommand, E: IEvent, P: IProjection<C, E>>
+    IEventDispatcher<C, E> for ProjectionStore<C, E, P>
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        events: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        self.dispatch_projection_events(events)
+            .await
+    }
+}
@@ -3,9 +3,16 @@ use log::{
     debug,
     trace,
 };
-use std::marker::PhantomData;
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::Arc,
+};
 
-use sqlx::sqlite::SqlitePool;
+use sqlx::{
+    sqlite::SqlitePool,
+    Sqlite,
+};
 
 use cqrs_es2::{
     Error,
@@ -18,11 +25,27 @@ use cqrs_es2::{
 };
 
 use crate::repository::{
+    query_conflict,
+    HealthStatus,
     IEventDispatcher,
+    IFilteredQueryStore,
+    IHealthCheck,
+    IPayloadTransformer,
     IQueryStore,
+    PayloadCodec,
+    QueryFilter,
 };
 
-use super::super::mysql_constants::*;
+use super::health::check_health;
+
+use super::super::{
+    event_filter_query::{
+        BindValue,
+        Dialect,
+    },
+    mysql_constants::*,
+    query_filter_query::build_queries_filter_query,
+};
 
 static CREATE_QUERY_TABLE: &str = "
 CREATE TABLE IF NOT EXISTS
@@ -32,11 +55,32 @@ queries
     aggregate_id   TEXT                        NOT NULL,
     query_type     TEXT                        NOT NULL,
     version        bigint CHECK (version >= 0) NOT NULL,
-    payload        TEXT                        NOT NULL,
+    payload        TEXT CHECK (json_valid(payload)) NOT NULL,
     PRIMARY KEY (aggregate_type, aggregate_id, query_type)
 );
 ";
 
+static INSERT_QUERY: &str = "
+INSERT INTO
+    queries
+    (
+        version,
+        payload,
+        aggregate_type,
+        aggregate_id,
+        query_type
+    )
+VALUES
+    (
+        ?,
+        ?,
+        ?,
+        ?,
+        ?
+    )
+ON CONFLICT DO NOTHING;
+";
+
 /// Async SQLite query store
 pub struct QueryStore<
     C: ICommand,
@@ -45,6 +89,7 @@ pub struct QueryStore<
     Q: IQuery<C, E>,
 > {
     pool: SqlitePool,
+    codec: PayloadCodec,
     _phantom: PhantomData<(C, E, A, Q)>,
 }
 
@@ -59,6 +104,7 @@ impl<
     pub fn new(pool: SqlitePool) -> Self {
         let x = Self {
             pool,
+            codec: PayloadCodec::default(),
             _phantom: PhantomData,
         };
 
@@ -67,6 +113,17 @@ impl<
         x
     }
 
+    /// Transform the payloads with `transformer` on their way to and
+    /// from the database, e.g. to encrypt them. The stored payloads
+    /// can then no longer be searched, `filter_queries` fails.
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
     async fn create_query_table(&mut self) -> Result<(), Error> {
         let res = match sqlx::query(CREATE_QUERY_TABLE)
             .execute(&self.pool)
@@ -92,6 +149,34 @@ impl<
 
         Ok(())
     }
+
+    async fn query_from_row(
+        &self,
+        row: (String, i64, serde_json::Value),
+    ) -> Result<QueryContext<C, E, Q>, Error> {
+        let payload = match self
+            .codec
+            .decode_value(A::aggregate_type(), &row.0, row.2)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "bad payload found in queries table for \
+                         query '{}' with aggregate id '{}', error: \
+                         {}",
+                        Q::query_type(),
+                        &row.0,
+                        e,
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(QueryContext::new(row.0, row.1, payload))
+    }
 }
 
 #[async_trait]
@@ -106,6 +191,7 @@ impl<
     async fn save_query(
         &mut self,
         context: QueryContext<C, E, Q>,
+        expected_version: i64,
     ) -> Result<(), Error> {
         self.create_query_table().await?;
 
@@ -119,12 +205,15 @@ impl<
             query_type, &aggregate_id
         );
 
-        let sql = match context.version {
-            1 => INSERT_QUERY,
-            _ => UPDATE_QUERY,
-        };
-
-        let payload = match serde_json::to_value(context.payload) {
+        let payload = match self
+            .codec
+            .encode_value(
+                aggregate_type,
+                &aggregate_id,
+                &context.payload,
+            )
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -138,24 +227,29 @@ impl<
             },
         };
 
-        match sqlx::query(sql)
+        let sql = match expected_version {
+            0 => INSERT_QUERY,
+            _ => UPDATE_QUERY,
+        };
+
+        let mut query = sqlx::query(sql)
             .bind(context.version)
             .bind(&payload)
-            .bind(&aggregate_type)
+            .bind(aggregate_type)
             .bind(&aggregate_id)
-            .bind(&query_type)
-            .execute(&self.pool)
-            .await
-        {
+            .bind(query_type);
+
+        if expected_version != 0 {
+            query = query.bind(expected_version);
+        }
+
+        match query.execute(&self.pool).await {
             Ok(x) => {
-                if x.rows_affected() != 1 {
-                    return Err(Error::new(
-                        format!(
-                            "insert/update query failed for \
-                             aggregate id '{}'",
-                            &aggregate_id
-                        )
-                        .as_str(),
+                if x.rows_affected() == 0 {
+                    return Err(query_conflict(
+                        query_type,
+                        &aggregate_id,
+                        expected_version,
                     ));
                 }
             },
@@ -227,7 +321,11 @@ impl<
 
         let row = rows[0].clone();
 
-        let payload = match serde_json::from_value(row.1) {
+        let payload = match self
+            .codec
+            .decode_value(aggregate_type, aggregate_id, row.1)
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 return Err(Error::new(
@@ -248,6 +346,167 @@ impl<
             payload,
         ))
     }
+
+    /// loads the most recent queries of several aggregates
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        if aggregate_ids.is_empty() {
+            trace!("Skip loading zero queries");
+            return Ok(Vec::new());
+        }
+
+        self.create_query_table().await?;
+
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading query '{}' for '{}' aggregate ids",
+            query_type,
+            aggregate_ids.len()
+        );
+
+        let sql = select_queries(aggregate_ids.len());
+
+        let mut sql_query = sqlx::query_as::<
+            Sqlite,
+            (String, i64, serde_json::Value),
+        >(sql.as_str())
+        .bind(aggregate_type)
+        .bind(query_type);
+
+        for aggregate_id in aggregate_ids {
+            sql_query = sql_query.bind(aggregate_id);
+        }
+
+        let rows = match sql_query.fetch_all(&self.pool).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to load queries table for query \
+                         '{}' with error: {}",
+                        &query_type, e,
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut found: HashMap<String, QueryContext<C, E, Q>> =
+            HashMap::new();
+
+        for row in rows {
+            let context = self.query_from_row(row).await?;
+            found.insert(context.aggregate_id.clone(), context);
+        }
+
+        Ok(aggregate_ids
+            .iter()
+            .map(|aggregate_id| {
+                match found.get(aggregate_id) {
+                    Some(x) => x.clone(),
+                    None => {
+                        QueryContext::new(
+                            aggregate_id.to_string(),
+                            0,
+                            Default::default(),
+                        )
+                    },
+                }
+            })
+            .collect())
+    }
+
+    /// loads up to `limit` queries in ascending order of aggregate
+    /// id, starting after the id `after` when given
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        self.create_query_table().await?;
+
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        trace!(
+            "loading '{}' queries '{}' after '{:?}'",
+            limit,
+            query_type,
+            after
+        );
+
+        let rows: Vec<(String, i64, serde_json::Value)> =
+            match sqlx::query_as(SELECT_ALL_QUERIES)
+                .bind(aggregate_type)
+                .bind(query_type)
+                .bind(after.unwrap_or(""))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to load queries table for query \
+                             '{}' with error: {}",
+                            &query_type, e,
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        let mut result = Vec::new();
+
+        for row in rows {
+            result.push(self.query_from_row(row).await?);
+        }
+
+        Ok(result)
+    }
+
+    /// deletes the query of `aggregate_id`
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.create_query_table().await?;
+
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        debug!(
+            "deleting query '{}' for aggregate id '{}'",
+            query_type, aggregate_id
+        );
+
+        match sqlx::query(DELETE_QUERY)
+            .bind(aggregate_type)
+            .bind(aggregate_id)
+            .bind(query_type)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {},
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to delete query '{}' for aggregate \
+                         id '{}' with error: {}",
+                        &query_type, &aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -267,3 +526,102 @@ impl<
             .await
     }
 }
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+    > IFilteredQueryStore<C, E, A, Q> for QueryStore<C, E, A, Q>
+{
+    /// Load the queries matching the `filter` ordered by the sort
+    /// fields then by aggregate id
+    async fn filter_queries(
+        &mut self,
+        filter: &QueryFilter,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        self.create_query_table().await?;
+
+        let aggregate_type = A::aggregate_type();
+        let query_type = Q::query_type();
+
+        trace!(
+            "filtering queries '{}' with '{:?}'",
+            query_type,
+            filter
+        );
+
+        // the filters run on the stored payloads, which are encrypted
+        // or compressed by a transformer
+        if !self.codec.is_identity() {
+            return Err(Error::new(
+                format!(
+                    "unable to filter queries '{}' with transformed \
+                     payloads",
+                    query_type
+                )
+                .as_str(),
+            ));
+        }
+
+        let query = build_queries_filter_query(
+            Dialect::Sqlite,
+            aggregate_type,
+            query_type,
+            filter,
+        );
+
+        let mut sql_query = sqlx::query_as::<
+            Sqlite,
+            (String, i64, serde_json::Value),
+        >(query.sql.as_str());
+
+        for value in query.binds {
+            sql_query = match value {
+                BindValue::Text(x) => sql_query.bind(x),
+                BindValue::Json(x) => sql_query.bind(x),
+                BindValue::Timestamp(x) => sql_query.bind(x),
+                BindValue::Int(x) => sql_query.bind(x),
+                BindValue::Float(x) => sql_query.bind(x),
+            };
+        }
+
+        let rows = match sql_query.fetch_all(&self.pool).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to filter queries table for query \
+                         '{}' with error: {}",
+                        &query_type, e,
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let mut result = Vec::new();
+
+        for row in rows {
+            result.push(self.query_from_row(row).await?);
+        }
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+    > IHealthCheck for QueryStore<C, E, A, Q>
+{
+    /// Probe the database, the `queries` table is created on first
+    /// use and not looked up
+    async fn health_check(&mut self) -> HealthStatus {
+        check_health(&self.pool).await
+    }
+}
@@ -0,0 +1,99 @@
+use sqlx::sqlite::{
+    SqliteConnectOptions,
+    SqlitePoolOptions,
+};
+
+use cqrs_es2::Error;
+
+use crate::{
+    sqlite_store::KeyStore,
+    IKeyStore,
+};
+
+use super::common::*;
+
+async fn check_save_load_keys() -> Result<(), Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(DB_NAME)
+        .create_if_missing(true);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await
+        .unwrap();
+
+    let store = KeyStore::new(pool);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    store
+        .save_key("customer", &id, &[1, 2, 3])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        Some(vec![1, 2, 3])
+    );
+
+    // existing keys are never overwritten
+    store
+        .save_key("customer", &id, &[4, 5, 6])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        Some(vec![1, 2, 3])
+    );
+
+    // keys are not shared between aggregate types
+    assert_eq!(
+        store
+            .load_key("other_type", &id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    store
+        .delete_key("customer", &id)
+        .await
+        .unwrap();
+
+    // erased keys leave an empty key as tombstone
+    store
+        .save_key("customer", &id, &[4, 5, 6])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store
+            .load_key("customer", &id)
+            .await
+            .unwrap(),
+        Some(Vec::new())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_save_load_keys() {
+    tokio_test::block_on(check_save_load_keys()).unwrap();
+}
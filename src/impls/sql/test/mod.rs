@@ -0,0 +1,5 @@
+#[cfg(test)]
+mod test_event_filter_query;
+
+#[cfg(test)]
+mod test_query_filter_query;
@@ -0,0 +1,111 @@
+#[cfg(feature = "with-postgres")]
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use crate::repository::EventFilter;
+
+use super::super::event_filter_query::*;
+
+#[test]
+#[cfg(feature = "with-postgres")]
+fn test_filter_by_aggregate_type_only() {
+    let query = build_events_filter_query(
+        Dialect::Postgres,
+        "customer",
+        &EventFilter::new(),
+    );
+
+    assert!(query
+        .sql
+        .contains("aggregate_type = $1"));
+    assert!(!query.sql.contains("LIMIT"));
+    assert_eq!(
+        query.binds,
+        vec![BindValue::Text("customer".to_string())]
+    );
+}
+
+#[test]
+#[cfg(feature = "with-postgres")]
+fn test_postgres_filter_placeholders() {
+    let from: DateTime<Utc> = "2021-01-01T00:00:00Z".parse().unwrap();
+    let to: DateTime<Utc> = "2021-02-01T00:00:00Z".parse().unwrap();
+
+    let filter = EventFilter::new()
+        .with_event_types(vec![
+            "NameAdded".to_string(),
+            "EmailUpdated".to_string(),
+        ])
+        .with_time_range(Some(from), Some(to))
+        .with_metadata("time", "now")
+        .with_page(10, 5);
+
+    let query = build_events_filter_query(
+        Dialect::Postgres,
+        "customer",
+        &filter,
+    );
+
+    assert!(query
+        .sql
+        .contains("event_type IN ($2, $3)"));
+    assert!(query.sql.contains("timestamp >= $4"));
+    assert!(query.sql.contains("timestamp < $5"));
+    assert!(query.sql.contains("metadata @> $6"));
+    assert!(query
+        .sql
+        .contains("LIMIT $7\nOFFSET $8"));
+
+    assert_eq!(
+        query.binds,
+        vec![
+            BindValue::Text("customer".to_string()),
+            BindValue::Text("NameAdded".to_string()),
+            BindValue::Text("EmailUpdated".to_string()),
+            BindValue::Timestamp(from),
+            BindValue::Timestamp(to),
+            BindValue::Json(serde_json::json!({ "time": "now" })),
+            BindValue::Int(5),
+            BindValue::Int(10),
+        ]
+    );
+}
+
+#[test]
+#[cfg(all(
+    feature = "with-mysql",
+    feature = "with-sqlite"
+))]
+fn test_mysql_and_sqlite_metadata_paths() {
+    let filter = EventFilter::new().with_metadata("user", "admin");
+
+    let query = build_events_filter_query(
+        Dialect::MySql,
+        "customer",
+        &filter,
+    );
+
+    assert!(query
+        .sql
+        .contains("JSON_UNQUOTE(JSON_EXTRACT(metadata, ?)) = ?"));
+    assert_eq!(
+        query.binds[1],
+        BindValue::Text("$.\"user\"".to_string())
+    );
+
+    let query = build_events_filter_query(
+        Dialect::Sqlite,
+        "customer",
+        &filter,
+    );
+
+    assert!(query
+        .sql
+        .contains("json_extract(metadata, ?) = ?"));
+    assert_eq!(
+        query.binds[1],
+        BindValue::Text("$.\"user\"".to_string())
+    );
+}
@@ -0,0 +1,129 @@
+use crate::repository::{
+    QueryFilter,
+    SortOrder,
+};
+
+use super::super::{
+    event_filter_query::*,
+    query_filter_query::*,
+};
+
+#[test]
+#[cfg(feature = "with-postgres")]
+fn test_postgres_query_filter() {
+    let filter = QueryFilter::new()
+        .with_gt("balance", 1000)
+        .with_eq("owner.name", "test")
+        .with_sort("balance", SortOrder::Descending)
+        .with_page(20, 10);
+
+    let query = build_queries_filter_query(
+        Dialect::Postgres,
+        "account",
+        "account_query",
+        &filter,
+    );
+
+    assert!(query
+        .sql
+        .contains("jsonb_extract_path(payload, $3) > $4"));
+    assert!(query
+        .sql
+        .contains("jsonb_extract_path(payload, $5, $6) = $7"));
+    assert!(query.sql.contains(
+        "jsonb_extract_path(payload, $8) DESC,\n    aggregate_id"
+    ));
+    assert!(query
+        .sql
+        .contains("LIMIT $9\nOFFSET $10"));
+
+    assert_eq!(
+        query.binds,
+        vec![
+            BindValue::Text("account".to_string()),
+            BindValue::Text("account_query".to_string()),
+            BindValue::Text("balance".to_string()),
+            BindValue::Json(serde_json::json!(1000)),
+            BindValue::Text("owner".to_string()),
+            BindValue::Text("name".to_string()),
+            BindValue::Json(serde_json::json!("test")),
+            BindValue::Text("balance".to_string()),
+            BindValue::Int(10),
+            BindValue::Int(20),
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "with-mysql")]
+fn test_mysql_query_filter_by_value_type() {
+    let filter = QueryFilter::new()
+        .with_lte("balance", 10.5)
+        .with_eq("owner.name", "test")
+        .with_eq("active", true);
+
+    let query = build_queries_filter_query(
+        Dialect::MySql,
+        "account",
+        "account_query",
+        &filter,
+    );
+
+    assert!(query.sql.contains(
+        "CAST(JSON_EXTRACT(payload, ?) AS DECIMAL(65, 10)) <= ?"
+    ));
+    assert!(query
+        .sql
+        .contains("JSON_UNQUOTE(JSON_EXTRACT(payload, ?)) = ?"));
+    assert!(!query.sql.contains("LIMIT"));
+
+    assert_eq!(
+        query.binds,
+        vec![
+            BindValue::Text("account".to_string()),
+            BindValue::Text("account_query".to_string()),
+            BindValue::Text("$.\"balance\"".to_string()),
+            BindValue::Float(10.5),
+            BindValue::Text("$.\"owner\".\"name\"".to_string()),
+            BindValue::Text("test".to_string()),
+            BindValue::Text("$.\"active\"".to_string()),
+            BindValue::Text("true".to_string()),
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "with-sqlite")]
+fn test_sqlite_query_filter_by_value_type() {
+    let filter = QueryFilter::new()
+        .with_gte("balance", 1000)
+        .with_eq("active", false)
+        .with_sort("owner.name", SortOrder::Ascending);
+
+    let query = build_queries_filter_query(
+        Dialect::Sqlite,
+        "account",
+        "account_query",
+        &filter,
+    );
+
+    assert!(query
+        .sql
+        .contains("json_extract(payload, ?) >= ?"));
+    assert!(query
+        .sql
+        .contains("json_extract(payload, ?) ASC"));
+
+    assert_eq!(
+        query.binds,
+        vec![
+            BindValue::Text("account".to_string()),
+            BindValue::Text("account_query".to_string()),
+            BindValue::Text("$.\"balance\"".to_string()),
+            BindValue::Int(1000),
+            BindValue::Text("$.\"active\"".to_string()),
+            BindValue::Int(0),
+            BindValue::Text("$.\"owner\".\"name\"".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,118 @@
+use log::debug;
+use std::io::Write;
+
+use cqrs_es2::{
+    Error,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+};
+
+use crate::repository::{
+    IEventStore,
+    IQueryStore,
+};
+
+use super::{
+    record::{
+        event_line,
+        query_line,
+        snapshot_line,
+        write_line,
+    },
+    PAGE_SIZE,
+};
+
+/// Export the events and snapshots of all the `A` aggregates of the
+/// `store` to `writer`, returns the number of exported lines
+pub async fn export_events<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    ES: IEventStore<C, E, A>,
+    W: Write + Send,
+>(
+    store: &mut ES,
+    writer: &mut W,
+) -> Result<usize, Error> {
+    let aggregate_type = A::aggregate_type();
+
+    debug!("exporting '{}' events", aggregate_type);
+
+    let mut count = 0;
+    let mut after: Option<String> = None;
+
+    loop {
+        let ids = store
+            .list_aggregate_ids(after.as_deref(), PAGE_SIZE)
+            .await?;
+
+        for id in &ids {
+            for x in store.load_events(id).await? {
+                write_line(writer, &event_line(aggregate_type, &x)?)?;
+                count += 1;
+            }
+
+            let snapshot = store
+                .load_aggregate_from_snapshot(id)
+                .await?;
+
+            if snapshot.version > 0 {
+                write_line(writer, &snapshot_line(&snapshot)?)?;
+                count += 1;
+            }
+        }
+
+        if (ids.len() as i64) < PAGE_SIZE {
+            break;
+        }
+
+        after = ids.last().cloned();
+    }
+
+    Ok(count)
+}
+
+/// Export all the `Q` queries of the `store` to `writer`, returns
+/// the number of exported lines
+pub async fn export_queries<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    QS: IQueryStore<C, E, A, Q>,
+    W: Write + Send,
+>(
+    store: &mut QS,
+    writer: &mut W,
+) -> Result<usize, Error> {
+    debug!(
+        "exporting '{}' queries",
+        Q::query_type()
+    );
+
+    let mut count = 0;
+    let mut after: Option<String> = None;
+
+    loop {
+        let queries = store
+            .load_all_queries(after.as_deref(), PAGE_SIZE)
+            .await?;
+
+        for x in &queries {
+            write_line(writer, &query_line::<C, E, A, Q>(x)?)?;
+            count += 1;
+        }
+
+        if (queries.len() as i64) < PAGE_SIZE {
+            break;
+        }
+
+        after = queries
+            .last()
+            .map(|x| x.aggregate_id.clone());
+    }
+
+    Ok(count)
+}
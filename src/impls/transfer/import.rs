@@ -0,0 +1,208 @@
+use log::debug;
+use serde_json::Value;
+use std::io::BufRead;
+
+use cqrs_es2::{
+    AggregateContext,
+    Error,
+    EventContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+    QueryContext,
+};
+
+use crate::repository::{
+    IEventStore,
+    IQueryStore,
+};
+
+use super::{
+    record::{
+        parse_query,
+        parse_record,
+        Record,
+    },
+    PAGE_SIZE,
+};
+
+fn read_lines<R: BufRead>(
+    reader: R
+) -> impl Iterator<Item = Result<Value, Error>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, x)| {
+            match x {
+                Ok(x) => !x.trim().is_empty(),
+                Err(_) => true,
+            }
+        })
+        .map(|(i, x)| {
+            let line = match x {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!("unable to import with error: {}", e)
+                            .as_str(),
+                    ));
+                },
+            };
+
+            match serde_json::from_str(line.as_str()) {
+                Ok(x) => Ok(x),
+                Err(e) => {
+                    Err(Error::new(
+                        format!(
+                            "bad line {} found with error: {}",
+                            i + 1,
+                            e
+                        )
+                        .as_str(),
+                    ))
+                },
+            }
+        })
+}
+
+/// Saves the snapshot whether or not the `store` already has one,
+/// the stores inserting only the snapshots of version 1
+pub(super) async fn upsert_snapshot<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    ES: IEventStore<C, E, A>,
+>(
+    store: &mut ES,
+    context: AggregateContext<C, E, A>,
+) -> Result<(), Error> {
+    if context.version != 1 &&
+        store
+            .load_aggregate_from_snapshot(&context.aggregate_id)
+            .await?
+            .version ==
+            0
+    {
+        store
+            .save_aggregate_snapshot(AggregateContext::new(
+                context.aggregate_id.clone(),
+                1,
+                context.payload.clone(),
+            ))
+            .await?;
+    }
+
+    store
+        .save_aggregate_snapshot(context)
+        .await
+}
+
+/// Saves the query whether or not the `store` already has one
+pub(super) async fn upsert_query<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    QS: IQueryStore<C, E, A, Q>,
+>(
+    store: &mut QS,
+    context: QueryContext<C, E, Q>,
+) -> Result<(), Error> {
+    let version = store
+        .load_query(&context.aggregate_id)
+        .await?
+        .version;
+
+    store.save_query(context, version).await
+}
+
+/// Import the `A` events and snapshots read from `reader` into the
+/// `store`, skipping the lines of other aggregate types and the
+/// queries, returns the number of imported lines
+///
+/// The events must not already exist in the `store`.
+pub async fn import_events<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    ES: IEventStore<C, E, A>,
+    R: BufRead + Send,
+>(
+    store: &mut ES,
+    reader: R,
+) -> Result<usize, Error> {
+    debug!(
+        "importing '{}' events",
+        A::aggregate_type()
+    );
+
+    let mut count = 0;
+    let mut batch: Vec<EventContext<C, E>> = Vec::new();
+
+    for line in read_lines(reader) {
+        let record = match parse_record::<C, E, A>(&line?)? {
+            Some(x) => x,
+            None => continue,
+        };
+
+        match record {
+            Record::Event(x) => {
+                if batch.len() as i64 >= PAGE_SIZE ||
+                    matches!(batch.last(), Some(y) if y.aggregate_id != x.aggregate_id)
+                {
+                    store.save_events(&batch).await?;
+                    batch.clear();
+                }
+
+                batch.push(x);
+            },
+            Record::Snapshot(x) => {
+                if !batch.is_empty() {
+                    store.save_events(&batch).await?;
+                    batch.clear();
+                }
+
+                upsert_snapshot(store, x).await?;
+            },
+        }
+
+        count += 1;
+    }
+
+    if !batch.is_empty() {
+        store.save_events(&batch).await?;
+    }
+
+    Ok(count)
+}
+
+/// Import the `Q` queries read from `reader` into the `store`,
+/// skipping the other lines, returns the number of imported queries
+pub async fn import_queries<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    QS: IQueryStore<C, E, A, Q>,
+    R: BufRead + Send,
+>(
+    store: &mut QS,
+    reader: R,
+) -> Result<usize, Error> {
+    debug!(
+        "importing '{}' queries",
+        Q::query_type()
+    );
+
+    let mut count = 0;
+
+    for line in read_lines(reader) {
+        if let Some(x) = parse_query::<C, E, A, Q>(&line?)? {
+            upsert_query(store, x).await?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,123 @@
+use log::debug;
+
+use cqrs_es2::{
+    Error,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+};
+
+use crate::repository::{
+    IEventStore,
+    IQueryStore,
+};
+
+use super::{
+    import::{
+        upsert_query,
+        upsert_snapshot,
+    },
+    PAGE_SIZE,
+};
+
+/// Copy the events and snapshots of all the `A` aggregates from
+/// `source` to `target` keeping their sequences and metadata,
+/// returns the number of copied aggregates
+///
+/// The events must not already exist in the `target`.
+pub async fn migrate<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    S: IEventStore<C, E, A>,
+    T: IEventStore<C, E, A>,
+>(
+    source: &mut S,
+    target: &mut T,
+) -> Result<usize, Error> {
+    debug!(
+        "migrating '{}' events",
+        A::aggregate_type()
+    );
+
+    let mut count = 0;
+    let mut after: Option<String> = None;
+
+    loop {
+        let ids = source
+            .list_aggregate_ids(after.as_deref(), PAGE_SIZE)
+            .await?;
+
+        for id in &ids {
+            let events = source.load_events(id).await?;
+
+            for x in events.chunks(PAGE_SIZE as usize) {
+                target.save_events(&x.to_vec()).await?;
+            }
+
+            let snapshot = source
+                .load_aggregate_from_snapshot(id)
+                .await?;
+
+            if snapshot.version > 0 {
+                upsert_snapshot(target, snapshot).await?;
+            }
+
+            count += 1;
+        }
+
+        if (ids.len() as i64) < PAGE_SIZE {
+            break;
+        }
+
+        after = ids.last().cloned();
+    }
+
+    Ok(count)
+}
+
+/// Copy all the `Q` queries from `source` to `target`, returns the
+/// number of copied queries
+pub async fn migrate_queries<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    S: IQueryStore<C, E, A, Q>,
+    T: IQueryStore<C, E, A, Q>,
+>(
+    source: &mut S,
+    target: &mut T,
+) -> Result<usize, Error> {
+    debug!(
+        "migrating '{}' queries",
+        Q::query_type()
+    );
+
+    let mut count = 0;
+    let mut after: Option<String> = None;
+
+    loop {
+        let queries = source
+            .load_all_queries(after.as_deref(), PAGE_SIZE)
+            .await?;
+
+        after = queries
+            .last()
+            .map(|x| x.aggregate_id.clone());
+
+        let len = queries.len() as i64;
+
+        for x in queries {
+            upsert_query(target, x).await?;
+            count += 1;
+        }
+
+        if len < PAGE_SIZE {
+            break;
+        }
+    }
+
+    Ok(count)
+}
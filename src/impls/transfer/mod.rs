@@ -0,0 +1,38 @@
+//!
+//! Backend agnostic export, import and migration of the stores
+//!
+//! The portable format is NDJSON, one JSON object per line holding
+//! an event, a snapshot or a query with its aggregate type:
+//!
+//! ```json
+//! {"kind":"event","aggregate_type":"Customer","aggregate_id":"a1","sequence":1,"payload":{...},"metadata":{...}}
+//! {"kind":"snapshot","aggregate_type":"Customer","aggregate_id":"a1","version":1,"payload":{...}}
+//! {"kind":"query","aggregate_type":"Customer","aggregate_id":"a1","query_type":"CustomerContactQuery","version":1,"payload":{...}}
+//! ```
+//!
+//! The payloads are exported as plain JSON, decoded by the payload
+//! transformers of the source stores, and imported through the
+//! transformers of the target stores. The commit times of the events
+//! are not preserved.
+
+pub use export::{
+    export_events,
+    export_queries,
+};
+pub use import::{
+    import_events,
+    import_queries,
+};
+pub use migrate::{
+    migrate,
+    migrate_queries,
+};
+
+mod export;
+mod import;
+mod migrate;
+mod record;
+
+mod test;
+
+static PAGE_SIZE: i64 = 1000;
@@ -0,0 +1,172 @@
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    io::Write,
+};
+
+use cqrs_es2::{
+    AggregateContext,
+    Error,
+    EventContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+    QueryContext,
+};
+
+use crate::repository::{
+    event_record,
+    query_record,
+    snapshot_record,
+};
+
+/// A line of the NDJSON format
+pub enum Record<C: ICommand, E: IEvent, A: IAggregate<C, E>> {
+    Event(EventContext<C, E>),
+    Snapshot(AggregateContext<C, E, A>),
+}
+
+fn to_value<T: serde::Serialize>(
+    payload: &T
+) -> Result<Value, Error> {
+    match serde_json::to_value(payload) {
+        Ok(x) => Ok(x),
+        Err(e) => Err(Error::new(e.to_string().as_str())),
+    }
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(
+    line: &Value,
+    key: &str,
+) -> Result<T, Error> {
+    match serde_json::from_value(line[key].clone()) {
+        Ok(x) => Ok(x),
+        Err(e) => {
+            Err(Error::new(
+                format!(
+                    "bad {} found for aggregate id '{}' with error: \
+                     {}",
+                    key, line["aggregate_id"], e
+                )
+                .as_str(),
+            ))
+        },
+    }
+}
+
+pub fn write_line<W: Write>(
+    writer: &mut W,
+    line: &Value,
+) -> Result<(), Error> {
+    match writeln!(writer, "{}", line) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            Err(Error::new(
+                format!("unable to export with error: {}", e)
+                    .as_str(),
+            ))
+        },
+    }
+}
+
+pub fn event_line<C: ICommand, E: IEvent>(
+    aggregate_type: &str,
+    context: &EventContext<C, E>,
+) -> Result<Value, Error> {
+    Ok(event_record(
+        aggregate_type,
+        &context.aggregate_id,
+        context.sequence,
+        to_value(&context.payload)?,
+        to_value(&context.metadata)?,
+    ))
+}
+
+pub fn snapshot_line<C: ICommand, E: IEvent, A: IAggregate<C, E>>(
+    context: &AggregateContext<C, E, A>
+) -> Result<Value, Error> {
+    Ok(snapshot_record(
+        A::aggregate_type(),
+        &context.aggregate_id,
+        context.version,
+        to_value(&context.payload)?,
+    ))
+}
+
+pub fn query_line<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+>(
+    context: &QueryContext<C, E, Q>
+) -> Result<Value, Error> {
+    Ok(query_record(
+        A::aggregate_type(),
+        &context.aggregate_id,
+        Q::query_type(),
+        context.version,
+        to_value(&context.payload)?,
+    ))
+}
+
+/// Parses a line of `A` events or snapshots, `None` for the lines of
+/// other aggregate types and the queries
+pub fn parse_record<C: ICommand, E: IEvent, A: IAggregate<C, E>>(
+    line: &Value
+) -> Result<Option<Record<C, E, A>>, Error> {
+    if line["aggregate_type"].as_str() != Some(A::aggregate_type()) {
+        return Ok(None);
+    }
+
+    let aggregate_id = from_value::<String>(line, "aggregate_id")?;
+
+    match line["kind"].as_str() {
+        Some("event") => {
+            let metadata: HashMap<String, String> =
+                from_value(line, "metadata")?;
+
+            Ok(Some(Record::Event(EventContext::new(
+                aggregate_id,
+                from_value(line, "sequence")?,
+                from_value(line, "payload")?,
+                metadata,
+            ))))
+        },
+        Some("snapshot") => {
+            Ok(Some(Record::Snapshot(
+                AggregateContext::new(
+                    aggregate_id,
+                    from_value(line, "version")?,
+                    from_value(line, "payload")?,
+                ),
+            )))
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Parses a line of `Q` queries, `None` for the other lines
+pub fn parse_query<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+>(
+    line: &Value
+) -> Result<Option<QueryContext<C, E, Q>>, Error> {
+    if line["kind"].as_str() != Some("query") ||
+        line["aggregate_type"].as_str() !=
+            Some(A::aggregate_type()) ||
+        line["query_type"].as_str() != Some(Q::query_type())
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(QueryContext::new(
+        from_value(line, "aggregate_id")?,
+        from_value(line, "version")?,
+        from_value(line, "payload")?,
+    )))
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use cqrs_es2::{
+    example_impl::*,
+    AggregateContext,
+    Error,
+    EventContext,
+    QueryContext,
+};
+
+use crate::{
+    memory_store::{
+        EventStore,
+        QueryStore,
+    },
+    transfer::{
+        export_events,
+        export_queries,
+        import_events,
+        import_queries,
+        migrate,
+        migrate_queries,
+    },
+    IEventStore,
+    IQueryStore,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+fn get_metadata() -> HashMap<String, String> {
+    let now = "2021-03-18T12:32:45.930Z".to_string();
+    let mut metadata = HashMap::new();
+    metadata.insert("time".to_string(), now);
+    metadata
+}
+
+fn get_event(
+    id: &str,
+    sequence: i64,
+) -> EventContext<CustomerCommand, CustomerEvent> {
+    EventContext::new(
+        id.to_string(),
+        sequence,
+        CustomerEvent::EmailUpdated(EmailUpdated {
+            new_email: format!("test{}@email.com", sequence),
+        }),
+        get_metadata(),
+    )
+}
+
+fn get_snapshot(
+    id: &str,
+    version: i64,
+) -> AggregateContext<CustomerCommand, CustomerEvent, Customer> {
+    AggregateContext::new(
+        id.to_string(),
+        version,
+        Customer {
+            customer_id: id.to_string(),
+            name: "test name".to_string(),
+            email: format!("test{}@email.com", version),
+            addresses: Vec::new(),
+        },
+    )
+}
+
+fn get_query(
+    id: &str,
+    version: i64,
+) -> QueryContext<CustomerCommand, CustomerEvent, CustomerContactQuery>
+{
+    QueryContext::new(
+        id.to_string(),
+        version,
+        CustomerContactQuery {
+            name: "test name".to_string(),
+            email: format!("test{}@email.com", version),
+            latest_address: "one address".to_string(),
+        },
+    )
+}
+
+async fn populate(
+    events: &mut ThisEventStore,
+    queries: &mut ThisQueryStore,
+) -> Result<(), Error> {
+    events
+        .save_events_and_snapshot(
+            &vec![
+                get_event("id_A", 1),
+                get_event("id_A", 2),
+            ],
+            Some(&get_snapshot("id_A", 2)),
+        )
+        .await?;
+    events
+        .save_events(&vec![get_event("id_B", 1)])
+        .await?;
+
+    queries
+        .save_query(get_query("id_A", 1), 0)
+        .await?;
+    queries
+        .save_query(get_query("id_A", 2), 1)
+        .await?;
+    queries
+        .save_query(get_query("id_B", 1), 0)
+        .await?;
+
+    Ok(())
+}
+
+async fn check_export_import() -> Result<(), Error> {
+    let mut events = ThisEventStore::default();
+    let mut queries = ThisQueryStore::default();
+
+    populate(&mut events, &mut queries).await?;
+
+    let mut buffer = Vec::new();
+
+    assert_eq!(
+        export_events(&mut events, &mut buffer).await?,
+        4
+    );
+    assert_eq!(
+        export_queries(&mut queries, &mut buffer).await?,
+        2
+    );
+
+    let mut imported_events = ThisEventStore::default();
+    let mut imported_queries = ThisQueryStore::default();
+
+    assert_eq!(
+        import_events(&mut imported_events, buffer.as_slice())
+            .await?,
+        4
+    );
+    assert_eq!(
+        import_queries(&mut imported_queries, buffer.as_slice())
+            .await?,
+        2
+    );
+
+    for id in &["id_A", "id_B"] {
+        assert_eq!(
+            imported_events.load_events(id).await?,
+            events.load_events(id).await?
+        );
+        assert_eq!(
+            imported_events
+                .load_aggregate_from_snapshot(id)
+                .await?,
+            events
+                .load_aggregate_from_snapshot(id)
+                .await?
+        );
+        assert_eq!(
+            imported_queries.load_query(id).await?,
+            queries.load_query(id).await?
+        );
+    }
+
+    // lines of other aggregate types and blank lines are skipped
+    let other =
+        b"{\"kind\":\"event\",\"aggregate_type\":\"Other\"}\n\n";
+
+    assert_eq!(
+        import_events(
+            &mut ThisEventStore::default(),
+            &other[..]
+        )
+        .await?,
+        0
+    );
+
+    assert!(import_events(
+        &mut ThisEventStore::default(),
+        &b"not json\n"[..]
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_export_import() {
+    tokio_test::block_on(check_export_import()).unwrap();
+}
+
+async fn check_migrate() -> Result<(), Error> {
+    let mut events = ThisEventStore::default();
+    let mut queries = ThisQueryStore::default();
+
+    populate(&mut events, &mut queries).await?;
+
+    let mut migrated_events = ThisEventStore::default();
+    let mut migrated_queries = ThisQueryStore::default();
+
+    assert_eq!(
+        migrate(&mut events, &mut migrated_events).await?,
+        2
+    );
+    assert_eq!(
+        migrate_queries(&mut queries, &mut migrated_queries).await?,
+        2
+    );
+
+    assert_eq!(
+        migrated_events
+            .load_events("id_A")
+            .await?,
+        vec![
+            get_event("id_A", 1),
+            get_event("id_A", 2)
+        ]
+    );
+    assert_eq!(
+        migrated_events
+            .load_aggregate_from_snapshot("id_A")
+            .await?,
+        get_snapshot("id_A", 2)
+    );
+    assert_eq!(
+        migrated_queries
+            .load_query("id_A")
+            .await?,
+        get_query("id_A", 2)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_migrate() {
+    tokio_test::block_on(check_migrate()).unwrap();
+}
+
+#[cfg(feature = "with-sqlite")]
+async fn check_migrate_to_sqlite() -> Result<(), Error> {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::sqlite_store;
+
+    let mut events = ThisEventStore::default();
+    let mut queries = ThisQueryStore::default();
+
+    populate(&mut events, &mut queries).await?;
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let mut migrated_events = sqlite_store::EventStore::<
+        CustomerCommand,
+        CustomerEvent,
+        Customer,
+    >::new(pool.clone());
+    let mut migrated_queries = sqlite_store::QueryStore::<
+        CustomerCommand,
+        CustomerEvent,
+        Customer,
+        CustomerContactQuery,
+    >::new(pool);
+
+    migrate(&mut events, &mut migrated_events).await?;
+    migrate_queries(&mut queries, &mut migrated_queries).await?;
+
+    // the snapshot and the query of version 2 were inserted
+    assert_eq!(
+        migrated_events
+            .load_aggregate_from_snapshot("id_A")
+            .await?,
+        get_snapshot("id_A", 2)
+    );
+    assert_eq!(
+        migrated_queries
+            .load_query("id_A")
+            .await?,
+        get_query("id_A", 2)
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-sqlite")]
+#[test]
+fn test_migrate_to_sqlite() {
+    tokio_test::block_on(check_migrate_to_sqlite()).unwrap();
+}
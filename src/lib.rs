@@ -18,7 +18,28 @@
 //!
 //!   - `IEventDispatcher` - an interface for async events listeners
 //!   - `IEventStore` - an interface for async event stores
+//!   - `IFilteredEventStore` - an interface for searching events by
+//!     type, time and metadata
 //!   - `IQueryStore` - an interface for async query stores
+//!   - `IFilteredQueryStore` - an interface for searching queries by
+//!     the fields of their payloads
+//!   - `IProjectionStore` - an interface for async stores of the
+//!     projections spanning several aggregates, keyed by keys
+//!     computed from the events
+//!   - `IPayloadTransformer` - an interface for transforming the
+//!     payloads before they are stored, e.g. encryption
+//!   - `IKeyStore` - an interface for async stores of the
+//!     per-aggregate encryption keys
+//!   - `IKeyProvider` - an interface for providers of rotatable
+//!     encryption keys
+//!   - `IArchiveMarkerStore` - an interface for async stores of the
+//!     markers of the archived aggregates
+//!   - `IDeadLetterStore` - an interface for async stores of the
+//!     events the dispatchers failed to handle
+//!   - `IAdminStore` - an interface for inspecting and repairing the
+//!     stored records of any aggregate type
+//!   - `IHealthCheck` - an interface for probing the connectivity and
+//!     the schema of the stores
 //!
 //! ## Features
 //!
@@ -31,6 +52,17 @@
 //! - `with-redis` - async Redis store
 //! - `with-all-kv-db` - all key-value DBs drivers
 //! - `with-all-async` - all async drivers (default)
+//! - `with-encryption` - payload and field encryption at rest and
+//!   crypto-shredding (default)
+//! - `with-zstd` - Zstandard payload compression
+//! - `with-lz4` - LZ4 payload compression
+//! - `with-compression` - all payload compressions
+//! - `with-archive` - archival of inactive aggregates to compressed
+//!   NDJSON files
+//! - `with-tracing` - `tracing` spans around the commands, the
+//!   dispatchers and the instrumented stores operations
+//! - `with-metrics` - operations durations, events written and cache
+//!   hits counters through the `metrics` facade
 //!
 //! ## Installation
 //!
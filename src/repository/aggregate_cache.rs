@@ -0,0 +1,156 @@
+use log::trace;
+use lru::LruCache;
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use cqrs_es2::{
+    AggregateContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+};
+
+struct CacheEntry<C: ICommand, E: IEvent, A: IAggregate<C, E>> {
+    context: AggregateContext<C, E, A>,
+    cached_at: Instant,
+}
+
+type Entries<C, E, A> =
+    Arc<Mutex<LruCache<String, CacheEntry<C, E, A>>>>;
+
+/// Bounded in-memory cache of hydrated aggregates evicting the least
+/// recently used ones, with an optional time to live.
+///
+/// Clones share the same entries, so one cache can serve several
+/// `Repository` instances of the same aggregate type. The
+/// `Repository` checks the cached versions against the latest stored
+/// sequences before using them.
+pub struct AggregateCache<C: ICommand, E: IEvent, A: IAggregate<C, E>>
+{
+    entries: Entries<C, E, A>,
+    ttl: Option<Duration>,
+}
+
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>>
+    AggregateCache<C, E, A>
+{
+    /// Constructor of a cache holding up to `capacity` aggregates,
+    /// at least one
+    pub fn new(capacity: usize) -> Self {
+        let capacity =
+            NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+
+        let x = Self {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+            ttl: None,
+        };
+
+        trace!("Created new aggregate cache");
+
+        x
+    }
+
+    /// Expire the aggregates `ttl` after they were cached
+    pub fn with_ttl(
+        mut self,
+        ttl: Duration,
+    ) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Get a copy of the cached aggregate of `aggregate_id`, if any
+    /// and not expired
+    pub fn get(
+        &self,
+        aggregate_id: &str,
+    ) -> Option<AggregateContext<C, E, A>> {
+        let mut entries = self.entries.lock().unwrap();
+
+        let expired = match entries.get(aggregate_id) {
+            None => {
+                return None;
+            },
+            Some(x) => {
+                match self.ttl {
+                    Some(ttl) => x.cached_at.elapsed() > ttl,
+                    None => false,
+                }
+            },
+        };
+
+        if expired {
+            trace!(
+                "cached aggregate id '{}' expired",
+                aggregate_id
+            );
+            entries.pop(aggregate_id);
+            return None;
+        }
+
+        entries
+            .peek(aggregate_id)
+            .map(|x| x.context.clone())
+    }
+
+    /// Cache `context`, replacing the previous aggregate of the same
+    /// id
+    pub fn put(
+        &self,
+        context: AggregateContext<C, E, A>,
+    ) {
+        self.entries.lock().unwrap().put(
+            context.aggregate_id.clone(),
+            CacheEntry {
+                context,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Drop the cached aggregate of `aggregate_id`
+    pub fn invalidate(
+        &self,
+        aggregate_id: &str,
+    ) {
+        self.entries
+            .lock()
+            .unwrap()
+            .pop(aggregate_id);
+    }
+
+    /// Drop all the cached aggregates
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Number of cached aggregates, expired ones included
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no aggregate is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<C: ICommand, E: IEvent, A: IAggregate<C, E>> Clone
+    for AggregateCache<C, E, A>
+{
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries),
+            ttl: self.ttl,
+        }
+    }
+}
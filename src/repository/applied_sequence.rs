@@ -0,0 +1,27 @@
+/// Sequence of the last event of an aggregate applied to the rows of
+/// a projection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedSequence {
+    /// id of the aggregate
+    pub aggregate_id: String,
+    /// sequence of the last applied event
+    pub sequence: i64,
+    /// sequence stored when the events were applied, 0 when none
+    /// was
+    pub expected_sequence: i64,
+}
+
+impl AppliedSequence {
+    /// Constructor
+    pub fn new(
+        aggregate_id: &str,
+        sequence: i64,
+        expected_sequence: i64,
+    ) -> Self {
+        Self {
+            aggregate_id: aggregate_id.to_string(),
+            sequence,
+            expected_sequence,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+/// Marker left in the store in place of the archived events of an
+/// aggregate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveMarker {
+    /// id of the archived aggregate
+    pub aggregate_id: String,
+    /// sequence of the last archived event
+    pub last_sequence: i64,
+    /// location of the archive holding the events
+    pub location: String,
+}
+
+impl ArchiveMarker {
+    /// Constructor
+    pub fn new(
+        aggregate_id: &str,
+        last_sequence: i64,
+        location: &str,
+    ) -> Self {
+        Self {
+            aggregate_id: aggregate_id.to_string(),
+            last_sequence,
+            location: location.to_string(),
+        }
+    }
+}
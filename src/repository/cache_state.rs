@@ -0,0 +1,145 @@
+use futures::lock::Mutex as AsyncMutex;
+use log::trace;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+/// Bookkeeping of the cached stores: when the records of every
+/// aggregate were cached, for the optional time to live, and the
+/// loads in flight, so that concurrent misses of one aggregate cause
+/// a single load from the store.
+///
+/// Clones share the same bookkeeping, the cached stores in front of
+/// the same cache should be given clones of one state. Without a time
+/// to live any record found in the cache is used and nothing is kept,
+/// with one the expired timestamps are dropped as new ones are
+/// recorded.
+#[derive(Clone, Default)]
+pub struct CacheState {
+    ttl: Option<Duration>,
+    cached_at: Arc<Mutex<HashMap<String, Instant>>>,
+    loads: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+}
+
+impl CacheState {
+    /// Constructor
+    pub fn new() -> Self {
+        let x = Self::default();
+
+        trace!("Created new cache state");
+
+        x
+    }
+
+    /// Expire the cached records `ttl` after they were cached, the
+    /// records cached by another process are loaded again from the
+    /// store once
+    pub fn with_ttl(
+        mut self,
+        ttl: Duration,
+    ) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Whether the cached records of `aggregate_id` can be used
+    pub(crate) fn is_fresh(
+        &self,
+        aggregate_id: &str,
+    ) -> bool {
+        let ttl = match self.ttl {
+            Some(x) => x,
+            None => {
+                return true;
+            },
+        };
+
+        let mut cached_at = self.cached_at.lock().unwrap();
+
+        match cached_at.get(aggregate_id) {
+            Some(x) if x.elapsed() <= ttl => true,
+            Some(_) => {
+                cached_at.remove(aggregate_id);
+                false
+            },
+            None => false,
+        }
+    }
+
+    /// Record that the records of `aggregate_id` were just cached
+    pub(crate) fn mark_cached(
+        &self,
+        aggregate_id: &str,
+    ) {
+        let ttl = match self.ttl {
+            Some(x) => x,
+            None => {
+                return;
+            },
+        };
+
+        let mut cached_at = self.cached_at.lock().unwrap();
+
+        // drop the expired timestamps before the map grows
+        if cached_at.len() == cached_at.capacity() {
+            cached_at.retain(|_, x| x.elapsed() <= ttl);
+        }
+
+        cached_at.insert(aggregate_id.to_string(), Instant::now());
+    }
+
+    /// The number of recorded timestamps
+    #[cfg(test)]
+    pub(crate) fn cached_count(&self) -> usize {
+        self.cached_at.lock().unwrap().len()
+    }
+
+    /// Forget the records of `aggregate_id`
+    pub(crate) fn forget(
+        &self,
+        aggregate_id: &str,
+    ) {
+        self.cached_at
+            .lock()
+            .unwrap()
+            .remove(aggregate_id);
+    }
+
+    /// Run `load` once no other load of `aggregate_id` is in flight
+    pub(crate) async fn single_flight<T>(
+        &self,
+        aggregate_id: &str,
+        load: impl Future<Output = T>,
+    ) -> T {
+        let lock = Arc::clone(
+            self.loads
+                .lock()
+                .unwrap()
+                .entry(aggregate_id.to_string())
+                .or_default(),
+        );
+
+        let result = {
+            let _guard = lock.lock().await;
+            load.await
+        };
+
+        let mut loads = self.loads.lock().unwrap();
+
+        // only the map and this load hold the lock
+        if Arc::strong_count(&lock) == 2 {
+            loads.remove(aggregate_id);
+        }
+
+        result
+    }
+}
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use log::{
     debug,
+    error,
     trace,
 };
 use std::marker::PhantomData;
@@ -14,9 +15,22 @@ use cqrs_es2::{
     IEvent,
 };
 
-use super::i_event_store::IEventStore;
+use super::{
+    cache_state::CacheState,
+    event_filter::EventFilter,
+    health_status::HealthStatus,
+    i_event_store::IEventStore,
+    i_filtered_event_store::IFilteredEventStore,
+    i_health_check::IHealthCheck,
+    telemetry,
+};
 
-/// Async cached event store
+/// Async cached event store, writing through the events and the
+/// snapshots once the store committed them.
+///
+/// The cached event streams are trusted until they are invalidated,
+/// `with_store_check` completes them with the events the store
+/// committed after them when other writers share the store.
 pub struct CachedEventStore<
     C: ICommand,
     E: IEvent,
@@ -26,6 +40,8 @@ pub struct CachedEventStore<
 > {
     store: ES,
     cache: EC,
+    state: CacheState,
+    check_store: bool,
     _phantom: PhantomData<(C, E, A)>,
 }
 
@@ -45,6 +61,8 @@ impl<
         let x = Self {
             store,
             cache,
+            state: CacheState::new(),
+            check_store: false,
             _phantom: PhantomData,
         };
 
@@ -52,6 +70,203 @@ impl<
 
         x
     }
+
+    /// Share `state`, with its time to live and loads in flight,
+    /// with the other cached stores in front of the same cache
+    pub fn with_state(
+        mut self,
+        state: CacheState,
+    ) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Look up the events committed after the cached streams on
+    /// every cache hit, for stores written behind this cache
+    pub fn with_store_check(mut self) -> Self {
+        self.check_store = true;
+        self
+    }
+
+    /// Drop the cached records of `aggregate_id`
+    pub async fn invalidate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.state.forget(aggregate_id);
+
+        self.cache
+            .delete_aggregate(aggregate_id)
+            .await
+    }
+
+    /// Drop the cached records of `aggregate_id` after a failed
+    /// write, the error is only logged
+    async fn discard(
+        &mut self,
+        aggregate_id: &str,
+    ) {
+        if let Err(e) = self.invalidate(aggregate_id).await {
+            error!(
+                "invalidating cached aggregate id '{}' returned \
+                 error '{}'",
+                aggregate_id, e
+            );
+        }
+    }
+
+    /// Write `context` to the cache, discarding the cached records
+    /// when it fails
+    async fn cache_snapshot(
+        &mut self,
+        context: AggregateContext<C, E, A>,
+    ) {
+        let aggregate_id = context.aggregate_id.clone();
+
+        match self
+            .cache
+            .save_aggregate_snapshot(context)
+            .await
+        {
+            Ok(_) => {
+                self.state.mark_cached(&aggregate_id);
+            },
+            Err(e) => {
+                error!(
+                    "caching snapshot returned error '{}'",
+                    e
+                );
+                self.discard(&aggregate_id).await;
+            },
+        };
+    }
+
+    /// Append `contexts`, just committed to the store, to the cached
+    /// stream of `aggregate_id` when they directly follow it
+    async fn cache_events(
+        &mut self,
+        aggregate_id: &str,
+        contexts: &Vec<EventContext<C, E>>,
+    ) {
+        let first = match contexts.first() {
+            Some(x) => x.sequence,
+            None => {
+                return;
+            },
+        };
+
+        let result = match self
+            .cache
+            .load_latest_sequence(aggregate_id)
+            .await
+        {
+            Ok(x) if x + 1 == first => {
+                self.cache.save_events(contexts).await
+            },
+            Ok(x) if x >= first => {
+                debug!(
+                    "cached events of aggregate id '{}' are ahead \
+                     of the store",
+                    aggregate_id
+                );
+                self.discard(aggregate_id).await;
+                return;
+            },
+            // the missing events are loaded on the next load
+            Ok(_) => {
+                return;
+            },
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(_) => {
+                self.state.mark_cached(aggregate_id);
+            },
+            Err(e) => {
+                error!("caching events returned error '{}'", e);
+                self.discard(aggregate_id).await;
+            },
+        };
+    }
+
+    /// Load the events after `sequence` from the cache when fresh,
+    /// completed with the events the store committed after the
+    /// cached ones with `with_store_check`, `None` when none is
+    /// cached
+    async fn load_cached_events(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<Option<Vec<EventContext<C, E>>>, Error> {
+        if !self.state.is_fresh(aggregate_id) {
+            return Ok(None);
+        }
+
+        let mut result = self
+            .cache
+            .load_events(aggregate_id)
+            .await?;
+
+        let last = match result.last() {
+            Some(x) => x.sequence,
+            None => {
+                return Ok(None);
+            },
+        };
+
+        telemetry::record_cache("cached_event_store", 1, 0);
+
+        if !self.check_store {
+            debug!("cache hit");
+
+            result.retain(|x| x.sequence > sequence);
+
+            return Ok(Some(result));
+        }
+
+        let tail = self
+            .store
+            .load_events_after(aggregate_id, last)
+            .await?;
+
+        if tail.is_empty() {
+            debug!("cache hit");
+        }
+        else {
+            debug!(
+                "partial cache hit, '{}' events missing",
+                tail.len()
+            );
+            self.cache_events(aggregate_id, &tail)
+                .await;
+            result.extend(tail);
+        }
+
+        result.retain(|x| x.sequence > sequence);
+
+        Ok(Some(result))
+    }
+
+    /// Load the snapshot from the cache when fresh, `None` otherwise
+    async fn load_cached_snapshot(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<Option<AggregateContext<C, E, A>>, Error> {
+        let result = self
+            .cache
+            .load_aggregate_from_snapshot(aggregate_id)
+            .await?;
+
+        if result.version == 0 || !self.state.is_fresh(aggregate_id) {
+            return Ok(None);
+        }
+
+        debug!("cache hit");
+        telemetry::record_cache("cached_event_store", 1, 0);
+
+        Ok(Some(result))
+    }
 }
 
 #[async_trait]
@@ -68,7 +283,61 @@ impl<
         &mut self,
         contexts: &Vec<EventContext<C, E>>,
     ) -> Result<(), Error> {
-        self.store.save_events(contexts).await
+        let aggregate_id = match contexts.first() {
+            Some(x) => x.aggregate_id.clone(),
+            None => {
+                return self.store.save_events(contexts).await;
+            },
+        };
+
+        if let Err(e) = self.store.save_events(contexts).await {
+            self.discard(&aggregate_id).await;
+            return Err(e);
+        }
+
+        self.cache_events(&aggregate_id, contexts)
+            .await;
+
+        Ok(())
+    }
+
+    /// Save new events together with the aggregate snapshot taken
+    /// after them, the cache is only updated once the store
+    /// committed
+    async fn save_events_and_snapshot(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+        snapshot: Option<&AggregateContext<C, E, A>>,
+    ) -> Result<(), Error> {
+        if let Err(e) = self
+            .store
+            .save_events_and_snapshot(contexts, snapshot)
+            .await
+        {
+            let aggregate_id = contexts
+                .first()
+                .map(|x| x.aggregate_id.clone())
+                .or_else(|| snapshot.map(|x| x.aggregate_id.clone()));
+
+            if let Some(x) = aggregate_id {
+                self.discard(&x).await;
+            }
+
+            return Err(e);
+        }
+
+        if let Some(x) = contexts.first() {
+            let aggregate_id = x.aggregate_id.clone();
+
+            self.cache_events(&aggregate_id, contexts)
+                .await;
+        }
+
+        if let Some(x) = snapshot {
+            self.cache_snapshot(x.clone()).await;
+        }
+
+        Ok(())
     }
 
     /// Load all events for a particular `aggregate_id`
@@ -76,45 +345,212 @@ impl<
         &mut self,
         aggregate_id: &str,
     ) -> Result<Vec<EventContext<C, E>>, Error> {
+        self.load_events_after(aggregate_id, 0)
+            .await
+    }
+
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `sequence`, only the events missing from the cache are loaded
+    /// from the store and concurrent misses of the same aggregate
+    /// load its events once
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        if let Some(x) = self
+            .load_cached_events(aggregate_id, sequence)
+            .await?
+        {
+            return Ok(x);
+        }
+
+        let state = self.state.clone();
+
+        state
+            .single_flight(aggregate_id, async {
+                // another load may have filled the cache meanwhile
+                if let Some(x) = self
+                    .load_cached_events(aggregate_id, sequence)
+                    .await?
+                {
+                    return Ok(x);
+                }
+
+                debug!("cache miss");
+                telemetry::record_cache("cached_event_store", 0, 1);
+
+                let mut result = self
+                    .store
+                    .load_events(aggregate_id)
+                    .await?;
+
+                if !result.is_empty() {
+                    // drop the expired records before caching the
+                    // stream again
+                    if !self.state.is_fresh(aggregate_id) {
+                        self.discard(aggregate_id).await;
+                    }
+
+                    self.cache_events(aggregate_id, &result)
+                        .await;
+                }
+
+                result.retain(|x| x.sequence > sequence);
+
+                Ok(result)
+            })
+            .await
+    }
+
+    /// Load the sequence of the last event of `aggregate_id`, 0 if it
+    /// has none
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
         self.store
-            .load_events(aggregate_id)
+            .load_latest_sequence(aggregate_id)
             .await
     }
 
-    /// save a new aggregate snapshot
+    /// save a new aggregate snapshot, the cache is only updated once
+    /// the store committed
     async fn save_aggregate_snapshot(
         &mut self,
         context: AggregateContext<C, E, A>,
     ) -> Result<(), Error> {
-        self.cache
+        if let Err(e) = self
+            .store
             .save_aggregate_snapshot(context.clone())
-            .await?;
-        self.store
-            .save_aggregate_snapshot(context)
-            .await?;
+            .await
+        {
+            self.discard(&context.aggregate_id)
+                .await;
+            return Err(e);
+        }
+
+        self.cache_snapshot(context).await;
 
         Ok(())
     }
 
-    /// Load aggregate at current state from snapshots
+    /// Load aggregate at current state from snapshots, concurrent
+    /// misses of the same aggregate load it once from the store
     async fn load_aggregate_from_snapshot(
         &mut self,
         aggregate_id: &str,
     ) -> Result<AggregateContext<C, E, A>, Error> {
-        let result = self
-            .cache
-            .load_aggregate_from_snapshot(aggregate_id)
-            .await?;
-
-        if result.version == 0 {
-            debug!("cache miss");
-            self.store
-                .load_aggregate_from_snapshot(aggregate_id)
-                .await
-        }
-        else {
-            debug!("cache hit");
-            Ok(result)
+        if let Some(x) = self
+            .load_cached_snapshot(aggregate_id)
+            .await?
+        {
+            return Ok(x);
         }
+
+        let state = self.state.clone();
+
+        state
+            .single_flight(aggregate_id, async {
+                // another load may have filled the cache meanwhile
+                if let Some(x) = self
+                    .load_cached_snapshot(aggregate_id)
+                    .await?
+                {
+                    return Ok(x);
+                }
+
+                debug!("cache miss");
+                telemetry::record_cache("cached_event_store", 0, 1);
+
+                let result = self
+                    .store
+                    .load_aggregate_from_snapshot(aggregate_id)
+                    .await?;
+
+                if result.version != 0 {
+                    self.cache_snapshot(result.clone())
+                        .await;
+                }
+
+                Ok(result)
+            })
+            .await
+    }
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        self.store
+            .list_aggregate_ids(after, limit)
+            .await
+    }
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        self.store.count_aggregates().await
+    }
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.invalidate(aggregate_id).await?;
+        self.store
+            .delete_aggregate(aggregate_id)
+            .await
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        self.invalidate(aggregate_id).await?;
+        self.store
+            .delete_events_until(aggregate_id, sequence)
+            .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IFilteredEventStore<C, E, A>,
+        EC: IEventStore<C, E, A>,
+    > IFilteredEventStore<C, E, A>
+    for CachedEventStore<C, E, A, ES, EC>
+{
+    /// Load the events matching the `filter` ordered by commit
+    /// time, aggregate id and sequence
+    async fn filter_events(
+        &mut self,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        self.store.filter_events(filter).await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IEventStore<C, E, A> + IHealthCheck,
+        EC: IEventStore<C, E, A>,
+    > IHealthCheck for CachedEventStore<C, E, A, ES, EC>
+{
+    /// Check the health of the store, the cache is not probed
+    async fn health_check(&mut self) -> HealthStatus {
+        self.store.health_check().await
     }
 }
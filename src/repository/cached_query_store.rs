@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use log::{
     debug,
+    error,
     trace,
 };
 use std::marker::PhantomData;
@@ -16,11 +17,19 @@ use cqrs_es2::{
 };
 
 use super::{
+    cache_state::CacheState,
+    health_status::HealthStatus,
     i_event_dispatcher::IEventDispatcher,
+    i_filtered_query_store::IFilteredQueryStore,
+    i_health_check::IHealthCheck,
     i_query_store::IQueryStore,
+    query_conflict::is_query_conflict,
+    query_filter::QueryFilter,
+    telemetry,
 };
 
-/// Async cached query store
+/// Async cached query store, writing through the queries once the
+/// store committed them
 pub struct CachedQueryStore<
     C: ICommand,
     E: IEvent,
@@ -31,6 +40,7 @@ pub struct CachedQueryStore<
 > {
     store: QS,
     cache: QC,
+    state: CacheState,
     _phantom: PhantomData<(C, E, A, Q)>,
 }
 
@@ -51,6 +61,7 @@ impl<
         let x = Self {
             store,
             cache,
+            state: CacheState::new(),
             _phantom: PhantomData,
         };
 
@@ -58,6 +69,94 @@ impl<
 
         x
     }
+
+    /// Share `state`, with its time to live and loads in flight,
+    /// with the other cached stores in front of the same cache
+    pub fn with_state(
+        mut self,
+        state: CacheState,
+    ) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Drop the cached query of `aggregate_id`
+    pub async fn invalidate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.state.forget(aggregate_id);
+
+        self.cache
+            .delete_query(aggregate_id)
+            .await
+    }
+
+    /// Drop the cached query of `aggregate_id` after a failed write,
+    /// the error is only logged
+    async fn discard(
+        &mut self,
+        aggregate_id: &str,
+    ) {
+        if let Err(e) = self.invalidate(aggregate_id).await {
+            error!(
+                "invalidating cached query of aggregate id '{}' \
+                 returned error '{}'",
+                aggregate_id, e
+            );
+        }
+    }
+
+    /// Write `context` over the cached query of version
+    /// `cached_version`, discarding the cached query when it fails
+    async fn cache_query(
+        &mut self,
+        context: QueryContext<C, E, Q>,
+        cached_version: i64,
+    ) {
+        let aggregate_id = context.aggregate_id.clone();
+
+        match self
+            .cache
+            .save_query(context, cached_version)
+            .await
+        {
+            Ok(_) => {
+                self.state.mark_cached(&aggregate_id);
+            },
+            Err(e) => {
+                // the cache missed the query or holds another version
+                if is_query_conflict(&e) {
+                    debug!("caching query returned error '{}'", e);
+                }
+                else {
+                    error!("caching query returned error '{}'", e);
+                }
+
+                self.discard(&aggregate_id).await;
+            },
+        };
+    }
+
+    /// Load the query from the cache, with whether it is fresh
+    async fn load_cached_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(QueryContext<C, E, Q>, bool), Error> {
+        let result = self
+            .cache
+            .load_query(aggregate_id)
+            .await?;
+
+        if result.version == 0 || !self.state.is_fresh(aggregate_id) {
+            return Ok((result, false));
+        }
+
+        debug!("cache hit");
+        telemetry::record_cache("cached_query_store", 1, 0);
+
+        Ok((result, true))
+    }
 }
 
 #[async_trait]
@@ -71,39 +170,165 @@ impl<
     > IQueryStore<C, E, A, Q>
     for CachedQueryStore<C, E, A, Q, QS, QC>
 {
-    /// saves the updated query
+    /// saves the updated query, the cache is only updated once the
+    /// store committed
     async fn save_query(
         &mut self,
         context: QueryContext<C, E, Q>,
+        expected_version: i64,
     ) -> Result<(), Error> {
-        self.cache
-            .save_query(context.clone())
-            .await?;
-        self.store.save_query(context).await?;
+        if let Err(e) = self
+            .store
+            .save_query(context.clone(), expected_version)
+            .await
+        {
+            self.discard(&context.aggregate_id)
+                .await;
+            return Err(e);
+        }
+
+        self.cache_query(context, expected_version)
+            .await;
 
         Ok(())
     }
 
-    /// loads the most recent query
+    /// loads the most recent query, concurrent misses of the same
+    /// aggregate load it once from the store
     async fn load_query(
         &mut self,
         aggregate_id: &str,
     ) -> Result<QueryContext<C, E, Q>, Error> {
-        let result = self
+        if let (x, true) = self
+            .load_cached_query(aggregate_id)
+            .await?
+        {
+            return Ok(x);
+        }
+
+        let state = self.state.clone();
+
+        state
+            .single_flight(aggregate_id, async {
+                // another load may have filled the cache meanwhile
+                let cached_version = match self
+                    .load_cached_query(aggregate_id)
+                    .await?
+                {
+                    (x, true) => {
+                        return Ok(x);
+                    },
+                    (x, false) => x.version,
+                };
+
+                debug!("cache miss");
+                telemetry::record_cache("cached_query_store", 0, 1);
+
+                let result = self
+                    .store
+                    .load_query(aggregate_id)
+                    .await?;
+
+                if result.version != 0 {
+                    self.cache_query(result.clone(), cached_version)
+                        .await;
+                }
+
+                Ok(result)
+            })
+            .await
+    }
+
+    /// loads the most recent queries of several aggregates, falling
+    /// back to the store for the ones missing from the cache
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let mut result = self
             .cache
-            .load_query(aggregate_id)
+            .load_queries(aggregate_ids)
             .await?;
 
-        if result.version == 0 {
-            debug!("cache miss");
-            self.store
-                .load_query(aggregate_id)
-                .await
-        }
-        else {
+        let stale: Vec<bool> = result
+            .iter()
+            .map(|x| {
+                x.version == 0 ||
+                    !self.state.is_fresh(&x.aggregate_id)
+            })
+            .collect();
+
+        let missing: Vec<String> = result
+            .iter()
+            .zip(&stale)
+            .filter(|(_, x)| **x)
+            .map(|(x, _)| x.aggregate_id.clone())
+            .collect();
+
+        telemetry::record_cache(
+            "cached_query_store",
+            result.len() - missing.len(),
+            missing.len(),
+        );
+
+        if missing.is_empty() {
             debug!("cache hit");
-            Ok(result)
+            return Ok(result);
+        }
+
+        debug!(
+            "cache miss for '{}' queries",
+            missing.len()
+        );
+
+        let mut loaded = self
+            .store
+            .load_queries(&missing)
+            .await?
+            .into_iter();
+
+        let mut refreshed = Vec::new();
+
+        for (context, stale) in result.iter_mut().zip(stale) {
+            if stale {
+                if let Some(x) = loaded.next() {
+                    if x.version != 0 {
+                        refreshed.push((x.clone(), context.version));
+                    }
+                    *context = x;
+                }
+            }
         }
+
+        for (x, cached_version) in refreshed {
+            self.cache_query(x, cached_version)
+                .await;
+        }
+
+        Ok(result)
+    }
+
+    /// loads up to `limit` queries from the store, the cache may
+    /// not hold all of them
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        self.store
+            .load_all_queries(after, limit)
+            .await
+    }
+
+    /// deletes the query of `aggregate_id`
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.invalidate(aggregate_id).await?;
+        self.store
+            .delete_query(aggregate_id)
+            .await
     }
 }
 
@@ -127,3 +352,39 @@ impl<
             .await
     }
 }
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+        QS: IFilteredQueryStore<C, E, A, Q>,
+        QC: IQueryStore<C, E, A, Q>,
+    > IFilteredQueryStore<C, E, A, Q>
+    for CachedQueryStore<C, E, A, Q, QS, QC>
+{
+    /// Load the queries matching the `filter` from the store
+    async fn filter_queries(
+        &mut self,
+        filter: &QueryFilter,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        self.store.filter_queries(filter).await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+        QS: IQueryStore<C, E, A, Q> + IHealthCheck,
+        QC: IQueryStore<C, E, A, Q>,
+    > IHealthCheck for CachedQueryStore<C, E, A, Q, QS, QC>
+{
+    /// Check the health of the store, the cache is not probed
+    async fn health_check(&mut self) -> HealthStatus {
+        self.store.health_check().await
+    }
+}
@@ -0,0 +1,42 @@
+use serde_json::Value;
+
+/// Events a dispatcher failed to handle, kept to be dispatched again.
+///
+/// A dead letter is identified by its dispatcher, aggregate id and
+/// the sequence of its first event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLetter {
+    /// name of the dispatcher that failed
+    pub dispatcher: String,
+    /// id of the aggregate of the events
+    pub aggregate_id: String,
+    /// sequence of the first event
+    pub sequence: i64,
+    /// the events in the NDJSON records format of the `transfer`
+    /// module
+    pub events: Value,
+    /// the last error returned by the dispatcher
+    pub error: String,
+    /// number of times the dispatcher failed to handle the events
+    pub attempts: i64,
+}
+
+impl DeadLetter {
+    /// Constructor of the dead letter of a first failure
+    pub fn new(
+        dispatcher: &str,
+        aggregate_id: &str,
+        sequence: i64,
+        events: Value,
+        error: &str,
+    ) -> Self {
+        Self {
+            dispatcher: dispatcher.to_string(),
+            aggregate_id: aggregate_id.to_string(),
+            sequence,
+            events,
+            error: error.to_string(),
+            attempts: 1,
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+    warn,
+};
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+};
+
+use super::{
+    dead_letter::DeadLetter,
+    i_dead_letter_store::IDeadLetterStore,
+    i_event_dispatcher::IEventDispatcher,
+    i_payload_transformer::IPayloadTransformer,
+    payload_codec::PayloadCodec,
+    raw_record::event_record,
+};
+
+/// Dispatcher recording the events its dispatcher failed to handle
+/// in a dead letter store, to retry or discard them later.
+///
+/// A recorded failure is not returned to the `Repository`, only the
+/// failures to record it are. The events of an aggregate with a dead
+/// letter are recorded behind it without being dispatched, so that
+/// they are retried in order and none is skipped as already applied.
+///
+/// The payloads of the recorded events are transformed like in the
+/// event store when given the same transformer, so that the dead
+/// letters do not keep them in clear.
+pub struct DeadLetterDispatcher<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    DS: IDeadLetterStore,
+> {
+    dispatcher: Box<dyn IEventDispatcher<C, E>>,
+    name: String,
+    store: DS,
+    codec: PayloadCodec,
+    _phantom: PhantomData<A>,
+}
+
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        DS: IDeadLetterStore,
+    > DeadLetterDispatcher<C, E, A, DS>
+{
+    /// Constructor of the dispatcher recording the failures of
+    /// `dispatcher` under `name`
+    pub fn new(
+        dispatcher: Box<dyn IEventDispatcher<C, E>>,
+        name: &str,
+        store: DS,
+    ) -> Self {
+        let x = Self {
+            dispatcher,
+            name: name.to_string(),
+            store,
+            codec: PayloadCodec::default(),
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new async dead letter dispatcher");
+
+        x
+    }
+
+    /// Transform the payloads of the events with `transformer` on
+    /// their way to and from the dead letter store, e.g. to encrypt
+    /// them
+    pub fn with_transformer(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.codec = PayloadCodec::new(transformer);
+        self
+    }
+
+    /// Load up to `limit` dead letters of the dispatcher ordered by
+    /// aggregate id and sequence
+    pub async fn load_dead_letters(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        self.store
+            .load_dead_letters(A::aggregate_type(), &self.name, limit)
+            .await
+    }
+
+    /// Dispatch the events of `letter` again, deleting it when they
+    /// are handled or recording the new error otherwise, the earlier
+    /// dead letters of its aggregate must be handled first
+    pub async fn retry(
+        &mut self,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        debug!(
+            "retrying dead letter of aggregate id '{}' at sequence \
+             '{}'",
+            letter.aggregate_id, letter.sequence
+        );
+
+        let pending = self
+            .store
+            .load_aggregate_dead_letters(
+                A::aggregate_type(),
+                &self.name,
+                &letter.aggregate_id,
+            )
+            .await?;
+
+        if let Some(x) = pending
+            .iter()
+            .find(|x| x.sequence < letter.sequence)
+        {
+            return Err(Error::new(
+                format!(
+                    "dead letter of aggregate id '{}' at sequence \
+                     '{}' must be retried first",
+                    x.aggregate_id, x.sequence
+                )
+                .as_str(),
+            ));
+        }
+
+        let events =
+            decode_events(&self.codec, A::aggregate_type(), letter)
+                .await?;
+
+        match self
+            .dispatcher
+            .dispatch(&letter.aggregate_id, &events)
+            .await
+        {
+            Ok(_) => {
+                self.store
+                    .delete_dead_letter(A::aggregate_type(), letter)
+                    .await
+            },
+            Err(e) => {
+                let mut letter = letter.clone();
+
+                letter.error = e.to_string();
+                letter.attempts += 1;
+
+                self.store
+                    .save_dead_letter(A::aggregate_type(), &letter)
+                    .await?;
+
+                Err(e)
+            },
+        }
+    }
+
+    /// Retry up to `limit` dead letters in order, the following
+    /// letters of an aggregate are skipped after a failure, and
+    /// return the number of letters handled
+    pub async fn retry_all(
+        &mut self,
+        limit: i64,
+    ) -> Result<usize, Error> {
+        let letters = self.load_dead_letters(limit).await?;
+
+        let mut failed = HashSet::new();
+        let mut handled = 0;
+
+        for x in &letters {
+            if failed.contains(&x.aggregate_id) {
+                continue;
+            }
+
+            match self.retry(x).await {
+                Ok(_) => {
+                    handled += 1;
+                },
+                Err(_) => {
+                    failed.insert(x.aggregate_id.clone());
+                },
+            };
+        }
+
+        Ok(handled)
+    }
+
+    /// Delete `letter` without dispatching its events
+    pub async fn discard(
+        &self,
+        letter: &DeadLetter,
+    ) -> Result<(), Error> {
+        self.store
+            .delete_dead_letter(A::aggregate_type(), letter)
+            .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        DS: IDeadLetterStore,
+    > IEventDispatcher<C, E> for DeadLetterDispatcher<C, E, A, DS>
+{
+    async fn dispatch(
+        &mut self,
+        aggregate_id: &str,
+        events: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        let pending = self
+            .store
+            .load_aggregate_dead_letters(
+                A::aggregate_type(),
+                &self.name,
+                aggregate_id,
+            )
+            .await?;
+
+        let error = match pending.first() {
+            Some(x) => {
+                Error::new(
+                    format!(
+                        "dead letter of aggregate id '{}' at \
+                         sequence '{}' is pending",
+                        aggregate_id, x.sequence
+                    )
+                    .as_str(),
+                )
+            },
+            None => {
+                match self
+                    .dispatcher
+                    .dispatch(aggregate_id, events)
+                    .await
+                {
+                    Ok(_) => {
+                        return Ok(());
+                    },
+                    Err(e) => e,
+                }
+            },
+        };
+
+        warn!(
+            "dispatcher '{}' did not handle the events of aggregate \
+             id '{}' with error '{}', recording a dead letter",
+            self.name, aggregate_id, error
+        );
+
+        let letter = DeadLetter::new(
+            &self.name,
+            aggregate_id,
+            events.first().map_or(0, |x| x.sequence),
+            encode_events(
+                &self.codec,
+                A::aggregate_type(),
+                aggregate_id,
+                events,
+            )
+            .await?,
+            error.to_string().as_str(),
+        );
+
+        match self
+            .store
+            .save_dead_letter(A::aggregate_type(), &letter)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                Err(Error::new(
+                    format!(
+                        "dispatcher '{}' failed with error '{}' and \
+                         recording its dead letter failed with \
+                         error '{}'",
+                        self.name, error, e
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+}
+
+async fn encode_events<C: ICommand, E: IEvent>(
+    codec: &PayloadCodec,
+    aggregate_type: &str,
+    aggregate_id: &str,
+    events: &[EventContext<C, E>],
+) -> Result<Value, Error> {
+    let mut records = Vec::new();
+
+    for x in events {
+        let payload = match codec
+            .encode_value(aggregate_type, aggregate_id, &x.payload)
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(
+                    format!(
+                        "unable to encode the dead letter events of \
+                         aggregate id '{}' with error: {}",
+                        aggregate_id, e
+                    )
+                    .as_str(),
+                ));
+            },
+        };
+
+        let metadata = match serde_json::to_value(&x.metadata) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(Error::new(e.to_string().as_str()));
+            },
+        };
+
+        records.push(event_record(
+            aggregate_type,
+            &x.aggregate_id,
+            x.sequence,
+            payload,
+            metadata,
+        ));
+    }
+
+    Ok(Value::Array(records))
+}
+
+async fn decode_events<C: ICommand, E: IEvent>(
+    codec: &PayloadCodec,
+    aggregate_type: &str,
+    letter: &DeadLetter,
+) -> Result<Vec<EventContext<C, E>>, Error> {
+    let records = match letter.events.as_array() {
+        Some(x) => x,
+        None => {
+            return Err(Error::new(
+                format!(
+                    "bad events found in dead letter of aggregate \
+                     id '{}'",
+                    letter.aggregate_id
+                )
+                .as_str(),
+            ));
+        },
+    };
+
+    let payloads = match codec
+        .decode_values::<E>(
+            aggregate_type,
+            &letter.aggregate_id,
+            records
+                .iter()
+                .map(|x| x["payload"].clone())
+                .collect(),
+        )
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(Error::new(
+                format!(
+                    "bad event found in dead letter of aggregate id \
+                     '{}' with error: {}",
+                    letter.aggregate_id, e
+                )
+                .as_str(),
+            ));
+        },
+    };
+
+    let mut result = Vec::new();
+
+    for (x, payload) in records.iter().zip(payloads) {
+        let sequence = x["sequence"]
+            .as_i64()
+            .unwrap_or_default();
+
+        let metadata =
+            match serde_json::from_value(x["metadata"].clone()) {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "bad event found in dead letter of \
+                             aggregate id '{}' with error: {}",
+                            letter.aggregate_id, e
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+        result.push(EventContext::new(
+            letter.aggregate_id.clone(),
+            sequence,
+            payload,
+            metadata,
+        ));
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,15 @@
+/// How a `Repository` handles the dispatchers failing after the
+/// events of a command were committed, the dispatchers always run
+/// concurrently and their failures never fail the committed command
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DispatchPolicy {
+    /// report the first error, the dispatchers still running are
+    /// cancelled
+    #[default]
+    FailFast,
+    /// wait for all the dispatchers and report all their errors
+    Collect,
+    /// wait for all the dispatchers and only log their errors, the
+    /// report has no failures
+    Ignore,
+}
@@ -0,0 +1,278 @@
+use log::{
+    error,
+    trace,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+        RwLock,
+    },
+};
+use tokio::{
+    sync::mpsc::{
+        self,
+        Receiver,
+        Sender,
+    },
+    task::JoinHandle,
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use super::{
+    dispatch_report::DispatchFailure,
+    event_route::EventRoute,
+    i_event_dispatcher::IEventDispatcher,
+    telemetry,
+};
+
+type Message<C, E> = (String, Arc<Vec<EventContext<C, E>>>);
+
+type Senders<C, E> =
+    Arc<Mutex<Option<Vec<(String, Sender<Message<C, E>>)>>>>;
+
+type Routes<C, E> = Arc<RwLock<HashMap<String, EventRoute<C, E>>>>;
+
+/// Bounded queue of committed events processed in the background by
+/// a worker task per dispatcher, so that the commands do not wait for
+/// the read models to be updated.
+///
+/// Every worker handles the events in the order they were pushed,
+/// which keeps the order of the events of each aggregate. Pushing
+/// waits while the queue of a worker is full. The errors of the
+/// dispatchers are logged.
+///
+/// The dispatchers are identified by their position, e.g. `"0"` for
+/// the first one, or by the id given to `with_dispatcher`, and are
+/// only given the events matching their route.
+///
+/// Clones share the same workers and routes.
+pub struct DispatchQueue<C: ICommand, E: IEvent> {
+    senders: Senders<C, E>,
+    workers: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    routes: Routes<C, E>,
+    capacity: usize,
+}
+
+impl<C: ICommand + 'static, E: IEvent + 'static> DispatchQueue<C, E> {
+    /// Constructor spawning a worker per dispatcher on the current
+    /// tokio runtime, each one queuing up to `capacity` commits
+    pub fn new(
+        dispatchers: Vec<Box<dyn IEventDispatcher<C, E>>>,
+        capacity: usize,
+    ) -> Self {
+        let mut x = Self {
+            senders: Arc::new(Mutex::new(Some(Vec::new()))),
+            workers: Arc::new(Mutex::new(Vec::new())),
+            routes: Arc::new(RwLock::new(HashMap::new())),
+            capacity: capacity.max(1),
+        };
+
+        for (i, dispatcher) in dispatchers.into_iter().enumerate() {
+            x = x.with_dispatcher(&i.to_string(), dispatcher);
+        }
+
+        trace!("Created new async dispatch queue");
+
+        x
+    }
+
+    /// Spawn a worker for `dispatcher` identified by `id` in its
+    /// route and its errors
+    pub fn with_dispatcher(
+        self,
+        id: &str,
+        dispatcher: Box<dyn IEventDispatcher<C, E>>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(self.capacity);
+
+        if let Some(x) = self.senders.lock().unwrap().as_mut() {
+            x.push((id.to_string(), sender));
+        }
+
+        self.workers
+            .lock()
+            .unwrap()
+            .push(tokio::spawn(run_worker(
+                id.to_string(),
+                dispatcher,
+                receiver,
+            )));
+
+        self
+    }
+}
+
+impl<C: ICommand, E: IEvent> DispatchQueue<C, E> {
+    /// Give the dispatcher identified by `dispatcher` only the events
+    /// matching `route`, nothing is queued for it when none matches.
+    /// The route applies to all the clones of the queue.
+    pub fn with_route(
+        self,
+        dispatcher: &str,
+        route: EventRoute<C, E>,
+    ) -> Self {
+        self.routes
+            .write()
+            .unwrap()
+            .insert(dispatcher.to_string(), route);
+        self
+    }
+
+    /// Queue the committed `events` of `aggregate_id` for every
+    /// dispatcher they are routed to, waiting while a queue is full.
+    ///
+    /// A stopped worker does not keep the events from being queued
+    /// for the other dispatchers, the dispatchers that were not given
+    /// the events are returned.
+    pub async fn push(
+        &self,
+        aggregate_id: &str,
+        events: &[EventContext<C, E>],
+    ) -> Result<(), Vec<DispatchFailure>> {
+        let senders = match self.senders.lock().unwrap().as_ref() {
+            Some(x) => x.clone(),
+            None => {
+                return Err(vec![DispatchFailure {
+                    dispatcher: None,
+                    queued: true,
+                    error: Error::new("dispatch queue is shut down"),
+                }]);
+            },
+        };
+
+        let all = Arc::new(events.to_vec());
+
+        let messages: Vec<(String, Sender<Message<C, E>>, _)> = {
+            let routes = self.routes.read().unwrap();
+
+            senders
+                .into_iter()
+                .filter_map(|(id, x)| {
+                    let events = match routes.get(&id) {
+                        Some(route) => {
+                            let routed = route.filter(events);
+
+                            if routed.is_empty() {
+                                return None;
+                            }
+
+                            Arc::new(routed)
+                        },
+                        None => Arc::clone(&all),
+                    };
+
+                    Some((id, x, events))
+                })
+                .collect()
+        };
+
+        let mut failures = Vec::new();
+
+        for (id, x, events) in messages {
+            if x.send((aggregate_id.to_string(), events))
+                .await
+                .is_err()
+            {
+                failures.push(DispatchFailure {
+                    dispatcher: Some(id),
+                    queued: true,
+                    error: Error::new(
+                        format!(
+                            "dispatch queue worker stopped, events \
+                             of aggregate id '{}' not queued",
+                            aggregate_id
+                        )
+                        .as_str(),
+                    ),
+                });
+            }
+        }
+
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(failures),
+        }
+    }
+
+    /// Stop accepting events and wait for the workers to dispatch
+    /// the queued ones, the workers that panicked are reported once
+    /// all the others are done
+    pub async fn shutdown(&self) -> Result<(), Error> {
+        self.senders.lock().unwrap().take();
+
+        let workers: Vec<JoinHandle<()>> = self
+            .workers
+            .lock()
+            .unwrap()
+            .drain(..)
+            .collect();
+
+        // the other workers still drain their queues when one failed
+        let mut errors = Vec::new();
+
+        for x in workers {
+            if let Err(e) = x.await {
+                errors.push(e.to_string());
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::new(
+                format!(
+                    "dispatch queue workers failed with errors '{}'",
+                    errors.join("', '")
+                )
+                .as_str(),
+            ));
+        }
+
+        trace!("Dispatch queue shut down");
+
+        Ok(())
+    }
+}
+
+impl<C: ICommand, E: IEvent> Clone for DispatchQueue<C, E> {
+    fn clone(&self) -> Self {
+        Self {
+            senders: Arc::clone(&self.senders),
+            workers: Arc::clone(&self.workers),
+            routes: Arc::clone(&self.routes),
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// Dispatch the queued events until the queue is closed and empty
+async fn run_worker<C: ICommand, E: IEvent>(
+    id: String,
+    mut dispatcher: Box<dyn IEventDispatcher<C, E>>,
+    mut receiver: Receiver<Message<C, E>>,
+) {
+    while let Some((aggregate_id, events)) = receiver.recv().await {
+        if let Err(e) = telemetry::observe(
+            "dispatch_queue",
+            "dispatch",
+            &aggregate_id,
+            dispatcher.dispatch(&aggregate_id, &events),
+        )
+        .await
+        {
+            error!(
+                "queued dispatcher '{}' returned error '{}' for \
+                 aggregate id '{}'",
+                id, e, aggregate_id
+            );
+        }
+    }
+
+    trace!("Dispatch queue worker '{}' stopped", id);
+}
@@ -0,0 +1,67 @@
+use cqrs_es2::Error;
+
+/// The error of a dispatcher
+#[derive(Debug)]
+pub struct DispatchFailure {
+    /// the id of the dispatcher in the `Repository` or in its
+    /// `DispatchQueue`, `None` when the dispatch queue is shut down
+    pub dispatcher: Option<String>,
+    /// whether the events could not be queued for the dispatcher,
+    /// its worker having stopped
+    pub queued: bool,
+    /// the error returned by the dispatcher, or its timeout
+    pub error: Error,
+}
+
+impl DispatchFailure {
+    /// The failed dispatcher, for the error messages
+    pub fn name(&self) -> String {
+        match (&self.dispatcher, self.queued) {
+            (Some(x), false) => format!("dispatcher '{}'", x),
+            (Some(x), true) => format!("queued dispatcher '{}'", x),
+            (None, _) => "dispatch queue".to_string(),
+        }
+    }
+}
+
+/// Outcome of the dispatch of the events of a committed command
+#[derive(Debug, Default)]
+pub struct DispatchReport {
+    /// the number of committed events
+    pub events: usize,
+    /// the dispatchers that failed, with the `FailFast` policy only
+    /// the first one and with the `Ignore` policy none
+    pub failures: Vec<DispatchFailure>,
+}
+
+impl DispatchReport {
+    /// Whether every dispatcher handled the events
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// The error of the failed dispatchers, `None` if all succeeded
+    pub fn into_error(mut self) -> Option<Error> {
+        match self.failures.len() {
+            0 => None,
+            1 => self.failures.pop().map(|x| x.error),
+            n => {
+                let errors: Vec<String> = self
+                    .failures
+                    .iter()
+                    .map(|x| format!("{}: {}", x.name(), x.error))
+                    .collect();
+
+                Some(Error::new(
+                    format!(
+                        "the events were committed but {} \
+                         dispatchers failed: {}",
+                        n,
+                        errors.join(", ")
+                    )
+                    .as_str(),
+                ))
+            },
+        }
+    }
+}
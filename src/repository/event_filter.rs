@@ -0,0 +1,107 @@
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+/// Criteria for querying events across all the aggregates of one
+/// aggregate type.
+///
+/// All the criteria are combined with `AND`. An empty list of event
+/// types matches all event types. The time window includes `from`
+/// and excludes `to`.
+///
+/// # Example
+///
+/// ```rust
+/// use chrono::{
+///     DateTime,
+///     Utc,
+/// };
+///
+/// use tokio_cqrs_es2_store::EventFilter;
+///
+/// let from: DateTime<Utc> = "2021-01-01T00:00:00Z".parse().unwrap();
+/// let to: DateTime<Utc> = "2021-02-01T00:00:00Z".parse().unwrap();
+///
+/// let filter = EventFilter::new()
+///     .with_event_types(vec!["CustomerWithdrewCash".to_string()])
+///     .with_time_range(Some(from), Some(to))
+///     .with_metadata("user", "admin")
+///     .with_page(0, 100);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventFilter {
+    /// id of the only aggregate to match
+    pub aggregate_id: Option<String>,
+    /// event variant names to match
+    pub event_types: Vec<String>,
+    /// inclusive lower bound of the commit time
+    pub from: Option<DateTime<Utc>>,
+    /// exclusive upper bound of the commit time
+    pub to: Option<DateTime<Utc>>,
+    /// metadata key/value pairs that must all match
+    pub metadata: Vec<(String, String)>,
+    /// number of matching events to skip
+    pub offset: i64,
+    /// maximum number of events to return
+    pub limit: Option<i64>,
+}
+
+impl EventFilter {
+    /// Constructor of an empty filter matching all the events
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the results to the events of one aggregate
+    pub fn with_aggregate_id(
+        mut self,
+        aggregate_id: &str,
+    ) -> Self {
+        self.aggregate_id = Some(aggregate_id.to_string());
+        self
+    }
+
+    /// Restricts the results to the given event variant names
+    pub fn with_event_types(
+        mut self,
+        event_types: Vec<String>,
+    ) -> Self {
+        self.event_types = event_types;
+        self
+    }
+
+    /// Restricts the results to the given commit time window
+    pub fn with_time_range(
+        mut self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    /// Restricts the results to events carrying the given metadata
+    /// entry
+    pub fn with_metadata(
+        mut self,
+        key: &str,
+        value: &str,
+    ) -> Self {
+        self.metadata
+            .push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Selects a page of the results
+    pub fn with_page(
+        mut self,
+        offset: i64,
+        limit: i64,
+    ) -> Self {
+        self.offset = offset;
+        self.limit = Some(limit);
+        self
+    }
+}
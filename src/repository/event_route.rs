@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use cqrs_es2::{
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use super::event_type::event_type_of;
+
+type Predicate<C, E> =
+    Arc<dyn Fn(&EventContext<C, E>) -> bool + Send + Sync>;
+
+/// Events a dispatcher is interested in, the `Repository` only gives
+/// it the matching events and does not invoke it when none matches.
+///
+/// All the criteria are combined with `AND`. An empty list of event
+/// types matches all event types.
+///
+/// # Example
+///
+/// ```rust
+/// use cqrs_es2::example_impl::{
+///     CustomerCommand,
+///     CustomerEvent,
+/// };
+///
+/// use tokio_cqrs_es2_store::EventRoute;
+///
+/// let route = EventRoute::<CustomerCommand, CustomerEvent>::new()
+///     .with_event_types(vec!["AddressUpdated".to_string()])
+///     .with_metadata("user", "admin")
+///     .with_predicate(|x| x.sequence > 1);
+/// ```
+pub struct EventRoute<C: ICommand, E: IEvent> {
+    event_types: Vec<String>,
+    metadata: Vec<(String, String)>,
+    predicates: Vec<Predicate<C, E>>,
+}
+
+impl<C: ICommand, E: IEvent> EventRoute<C, E> {
+    /// Constructor of an empty route matching all the events
+    pub fn new() -> Self {
+        Self {
+            event_types: Vec::new(),
+            metadata: Vec::new(),
+            predicates: Vec::new(),
+        }
+    }
+
+    /// Restricts the events to the given event variant names
+    pub fn with_event_types(
+        mut self,
+        event_types: Vec<String>,
+    ) -> Self {
+        self.event_types = event_types;
+        self
+    }
+
+    /// Restricts the events to the ones carrying the given metadata
+    /// entry
+    pub fn with_metadata(
+        mut self,
+        key: &str,
+        value: &str,
+    ) -> Self {
+        self.metadata
+            .push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Restricts the events to the ones `predicate` returns `true`
+    /// for
+    pub fn with_predicate(
+        mut self,
+        predicate: impl Fn(&EventContext<C, E>) -> bool
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.predicates
+            .push(Arc::new(predicate));
+        self
+    }
+
+    /// Whether `event` matches the route
+    pub fn matches(
+        &self,
+        event: &EventContext<C, E>,
+    ) -> bool {
+        if !self.event_types.is_empty() {
+            let event_type =
+                match serde_json::to_value(&event.payload) {
+                    Ok(x) => event_type_of(&x),
+                    Err(_) => {
+                        return false;
+                    },
+                };
+
+            if !self.event_types.contains(&event_type) {
+                return false;
+            }
+        }
+
+        self.metadata
+            .iter()
+            .all(|(k, v)| event.metadata.get(k) == Some(v)) &&
+            self.predicates.iter().all(|x| x(event))
+    }
+
+    /// The matching `events`, in order
+    pub fn filter(
+        &self,
+        events: &[EventContext<C, E>],
+    ) -> Vec<EventContext<C, E>> {
+        events
+            .iter()
+            .filter(|x| self.matches(x))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<C: ICommand, E: IEvent> Default for EventRoute<C, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ICommand, E: IEvent> Clone for EventRoute<C, E> {
+    fn clone(&self) -> Self {
+        Self {
+            event_types: self.event_types.clone(),
+            metadata: self.metadata.clone(),
+            predicates: self.predicates.clone(),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+/// Extracts the event variant name from a serialized event payload.
+///
+/// Events are expected to be enums using the default externally
+/// tagged serde representation, i.e. `{"VariantName": {...}}` or
+/// `"VariantName"` for unit variants. Any other shape yields an
+/// empty string.
+pub(crate) fn event_type_of(payload: &serde_json::Value) -> String {
+    match payload {
+        serde_json::Value::Object(x) if x.len() == 1 => {
+            x.keys().next().unwrap().to_string()
+        },
+        serde_json::Value::String(x) => x.to_string(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+/// Structured result of a store health check, e.g. for readiness
+/// probes
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthStatus {
+    /// the name of the backend, e.g. `postgres`
+    pub backend: String,
+    /// whether the database answered the probe
+    pub reachable: bool,
+    /// the tables the store needs and could not find
+    pub missing_tables: Vec<String>,
+    /// the round trip time of the probe
+    pub latency: Duration,
+    /// the error returned by the database, if any
+    pub error: Option<String>,
+}
+
+impl HealthStatus {
+    /// Status of a database that answered the probe
+    pub fn reachable(
+        backend: &str,
+        latency: Duration,
+    ) -> Self {
+        Self {
+            backend: backend.to_string(),
+            reachable: true,
+            missing_tables: Vec::new(),
+            latency,
+            error: None,
+        }
+    }
+
+    /// Status of a database that failed the probe with `error`
+    pub fn unreachable(
+        backend: &str,
+        latency: Duration,
+        error: String,
+    ) -> Self {
+        Self {
+            backend: backend.to_string(),
+            reachable: false,
+            missing_tables: Vec::new(),
+            latency,
+            error: Some(error),
+        }
+    }
+
+    /// Whether the database is reachable and has all the tables of
+    /// the store
+    pub fn is_healthy(&self) -> bool {
+        self.reachable &&
+            self.missing_tables.is_empty() &&
+            self.error.is_none()
+    }
+}
@@ -20,12 +20,57 @@ pub trait IEventStore<C: ICommand, E: IEvent, A: IAggregate<C, E>>:
         contexts: &Vec<EventContext<C, E>>,
     ) -> Result<(), Error>;
 
+    /// Save new events together with the aggregate snapshot taken
+    /// after them, stores supporting transactions commit both or
+    /// nothing
+    async fn save_events_and_snapshot(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+        snapshot: Option<&AggregateContext<C, E, A>>,
+    ) -> Result<(), Error> {
+        self.save_events(contexts).await?;
+
+        if let Some(x) = snapshot {
+            self.save_aggregate_snapshot(x.clone())
+                .await?;
+        }
+
+        Ok(())
+    }
+
     /// Load all events for a particular `aggregate_id`
     async fn load_events(
         &mut self,
         aggregate_id: &str,
     ) -> Result<Vec<EventContext<C, E>>, Error>;
 
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `sequence`, stores should override the default loading all the
+    /// events
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        let mut events = self.load_events(aggregate_id).await?;
+
+        events.retain(|x| x.sequence > sequence);
+
+        Ok(events)
+    }
+
+    /// Load the sequence of the last event of `aggregate_id`, 0 if it
+    /// has none, stores should override the default loading all the
+    /// events
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        let events = self.load_events(aggregate_id).await?;
+
+        Ok(events.last().map_or(0, |x| x.sequence))
+    }
+
     /// save a new aggregate snapshot
     async fn save_aggregate_snapshot(
         &mut self,
@@ -37,4 +82,29 @@ pub trait IEventStore<C: ICommand, E: IEvent, A: IAggregate<C, E>>:
         &mut self,
         aggregate_id: &str,
     ) -> Result<AggregateContext<C, E, A>, Error>;
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error>;
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error>;
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error>;
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error>;
 }
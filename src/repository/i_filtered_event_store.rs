@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+};
+
+use super::{
+    event_filter::EventFilter,
+    i_event_store::IEventStore,
+};
+
+/// An event store able to search the events of all the aggregates
+/// of its aggregate type.
+#[async_trait]
+pub trait IFilteredEventStore<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+>: IEventStore<C, E, A> {
+    /// Load the events matching the `filter` ordered by commit
+    /// time, aggregate id and sequence
+    async fn filter_events(
+        &mut self,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventContext<C, E>>, Error>;
+}
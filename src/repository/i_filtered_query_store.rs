@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+
+use cqrs_es2::{
+    Error,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+    QueryContext,
+};
+
+use super::{
+    i_query_store::IQueryStore,
+    query_filter::QueryFilter,
+};
+
+/// A query store able to search its queries by the fields of their
+/// payloads.
+#[async_trait]
+pub trait IFilteredQueryStore<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+>: IQueryStore<C, E, A, Q> {
+    /// Load the queries matching the `filter` ordered by the sort
+    /// fields then by aggregate id
+    async fn filter_queries(
+        &mut self,
+        filter: &QueryFilter,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error>;
+}
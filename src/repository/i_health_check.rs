@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+
+use super::health_status::HealthStatus;
+
+/// The abstract connectivity probe of a store.
+#[async_trait]
+pub trait IHealthCheck: Send {
+    /// Probe the database of the store and check that its tables
+    /// exist, failures are reported in the returned status
+    async fn health_check(&mut self) -> HealthStatus;
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use cqrs_es2::Error;
+
+/// Provides the keys encrypting the payloads at rest. Every key is
+/// identified by a key id stored along the encrypted data so that the
+/// current key can be rotated while the payloads encrypted with the
+/// previous keys stay readable.
+#[async_trait]
+pub trait IKeyProvider: Send + Sync {
+    /// Id and value of the key encrypting new payloads
+    async fn current_key(&self) -> Result<(String, Vec<u8>), Error>;
+
+    /// Load the key `key_id`, `None` if it is unknown
+    async fn load_key(
+        &self,
+        key_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error>;
+}
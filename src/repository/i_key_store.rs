@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+
+use cqrs_es2::Error;
+
+/// The abstract storage of the per aggregate encryption keys.
+///
+/// Erased keys are replaced by an empty key acting as a tombstone, so
+/// that no new key is ever created for an erased aggregate.
+#[async_trait]
+pub trait IKeyStore: Send + Sync {
+    /// Save the key of the aggregate unless it already has one
+    async fn save_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        key: &[u8],
+    ) -> Result<(), Error>;
+
+    /// Load the key of the aggregate, `None` if it has none and an
+    /// empty key if it was erased
+    async fn load_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Destroy the key of the aggregate, leaving an empty key as its
+    /// tombstone
+    async fn delete_key(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+    ) -> Result<(), Error>;
+}
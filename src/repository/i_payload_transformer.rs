@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+
+use cqrs_es2::Error;
+
+/// Transforms the serialized payloads of events, snapshots and
+/// queries on their way to and from the stores, e.g. to encrypt them
+/// at rest.
+#[async_trait]
+pub trait IPayloadTransformer: Send + Sync {
+    /// Transform the `payload` of the aggregate before storing it
+    async fn encode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payload: serde_json::Value,
+    ) -> Result<serde_json::Value, Error>;
+
+    /// Restore a stored `payload` of the aggregate
+    async fn decode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payload: serde_json::Value,
+    ) -> Result<serde_json::Value, Error>;
+
+    /// Restore all the stored `payloads` of the aggregate loaded
+    /// together, e.g. its events
+    async fn decode_all(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payloads: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        let mut result = Vec::with_capacity(payloads.len());
+
+        for payload in payloads {
+            result.push(
+                self.decode(aggregate_type, aggregate_id, payload)
+                    .await?,
+            );
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+use std::fmt::Debug;
+
+use cqrs_es2::{
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+/// A read model spanning several aggregates, made of rows keyed by
+/// keys computed from the events, e.g. daily totals or accounts per
+/// branch.
+///
+/// An event can update several rows of the projection, or none.
+pub trait IProjection<C: ICommand, E: IEvent>:
+    Debug
+    + PartialEq
+    + Default
+    + Clone
+    + Serialize
+    + DeserializeOwned
+    + Sync
+    + Send {
+    /// The unique name of the projection
+    fn projection_type() -> &'static str;
+
+    /// The keys of the rows updated by `event`, empty when the event
+    /// does not concern the projection
+    fn keys(event: &EventContext<C, E>) -> Vec<String>;
+
+    /// Updates the row of `key` with `event`
+    fn update(
+        &mut self,
+        key: &str,
+        event: &EventContext<C, E>,
+    );
+}
@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+use super::{
+    applied_sequence::AppliedSequence,
+    i_event_dispatcher::IEventDispatcher,
+    i_projection::IProjection,
+    projection_context::ProjectionContext,
+    query_conflict::is_query_conflict,
+};
+
+/// Number of times `dispatch_projection_events` tries to save rows
+/// saved concurrently by another writer
+const SAVE_PROJECTION_ATTEMPTS: usize = 5;
+
+/// The abstract central source for loading and committing the rows
+/// of a projection.
+#[async_trait]
+pub trait IProjectionStore<
+    C: ICommand,
+    E: IEvent,
+    P: IProjection<C, E>,
+>: IEventDispatcher<C, E> {
+    /// saves the updated row when the stored one is still at
+    /// `expected_version`, the version it was loaded at, or inserts
+    /// it when `expected_version` is 0, returns the
+    /// `projection_conflict` error otherwise
+    async fn save_projection(
+        &mut self,
+        context: ProjectionContext<P>,
+        expected_version: i64,
+    ) -> Result<(), Error>;
+
+    /// saves the updated rows, each at the version it was loaded at
+    /// as in `save_projection`, together with the `applied`
+    /// sequences, each when the stored one is still the expected one,
+    /// returns the `sequence_conflict` error otherwise, all of them
+    /// or none when the store supports transactions
+    async fn save_projections(
+        &mut self,
+        contexts: Vec<(ProjectionContext<P>, i64)>,
+        applied: Vec<AppliedSequence>,
+    ) -> Result<(), Error>;
+
+    /// loads the sequence of the last event of `aggregate_id` applied
+    /// to the rows, 0 when none was
+    async fn load_applied_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error>;
+
+    /// loads the row of `key`, a default row when not found
+    async fn load_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<ProjectionContext<P>, Error>;
+
+    /// loads up to `limit` rows in ascending order of key, starting
+    /// after the key `after` when given
+    async fn load_all_projections(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ProjectionContext<P>>, Error>;
+
+    /// deletes the row of `key`
+    async fn delete_projection(
+        &mut self,
+        key: &str,
+    ) -> Result<(), Error>;
+
+    /// used as a default implementation for dispatching, applies
+    /// every event to the rows of its keys and saves the updated rows
+    /// together with `save_projections`, the sequence of the last
+    /// applied event of each aggregate is saved with them and the
+    /// events up to it are skipped, so that redelivered events are
+    /// applied once
+    ///
+    /// The rows and sequences are reloaded and the events applied
+    /// again when another writer saved one of them meanwhile.
+    async fn dispatch_projection_events(
+        &mut self,
+        events: &[EventContext<C, E>],
+    ) -> Result<(), Error> {
+        let mut aggregate_ids: Vec<&str> = Vec::new();
+
+        for event in events {
+            if !aggregate_ids.contains(&event.aggregate_id.as_str()) {
+                aggregate_ids.push(&event.aggregate_id);
+            }
+        }
+
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let mut applied = Vec::with_capacity(aggregate_ids.len());
+
+            for aggregate_id in &aggregate_ids {
+                let sequence = self
+                    .load_applied_sequence(aggregate_id)
+                    .await?;
+
+                applied.push(AppliedSequence::new(
+                    aggregate_id,
+                    sequence,
+                    sequence,
+                ));
+            }
+
+            let mut rows: Vec<(String, Vec<&EventContext<C, E>>)> =
+                Vec::new();
+
+            for event in events {
+                let sequence = match applied
+                    .iter_mut()
+                    .find(|x| x.aggregate_id == event.aggregate_id)
+                {
+                    Some(x)
+                        if event.sequence > x.expected_sequence =>
+                    {
+                        x
+                    },
+                    _ => {
+                        continue;
+                    },
+                };
+
+                sequence.sequence =
+                    sequence.sequence.max(event.sequence);
+
+                for key in P::keys(event) {
+                    match rows.iter_mut().find(|x| x.0 == key) {
+                        Some(x) => x.1.push(event),
+                        None => rows.push((key, vec![event])),
+                    };
+                }
+            }
+
+            if rows.is_empty() {
+                trace!(
+                    "no event to apply to projection '{}'",
+                    P::projection_type()
+                );
+                return Ok(());
+            }
+
+            applied.retain(|x| x.sequence > x.expected_sequence);
+
+            let mut contexts = Vec::with_capacity(rows.len());
+
+            for (key, events) in &rows {
+                let mut context = self.load_projection(key).await?;
+
+                let version = context.version;
+
+                for event in events {
+                    context.payload.update(key, event);
+                }
+
+                context.version = version + 1;
+
+                contexts.push((context, version));
+            }
+
+            match self
+                .save_projections(contexts, applied)
+                .await
+            {
+                Err(e)
+                    if is_query_conflict(&e) &&
+                        attempts < SAVE_PROJECTION_ATTEMPTS =>
+                {
+                    debug!(
+                        "projection '{}' saved concurrently, \
+                         reloading its rows",
+                        P::projection_type()
+                    );
+                },
+                x => {
+                    return x;
+                },
+            };
+        }
+    }
+}
@@ -1,4 +1,8 @@
 use async_trait::async_trait;
+use log::{
+    debug,
+    trace,
+};
 
 use cqrs_es2::{
     Error,
@@ -10,7 +14,14 @@ use cqrs_es2::{
     QueryContext,
 };
 
-use super::i_event_dispatcher::IEventDispatcher;
+use super::{
+    i_event_dispatcher::IEventDispatcher,
+    query_conflict::is_query_conflict,
+};
+
+/// Number of times `dispatch_events` tries to save a query saved
+/// concurrently by another writer
+const SAVE_QUERY_ATTEMPTS: usize = 5;
 
 /// The abstract central source for loading and committing
 /// queries.
@@ -21,10 +32,14 @@ pub trait IQueryStore<
     A: IAggregate<C, E>,
     Q: IQuery<C, E>,
 >: IEventDispatcher<C, E> {
-    /// saves the updated query
+    /// saves the updated query when the stored one is still at
+    /// `expected_version`, the version it was loaded at, or inserts
+    /// it when `expected_version` is 0, returns the `query_conflict`
+    /// error otherwise
     async fn save_query(
         &mut self,
         context: QueryContext<C, E, Q>,
+        expected_version: i64,
     ) -> Result<(), Error>;
 
     /// loads the most recent query
@@ -33,25 +48,86 @@ pub trait IQueryStore<
         aggregate_id: &str,
     ) -> Result<QueryContext<C, E, Q>, Error>;
 
-    /// used as a default implementation for dispatching
+    /// loads the most recent queries of several aggregates in one
+    /// round trip, in the order of `aggregate_ids`, with a default
+    /// query for the aggregates not found
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error>;
+
+    /// loads up to `limit` queries in ascending order of aggregate
+    /// id, starting after the id `after` when given
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error>;
+
+    /// deletes the query of `aggregate_id`
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error>;
+
+    /// used as a default implementation for dispatching, the version
+    /// of the query is the sequence of the last applied event and
+    /// the events up to it are skipped, so that redelivered events
+    /// are applied once
+    ///
+    /// The query is reloaded and the events applied again when
+    /// another writer saved it meanwhile.
     async fn dispatch_events(
         &mut self,
         aggregate_id: &str,
         events: &[EventContext<C, E>],
     ) -> Result<(), Error> {
-        let mut context = match self.load_query(aggregate_id).await {
-            Ok(x) => x,
-            Err(e) => {
-                return Err(e);
-            },
-        };
-
-        for event in events {
-            context.payload.update(event);
+        if events.is_empty() {
+            return Ok(());
         }
 
-        context.version += 1;
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let mut context = self.load_query(aggregate_id).await?;
 
-        self.save_query(context).await
+            let version = context.version;
+
+            for event in events
+                .iter()
+                .filter(|x| x.sequence > version)
+            {
+                context.payload.update(event);
+                context.version = event.sequence;
+            }
+
+            if context.version == version {
+                trace!(
+                    "events of aggregate id '{}' already applied up \
+                     to sequence '{}'",
+                    aggregate_id,
+                    version
+                );
+                return Ok(());
+            }
+
+            match self.save_query(context, version).await {
+                Err(e)
+                    if is_query_conflict(&e) &&
+                        attempts < SAVE_QUERY_ATTEMPTS =>
+                {
+                    debug!(
+                        "query of aggregate id '{}' saved \
+                         concurrently, reloading it",
+                        aggregate_id
+                    );
+                },
+                x => {
+                    return x;
+                },
+            };
+        }
     }
 }
@@ -0,0 +1,295 @@
+use async_trait::async_trait;
+use log::trace;
+use std::marker::PhantomData;
+
+use cqrs_es2::{
+    AggregateContext,
+    Error,
+    EventContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+};
+
+use super::{
+    event_filter::EventFilter,
+    health_status::HealthStatus,
+    i_event_store::IEventStore,
+    i_filtered_event_store::IFilteredEventStore,
+    i_health_check::IHealthCheck,
+    telemetry,
+};
+
+/// Async event store recording a `tracing` span, the duration and the
+/// failures of every operation of the wrapped store under the
+/// `component` name, together with the count and bytes of the
+/// written events
+pub struct InstrumentedEventStore<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    ES: IEventStore<C, E, A>,
+> {
+    store: ES,
+    component: &'static str,
+    _phantom: PhantomData<(C, E, A)>,
+}
+
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IEventStore<C, E, A>,
+    > InstrumentedEventStore<C, E, A, ES>
+{
+    /// Constructor, `component` labels the spans and metrics, e.g.
+    /// `"postgres_event_store"`
+    pub fn new(
+        store: ES,
+        component: &'static str,
+    ) -> Self {
+        let x = Self {
+            store,
+            component,
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new async instrumented event store");
+
+        x
+    }
+}
+
+fn aggregate_id_of<C: ICommand, E: IEvent>(
+    contexts: &[EventContext<C, E>]
+) -> &str {
+    contexts
+        .first()
+        .map_or("", |x| x.aggregate_id.as_str())
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IEventStore<C, E, A>,
+    > IEventStore<C, E, A> for InstrumentedEventStore<C, E, A, ES>
+{
+    /// Save new events
+    async fn save_events(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        telemetry::observe(
+            self.component,
+            "save_events",
+            aggregate_id_of(contexts),
+            self.store.save_events(contexts),
+        )
+        .await?;
+
+        telemetry::record_events_written(self.component, contexts);
+
+        Ok(())
+    }
+
+    /// Save new events together with the aggregate snapshot taken
+    /// after them
+    async fn save_events_and_snapshot(
+        &mut self,
+        contexts: &Vec<EventContext<C, E>>,
+        snapshot: Option<&AggregateContext<C, E, A>>,
+    ) -> Result<(), Error> {
+        telemetry::observe(
+            self.component,
+            "save_events_and_snapshot",
+            aggregate_id_of(contexts),
+            self.store
+                .save_events_and_snapshot(contexts, snapshot),
+        )
+        .await?;
+
+        telemetry::record_events_written(self.component, contexts);
+
+        Ok(())
+    }
+
+    /// Load all events for a particular `aggregate_id`
+    async fn load_events(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        telemetry::observe(
+            self.component,
+            "load_events",
+            aggregate_id,
+            self.store.load_events(aggregate_id),
+        )
+        .await
+    }
+
+    /// Load the events of `aggregate_id` with a sequence greater than
+    /// `sequence`
+    async fn load_events_after(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        telemetry::observe(
+            self.component,
+            "load_events_after",
+            aggregate_id,
+            self.store
+                .load_events_after(aggregate_id, sequence),
+        )
+        .await
+    }
+
+    /// Load the sequence of the last event of `aggregate_id`, 0 if it
+    /// has none
+    async fn load_latest_sequence(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<i64, Error> {
+        telemetry::observe(
+            self.component,
+            "load_latest_sequence",
+            aggregate_id,
+            self.store
+                .load_latest_sequence(aggregate_id),
+        )
+        .await
+    }
+
+    /// save a new aggregate snapshot
+    async fn save_aggregate_snapshot(
+        &mut self,
+        context: AggregateContext<C, E, A>,
+    ) -> Result<(), Error> {
+        let aggregate_id = context.aggregate_id.clone();
+
+        telemetry::observe(
+            self.component,
+            "save_aggregate_snapshot",
+            &aggregate_id,
+            self.store
+                .save_aggregate_snapshot(context),
+        )
+        .await
+    }
+
+    /// Load aggregate at current state from snapshots
+    async fn load_aggregate_from_snapshot(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<AggregateContext<C, E, A>, Error> {
+        telemetry::observe(
+            self.component,
+            "load_aggregate_from_snapshot",
+            aggregate_id,
+            self.store
+                .load_aggregate_from_snapshot(aggregate_id),
+        )
+        .await
+    }
+
+    /// List up to `limit` ids of the aggregates having events in
+    /// ascending order, starting after the id `after` when given
+    async fn list_aggregate_ids(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<String>, Error> {
+        telemetry::observe(
+            self.component,
+            "list_aggregate_ids",
+            "",
+            self.store
+                .list_aggregate_ids(after, limit),
+        )
+        .await
+    }
+
+    /// Count the aggregates having events
+    async fn count_aggregates(&mut self) -> Result<i64, Error> {
+        telemetry::observe(
+            self.component,
+            "count_aggregates",
+            "",
+            self.store.count_aggregates(),
+        )
+        .await
+    }
+
+    /// Delete all events and the snapshot of `aggregate_id`
+    async fn delete_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        telemetry::observe(
+            self.component,
+            "delete_aggregate",
+            aggregate_id,
+            self.store
+                .delete_aggregate(aggregate_id),
+        )
+        .await
+    }
+
+    /// Delete the events of `aggregate_id` with a sequence up to
+    /// `sequence`, keeping its later events and its snapshot
+    async fn delete_events_until(
+        &mut self,
+        aggregate_id: &str,
+        sequence: i64,
+    ) -> Result<(), Error> {
+        telemetry::observe(
+            self.component,
+            "delete_events_until",
+            aggregate_id,
+            self.store
+                .delete_events_until(aggregate_id, sequence),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IFilteredEventStore<C, E, A>,
+    > IFilteredEventStore<C, E, A>
+    for InstrumentedEventStore<C, E, A, ES>
+{
+    /// Load the events matching the `filter` ordered by commit
+    /// time, aggregate id and sequence
+    async fn filter_events(
+        &mut self,
+        filter: &EventFilter,
+    ) -> Result<Vec<EventContext<C, E>>, Error> {
+        telemetry::observe(
+            self.component,
+            "filter_events",
+            "",
+            self.store.filter_events(filter),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        ES: IEventStore<C, E, A> + IHealthCheck,
+    > IHealthCheck for InstrumentedEventStore<C, E, A, ES>
+{
+    /// Check the health of the wrapped store
+    async fn health_check(&mut self) -> HealthStatus {
+        self.store.health_check().await
+    }
+}
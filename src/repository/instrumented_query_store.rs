@@ -0,0 +1,229 @@
+use async_trait::async_trait;
+use log::trace;
+use std::marker::PhantomData;
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    IAggregate,
+    ICommand,
+    IEvent,
+    IQuery,
+    QueryContext,
+};
+
+use super::{
+    health_status::HealthStatus,
+    i_event_dispatcher::IEventDispatcher,
+    i_filtered_query_store::IFilteredQueryStore,
+    i_health_check::IHealthCheck,
+    i_query_store::IQueryStore,
+    query_filter::QueryFilter,
+    telemetry,
+};
+
+/// Async query store recording a `tracing` span, the duration and the
+/// failures of every operation of the wrapped store under the
+/// `component` name
+pub struct InstrumentedQueryStore<
+    C: ICommand,
+    E: IEvent,
+    A: IAggregate<C, E>,
+    Q: IQuery<C, E>,
+    QS: IQueryStore<C, E, A, Q>,
+> {
+    store: QS,
+    component: &'static str,
+    _phantom: PhantomData<(C, E, A, Q)>,
+}
+
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+        QS: IQueryStore<C, E, A, Q>,
+    > InstrumentedQueryStore<C, E, A, Q, QS>
+{
+    /// Constructor, `component` labels the spans and metrics, e.g.
+    /// `"postgres_query_store"`
+    pub fn new(
+        store: QS,
+        component: &'static str,
+    ) -> Self {
+        let x = Self {
+            store,
+            component,
+            _phantom: PhantomData,
+        };
+
+        trace!("Created new async instrumented query store");
+
+        x
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+        QS: IQueryStore<C, E, A, Q>,
+    > IQueryStore<C, E, A, Q>
+    for InstrumentedQueryStore<C, E, A, Q, QS>
+{
+    /// saves the updated query
+    async fn save_query(
+        &mut self,
+        context: QueryContext<C, E, Q>,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        let aggregate_id = context.aggregate_id.clone();
+
+        telemetry::observe(
+            self.component,
+            "save_query",
+            &aggregate_id,
+            self.store
+                .save_query(context, expected_version),
+        )
+        .await
+    }
+
+    /// loads the most recent query
+    async fn load_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<QueryContext<C, E, Q>, Error> {
+        telemetry::observe(
+            self.component,
+            "load_query",
+            aggregate_id,
+            self.store.load_query(aggregate_id),
+        )
+        .await
+    }
+
+    /// loads the most recent queries of several aggregates
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        telemetry::observe(
+            self.component,
+            "load_queries",
+            "",
+            self.store.load_queries(aggregate_ids),
+        )
+        .await
+    }
+
+    /// loads up to `limit` queries in ascending order of aggregate
+    /// id, starting after the id `after` when given
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        telemetry::observe(
+            self.component,
+            "load_all_queries",
+            "",
+            self.store
+                .load_all_queries(after, limit),
+        )
+        .await
+    }
+
+    /// deletes the query of `aggregate_id`
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        telemetry::observe(
+            self.component,
+            "delete_query",
+            aggregate_id,
+            self.store.delete_query(aggregate_id),
+        )
+        .await
+    }
+
+    /// dispatches the events with the wrapped store, keeping its own
+    /// dispatching logic
+    async fn dispatch_events(
+        &mut self,
+        aggregate_id: &str,
+        events: &[EventContext<C, E>],
+    ) -> Result<(), Error> {
+        telemetry::observe(
+            self.component,
+            "dispatch_events",
+            aggregate_id,
+            self.store
+                .dispatch_events(aggregate_id, events),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+        QS: IQueryStore<C, E, A, Q>,
+    > IEventDispatcher<C, E>
+    for InstrumentedQueryStore<C, E, A, Q, QS>
+{
+    async fn dispatch(
+        &mut self,
+        aggregate_id: &str,
+        events: &Vec<EventContext<C, E>>,
+    ) -> Result<(), Error> {
+        self.dispatch_events(aggregate_id, events)
+            .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+        QS: IFilteredQueryStore<C, E, A, Q>,
+    > IFilteredQueryStore<C, E, A, Q>
+    for InstrumentedQueryStore<C, E, A, Q, QS>
+{
+    /// Load the queries matching the `filter` from the store
+    async fn filter_queries(
+        &mut self,
+        filter: &QueryFilter,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        telemetry::observe(
+            self.component,
+            "filter_queries",
+            "",
+            self.store.filter_queries(filter),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<
+        C: ICommand,
+        E: IEvent,
+        A: IAggregate<C, E>,
+        Q: IQuery<C, E>,
+        QS: IQueryStore<C, E, A, Q> + IHealthCheck,
+    > IHealthCheck for InstrumentedQueryStore<C, E, A, Q, QS>
+{
+    /// Check the health of the wrapped store
+    async fn health_check(&mut self) -> HealthStatus {
+        self.store.health_check().await
+    }
+}
@@ -1,16 +1,115 @@
+pub use aggregate_cache::AggregateCache;
+pub use applied_sequence::AppliedSequence;
+pub use archive_marker::ArchiveMarker;
+pub use cache_state::CacheState;
 pub use cached_event_store::CachedEventStore;
 pub use cached_query_store::CachedQueryStore;
+pub use dead_letter::DeadLetter;
+pub use dead_letter_dispatcher::DeadLetterDispatcher;
+pub use dispatch_policy::DispatchPolicy;
+pub use dispatch_queue::DispatchQueue;
+pub use dispatch_report::{
+    DispatchFailure,
+    DispatchReport,
+};
+pub use event_filter::EventFilter;
+pub use event_route::EventRoute;
+pub use health_status::HealthStatus;
+pub use i_admin_store::IAdminStore;
+pub use i_archive_marker_store::IArchiveMarkerStore;
+pub use i_dead_letter_store::IDeadLetterStore;
 pub use i_event_dispatcher::IEventDispatcher;
 pub use i_event_store::IEventStore;
+pub use i_filtered_event_store::IFilteredEventStore;
+pub use i_filtered_query_store::IFilteredQueryStore;
+pub use i_health_check::IHealthCheck;
+pub use i_key_provider::IKeyProvider;
+pub use i_key_store::IKeyStore;
+pub use i_payload_transformer::IPayloadTransformer;
+pub use i_projection::IProjection;
+pub use i_projection_store::IProjectionStore;
 pub use i_query_store::IQueryStore;
+pub use instrumented_event_store::InstrumentedEventStore;
+pub use instrumented_query_store::InstrumentedQueryStore;
+pub use projection_context::ProjectionContext;
+pub use query_conflict::{
+    is_query_conflict,
+    projection_conflict,
+    query_conflict,
+    sequence_conflict,
+};
+pub use query_filter::{
+    Comparison,
+    FieldCondition,
+    QueryFilter,
+    SortOrder,
+};
 pub use repository::Repository;
+pub use transformer_chain::TransformerChain;
 
+#[cfg(any(
+  //feature = "with-mssql",
+  feature = "with-mysql",
+  feature = "with-postgres",
+  feature = "with-sqlite",
+  feature = "with-mongodb",
+))]
+pub(crate) use event_type::event_type_of;
+#[cfg(any(
+  //feature = "with-mssql",
+  feature = "with-mysql",
+  feature = "with-postgres",
+  feature = "with-sqlite",
+  feature = "with-mongodb",
+  feature = "with-redis",
+  feature = "with-archive",
+))]
+pub(crate) use payload_codec::PayloadCodec;
+pub(crate) use raw_record::{
+    event_record,
+    query_record,
+    snapshot_record,
+};
+
+mod aggregate_cache;
+mod applied_sequence;
+mod archive_marker;
+mod cache_state;
 mod cached_event_store;
 mod cached_query_store;
+mod dead_letter;
+mod dead_letter_dispatcher;
+mod dispatch_policy;
+mod dispatch_queue;
+mod dispatch_report;
+mod event_filter;
+mod event_route;
+mod event_type;
+mod health_status;
+mod i_admin_store;
+mod i_archive_marker_store;
+mod i_dead_letter_store;
 mod i_event_dispatcher;
 mod i_event_store;
+mod i_filtered_event_store;
+mod i_filtered_query_store;
+mod i_health_check;
+mod i_key_provider;
+mod i_key_store;
+mod i_payload_transformer;
+mod i_projection;
+mod i_projection_store;
 mod i_query_store;
+mod instrumented_event_store;
+mod instrumented_query_store;
+mod payload_codec;
+mod projection_context;
+mod query_conflict;
+mod query_filter;
+mod raw_record;
 mod repository;
+mod telemetry;
+mod transformer_chain;
 
 #[cfg(test)]
-mod test;
+pub(crate) mod test;
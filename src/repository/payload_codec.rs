@@ -0,0 +1,150 @@
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+use std::sync::Arc;
+
+use super::i_payload_transformer::IPayloadTransformer;
+
+/// Serializes the payloads through the optional transformer of a
+/// store
+#[derive(Clone, Default)]
+pub(crate) struct PayloadCodec {
+    transformer: Option<Arc<dyn IPayloadTransformer>>,
+}
+
+impl PayloadCodec {
+    pub fn new(transformer: Arc<dyn IPayloadTransformer>) -> Self {
+        Self {
+            transformer: Some(transformer),
+        }
+    }
+
+    /// Whether the payloads are stored as serialized, without a
+    /// transformer
+    #[cfg(any(
+        feature = "with-mysql",
+        feature = "with-postgres",
+        feature = "with-sqlite",
+        feature = "with-mongodb"
+    ))]
+    pub fn is_identity(&self) -> bool {
+        self.transformer.is_none()
+    }
+
+    /// Transforms an already serialized payload
+    pub async fn encode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payload: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        match &self.transformer {
+            Some(x) => {
+                match x
+                    .encode(aggregate_type, aggregate_id, payload)
+                    .await
+                {
+                    Ok(x) => Ok(x),
+                    Err(e) => Err(e.to_string()),
+                }
+            },
+            None => Ok(payload),
+        }
+    }
+
+    /// Serializes and transforms a payload
+    pub async fn encode_value<T: Serialize + Sync>(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payload: &T,
+    ) -> Result<serde_json::Value, String> {
+        let payload = match serde_json::to_value(payload) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(e.to_string());
+            },
+        };
+
+        self.encode(aggregate_type, aggregate_id, payload)
+            .await
+    }
+
+    /// Restores and deserializes a stored payload
+    #[cfg(any(
+        feature = "with-mysql",
+        feature = "with-postgres",
+        feature = "with-sqlite",
+        feature = "with-mongodb",
+        feature = "with-redis",
+        feature = "with-archive"
+    ))]
+    pub async fn decode_value<T: DeserializeOwned>(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payload: serde_json::Value,
+    ) -> Result<T, String> {
+        let payload = match &self.transformer {
+            Some(x) => {
+                match x
+                    .decode(aggregate_type, aggregate_id, payload)
+                    .await
+                {
+                    Ok(x) => x,
+                    Err(e) => {
+                        return Err(e.to_string());
+                    },
+                }
+            },
+            None => payload,
+        };
+
+        match serde_json::from_value(payload) {
+            Ok(x) => Ok(x),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Restores and deserializes the stored payloads of an aggregate
+    /// loaded together
+    pub async fn decode_values<T: DeserializeOwned>(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        payloads: Vec<serde_json::Value>,
+    ) -> Result<Vec<T>, String> {
+        let payloads = match &self.transformer {
+            Some(x) => {
+                match x
+                    .decode_all(
+                        aggregate_type,
+                        aggregate_id,
+                        payloads,
+                    )
+                    .await
+                {
+                    Ok(x) => x,
+                    Err(e) => {
+                        return Err(e.to_string());
+                    },
+                }
+            },
+            None => payloads,
+        };
+
+        let mut result = Vec::with_capacity(payloads.len());
+
+        for payload in payloads {
+            match serde_json::from_value(payload) {
+                Ok(x) => result.push(x),
+                Err(e) => {
+                    return Err(e.to_string());
+                },
+            }
+        }
+
+        Ok(result)
+    }
+}
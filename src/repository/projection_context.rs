@@ -0,0 +1,25 @@
+/// A row of a projection with its key and version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectionContext<P> {
+    /// key of the row
+    pub key: String,
+    /// number of times the row was saved, 0 when it is not stored
+    pub version: i64,
+    /// the row itself
+    pub payload: P,
+}
+
+impl<P> ProjectionContext<P> {
+    /// Constructor
+    pub fn new(
+        key: String,
+        version: i64,
+        payload: P,
+    ) -> Self {
+        Self {
+            key,
+            version,
+            payload,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use cqrs_es2::{
+    Error,
+    UserError,
+};
+
+static QUERY_CONFLICT: &str = "query_version_conflict";
+
+fn conflict(message: String) -> Error {
+    Error::UserError(UserError {
+        code: Some(QUERY_CONFLICT.to_string()),
+        message: Some(message),
+        params: None,
+    })
+}
+
+/// The error returned by `save_query` when the stored query is not at
+/// the expected version, another writer saved it meanwhile
+pub fn query_conflict(
+    query_type: &str,
+    aggregate_id: &str,
+    expected_version: i64,
+) -> Error {
+    conflict(format!(
+        "query version conflict for query '{}' with aggregate id \
+         '{}', expected version '{}'",
+        query_type, aggregate_id, expected_version
+    ))
+}
+
+/// The error returned by `save_projection` when the stored row is not
+/// at the expected version
+pub fn projection_conflict(
+    projection_type: &str,
+    key: &str,
+    expected_version: i64,
+) -> Error {
+    conflict(format!(
+        "query version conflict for projection '{}' with key '{}', \
+         expected version '{}'",
+        projection_type, key, expected_version
+    ))
+}
+
+/// The error returned by `save_projections` when the sequence
+/// applied to a projection is not the expected one
+pub fn sequence_conflict(
+    projection_type: &str,
+    aggregate_id: &str,
+    expected_sequence: i64,
+) -> Error {
+    conflict(format!(
+        "query version conflict for projection '{}' with aggregate \
+         id '{}', expected sequence '{}'",
+        projection_type, aggregate_id, expected_sequence
+    ))
+}
+
+/// Whether `error` was returned by `save_query` or `save_projection`
+/// for a query or a row not at the expected version, or by
+/// `save_projections` for a sequence not at the expected one
+pub fn is_query_conflict(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::UserError(UserError {
+            code: Some(x),
+            ..
+        }) if x == QUERY_CONFLICT
+    )
+}
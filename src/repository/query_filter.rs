@@ -0,0 +1,306 @@
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+use cqrs_es2::{
+    Error,
+    ICommand,
+    IEvent,
+    IQuery,
+    QueryContext,
+};
+
+/// Comparison applied by a `FieldCondition`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    /// equal to
+    Eq,
+    /// greater than
+    Gt,
+    /// greater than or equal to
+    Gte,
+    /// less than
+    Lt,
+    /// less than or equal to
+    Lte,
+}
+
+/// Direction of a sort on a query field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    /// smallest values first
+    Ascending,
+    /// largest values first
+    Descending,
+}
+
+/// A condition on one field of the query payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldCondition {
+    /// dot separated path of the field in the query payload
+    pub field: String,
+    /// comparison between the field and the value
+    pub comparison: Comparison,
+    /// value compared to the field
+    pub value: Value,
+}
+
+/// Criteria for searching the queries of one query type by the
+/// fields of their payloads.
+///
+/// Fields are addressed by their dot separated path in the JSON
+/// serialization of the query, e.g. `address.city`. All the
+/// conditions are combined with `AND`. Numbers are compared
+/// numerically and strings lexicographically.
+///
+/// # Example
+///
+/// ```rust
+/// use tokio_cqrs_es2_store::{
+///     QueryFilter,
+///     SortOrder,
+/// };
+///
+/// let filter = QueryFilter::new()
+///     .with_gt("balance", 1000)
+///     .with_eq("currency", "USD")
+///     .with_sort("balance", SortOrder::Descending)
+///     .with_page(0, 20);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryFilter {
+    /// conditions that must all match
+    pub conditions: Vec<FieldCondition>,
+    /// fields to sort by, ties are ordered by aggregate id
+    pub sort: Vec<(String, SortOrder)>,
+    /// number of matching queries to skip
+    pub offset: i64,
+    /// maximum number of queries to return
+    pub limit: Option<i64>,
+}
+
+impl QueryFilter {
+    /// Constructor of an empty filter matching all the queries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a condition on a payload field
+    pub fn with_condition(
+        mut self,
+        field: &str,
+        comparison: Comparison,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.conditions.push(FieldCondition {
+            field: field.to_string(),
+            comparison,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Restricts the results to queries where `field` equals `value`
+    pub fn with_eq(
+        self,
+        field: &str,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.with_condition(field, Comparison::Eq, value)
+    }
+
+    /// Restricts the results to queries where `field` is greater
+    /// than `value`
+    pub fn with_gt(
+        self,
+        field: &str,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.with_condition(field, Comparison::Gt, value)
+    }
+
+    /// Restricts the results to queries where `field` is greater
+    /// than or equal to `value`
+    pub fn with_gte(
+        self,
+        field: &str,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.with_condition(field, Comparison::Gte, value)
+    }
+
+    /// Restricts the results to queries where `field` is less than
+    /// `value`
+    pub fn with_lt(
+        self,
+        field: &str,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.with_condition(field, Comparison::Lt, value)
+    }
+
+    /// Restricts the results to queries where `field` is less than
+    /// or equal to `value`
+    pub fn with_lte(
+        self,
+        field: &str,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.with_condition(field, Comparison::Lte, value)
+    }
+
+    /// Sorts the results by a payload field, may be called several
+    /// times
+    pub fn with_sort(
+        mut self,
+        field: &str,
+        order: SortOrder,
+    ) -> Self {
+        self.sort
+            .push((field.to_string(), order));
+        self
+    }
+
+    /// Selects a page of the results
+    pub fn with_page(
+        mut self,
+        offset: i64,
+        limit: i64,
+    ) -> Self {
+        self.offset = offset;
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Filters, sorts and pages queries in process, for the stores
+    /// without native support
+    pub(crate) fn apply<C: ICommand, E: IEvent, Q: IQuery<C, E>>(
+        &self,
+        contexts: Vec<QueryContext<C, E, Q>>,
+    ) -> Result<Vec<QueryContext<C, E, Q>>, Error> {
+        let mut rows = Vec::new();
+
+        for context in contexts {
+            let payload = match serde_json::to_value(&context.payload)
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    return Err(Error::new(
+                        format!(
+                            "unable to serialize the payload of \
+                             query with aggregate id '{}', error: {}",
+                            &context.aggregate_id, e,
+                        )
+                        .as_str(),
+                    ));
+                },
+            };
+
+            if self.matches(&payload) {
+                rows.push((payload, context));
+            }
+        }
+
+        rows.sort_by(|a, b| {
+            self.compare(&a.0, &b.0)
+                .then_with(|| a.1.aggregate_id.cmp(&b.1.aggregate_id))
+        });
+
+        Ok(rows
+            .into_iter()
+            .map(|x| x.1)
+            .skip(self.offset.max(0) as usize)
+            .take(
+                self.limit
+                    .map_or(usize::MAX, |x| x.max(0) as usize),
+            )
+            .collect())
+    }
+
+    /// Checks a serialized query payload against the conditions
+    fn matches(
+        &self,
+        payload: &Value,
+    ) -> bool {
+        self.conditions.iter().all(|x| {
+            let field = match field_value(payload, &x.field) {
+                Some(v) => v,
+                None => {
+                    return false;
+                },
+            };
+
+            match compare_values(field, &x.value) {
+                None => false,
+                Some(o) => {
+                    match x.comparison {
+                        Comparison::Eq => o == Ordering::Equal,
+                        Comparison::Gt => o == Ordering::Greater,
+                        Comparison::Gte => o != Ordering::Less,
+                        Comparison::Lt => o == Ordering::Less,
+                        Comparison::Lte => o != Ordering::Greater,
+                    }
+                },
+            }
+        })
+    }
+
+    /// Orders two serialized query payloads by the sort fields
+    fn compare(
+        &self,
+        a: &Value,
+        b: &Value,
+    ) -> Ordering {
+        for (field, order) in &self.sort {
+            let o = match (
+                field_value(a, field),
+                field_value(b, field),
+            ) {
+                (Some(x), Some(y)) => {
+                    compare_values(x, y).unwrap_or(Ordering::Equal)
+                },
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => Ordering::Equal,
+            };
+
+            let o = match order {
+                SortOrder::Ascending => o,
+                SortOrder::Descending => o.reverse(),
+            };
+
+            if o != Ordering::Equal {
+                return o;
+            }
+        }
+
+        Ordering::Equal
+    }
+}
+
+/// Finds the value of a dot separated field path
+fn field_value<'a>(
+    payload: &'a Value,
+    field: &str,
+) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(payload, |v, key| v.get(key))
+}
+
+/// Compares two JSON values of the same kind
+fn compare_values(
+    a: &Value,
+    b: &Value,
+) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            x.as_f64()?.partial_cmp(&y.as_f64()?)
+        },
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (x, y) if x == y => Some(Ordering::Equal),
+        _ => None,
+    }
+}
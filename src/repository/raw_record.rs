@@ -0,0 +1,56 @@
+use serde_json::{
+    json,
+    Value,
+};
+
+/// The NDJSON record of an event
+pub fn event_record(
+    aggregate_type: &str,
+    aggregate_id: &str,
+    sequence: i64,
+    payload: Value,
+    metadata: Value,
+) -> Value {
+    json!({
+        "kind": "event",
+        "aggregate_type": aggregate_type,
+        "aggregate_id": aggregate_id,
+        "sequence": sequence,
+        "payload": payload,
+        "metadata": metadata,
+    })
+}
+
+/// The NDJSON record of a snapshot
+pub fn snapshot_record(
+    aggregate_type: &str,
+    aggregate_id: &str,
+    version: i64,
+    payload: Value,
+) -> Value {
+    json!({
+        "kind": "snapshot",
+        "aggregate_type": aggregate_type,
+        "aggregate_id": aggregate_id,
+        "version": version,
+        "payload": payload,
+    })
+}
+
+/// The NDJSON record of a query
+pub fn query_record(
+    aggregate_type: &str,
+    aggregate_id: &str,
+    query_type: &str,
+    version: i64,
+    payload: Value,
+) -> Value {
+    json!({
+        "kind": "query",
+        "aggregate_type": aggregate_type,
+        "aggregate_id": aggregate_id,
+        "query_type": query_type,
+        "version": version,
+        "payload": payload,
+    })
+}
@@ -1,3 +1,7 @@
+use futures::future::{
+    join_all,
+    try_join_all,
+};
 use log::{
     debug,
     error,
@@ -6,6 +10,7 @@ use log::{
 use std::{
     collections::HashMap,
     marker::PhantomData,
+    time::Duration,
 };
 
 use cqrs_es2::{
@@ -18,8 +23,17 @@ use cqrs_es2::{
 };
 
 use super::{
+    aggregate_cache::AggregateCache,
+    dispatch_policy::DispatchPolicy,
+    dispatch_queue::DispatchQueue,
+    dispatch_report::{
+        DispatchFailure,
+        DispatchReport,
+    },
+    event_route::EventRoute,
     i_event_dispatcher::IEventDispatcher,
     i_event_store::IEventStore,
+    telemetry,
 };
 
 /// This is the base framework for applying commands to produce
@@ -42,8 +56,14 @@ pub struct Repository<
     ES: IEventStore<C, E, A>,
 > {
     store: ES,
-    dispatchers: Vec<Box<dyn IEventDispatcher<C, E>>>,
+    dispatchers: Vec<(String, Box<dyn IEventDispatcher<C, E>>)>,
     with_snapshots: bool,
+    cache: Option<AggregateCache<C, E, A>>,
+    dispatch_policy: DispatchPolicy,
+    dispatch_timeout: Option<Duration>,
+    dispatcher_timeouts: HashMap<String, Duration>,
+    routes: HashMap<String, EventRoute<C, E>>,
+    queue: Option<DispatchQueue<C, E>>,
     _phantom: PhantomData<A>,
 }
 
@@ -56,6 +76,10 @@ impl<
 {
     /// Creates new framework for dispatching commands using the
     /// provided elements.
+    ///
+    /// The `dispatchers` are identified by their position, e.g. `"0"`
+    /// for the first one, use `with_dispatcher` to give them ids
+    /// that do not change when dispatchers are added or removed.
     pub fn new(
         store: ES,
         dispatchers: Vec<Box<dyn IEventDispatcher<C, E>>>,
@@ -63,8 +87,18 @@ impl<
     ) -> Self {
         let x = Self {
             store,
-            dispatchers,
+            dispatchers: dispatchers
+                .into_iter()
+                .enumerate()
+                .map(|(i, x)| (i.to_string(), x))
+                .collect(),
             with_snapshots,
+            cache: None,
+            dispatch_policy: DispatchPolicy::default(),
+            dispatch_timeout: None,
+            dispatcher_timeouts: HashMap::new(),
+            routes: HashMap::new(),
+            queue: None,
             _phantom: PhantomData,
         };
 
@@ -73,6 +107,86 @@ impl<
         x
     }
 
+    /// Keep the hydrated aggregates in `cache`, a cached aggregate is
+    /// used once its version was checked against the latest stored
+    /// sequence, replaying only the newer events when it is behind
+    pub fn with_cache(
+        mut self,
+        cache: AggregateCache<C, E, A>,
+    ) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Handle the dispatchers failures with `policy`, `FailFast` by
+    /// default
+    pub fn with_dispatch_policy(
+        mut self,
+        policy: DispatchPolicy,
+    ) -> Self {
+        self.dispatch_policy = policy;
+        self
+    }
+
+    /// Fail the dispatchers not done `timeout` after they were given
+    /// the events
+    pub fn with_dispatch_timeout(
+        mut self,
+        timeout: Duration,
+    ) -> Self {
+        self.dispatch_timeout = Some(timeout);
+        self
+    }
+
+    /// Add `dispatcher` identified by `id` in its timeout, its route
+    /// and its failures
+    pub fn with_dispatcher(
+        mut self,
+        id: &str,
+        dispatcher: Box<dyn IEventDispatcher<C, E>>,
+    ) -> Self {
+        self.dispatchers
+            .push((id.to_string(), dispatcher));
+        self
+    }
+
+    /// Fail the dispatcher identified by `dispatcher` when not done
+    /// `timeout` after it was given the events, overriding the
+    /// timeout of all the dispatchers
+    pub fn with_dispatcher_timeout(
+        mut self,
+        dispatcher: &str,
+        timeout: Duration,
+    ) -> Self {
+        self.dispatcher_timeouts
+            .insert(dispatcher.to_string(), timeout);
+        self
+    }
+
+    /// Give the dispatcher identified by `dispatcher` only the
+    /// committed events matching `route`, it is not invoked when none
+    /// matches. The dispatchers of a `DispatchQueue` are routed with
+    /// its own `with_route`.
+    pub fn with_dispatcher_route(
+        mut self,
+        dispatcher: &str,
+        route: EventRoute<C, E>,
+    ) -> Self {
+        self.routes
+            .insert(dispatcher.to_string(), route);
+        self
+    }
+
+    /// Push the committed events to `queue` to be dispatched in the
+    /// background, after the dispatchers of the `Repository` ran
+    pub fn with_dispatch_queue(
+        mut self,
+        queue: DispatchQueue<C, E>,
+    ) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
     /// This applies a command to an aggregate. Executing a command
     /// in this way is the only way to make any change to
     /// the state of an aggregate.
@@ -81,7 +195,8 @@ impl<
     /// and an Error being returned.
     ///
     /// If successful the events produced will be applied to the
-    /// configured `QueryProcessor`s.
+    /// configured `QueryProcessor`s and their failures returned in
+    /// the `DispatchReport`.
     ///
     /// # Error
     /// If an error is generated while processing the command this
@@ -90,7 +205,7 @@ impl<
         &mut self,
         aggregate_id: &str,
         command: C,
-    ) -> Result<(), Error> {
+    ) -> Result<DispatchReport, Error> {
         self.execute_with_metadata(
             aggregate_id,
             command,
@@ -116,13 +231,33 @@ impl<
     /// and an Error being returned.
     ///
     /// If successful the events produced will be applied to the
-    /// configured `QueryProcessor`s.
+    /// configured `QueryProcessor`s, their failures are handled
+    /// according to the `DispatchPolicy` and returned in the
+    /// `DispatchReport`, the events being committed anyway.
+    ///
+    /// With the `with-tracing` feature the command runs in a span
+    /// enclosing the ones of the store and dispatchers calls.
     pub async fn execute_with_metadata(
         &mut self,
         aggregate_id: &str,
         command: C,
         metadata: HashMap<String, String>,
-    ) -> Result<(), Error> {
+    ) -> Result<DispatchReport, Error> {
+        telemetry::observe(
+            "repository",
+            "execute",
+            aggregate_id,
+            self.apply_command(aggregate_id, command, metadata),
+        )
+        .await
+    }
+
+    async fn apply_command(
+        &mut self,
+        aggregate_id: &str,
+        command: C,
+        metadata: HashMap<String, String>,
+    ) -> Result<DispatchReport, Error> {
         trace!(
             "Applying command '{:?}' to aggregate '{}' with \
              metadata '{:?}'",
@@ -162,7 +297,7 @@ impl<
         };
 
         if events.len() == 0 {
-            return Ok(());
+            return Ok(DispatchReport::default());
         }
 
         let event_contexts = match self
@@ -179,34 +314,127 @@ impl<
             },
         };
 
-        for x in &mut self.dispatchers {
-            match x
-                .dispatch(&aggregate_id, &event_contexts)
+        let mut report = self
+            .dispatch(aggregate_id, &event_contexts)
+            .await;
+
+        if let Some(x) = &self.queue {
+            if let Err(mut x) = x
+                .push(aggregate_id, &event_contexts)
                 .await
             {
-                Ok(_) => {},
-                Err(e) => {
-                    error!(
-                        "dispatcher returned error '{}'",
-                        e.to_string()
-                    );
-                    return Err(e);
-                },
+                report.failures.append(&mut x);
             }
         }
 
+        for x in &report.failures {
+            error!(
+                "{} returned error '{}'",
+                x.name(),
+                x.error
+            );
+        }
+
+        if self.dispatch_policy == DispatchPolicy::Ignore {
+            report.failures.clear();
+        }
+
         debug!(
             "Successfully applied command '{:?}' to aggregate '{}'",
             &command, &aggregate_id
         );
 
-        Ok(())
+        Ok(report)
+    }
+
+    /// Run the dispatchers concurrently on the committed events
+    /// matching their routes
+    async fn dispatch(
+        &mut self,
+        aggregate_id: &str,
+        events: &Vec<EventContext<C, E>>,
+    ) -> DispatchReport {
+        let timeouts: Vec<Option<Duration>> = self
+            .dispatchers
+            .iter()
+            .map(|(id, _)| {
+                self.dispatcher_timeouts
+                    .get(id)
+                    .copied()
+                    .or(self.dispatch_timeout)
+            })
+            .collect();
+
+        let routed: Vec<Option<Vec<EventContext<C, E>>>> = self
+            .dispatchers
+            .iter()
+            .map(|(id, _)| {
+                self.routes
+                    .get(id)
+                    .map(|route| route.filter(events))
+            })
+            .collect();
+
+        let dispatches = self
+            .dispatchers
+            .iter_mut()
+            .zip(timeouts)
+            .zip(&routed)
+            .filter(|(_, routed)| {
+                !matches!(routed, Some(x) if x.is_empty())
+            })
+            .map(|(((id, x), timeout), routed)| {
+                async move {
+                    dispatch_with_timeout(
+                        x.as_mut(),
+                        aggregate_id,
+                        routed.as_ref().unwrap_or(events),
+                        timeout,
+                    )
+                    .await
+                    .map_err(|e| {
+                        DispatchFailure {
+                            dispatcher: Some(id.clone()),
+                            queued: false,
+                            error: e,
+                        }
+                    })
+                }
+            });
+
+        let failures = match self.dispatch_policy {
+            DispatchPolicy::FailFast => {
+                match try_join_all(dispatches).await {
+                    Ok(_) => Vec::new(),
+                    Err(x) => vec![x],
+                }
+            },
+            _ => {
+                join_all(dispatches)
+                    .await
+                    .into_iter()
+                    .filter_map(Result::err)
+                    .collect()
+            },
+        };
+
+        DispatchReport {
+            events: events.len(),
+            failures,
+        }
     }
 
     async fn load_aggregate(
         &mut self,
         aggregate_id: &str,
     ) -> Result<AggregateContext<C, E, A>, Error> {
+        if let Some(x) = self
+            .load_cached_aggregate(aggregate_id)
+            .await?
+        {
+            return Ok(x);
+        }
+
         match self.with_snapshots {
             true => {
                 self.store
@@ -220,6 +448,74 @@ impl<
         }
     }
 
+    /// Load the cached aggregate brought up to date with the events
+    /// stored after it, `None` if it is not cached or stale
+    async fn load_cached_aggregate(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<Option<AggregateContext<C, E, A>>, Error> {
+        // without a cache there is neither a hit nor a miss to record
+        let cache = match &self.cache {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let cached = match cache.get(aggregate_id) {
+            Some(x) => x,
+            None => {
+                telemetry::record_cache("aggregate_cache", 0, 1);
+                return Ok(None);
+            },
+        };
+
+        let sequence = self
+            .store
+            .load_latest_sequence(aggregate_id)
+            .await?;
+
+        if sequence < cached.version {
+            debug!(
+                "cached aggregate id '{}' is ahead of the store",
+                aggregate_id
+            );
+
+            telemetry::record_cache("aggregate_cache", 0, 1);
+
+            if let Some(x) = &self.cache {
+                x.invalidate(aggregate_id);
+            }
+
+            return Ok(None);
+        }
+
+        telemetry::record_cache("aggregate_cache", 1, 0);
+
+        if sequence == cached.version {
+            return Ok(Some(cached));
+        }
+
+        trace!(
+            "replaying events after sequence '{}' on cached \
+             aggregate id '{}'",
+            cached.version,
+            aggregate_id
+        );
+
+        let contexts = self
+            .store
+            .load_events_after(aggregate_id, cached.version)
+            .await?;
+
+        let mut context = cached;
+
+        for x in contexts {
+            context.payload.apply(&x.payload);
+            context.version = x.sequence;
+        }
+
+        Ok(Some(context))
+    }
+
     async fn save_events(
         &mut self,
         events: Vec<E>,
@@ -235,43 +531,54 @@ impl<
             metadata,
         );
 
-        match self.store.save_events(&contexts).await {
+        let aggregate =
+            match self.with_snapshots || self.cache.is_some() {
+                true => {
+                    let mut aggregate = stored_context.payload;
+
+                    contexts
+                        .iter()
+                        .map(|x| &x.payload)
+                        .for_each(|x| aggregate.apply(x));
+
+                    Some(AggregateContext::new(
+                        aggregate_id.clone(),
+                        contexts.last().unwrap().sequence,
+                        aggregate,
+                    ))
+                },
+                false => None,
+            };
+
+        let snapshot = match self.with_snapshots {
+            true => aggregate.as_ref(),
+            false => None,
+        };
+
+        match self
+            .store
+            .save_events_and_snapshot(&contexts, snapshot)
+            .await
+        {
             Ok(_) => {},
             Err(e) => {
                 error!(
-                    "save events returned error '{}'",
+                    "save events and snapshot returned error '{}'",
                     e.to_string()
                 );
+
+                // the stored events may have moved past the cached
+                // aggregate
+                if let Some(x) = &self.cache {
+                    x.invalidate(&aggregate_id);
+                }
+
                 return Err(e);
             },
         };
 
-        if self.with_snapshots {
-            let mut aggregate = stored_context.payload;
-
-            contexts
-                .iter()
-                .map(|x| &x.payload)
-                .for_each(|x| aggregate.apply(&x));
-
-            match self
-                .store
-                .save_aggregate_snapshot(AggregateContext::new(
-                    aggregate_id,
-                    contexts.last().unwrap().sequence,
-                    aggregate,
-                ))
-                .await
-            {
-                Ok(_) => {},
-                Err(e) => {
-                    error!(
-                        "save aggregate snapshot returned error '{}'",
-                        e.to_string()
-                    );
-                    return Err(e);
-                },
-            };
+        if let (Some(cache), Some(x)) = (&self.cache, aggregate) {
+            cache.put(x);
         }
 
         Ok(contexts)
@@ -336,3 +643,38 @@ impl<
         ))
     }
 }
+
+/// Dispatch `events`, failing when `timeout` elapsed first
+async fn dispatch_with_timeout<C: ICommand, E: IEvent>(
+    dispatcher: &mut dyn IEventDispatcher<C, E>,
+    aggregate_id: &str,
+    events: &Vec<EventContext<C, E>>,
+    timeout: Option<Duration>,
+) -> Result<(), Error> {
+    let dispatch = telemetry::observe(
+        "dispatcher",
+        "dispatch",
+        aggregate_id,
+        dispatcher.dispatch(aggregate_id, events),
+    );
+
+    let timeout = match timeout {
+        Some(x) => x,
+        None => {
+            return dispatch.await;
+        },
+    };
+
+    match tokio::time::timeout(timeout, dispatch).await {
+        Ok(x) => x,
+        Err(_) => {
+            Err(Error::new(
+                format!(
+                    "dispatcher timed out after {:?}",
+                    timeout
+                )
+                .as_str(),
+            ))
+        },
+    }
+}
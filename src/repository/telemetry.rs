@@ -0,0 +1,130 @@
+use std::future::Future;
+
+use cqrs_es2::{
+    Error,
+    EventContext,
+    ICommand,
+    IEvent,
+};
+
+/// Runs the `operation` of `component` on `aggregate_id` inside a
+/// `tracing` span with the `with-tracing` feature, and records its
+/// duration and failures with the `with-metrics` feature
+#[cfg_attr(
+    not(all(
+        feature = "with-tracing",
+        feature = "with-metrics"
+    )),
+    allow(unused_variables)
+)]
+pub(crate) async fn observe<T, F>(
+    component: &'static str,
+    operation: &'static str,
+    aggregate_id: &str,
+    future: F,
+) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>, {
+    #[cfg(feature = "with-metrics")]
+    let start = std::time::Instant::now();
+
+    #[cfg(feature = "with-tracing")]
+    let result = {
+        use tracing::Instrument;
+
+        let span = tracing::info_span!(
+            "cqrs_store",
+            component,
+            operation,
+            aggregate_id
+        );
+
+        future.instrument(span).await
+    };
+
+    #[cfg(not(feature = "with-tracing"))]
+    let result = future.await;
+
+    #[cfg(feature = "with-metrics")]
+    {
+        metrics::histogram!(
+            "cqrs_store_operation_seconds",
+            start.elapsed().as_secs_f64(),
+            "component" => component,
+            "operation" => operation
+        );
+
+        if result.is_err() {
+            metrics::counter!(
+                "cqrs_store_operation_errors_total",
+                1,
+                "component" => component,
+                "operation" => operation
+            );
+        }
+    }
+
+    result
+}
+
+/// Counts the events written by `component` and the bytes of their
+/// serialized payloads
+#[cfg_attr(
+    not(feature = "with-metrics"),
+    allow(unused_variables)
+)]
+pub(crate) fn record_events_written<C: ICommand, E: IEvent>(
+    component: &'static str,
+    contexts: &[EventContext<C, E>],
+) {
+    #[cfg(feature = "with-metrics")]
+    {
+        let bytes: usize = contexts
+            .iter()
+            .filter_map(|x| serde_json::to_vec(&x.payload).ok())
+            .map(|x| x.len())
+            .sum();
+
+        metrics::counter!(
+            "cqrs_store_events_written_total",
+            contexts.len() as u64,
+            "component" => component
+        );
+        metrics::counter!(
+            "cqrs_store_event_bytes_written_total",
+            bytes as u64,
+            "component" => component
+        );
+    }
+}
+
+/// Counts the cache hits and misses of `component`, their ratio is
+/// the cache hit ratio
+#[cfg_attr(
+    not(feature = "with-metrics"),
+    allow(unused_variables)
+)]
+pub(crate) fn record_cache(
+    component: &'static str,
+    hits: usize,
+    misses: usize,
+) {
+    #[cfg(feature = "with-metrics")]
+    {
+        if hits > 0 {
+            metrics::counter!(
+                "cqrs_store_cache_hits_total",
+                hits as u64,
+                "component" => component
+            );
+        }
+
+        if misses > 0 {
+            metrics::counter!(
+                "cqrs_store_cache_misses_total",
+                misses as u64,
+                "component" => component
+            );
+        }
+    }
+}
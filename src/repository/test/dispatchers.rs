@@ -1,7 +1,14 @@
 use async_trait::async_trait;
-use std::sync::{
-    Arc,
-    RwLock,
+use std::{
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        RwLock,
+    },
+    time::Duration,
 };
 
 use cqrs_es2::{
@@ -45,3 +52,96 @@ impl IEventDispatcher<CustomerCommand, CustomerEvent>
         Ok(())
     }
 }
+
+pub struct FailingDispatcher;
+
+#[async_trait]
+impl IEventDispatcher<CustomerCommand, CustomerEvent>
+    for FailingDispatcher
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        _events: &Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    ) -> Result<(), Error> {
+        Err(Error::new("dispatcher is failing"))
+    }
+}
+
+pub struct PanickingDispatcher;
+
+#[async_trait]
+impl IEventDispatcher<CustomerCommand, CustomerEvent>
+    for PanickingDispatcher
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        _events: &Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    ) -> Result<(), Error> {
+        panic!("dispatcher is panicking")
+    }
+}
+
+pub struct ToggleDispatcher {
+    failing: Arc<AtomicBool>,
+    events: Arc<
+        RwLock<Vec<EventContext<CustomerCommand, CustomerEvent>>>,
+    >,
+}
+
+impl ToggleDispatcher {
+    pub fn new(
+        failing: Arc<AtomicBool>,
+        events: Arc<
+            RwLock<Vec<EventContext<CustomerCommand, CustomerEvent>>>,
+        >,
+    ) -> Self {
+        Self { failing, events }
+    }
+}
+
+#[async_trait]
+impl IEventDispatcher<CustomerCommand, CustomerEvent>
+    for ToggleDispatcher
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        events: &Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    ) -> Result<(), Error> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(Error::new("dispatcher is failing"));
+        }
+
+        let mut event_list = self.events.write().unwrap();
+        event_list.extend(events.iter().cloned());
+
+        Ok(())
+    }
+}
+
+pub struct SlowDispatcher {
+    delay: Duration,
+}
+
+impl SlowDispatcher {
+    pub fn new(delay: Duration) -> Self {
+        Self { delay }
+    }
+}
+
+#[async_trait]
+impl IEventDispatcher<CustomerCommand, CustomerEvent>
+    for SlowDispatcher
+{
+    async fn dispatch(
+        &mut self,
+        _aggregate_id: &str,
+        _events: &Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    ) -> Result<(), Error> {
+        tokio::time::sleep(self.delay).await;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,23 @@
 mod dispatchers;
 
+pub(crate) mod projections;
+
+mod stores;
+
+mod test_aggregate_cache;
+
+mod test_cached_store;
+
+mod test_dead_letters;
+
+mod test_dispatch_policy;
+
+mod test_dispatch_queue;
+
+mod test_event_route;
+
+mod test_instrumented_store;
+
+mod test_query_conflict;
+
 mod test_repository;
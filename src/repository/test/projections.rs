@@ -0,0 +1,80 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::HashMap;
+
+use cqrs_es2::{
+    example_impl::*,
+    EventContext,
+};
+
+use crate::IProjection;
+
+/// Customers per address and per city, the addresses ending with
+/// `, <city>`
+#[derive(
+    Debug,
+    PartialEq,
+    Default,
+    Clone,
+    Serialize,
+    Deserialize
+)]
+pub struct CustomerLocations {
+    pub aggregate_ids: Vec<String>,
+}
+
+impl IProjection<CustomerCommand, CustomerEvent>
+    for CustomerLocations
+{
+    fn projection_type() -> &'static str {
+        "customer_locations"
+    }
+
+    fn keys(
+        event: &EventContext<CustomerCommand, CustomerEvent>
+    ) -> Vec<String> {
+        match &event.payload {
+            CustomerEvent::AddressUpdated(x) => {
+                let city = match x.new_address.rsplit(", ").next() {
+                    Some(x) => x.to_string(),
+                    None => x.new_address.clone(),
+                };
+
+                vec![x.new_address.clone(), city]
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    fn update(
+        &mut self,
+        _key: &str,
+        event: &EventContext<CustomerCommand, CustomerEvent>,
+    ) {
+        if !self
+            .aggregate_ids
+            .contains(&event.aggregate_id)
+        {
+            self.aggregate_ids
+                .push(event.aggregate_id.clone());
+        }
+    }
+}
+
+/// The `AddressUpdated` event of `aggregate_id`
+pub fn address_updated(
+    aggregate_id: &str,
+    sequence: i64,
+    address: &str,
+) -> EventContext<CustomerCommand, CustomerEvent> {
+    EventContext::new(
+        aggregate_id.to_string(),
+        sequence,
+        CustomerEvent::AddressUpdated(AddressUpdated {
+            new_address: address.to_string(),
+        }),
+        HashMap::new(),
+    )
+}
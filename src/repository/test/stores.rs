@@ -0,0 +1,320 @@
+use async_trait::async_trait;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{
+            AtomicBool,
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+    },
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+    EventContext,
+    QueryContext,
+};
+
+use crate::{
+    memory_store::QueryStore,
+    IEventDispatcher,
+    IQueryStore,
+};
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+/// Pending once, so that concurrent loads interleave
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Memory query store counting its loads and failing its saves on
+/// demand
+pub struct FlakyQueryStore {
+    store: ThisQueryStore,
+    loads: Arc<AtomicUsize>,
+    failing: Arc<AtomicBool>,
+}
+
+impl FlakyQueryStore {
+    pub fn new(
+        store: ThisQueryStore,
+        loads: Arc<AtomicUsize>,
+        failing: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            store,
+            loads,
+            failing,
+        }
+    }
+}
+
+#[async_trait]
+impl
+    IQueryStore<
+        CustomerCommand,
+        CustomerEvent,
+        Customer,
+        CustomerContactQuery,
+    > for FlakyQueryStore
+{
+    async fn save_query(
+        &mut self,
+        context: QueryContext<
+            CustomerCommand,
+            CustomerEvent,
+            CustomerContactQuery,
+        >,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(Error::new("store is failing"));
+        }
+
+        self.store
+            .save_query(context, expected_version)
+            .await
+    }
+
+    async fn load_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<
+        QueryContext<
+            CustomerCommand,
+            CustomerEvent,
+            CustomerContactQuery,
+        >,
+        Error,
+    > {
+        self.loads
+            .fetch_add(1, Ordering::SeqCst);
+
+        YieldOnce(false).await;
+
+        self.store
+            .load_query(aggregate_id)
+            .await
+    }
+
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<
+        Vec<
+            QueryContext<
+                CustomerCommand,
+                CustomerEvent,
+                CustomerContactQuery,
+            >,
+        >,
+        Error,
+    > {
+        self.store
+            .load_queries(aggregate_ids)
+            .await
+    }
+
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<
+        Vec<
+            QueryContext<
+                CustomerCommand,
+                CustomerEvent,
+                CustomerContactQuery,
+            >,
+        >,
+        Error,
+    > {
+        self.store
+            .load_all_queries(after, limit)
+            .await
+    }
+
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.store
+            .delete_query(aggregate_id)
+            .await
+    }
+}
+
+#[async_trait]
+impl IEventDispatcher<CustomerCommand, CustomerEvent>
+    for FlakyQueryStore
+{
+    async fn dispatch(
+        &mut self,
+        aggregate_id: &str,
+        events: &Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    ) -> Result<(), Error> {
+        self.dispatch_events(aggregate_id, events)
+            .await
+    }
+}
+
+/// Memory query store letting a concurrent writer dispatch `events`
+/// between its first load and save
+pub struct RacingQueryStore {
+    store: ThisQueryStore,
+    racer: Option<(
+        ThisQueryStore,
+        Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    )>,
+}
+
+impl RacingQueryStore {
+    pub fn new(
+        store: ThisQueryStore,
+        racer: ThisQueryStore,
+        events: Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    ) -> Self {
+        Self {
+            store,
+            racer: Some((racer, events)),
+        }
+    }
+}
+
+#[async_trait]
+impl
+    IQueryStore<
+        CustomerCommand,
+        CustomerEvent,
+        Customer,
+        CustomerContactQuery,
+    > for RacingQueryStore
+{
+    async fn save_query(
+        &mut self,
+        context: QueryContext<
+            CustomerCommand,
+            CustomerEvent,
+            CustomerContactQuery,
+        >,
+        expected_version: i64,
+    ) -> Result<(), Error> {
+        self.store
+            .save_query(context, expected_version)
+            .await
+    }
+
+    async fn load_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<
+        QueryContext<
+            CustomerCommand,
+            CustomerEvent,
+            CustomerContactQuery,
+        >,
+        Error,
+    > {
+        let context = self
+            .store
+            .load_query(aggregate_id)
+            .await?;
+
+        if let Some((mut racer, events)) = self.racer.take() {
+            racer
+                .dispatch_events(aggregate_id, &events)
+                .await?;
+        }
+
+        Ok(context)
+    }
+
+    async fn load_queries(
+        &mut self,
+        aggregate_ids: &[String],
+    ) -> Result<
+        Vec<
+            QueryContext<
+                CustomerCommand,
+                CustomerEvent,
+                CustomerContactQuery,
+            >,
+        >,
+        Error,
+    > {
+        self.store
+            .load_queries(aggregate_ids)
+            .await
+    }
+
+    async fn load_all_queries(
+        &mut self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<
+        Vec<
+            QueryContext<
+                CustomerCommand,
+                CustomerEvent,
+                CustomerContactQuery,
+            >,
+        >,
+        Error,
+    > {
+        self.store
+            .load_all_queries(after, limit)
+            .await
+    }
+
+    async fn delete_query(
+        &mut self,
+        aggregate_id: &str,
+    ) -> Result<(), Error> {
+        self.store
+            .delete_query(aggregate_id)
+            .await
+    }
+}
+
+#[async_trait]
+impl IEventDispatcher<CustomerCommand, CustomerEvent>
+    for RacingQueryStore
+{
+    async fn dispatch(
+        &mut self,
+        aggregate_id: &str,
+        events: &Vec<EventContext<CustomerCommand, CustomerEvent>>,
+    ) -> Result<(), Error> {
+        self.dispatch_events(aggregate_id, events)
+            .await
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    AggregateContext,
+    Error,
+};
+
+use crate::{
+    memory_store::EventStore,
+    AggregateCache,
+    Repository,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisAggregateCache =
+    AggregateCache<CustomerCommand, CustomerEvent, Customer>;
+
+fn add_address(address: &str) -> CustomerCommand {
+    CustomerCommand::AddAddress(AddAddress {
+        new_address: address.to_string(),
+    })
+}
+
+async fn check_aggregate_cache() -> Result<(), Error> {
+    let events = Default::default();
+    let snapshots = Default::default();
+
+    let cache = ThisAggregateCache::new(10);
+
+    let mut cached_repo = Repository::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        Vec::new(),
+        false,
+    )
+    .with_cache(cache.clone());
+
+    let mut other_repo = Repository::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        Vec::new(),
+        false,
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    cached_repo
+        .execute(
+            &id,
+            CustomerCommand::AddCustomerName(AddCustomerName {
+                changed_name: "some name".to_string(),
+            }),
+        )
+        .await?;
+
+    cached_repo
+        .execute(&id, add_address("first"))
+        .await?;
+
+    let cached = cache.get(&id).unwrap();
+
+    assert_eq!(cached.version, 2);
+    assert_eq!(cached.payload.name, "some name");
+    assert_eq!(
+        cached.payload.addresses,
+        vec!["first".to_string()]
+    );
+
+    // written behind the cache, replayed on the next load
+    other_repo
+        .execute(&id, add_address("second"))
+        .await?;
+
+    cached_repo
+        .execute(&id, add_address("third"))
+        .await?;
+
+    let cached = cache.get(&id).unwrap();
+
+    assert_eq!(cached.version, 4);
+    assert_eq!(
+        cached.payload.addresses,
+        vec![
+            "first".to_string(),
+            "second".to_string(),
+            "third".to_string()
+        ]
+    );
+
+    // a cached aggregate ahead of the store is dropped and reloaded
+    cache.put(AggregateContext::new(
+        id.clone(),
+        10,
+        Customer::default(),
+    ));
+
+    cached_repo
+        .execute(&id, add_address("fourth"))
+        .await?;
+
+    let cached = cache.get(&id).unwrap();
+
+    assert_eq!(cached.version, 5);
+    assert_eq!(cached.payload.name, "some name");
+    assert_eq!(cached.payload.addresses.len(), 4);
+
+    cache.invalidate(&id);
+
+    assert!(cache.get(&id).is_none());
+    assert!(cache.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_cache() {
+    tokio_test::block_on(check_aggregate_cache()).unwrap();
+}
+
+#[test]
+fn test_aggregate_cache_bounds() {
+    let cache =
+        ThisAggregateCache::new(2).with_ttl(Duration::from_millis(1));
+
+    for x in &["a", "b", "c"] {
+        cache.put(AggregateContext::new(
+            x.to_string(),
+            1,
+            Customer::default(),
+        ));
+    }
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get("a").is_none());
+
+    thread::sleep(Duration::from_millis(5));
+
+    assert!(cache.get("b").is_none());
+    assert!(cache.get("c").is_none());
+    assert!(cache.is_empty());
+}
@@ -0,0 +1,438 @@
+use futures::future::join_all;
+use std::{
+    sync::{
+        atomic::{
+            AtomicBool,
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    AggregateContext,
+    Error,
+    QueryContext,
+};
+
+use crate::{
+    memory_store::{
+        EventStore,
+        QueryStore,
+    },
+    CacheState,
+    CachedEventStore,
+    CachedQueryStore,
+    IEventStore,
+    IQueryStore,
+    Repository,
+};
+
+use super::stores::FlakyQueryStore;
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+fn snapshot(
+    id: &str,
+    version: i64,
+    name: &str,
+) -> AggregateContext<CustomerCommand, CustomerEvent, Customer> {
+    AggregateContext::new(
+        id.to_string(),
+        version,
+        Customer {
+            name: name.to_string(),
+            ..Default::default()
+        },
+    )
+}
+
+fn query(
+    id: &str,
+    version: i64,
+    name: &str,
+) -> QueryContext<CustomerCommand, CustomerEvent, CustomerContactQuery>
+{
+    QueryContext::new(
+        id.to_string(),
+        version,
+        CustomerContactQuery {
+            name: name.to_string(),
+            ..Default::default()
+        },
+    )
+}
+
+async fn check_cached_event_store() -> Result<(), Error> {
+    let events = Default::default();
+    let snapshots = Default::default();
+    let cached_snapshots = Default::default();
+
+    let mut store = CachedEventStore::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        ThisEventStore::new(
+            Default::default(),
+            Arc::clone(&cached_snapshots),
+        ),
+    )
+    .with_state(
+        CacheState::new().with_ttl(Duration::from_millis(50)),
+    );
+
+    let mut backend = ThisEventStore::new(events, snapshots);
+    let mut cache =
+        ThisEventStore::new(Default::default(), cached_snapshots);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    // written through to the store and the cache
+    store
+        .save_aggregate_snapshot(snapshot(&id, 1, "first"))
+        .await?;
+
+    assert_eq!(
+        backend
+            .load_aggregate_from_snapshot(&id)
+            .await?,
+        snapshot(&id, 1, "first")
+    );
+    assert_eq!(
+        cache
+            .load_aggregate_from_snapshot(&id)
+            .await?,
+        snapshot(&id, 1, "first")
+    );
+
+    // written behind the cache, seen once the cached snapshot expired
+    backend
+        .save_aggregate_snapshot(snapshot(&id, 2, "second"))
+        .await?;
+
+    assert_eq!(
+        store
+            .load_aggregate_from_snapshot(&id)
+            .await?,
+        snapshot(&id, 1, "first")
+    );
+
+    thread::sleep(Duration::from_millis(60));
+
+    assert_eq!(
+        store
+            .load_aggregate_from_snapshot(&id)
+            .await?,
+        snapshot(&id, 2, "second")
+    );
+    assert_eq!(
+        cache
+            .load_aggregate_from_snapshot(&id)
+            .await?,
+        snapshot(&id, 2, "second")
+    );
+
+    store.invalidate(&id).await?;
+
+    assert_eq!(
+        cache
+            .load_aggregate_from_snapshot(&id)
+            .await?
+            .version,
+        0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_event_store() {
+    tokio_test::block_on(check_cached_event_store()).unwrap();
+}
+
+fn add_address(address: &str) -> CustomerCommand {
+    CustomerCommand::AddAddress(AddAddress {
+        new_address: address.to_string(),
+    })
+}
+
+async fn check_cached_events() -> Result<(), Error> {
+    let events = Default::default();
+    let snapshots = Default::default();
+    let cached_events = Default::default();
+
+    let mut cached_repo = Repository::new(
+        CachedEventStore::new(
+            ThisEventStore::new(
+                Arc::clone(&events),
+                Arc::clone(&snapshots),
+            ),
+            ThisEventStore::new(
+                Arc::clone(&cached_events),
+                Default::default(),
+            ),
+        )
+        .with_store_check(),
+        Vec::new(),
+        false,
+    );
+
+    let mut other_repo = Repository::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        Vec::new(),
+        false,
+    );
+
+    let mut backend = ThisEventStore::new(
+        Arc::clone(&events),
+        Arc::clone(&snapshots),
+    );
+    let mut cache = ThisEventStore::new(
+        Arc::clone(&cached_events),
+        Default::default(),
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    for x in &["first", "second"] {
+        cached_repo
+            .execute(&id, add_address(x))
+            .await?;
+    }
+
+    // saved events are appended to the cached stream
+    assert_eq!(
+        cache.load_events(&id).await?,
+        backend.load_events(&id).await?
+    );
+
+    // written behind the cache, loaded as the missing tail
+    other_repo
+        .execute(&id, add_address("third"))
+        .await?;
+
+    cached_repo
+        .execute(&id, add_address("fourth"))
+        .await?;
+
+    let stored = backend.load_events(&id).await?;
+
+    assert_eq!(stored.len(), 4);
+    assert_eq!(cache.load_events(&id).await?, stored);
+
+    // a new cache is filled on the first load
+    let mut store = CachedEventStore::new(
+        ThisEventStore::new(events, snapshots),
+        ThisEventStore::default(),
+    );
+
+    assert_eq!(store.load_events(&id).await?, stored);
+    assert_eq!(
+        store.load_events_after(&id, 2).await?,
+        stored[2..].to_vec()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_events() {
+    tokio_test::block_on(check_cached_events()).unwrap();
+}
+
+async fn check_trusted_cached_events() -> Result<(), Error> {
+    let events = Default::default();
+    let snapshots = Default::default();
+    let cached_events = Default::default();
+
+    let mut store = CachedEventStore::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        ThisEventStore::new(cached_events, Default::default()),
+    );
+
+    let mut other_repo = Repository::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        Vec::new(),
+        false,
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    for x in &["first", "second"] {
+        other_repo
+            .execute(&id, add_address(x))
+            .await?;
+    }
+
+    assert_eq!(store.load_events(&id).await?.len(), 2);
+
+    // written behind the cache, not looked up until invalidated
+    other_repo
+        .execute(&id, add_address("third"))
+        .await?;
+
+    assert_eq!(store.load_events(&id).await?.len(), 2);
+
+    store.invalidate(&id).await?;
+
+    assert_eq!(store.load_events(&id).await?.len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_trusted_cached_events() {
+    tokio_test::block_on(check_trusted_cached_events()).unwrap();
+}
+
+async fn check_cached_query_store_failure() -> Result<(), Error> {
+    let queries = Default::default();
+    let cached_queries = Default::default();
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let failing = Arc::new(AtomicBool::new(false));
+
+    let mut store = CachedQueryStore::new(
+        FlakyQueryStore::new(
+            ThisQueryStore::new(Arc::clone(&queries)),
+            Arc::clone(&loads),
+            Arc::clone(&failing),
+        ),
+        ThisQueryStore::new(Arc::clone(&cached_queries)),
+    );
+
+    let mut cache = ThisQueryStore::new(cached_queries);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    store
+        .save_query(query(&id, 1, "first"), 0)
+        .await?;
+
+    assert_eq!(
+        cache.load_query(&id).await?,
+        query(&id, 1, "first")
+    );
+
+    failing.store(true, Ordering::SeqCst);
+
+    assert!(store
+        .save_query(query(&id, 2, "second"), 1)
+        .await
+        .is_err());
+
+    // the failed write is not cached and drops the cached query
+    assert_eq!(cache.load_query(&id).await?.version, 0);
+
+    assert_eq!(
+        store.load_query(&id).await?,
+        query(&id, 1, "first")
+    );
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_query_store_failure() {
+    tokio_test::block_on(check_cached_query_store_failure()).unwrap();
+}
+
+async fn check_cached_query_store_stampede() -> Result<(), Error> {
+    let queries = Default::default();
+    let cached_queries = Default::default();
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let failing = Arc::new(AtomicBool::new(false));
+    let state = CacheState::new();
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    ThisQueryStore::new(Arc::clone(&queries))
+        .save_query(query(&id, 1, "first"), 0)
+        .await?;
+
+    let mut stores: Vec<_> = (0..5)
+        .map(|_| {
+            CachedQueryStore::new(
+                FlakyQueryStore::new(
+                    ThisQueryStore::new(Arc::clone(&queries)),
+                    Arc::clone(&loads),
+                    Arc::clone(&failing),
+                ),
+                ThisQueryStore::new(Arc::clone(&cached_queries)),
+            )
+            .with_state(state.clone())
+        })
+        .collect();
+
+    let results = join_all(
+        stores
+            .iter_mut()
+            .map(|x| x.load_query(&id)),
+    )
+    .await;
+
+    for x in results {
+        assert_eq!(x?, query(&id, 1, "first"));
+    }
+
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_query_store_stampede() {
+    tokio_test::block_on(check_cached_query_store_stampede())
+        .unwrap();
+}
+
+#[test]
+fn test_cache_state_bounds() {
+    let state = CacheState::new();
+
+    // without a time to live nothing is recorded
+    state.mark_cached("first");
+
+    assert_eq!(state.cached_count(), 0);
+    assert!(state.is_fresh("first"));
+
+    let state = CacheState::new().with_ttl(Duration::from_millis(1));
+
+    for x in 0..1000 {
+        state.mark_cached(&x.to_string());
+    }
+
+    thread::sleep(Duration::from_millis(5));
+
+    // the expired timestamps are dropped as new ones are recorded
+    for x in 1000..2000 {
+        state.mark_cached(&x.to_string());
+    }
+
+    assert!(state.cached_count() <= 1000);
+    assert!(!state.is_fresh("0"));
+}
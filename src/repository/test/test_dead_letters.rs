@@ -0,0 +1,380 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+    EventContext,
+};
+
+use crate::{
+    memory_store::{
+        DeadLetterStore,
+        EventStore,
+    },
+    DeadLetterDispatcher,
+    IEventDispatcher,
+    Repository,
+};
+
+use super::dispatchers::ToggleDispatcher;
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisDeadLetterDispatcher = DeadLetterDispatcher<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    DeadLetterStore,
+>;
+
+async fn check_dead_letters() -> Result<(), Error> {
+    let failing = Arc::new(AtomicBool::new(true));
+    let dispatched = Arc::new(RwLock::new(Vec::new()));
+    let letters = Arc::new(RwLock::new(Default::default()));
+
+    let toggle = || {
+        Box::new(ToggleDispatcher::new(
+            Arc::clone(&failing),
+            Arc::clone(&dispatched),
+        ))
+    };
+
+    let mut repo = Repository::new(
+        ThisEventStore::default(),
+        vec![Box::new(ThisDeadLetterDispatcher::new(
+            toggle(),
+            "projection",
+            DeadLetterStore::new(Arc::clone(&letters)),
+        ))],
+        false,
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    // the failure is recorded instead of failing the command
+    repo.execute(
+        &id,
+        CustomerCommand::AddCustomerName(AddCustomerName {
+            changed_name: "John Doe".to_string(),
+        }),
+    )
+    .await?;
+
+    repo.execute(
+        &id,
+        CustomerCommand::AddAddress(AddAddress {
+            new_address: "One Main Street".to_string(),
+        }),
+    )
+    .await?;
+
+    let mut dispatcher = ThisDeadLetterDispatcher::new(
+        toggle(),
+        "projection",
+        DeadLetterStore::new(Arc::clone(&letters)),
+    );
+
+    let letters = dispatcher.load_dead_letters(10).await?;
+
+    assert_eq!(letters.len(), 2);
+    assert_eq!(letters[0].aggregate_id, id);
+    assert_eq!(letters[0].sequence, 1);
+    assert_eq!(letters[0].attempts, 1);
+    assert_eq!(letters[1].sequence, 2);
+
+    // retrying while the dispatcher still fails counts the attempts
+    assert!(dispatcher
+        .retry(&letters[0])
+        .await
+        .is_err());
+
+    let letters = dispatcher.load_dead_letters(10).await?;
+
+    assert_eq!(letters.len(), 2);
+    assert_eq!(letters[0].attempts, 2);
+    assert!(dispatched.read().unwrap().is_empty());
+
+    // retrying once it recovers dispatches and deletes the letter
+    failing.store(false, Ordering::SeqCst);
+
+    dispatcher.retry(&letters[0]).await?;
+
+    assert_eq!(dispatched.read().unwrap().len(), 1);
+    assert_eq!(
+        dispatched.read().unwrap()[0].sequence,
+        1
+    );
+
+    let letters = dispatcher.load_dead_letters(10).await?;
+
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].sequence, 2);
+
+    // discarded letters are not dispatched
+    dispatcher.discard(&letters[0]).await?;
+
+    assert!(dispatcher
+        .load_dead_letters(10)
+        .await?
+        .is_empty());
+    assert_eq!(dispatched.read().unwrap().len(), 1);
+
+    Ok(())
+}
+
+async fn check_retry_all() -> Result<(), Error> {
+    let failing = Arc::new(AtomicBool::new(true));
+    let dispatched = Arc::new(RwLock::new(Vec::new()));
+    let letters = Arc::new(RwLock::new(Default::default()));
+
+    let toggle = || {
+        Box::new(ToggleDispatcher::new(
+            Arc::clone(&failing),
+            Arc::clone(&dispatched),
+        ))
+    };
+
+    let mut repo = Repository::new(
+        ThisEventStore::default(),
+        vec![Box::new(ThisDeadLetterDispatcher::new(
+            toggle(),
+            "projection",
+            DeadLetterStore::new(Arc::clone(&letters)),
+        ))],
+        false,
+    );
+
+    let ids = vec![
+        uuid::Uuid::new_v4().to_string(),
+        uuid::Uuid::new_v4().to_string(),
+    ];
+
+    for id in &ids {
+        for i in 0..2 {
+            repo.execute(
+                id,
+                CustomerCommand::AddAddress(AddAddress {
+                    new_address: format!("address {}", i),
+                }),
+            )
+            .await?;
+        }
+    }
+
+    let mut dispatcher = ThisDeadLetterDispatcher::new(
+        toggle(),
+        "projection",
+        DeadLetterStore::new(letters),
+    );
+
+    // the later letters of a failing aggregate are skipped
+    assert_eq!(dispatcher.retry_all(10).await?, 0);
+
+    let letters = dispatcher.load_dead_letters(10).await?;
+
+    assert_eq!(letters.len(), 4);
+    assert!(letters
+        .iter()
+        .all(|x| x.attempts == if x.sequence == 1 { 2 } else { 1 }));
+
+    failing.store(false, Ordering::SeqCst);
+
+    assert_eq!(dispatcher.retry_all(10).await?, 4);
+    assert!(dispatcher
+        .load_dead_letters(10)
+        .await?
+        .is_empty());
+    assert_eq!(dispatched.read().unwrap().len(), 4);
+
+    Ok(())
+}
+
+async fn check_dead_letter_order() -> Result<(), Error> {
+    let failing = Arc::new(AtomicBool::new(true));
+    let dispatched = Arc::new(RwLock::new(Vec::new()));
+    let letters = Arc::new(RwLock::new(Default::default()));
+
+    let toggle = || {
+        Box::new(ToggleDispatcher::new(
+            Arc::clone(&failing),
+            Arc::clone(&dispatched),
+        ))
+    };
+
+    let mut repo = Repository::new(
+        ThisEventStore::default(),
+        vec![Box::new(ThisDeadLetterDispatcher::new(
+            toggle(),
+            "projection",
+            DeadLetterStore::new(Arc::clone(&letters)),
+        ))],
+        false,
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    repo.execute(
+        &id,
+        CustomerCommand::AddCustomerName(AddCustomerName {
+            changed_name: "John Doe".to_string(),
+        }),
+    )
+    .await?;
+
+    // the later batch is held behind the dead letter once the
+    // dispatcher recovered
+    failing.store(false, Ordering::SeqCst);
+
+    repo.execute(
+        &id,
+        CustomerCommand::AddAddress(AddAddress {
+            new_address: "One Main Street".to_string(),
+        }),
+    )
+    .await?;
+
+    assert!(dispatched.read().unwrap().is_empty());
+
+    let mut dispatcher = ThisDeadLetterDispatcher::new(
+        toggle(),
+        "projection",
+        DeadLetterStore::new(letters),
+    );
+
+    let letters = dispatcher.load_dead_letters(10).await?;
+
+    assert_eq!(letters.len(), 2);
+    assert_eq!(letters[1].sequence, 2);
+    assert_eq!(letters[1].attempts, 1);
+
+    // the letters are only retried in order
+    assert!(dispatcher
+        .retry(&letters[1])
+        .await
+        .is_err());
+    assert!(dispatched.read().unwrap().is_empty());
+
+    assert_eq!(dispatcher.retry_all(10).await?, 2);
+
+    let sequences: Vec<i64> = dispatched
+        .read()
+        .unwrap()
+        .iter()
+        .map(|x| x.sequence)
+        .collect();
+
+    assert_eq!(sequences, vec![1, 2]);
+
+    // with no letter left the events are dispatched again
+    repo.execute(
+        &id,
+        CustomerCommand::UpdateEmail(UpdateEmail {
+            new_email: "john@example.com".to_string(),
+        }),
+    )
+    .await?;
+
+    assert_eq!(dispatched.read().unwrap().len(), 3);
+    assert!(dispatcher
+        .load_dead_letters(10)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_dead_letters() {
+    tokio_test::block_on(check_dead_letters()).unwrap();
+}
+
+#[test]
+fn test_retry_all() {
+    tokio_test::block_on(check_retry_all()).unwrap();
+}
+
+#[test]
+fn test_dead_letter_order() {
+    tokio_test::block_on(check_dead_letter_order()).unwrap();
+}
+
+#[cfg(feature = "with-encryption")]
+async fn check_encrypted_dead_letters() -> Result<(), Error> {
+    use crate::{
+        encryption::CryptoShredder,
+        memory_store::KeyStore,
+    };
+
+    let failing = Arc::new(AtomicBool::new(true));
+    let dispatched = Arc::new(RwLock::new(Vec::new()));
+    let letters = Arc::new(RwLock::new(Default::default()));
+
+    let shredder = Arc::new(CryptoShredder::new(KeyStore::new(
+        Default::default(),
+    )));
+
+    let mut dispatcher = ThisDeadLetterDispatcher::new(
+        Box::new(ToggleDispatcher::new(
+            Arc::clone(&failing),
+            Arc::clone(&dispatched),
+        )),
+        "projection",
+        DeadLetterStore::new(Arc::clone(&letters)),
+    )
+    .with_transformer(shredder);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let events = vec![EventContext::new(
+        id.clone(),
+        1,
+        CustomerEvent::NameAdded(NameAdded {
+            changed_name: "John Doe".to_string(),
+        }),
+        HashMap::new(),
+    )];
+
+    dispatcher
+        .dispatch(&id, &events)
+        .await?;
+
+    // the recorded payload is ciphertext
+    let letter = dispatcher
+        .load_dead_letters(10)
+        .await?
+        .remove(0);
+
+    let payload = &letter.events[0]["payload"];
+
+    assert_ne!(
+        payload,
+        &serde_json::to_value(&events[0].payload).unwrap()
+    );
+    assert!(!payload.to_string().contains("John Doe"));
+
+    // and decrypted on retry
+    failing.store(false, Ordering::SeqCst);
+
+    dispatcher.retry(&letter).await?;
+
+    assert_eq!(*dispatched.read().unwrap(), events);
+
+    Ok(())
+}
+
+#[cfg(feature = "with-encryption")]
+#[test]
+fn test_encrypted_dead_letters() {
+    tokio_test::block_on(check_encrypted_dead_letters()).unwrap();
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+    time::Duration,
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+};
+
+use crate::{
+    memory_store::EventStore,
+    DispatchPolicy,
+    IEventDispatcher,
+    IEventStore,
+    Repository,
+};
+
+use super::dispatchers::{
+    CustomDispatcher,
+    FailingDispatcher,
+    SlowDispatcher,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisRepository = Repository<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    ThisEventStore,
+>;
+
+fn add_address(address: &str) -> CustomerCommand {
+    CustomerCommand::AddAddress(AddAddress {
+        new_address: address.to_string(),
+    })
+}
+
+async fn check_dispatch_policies() -> Result<(), Error> {
+    for policy in &[
+        DispatchPolicy::FailFast,
+        DispatchPolicy::Collect,
+        DispatchPolicy::Ignore,
+    ] {
+        let events = Default::default();
+        let snapshots = Default::default();
+        let dispatched = Arc::new(RwLock::new(Vec::new()));
+
+        let dispatchers: Vec<
+            Box<dyn IEventDispatcher<CustomerCommand, CustomerEvent>>,
+        > = vec![
+            Box::new(FailingDispatcher),
+            Box::new(CustomDispatcher::new(Arc::clone(
+                &dispatched,
+            ))),
+            Box::new(FailingDispatcher),
+        ];
+
+        let mut repo = ThisRepository::new(
+            ThisEventStore::new(
+                Arc::clone(&events),
+                Arc::clone(&snapshots),
+            ),
+            dispatchers,
+            false,
+        )
+        .with_dispatch_policy(*policy);
+
+        let id = uuid::Uuid::new_v4().to_string();
+
+        // the events are committed whatever the dispatchers did
+        let report = repo
+            .execute(&id, add_address("first"))
+            .await?;
+
+        assert_eq!(report.events, 1);
+
+        let report = repo
+            .execute_with_metadata(
+                &id,
+                add_address("second"),
+                HashMap::new(),
+            )
+            .await?;
+
+        assert_eq!(report.events, 1);
+
+        match policy {
+            DispatchPolicy::FailFast => {
+                assert_eq!(report.failures.len(), 1);
+            },
+            DispatchPolicy::Collect => {
+                let failed: Vec<Option<String>> = report
+                    .failures
+                    .iter()
+                    .map(|x| x.dispatcher.clone())
+                    .collect();
+
+                assert_eq!(
+                    failed,
+                    vec![
+                        Some("0".to_string()),
+                        Some("2".to_string())
+                    ]
+                );
+                assert_eq!(dispatched.read().unwrap().len(), 2);
+            },
+            DispatchPolicy::Ignore => {
+                assert!(report.is_complete());
+                assert_eq!(dispatched.read().unwrap().len(), 2);
+            },
+        };
+
+        assert_eq!(
+            ThisEventStore::new(events, snapshots)
+                .load_events(&id)
+                .await?
+                .len(),
+            2
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_dispatch_policies() {
+    tokio_test::block_on(check_dispatch_policies()).unwrap();
+}
+
+async fn check_dispatch_timeouts() -> Result<(), Error> {
+    let dispatched = Arc::new(RwLock::new(Vec::new()));
+
+    let dispatchers: Vec<
+        Box<dyn IEventDispatcher<CustomerCommand, CustomerEvent>>,
+    > = vec![
+        Box::new(SlowDispatcher::new(
+            Duration::from_secs(10),
+        )),
+        Box::new(SlowDispatcher::new(
+            Duration::from_millis(50),
+        )),
+        Box::new(CustomDispatcher::new(Arc::clone(
+            &dispatched,
+        ))),
+    ];
+
+    let mut repo = ThisRepository::new(
+        ThisEventStore::default(),
+        dispatchers,
+        false,
+    )
+    .with_dispatch_policy(DispatchPolicy::Collect)
+    .with_dispatch_timeout(Duration::from_millis(10))
+    .with_dispatcher_timeout("1", Duration::from_secs(10));
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let report = repo
+        .execute_with_metadata(
+            &id,
+            add_address("first"),
+            HashMap::new(),
+        )
+        .await?;
+
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(
+        report.failures[0].dispatcher,
+        Some("0".to_string())
+    );
+    assert!(report.failures[0]
+        .error
+        .to_string()
+        .contains("timed out"));
+    assert_eq!(dispatched.read().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_dispatch_timeouts() {
+    tokio_test::block_on(check_dispatch_timeouts()).unwrap();
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+    time::Duration,
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+    EventContext,
+};
+
+use crate::{
+    memory_store::EventStore,
+    DispatchQueue,
+    EventRoute,
+    Repository,
+};
+
+use super::dispatchers::{
+    CustomDispatcher,
+    PanickingDispatcher,
+    SlowDispatcher,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type Dispatched =
+    Arc<RwLock<Vec<EventContext<CustomerCommand, CustomerEvent>>>>;
+
+fn sequences(dispatched: &Dispatched) -> Vec<i64> {
+    dispatched
+        .read()
+        .unwrap()
+        .iter()
+        .map(|x| x.sequence)
+        .collect()
+}
+
+async fn check_dispatch_queue() -> Result<(), Error> {
+    let dispatched = Arc::new(RwLock::new(Vec::new()));
+
+    // a single slot makes the commands wait for the slow dispatcher
+    let queue = DispatchQueue::new(
+        vec![
+            Box::new(SlowDispatcher::new(
+                Duration::from_millis(5),
+            )),
+            Box::new(CustomDispatcher::new(Arc::clone(
+                &dispatched,
+            ))),
+        ],
+        1,
+    );
+
+    let mut repo = Repository::new(
+        ThisEventStore::default(),
+        Vec::new(),
+        false,
+    )
+    .with_dispatch_queue(queue.clone());
+
+    let ids = vec![
+        uuid::Uuid::new_v4().to_string(),
+        uuid::Uuid::new_v4().to_string(),
+    ];
+
+    for i in 0..5 {
+        for id in &ids {
+            repo.execute(
+                id,
+                CustomerCommand::AddAddress(AddAddress {
+                    new_address: format!("address {}", i),
+                }),
+            )
+            .await?;
+        }
+    }
+
+    queue.shutdown().await?;
+
+    assert_eq!(dispatched.read().unwrap().len(), 10);
+
+    for id in &ids {
+        let sequences: Vec<i64> = dispatched
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|x| &x.aggregate_id == id)
+            .map(|x| x.sequence)
+            .collect();
+
+        assert_eq!(sequences, vec![1, 2, 3, 4, 5]);
+    }
+
+    // the commands are still committed once the queue is shut down
+    let report = repo
+        .execute_with_metadata(
+            &ids[0],
+            CustomerCommand::AddAddress(AddAddress {
+                new_address: "last address".to_string(),
+            }),
+            HashMap::new(),
+        )
+        .await?;
+
+    assert_eq!(report.events, 1);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].dispatcher, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_dispatch_queue() {
+    tokio_test::block_on(check_dispatch_queue()).unwrap();
+}
+
+async fn check_routed_dispatch_queue() -> Result<(), Error> {
+    let addresses = Arc::new(RwLock::new(Vec::new()));
+    let all = Arc::new(RwLock::new(Vec::new()));
+
+    let queue = DispatchQueue::new(
+        vec![Box::new(CustomDispatcher::new(
+            Arc::clone(&all),
+        ))],
+        10,
+    )
+    .with_dispatcher(
+        "addresses",
+        Box::new(CustomDispatcher::new(Arc::clone(
+            &addresses,
+        ))),
+    );
+
+    let mut repo = Repository::new(
+        ThisEventStore::default(),
+        Vec::new(),
+        false,
+    )
+    .with_dispatch_queue(queue.clone());
+
+    // the clones share the routes
+    let queue = queue.clone().with_route(
+        "addresses",
+        EventRoute::new()
+            .with_event_types(vec!["AddressUpdated".to_string()]),
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    repo.execute(
+        &id,
+        CustomerCommand::AddCustomerName(AddCustomerName {
+            changed_name: "John Doe".to_string(),
+        }),
+    )
+    .await?;
+
+    repo.execute(
+        &id,
+        CustomerCommand::AddAddress(AddAddress {
+            new_address: "One Main Street".to_string(),
+        }),
+    )
+    .await?;
+
+    queue.shutdown().await?;
+
+    assert_eq!(sequences(&all), vec![1, 2]);
+    assert_eq!(sequences(&addresses), vec![2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_routed_dispatch_queue() {
+    tokio_test::block_on(check_routed_dispatch_queue()).unwrap();
+}
+
+async fn check_stopped_worker() -> Result<(), Error> {
+    let dispatched = Arc::new(RwLock::new(Vec::new()));
+
+    let queue = DispatchQueue::new(
+        vec![
+            Box::new(PanickingDispatcher),
+            Box::new(CustomDispatcher::new(Arc::clone(
+                &dispatched,
+            ))),
+        ],
+        10,
+    );
+
+    let mut repo = Repository::new(
+        ThisEventStore::default(),
+        Vec::new(),
+        false,
+    )
+    .with_dispatch_queue(queue.clone());
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    repo.execute(
+        &id,
+        CustomerCommand::AddCustomerName(AddCustomerName {
+            changed_name: "John Doe".to_string(),
+        }),
+    )
+    .await?;
+
+    // lets the first worker panic on the queued events
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let report = repo
+        .execute(
+            &id,
+            CustomerCommand::AddAddress(AddAddress {
+                new_address: "One Main Street".to_string(),
+            }),
+        )
+        .await?;
+
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(
+        report.failures[0].dispatcher,
+        Some("0".to_string())
+    );
+    assert!(report.failures[0].queued);
+
+    assert!(queue.shutdown().await.is_err());
+
+    // the other dispatcher was still given the events
+    assert_eq!(sequences(&dispatched), vec![1, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_stopped_worker() {
+    tokio_test::block_on(check_stopped_worker()).unwrap();
+}
@@ -0,0 +1,140 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+    EventContext,
+};
+
+use crate::{
+    memory_store::EventStore,
+    EventRoute,
+    Repository,
+};
+
+use super::dispatchers::{
+    CustomDispatcher,
+    FailingDispatcher,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisEventRoute = EventRoute<CustomerCommand, CustomerEvent>;
+
+type Dispatched =
+    Arc<RwLock<Vec<EventContext<CustomerCommand, CustomerEvent>>>>;
+
+fn sequences(dispatched: &Dispatched) -> Vec<i64> {
+    dispatched
+        .read()
+        .unwrap()
+        .iter()
+        .map(|x| x.sequence)
+        .collect()
+}
+
+async fn check_event_routes() -> Result<(), Error> {
+    let addresses = Arc::new(RwLock::new(Vec::new()));
+    let admin = Arc::new(RwLock::new(Vec::new()));
+    let all = Arc::new(RwLock::new(Vec::new()));
+
+    let mut repo = Repository::new(
+        ThisEventStore::default(),
+        vec![
+            Box::new(CustomDispatcher::new(Arc::clone(
+                &addresses,
+            ))),
+            Box::new(CustomDispatcher::new(Arc::clone(
+                &admin,
+            ))),
+            Box::new(CustomDispatcher::new(Arc::clone(&all))),
+        ],
+        false,
+    )
+    .with_dispatcher("failing", Box::new(FailingDispatcher))
+    .with_dispatcher_route(
+        "0",
+        ThisEventRoute::new()
+            .with_event_types(vec!["AddressUpdated".to_string()]),
+    )
+    .with_dispatcher_route(
+        "1",
+        ThisEventRoute::new()
+            .with_metadata("user", "admin")
+            .with_predicate(|x| x.sequence > 1),
+    )
+    // the failing dispatcher is only invoked for the email updates
+    .with_dispatcher_route(
+        "failing",
+        ThisEventRoute::new()
+            .with_event_types(vec!["EmailUpdated".to_string()]),
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let mut metadata = HashMap::new();
+    metadata.insert("user".to_string(), "admin".to_string());
+
+    repo.execute_with_metadata(
+        &id,
+        CustomerCommand::AddCustomerName(AddCustomerName {
+            changed_name: "John Doe".to_string(),
+        }),
+        metadata.clone(),
+    )
+    .await?;
+
+    repo.execute_with_metadata(
+        &id,
+        CustomerCommand::AddAddress(AddAddress {
+            new_address: "One Main Street".to_string(),
+        }),
+        metadata,
+    )
+    .await?;
+
+    repo.execute(
+        &id,
+        CustomerCommand::AddAddress(AddAddress {
+            new_address: "Two Main Street".to_string(),
+        }),
+    )
+    .await?;
+
+    assert_eq!(sequences(&addresses), vec![2, 3]);
+    assert_eq!(sequences(&admin), vec![2]);
+    assert_eq!(sequences(&all), vec![1, 2, 3]);
+
+    // the routed failing dispatcher fails the matching commands
+    let report = repo
+        .execute_with_metadata(
+            &id,
+            CustomerCommand::UpdateEmail(UpdateEmail {
+                new_email: "john@example.com".to_string(),
+            }),
+            HashMap::new(),
+        )
+        .await?;
+
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(
+        report.failures[0].dispatcher,
+        Some("failing".to_string())
+    );
+    assert_eq!(sequences(&addresses), vec![2, 3]);
+    assert_eq!(sequences(&all), vec![1, 2, 3, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn test_event_routes() {
+    tokio_test::block_on(check_event_routes()).unwrap();
+}
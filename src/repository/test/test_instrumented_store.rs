@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+};
+
+use crate::{
+    memory_store::{
+        EventStore,
+        QueryStore,
+    },
+    IEventStore,
+    IQueryStore,
+    InstrumentedEventStore,
+    InstrumentedQueryStore,
+    Repository,
+};
+
+type ThisEventStore =
+    EventStore<CustomerCommand, CustomerEvent, Customer>;
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+async fn check_instrumented_stores() -> Result<(), Error> {
+    let events = Default::default();
+    let snapshots = Default::default();
+    let queries = Default::default();
+
+    let event_store = InstrumentedEventStore::new(
+        ThisEventStore::new(
+            Arc::clone(&events),
+            Arc::clone(&snapshots),
+        ),
+        "memory_event_store",
+    );
+    let query_store = InstrumentedQueryStore::new(
+        ThisQueryStore::new(Arc::clone(&queries)),
+        "memory_query_store",
+    );
+
+    let mut repo = Repository::new(
+        event_store,
+        vec![Box::new(query_store)],
+        true,
+    );
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    repo.execute_with_metadata(
+        &id,
+        CustomerCommand::AddCustomerName(AddCustomerName {
+            changed_name: "some name".to_string(),
+        }),
+        HashMap::new(),
+    )
+    .await?;
+
+    repo.execute(
+        &id,
+        CustomerCommand::UpdateEmail(UpdateEmail {
+            new_email: "e@mail.com".to_string(),
+        }),
+    )
+    .await?;
+
+    let mut event_store = InstrumentedEventStore::new(
+        ThisEventStore::new(events, snapshots),
+        "memory_event_store",
+    );
+
+    assert_eq!(
+        2,
+        event_store
+            .load_events(&id)
+            .await?
+            .len()
+    );
+
+    let context = event_store
+        .load_aggregate_from_snapshot(&id)
+        .await?;
+
+    assert_eq!(2, context.version);
+    assert_eq!("some name", context.payload.name);
+    assert_eq!("e@mail.com", context.payload.email);
+
+    assert_eq!(
+        vec![id.clone()],
+        event_store
+            .list_aggregate_ids(None, 10)
+            .await?
+    );
+
+    let mut query_store = InstrumentedQueryStore::new(
+        ThisQueryStore::new(queries),
+        "memory_query_store",
+    );
+
+    let context = query_store.load_query(&id).await?;
+
+    assert_eq!(2, context.version);
+    assert_eq!(
+        CustomerContactQuery {
+            name: "some name".to_string(),
+            email: "e@mail.com".to_string(),
+            latest_address: Default::default(),
+        },
+        context.payload
+    );
+
+    query_store.delete_query(&id).await?;
+
+    assert_eq!(
+        0,
+        query_store
+            .load_query(&id)
+            .await?
+            .version
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_instrumented_stores() {
+    tokio_test::block_on(check_instrumented_stores()).unwrap();
+}
+
+#[cfg(feature = "with-metrics")]
+async fn check_metrics() -> Result<(), Error> {
+    use metrics_util::debugging::{
+        DebugValue,
+        DebuggingRecorder,
+        Snapshotter,
+    };
+
+    use crate::CachedEventStore;
+
+    // a recorder may already be installed by another test on the
+    // same thread
+    let _ = DebuggingRecorder::per_thread().install();
+
+    let store = CachedEventStore::new(
+        InstrumentedEventStore::new(
+            ThisEventStore::default(),
+            "metrics_event_store",
+        ),
+        ThisEventStore::default(),
+    );
+
+    let mut repo = Repository::new(store, Vec::new(), true);
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    for x in &["first address", "second address"] {
+        repo.execute(
+            &id,
+            CustomerCommand::AddAddress(AddAddress {
+                new_address: x.to_string(),
+            }),
+        )
+        .await?;
+    }
+
+    let snapshot = Snapshotter::current_thread_snapshot()
+        .unwrap()
+        .into_vec();
+
+    let value_of = |name: &str, labels: &[&str]| {
+        snapshot
+            .iter()
+            .find(|(k, _, _, _)| {
+                k.key().name() == name &&
+                    labels.iter().all(|x| {
+                        k.key()
+                            .labels()
+                            .any(|y| y.value() == *x)
+                    })
+            })
+            .map(|(_, _, _, v)| v)
+    };
+
+    assert_eq!(
+        Some(&DebugValue::Counter(2)),
+        value_of(
+            "cqrs_store_events_written_total",
+            &["metrics_event_store"]
+        )
+    );
+    assert!(matches!(
+        value_of(
+            "cqrs_store_event_bytes_written_total",
+            &["metrics_event_store"]
+        ),
+        Some(DebugValue::Counter(x)) if *x > 0
+    ));
+    assert!(matches!(
+        value_of(
+            "cqrs_store_operation_seconds",
+            &["metrics_event_store", "save_events_and_snapshot"]
+        ),
+        Some(DebugValue::Histogram(x)) if x.len() == 2
+    ));
+
+    // the first command misses the cache, the second one hits the
+    // snapshot written back by the first
+    assert!(matches!(
+        value_of("cqrs_store_cache_misses_total", &["cached_event_store"]),
+        Some(DebugValue::Counter(x)) if *x >= 1
+    ));
+    assert!(matches!(
+        value_of("cqrs_store_cache_hits_total", &["cached_event_store"]),
+        Some(DebugValue::Counter(x)) if *x >= 1
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "with-metrics")]
+#[test]
+fn test_metrics() {
+    tokio_test::block_on(check_metrics()).unwrap();
+}
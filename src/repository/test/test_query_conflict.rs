@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use cqrs_es2::{
+    example_impl::*,
+    Error,
+    EventContext,
+};
+
+use crate::{
+    is_query_conflict,
+    memory_store::QueryStore,
+    query_conflict,
+    IQueryStore,
+};
+
+use super::stores::RacingQueryStore;
+
+type ThisQueryStore = QueryStore<
+    CustomerCommand,
+    CustomerEvent,
+    Customer,
+    CustomerContactQuery,
+>;
+
+async fn check_query_conflict() -> Result<(), Error> {
+    let e = query_conflict("customer_contact_query", "id_A", 1);
+
+    assert!(is_query_conflict(&e));
+    assert!(!is_query_conflict(&Error::new(
+        "unable to update query"
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn test_query_conflict() {
+    tokio_test::block_on(check_query_conflict()).unwrap();
+}
+
+async fn check_dispatch_retries() -> Result<(), Error> {
+    let queries = Arc::new(RwLock::new(HashMap::new()));
+
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let address = |sequence: i64, address: &str| {
+        EventContext::new(
+            id.to_string(),
+            sequence,
+            CustomerEvent::AddressUpdated(AddressUpdated {
+                new_address: address.to_string(),
+            }),
+            HashMap::new(),
+        )
+    };
+
+    // the racer saves the first event after the store loaded the
+    // query, so that the first save of the store conflicts
+    let mut store = RacingQueryStore::new(
+        ThisQueryStore::new(Arc::clone(&queries)),
+        ThisQueryStore::new(Arc::clone(&queries)),
+        vec![address(1, "one address")],
+    );
+
+    store
+        .dispatch_events(
+            &id,
+            &[
+                address(1, "one address"),
+                address(2, "two address"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let stored_context = store.load_query(&id).await.unwrap();
+
+    assert_eq!(stored_context.version, 2);
+    assert_eq!(
+        stored_context.payload.latest_address,
+        "two address"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_dispatch_retries() {
+    tokio_test::block_on(check_dispatch_retries()).unwrap();
+}
@@ -0,0 +1,82 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use cqrs_es2::Error;
+
+use super::i_payload_transformer::IPayloadTransformer;
+
+/// Payload transformer applying several transformers in turn, e.g.
+/// compressing the payloads before encrypting them. The payloads are
+/// encoded in the order the transformers were added and decoded in
+/// the reverse order.
+#[derive(Clone, Default)]
+pub struct TransformerChain {
+    transformers: Vec<Arc<dyn IPayloadTransformer>>,
+}
+
+impl TransformerChain {
+    /// Constructor of an empty chain leaving the payloads unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the `transformer` to the chain
+    pub fn with(
+        mut self,
+        transformer: Arc<dyn IPayloadTransformer>,
+    ) -> Self {
+        self.transformers.push(transformer);
+        self
+    }
+}
+
+#[async_trait]
+impl IPayloadTransformer for TransformerChain {
+    /// Transform the `payload` with every transformer in order
+    async fn encode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        mut payload: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        for x in &self.transformers {
+            payload = x
+                .encode(aggregate_type, aggregate_id, payload)
+                .await?;
+        }
+
+        Ok(payload)
+    }
+
+    /// Restore the `payload` with every transformer in reverse order
+    async fn decode(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        mut payload: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        for x in self.transformers.iter().rev() {
+            payload = x
+                .decode(aggregate_type, aggregate_id, payload)
+                .await?;
+        }
+
+        Ok(payload)
+    }
+
+    /// Restore the `payloads` with every transformer in reverse order
+    async fn decode_all(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        mut payloads: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        for x in self.transformers.iter().rev() {
+            payloads = x
+                .decode_all(aggregate_type, aggregate_id, payloads)
+                .await?;
+        }
+
+        Ok(payloads)
+    }
+}